@@ -0,0 +1,454 @@
+use std::{ops::Range, path::Path, time::Duration};
+
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, TryStream, TryStreamExt, stream};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use reqwest::{Body, Method, RequestBuilder, Response, header};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    download::Download,
+    error::{Error, Result},
+    models::{
+        BatchDeleteResponse, Bucket, BucketPolicy, ErrorResponse, ListBucketsResponse,
+        ListObjectsResponse, ListOptions, ObjectMetadata, PresignResponse, PutOptions,
+        SearchOptions, SearchResponse, StatsResponse,
+    },
+};
+
+const USER_METADATA_PREFIX: &str = "x-lila-meta-";
+const KEY_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    endpoint: String,
+    token: Option<String>,
+    bucket: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ClientBuilder {
+    endpoint: String,
+    token: Option<String>,
+    timeout: Option<Duration>,
+    http: Option<reqwest::Client>,
+}
+
+impl ClientBuilder {
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = Some(http);
+        self
+    }
+
+    pub fn build(self) -> Result<Client> {
+        let endpoint = self.endpoint.trim_end_matches('/').to_string();
+        if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+            return Err(Error::InvalidEndpoint(endpoint));
+        }
+
+        let http = match self.http {
+            Some(http) => http,
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                builder.build()?
+            }
+        };
+
+        Ok(Client {
+            http,
+            endpoint,
+            token: self.token,
+            bucket: None,
+        })
+    }
+}
+
+impl Client {
+    pub fn builder(endpoint: impl Into<String>) -> ClientBuilder {
+        ClientBuilder {
+            endpoint: endpoint.into(),
+            token: None,
+            timeout: None,
+            http: None,
+        }
+    }
+
+    pub fn new(endpoint: impl Into<String>, token: impl Into<String>) -> Result<Self> {
+        Self::builder(endpoint).token(token).build()
+    }
+
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    pub fn bucket(&self, name: impl Into<String>) -> Client {
+        Client {
+            bucket: Some(name.into()),
+            ..self.clone()
+        }
+    }
+
+    pub fn current_bucket(&self) -> Option<&str> {
+        self.bucket.as_deref()
+    }
+
+    pub async fn put_object(
+        &self,
+        key: &str,
+        body: impl Into<Body>,
+        options: &PutOptions,
+    ) -> Result<ObjectMetadata> {
+        let mut request = self.request(Method::PUT, &self.object_url("objects", key));
+
+        if let Some(content_type) = &options.content_type {
+            request = request.header(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(length) = options.content_length {
+            request = request.header(header::CONTENT_LENGTH, length);
+        }
+        if options.public {
+            request = request.header("x-lila-public", "true");
+        }
+        if let Some(ttl) = options.ttl_seconds {
+            request = request.header("x-lila-ttl-seconds", ttl);
+        }
+        if let Some(expires_at) = options.expires_at {
+            request = request.header("x-lila-expires-at", expires_at.to_rfc3339());
+        }
+        if let Some(cache_control) = &options.cache_control {
+            request = request.header(header::CACHE_CONTROL, cache_control);
+        }
+        if let Some(generation) = options.if_generation_match {
+            request = request.header("x-lila-if-generation-match", generation);
+        }
+        for (name, value) in &options.metadata {
+            request = request.header(format!("{}{}", USER_METADATA_PREFIX, name), value);
+        }
+
+        Self::json(request.body(body)).await
+    }
+
+    pub async fn put_bytes(
+        &self,
+        key: &str,
+        data: impl Into<Bytes>,
+        options: &PutOptions,
+    ) -> Result<ObjectMetadata> {
+        let data = data.into();
+        let options = PutOptions {
+            content_length: Some(data.len() as u64),
+            ..options.clone()
+        };
+
+        self.put_object(key, data, &options).await
+    }
+
+    pub async fn put_stream<S>(
+        &self,
+        key: &str,
+        stream: S,
+        options: &PutOptions,
+    ) -> Result<ObjectMetadata>
+    where
+        S: TryStream + Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
+        self.put_object(key, Body::wrap_stream(stream), options)
+            .await
+    }
+
+    pub async fn put_reader<R>(
+        &self,
+        key: &str,
+        reader: R,
+        options: &PutOptions,
+    ) -> Result<ObjectMetadata>
+    where
+        R: AsyncRead + Send + 'static,
+    {
+        self.put_stream(key, ReaderStream::new(reader), options)
+            .await
+    }
+
+    pub async fn put_file(
+        &self,
+        key: &str,
+        path: impl AsRef<Path>,
+        options: &PutOptions,
+    ) -> Result<ObjectMetadata> {
+        let file = tokio::fs::File::open(path).await?;
+        let options = PutOptions {
+            content_length: Some(file.metadata().await?.len()),
+            ..options.clone()
+        };
+
+        self.put_reader(key, file, &options).await
+    }
+
+    pub async fn get_object(&self, key: &str) -> Result<Download> {
+        let request = self.request(Method::GET, &self.object_url("objects", key));
+        Ok(Download::new(Self::send(request).await?))
+    }
+
+    pub async fn get_range(&self, key: &str, range: Range<u64>) -> Result<Download> {
+        if range.is_empty() {
+            return Err(Error::InvalidRange(range));
+        }
+
+        let request = self
+            .request(Method::GET, &self.object_url("objects", key))
+            .header(
+                header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
+            );
+        Ok(Download::new(Self::send(request).await?))
+    }
+
+    pub async fn get_bytes(&self, key: &str) -> Result<Bytes> {
+        self.get_object(key).await?.bytes().await
+    }
+
+    pub async fn download_to_file(&self, key: &str, path: impl AsRef<Path>) -> Result<u64> {
+        let download = self.get_object(key).await?;
+        let mut file = tokio::fs::File::create(path).await?;
+        download.write_to(&mut file).await
+    }
+
+    pub async fn metadata(&self, key: &str) -> Result<ObjectMetadata> {
+        Self::json(self.request(Method::GET, &self.object_url("metadata", key))).await
+    }
+
+    pub async fn exists(&self, key: &str) -> Result<bool> {
+        let request = self.request(Method::HEAD, &self.object_url("objects", key));
+        match Self::send(request).await {
+            Ok(_) => Ok(true),
+            Err(e) if e.is_not_found() => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn delete_object(&self, key: &str) -> Result<()> {
+        Self::send(self.request(Method::DELETE, &self.object_url("objects", key))).await?;
+        Ok(())
+    }
+
+    pub async fn delete_objects(&self, keys: &[String]) -> Result<BatchDeleteResponse> {
+        let request = self
+            .request(Method::POST, &self.url("objects:batchDelete"))
+            .json(keys);
+        Self::json(request).await
+    }
+
+    pub async fn copy_object(&self, source: &str, destination: &str) -> Result<ObjectMetadata> {
+        let request = self
+            .request(Method::POST, &self.url("copy"))
+            .json(&json!({ "source": source, "destination": destination }));
+        Self::json(request).await
+    }
+
+    pub async fn list_objects(&self, options: &ListOptions) -> Result<ListObjectsResponse> {
+        let request = self
+            .request(Method::GET, &self.url("objects"))
+            .query(options);
+        Self::json(request).await
+    }
+
+    pub fn list_all(
+        &self,
+        options: ListOptions,
+    ) -> impl Stream<Item = Result<ObjectMetadata>> + Send + '_ {
+        stream::try_unfold(Some(options), move |options| async move {
+            let Some(options) = options else {
+                return Ok(None);
+            };
+
+            let page = self.list_objects(&options).await?;
+            let next = page.next_cursor.map(|cursor| ListOptions {
+                cursor: Some(cursor),
+                ..options
+            });
+
+            Ok::<_, Error>(Some((
+                stream::iter(page.objects.into_iter().map(Ok::<_, Error>)),
+                next,
+            )))
+        })
+        .try_flatten()
+    }
+
+    pub fn stream_objects(
+        &self,
+        prefix: &str,
+    ) -> impl Stream<Item = Result<ObjectMetadata>> + Send + '_ {
+        let request = self
+            .request(Method::GET, &self.url("objects.ndjson"))
+            .query(&[("prefix", prefix)]);
+
+        stream::once(Self::send(request))
+            .map_ok(|response| {
+                stream::try_unfold(
+                    (response.bytes_stream(), Vec::new()),
+                    |(mut body, mut buffer)| async move {
+                        loop {
+                            if let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+                                let line: Vec<u8> = buffer.drain(..=end).collect();
+                                let object = serde_json::from_slice(&line)?;
+                                return Ok(Some((object, (body, buffer))));
+                            }
+
+                            match body.next().await {
+                                Some(chunk) => buffer.extend_from_slice(&chunk?),
+                                None if buffer.iter().all(u8::is_ascii_whitespace) => {
+                                    return Ok(None);
+                                }
+                                None => {
+                                    let object = serde_json::from_slice(&buffer)?;
+                                    buffer.clear();
+                                    return Ok(Some((object, (body, buffer))));
+                                }
+                            }
+                        }
+                    },
+                )
+            })
+            .try_flatten()
+    }
+
+    pub async fn search(&self, options: &SearchOptions) -> Result<SearchResponse> {
+        let request = self
+            .request(Method::GET, &self.url("search"))
+            .query(options);
+        Self::json(request).await
+    }
+
+    pub async fn presign(
+        &self,
+        key: &str,
+        method: Method,
+        expires_in: Duration,
+    ) -> Result<PresignResponse> {
+        let request = self
+            .request(Method::POST, &self.url("presign"))
+            .json(&json!({
+                "key": key,
+                "method": method.as_str(),
+                "expires_in": expires_in.as_secs(),
+            }));
+
+        let mut response: PresignResponse = Self::json(request).await?;
+        if response.url.starts_with('/') {
+            response.url = format!("{}{}", self.endpoint, response.url);
+        }
+        Ok(response)
+    }
+
+    pub async fn stats(&self) -> Result<StatsResponse> {
+        Self::json(self.request(Method::GET, &self.url("stats"))).await
+    }
+
+    pub async fn list_buckets(&self) -> Result<Vec<Bucket>> {
+        let response: ListBucketsResponse =
+            Self::json(self.request(Method::GET, &self.root_url("buckets"))).await?;
+        Ok(response.buckets)
+    }
+
+    pub async fn create_bucket(&self, name: &str, policy: &BucketPolicy) -> Result<Bucket> {
+        let request = self
+            .request(Method::PUT, &self.bucket_url(name))
+            .json(policy);
+        Self::json(request).await
+    }
+
+    pub async fn delete_bucket(&self, name: &str) -> Result<()> {
+        Self::send(self.request(Method::DELETE, &self.bucket_url(name))).await?;
+        Ok(())
+    }
+
+    fn root_url(&self, path: &str) -> String {
+        format!("{}/api/v1/{}", self.endpoint, path)
+    }
+
+    fn bucket_url(&self, name: &str) -> String {
+        self.root_url(&format!(
+            "buckets/{}",
+            utf8_percent_encode(name, KEY_SEGMENT)
+        ))
+    }
+
+    fn url(&self, path: &str) -> String {
+        match &self.bucket {
+            Some(bucket) => format!("{}/{}", self.bucket_url(bucket), path),
+            None => self.root_url(path),
+        }
+    }
+
+    fn object_url(&self, route: &str, key: &str) -> String {
+        let key = key
+            .split('/')
+            .map(|segment| utf8_percent_encode(segment, KEY_SEGMENT).to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        self.url(&format!("{}/{}", route, key))
+    }
+
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let request = self.http.request(method, url);
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn send(request: RequestBuilder) -> Result<Response> {
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.bytes().await.unwrap_or_default();
+        let (message, request_id) = match serde_json::from_slice::<ErrorResponse>(&body) {
+            Ok(error) => (error.error, error.request_id),
+            Err(_) => (
+                status
+                    .canonical_reason()
+                    .unwrap_or("request failed")
+                    .to_string(),
+                None,
+            ),
+        };
+
+        Err(Error::Api {
+            status,
+            message,
+            request_id,
+        })
+    }
+
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T> {
+        Ok(Self::send(request).await?.json().await?)
+    }
+}
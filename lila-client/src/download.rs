@@ -0,0 +1,67 @@
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use reqwest::{Response, StatusCode, header};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::error::Result;
+
+#[derive(Debug)]
+pub struct Download {
+    response: Response,
+}
+
+impl Download {
+    pub(crate) fn new(response: Response) -> Self {
+        Self { response }
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.response.status() == StatusCode::PARTIAL_CONTENT
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.header(header::CONTENT_TYPE.as_str())
+    }
+
+    pub fn content_length(&self) -> Option<u64> {
+        self.response.content_length()
+    }
+
+    pub fn etag(&self) -> Option<&str> {
+        self.header(header::ETAG.as_str())
+            .map(|etag| etag.trim_matches('"'))
+    }
+
+    pub fn generation(&self) -> Option<i64> {
+        self.header("x-lila-generation")?.parse().ok()
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.response.headers().get(name)?.to_str().ok()
+    }
+
+    pub async fn bytes(self) -> Result<Bytes> {
+        Ok(self.response.bytes().await?)
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<Bytes>> + Send {
+        self.response.bytes_stream().map_err(Into::into)
+    }
+
+    pub async fn write_to<W>(self, writer: &mut W) -> Result<u64>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        let mut stream = self.response.bytes_stream();
+        let mut written = 0;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+
+        writer.flush().await?;
+        Ok(written)
+    }
+}
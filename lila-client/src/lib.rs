@@ -0,0 +1,14 @@
+mod client;
+mod download;
+mod error;
+mod models;
+
+pub use client::{Client, ClientBuilder};
+pub use download::Download;
+pub use error::{Error, Result};
+pub use models::{
+    BatchDeleteResponse, BatchDeleteResult, Bucket, BucketPolicy, ListBucketsResponse,
+    ListObjectsResponse, ListOptions, ObjectMetadata, PresignResponse, PutOptions, SearchOptions,
+    SearchResponse, SortField, SortOrder, StatsResponse,
+};
+pub use reqwest::{Method, StatusCode};
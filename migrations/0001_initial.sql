@@ -0,0 +1,98 @@
+CREATE TABLE IF NOT EXISTS objects (
+    id TEXT PRIMARY KEY,
+    bucket TEXT NOT NULL DEFAULT '',
+    key TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    content_type TEXT NOT NULL,
+    etag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    public INTEGER NOT NULL DEFAULT 0,
+    expires_at TEXT,
+    retain_until TEXT,
+    user_metadata TEXT NOT NULL DEFAULT '{}',
+    etag_algorithm TEXT NOT NULL DEFAULT 'sha256',
+    cache_control TEXT,
+    cache_expires TEXT,
+    UNIQUE (bucket, key)
+);
+
+CREATE INDEX IF NOT EXISTS idx_objects_key ON objects(key);
+CREATE INDEX IF NOT EXISTS idx_objects_content_type ON objects(content_type);
+CREATE INDEX IF NOT EXISTS idx_objects_size ON objects(size);
+CREATE INDEX IF NOT EXISTS idx_objects_expires_at ON objects(expires_at);
+CREATE INDEX IF NOT EXISTS idx_objects_etag ON objects(etag);
+
+CREATE TABLE IF NOT EXISTS uploads (
+    id TEXT PRIMARY KEY,
+    key TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    bucket TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS upload_parts (
+    upload_id TEXT NOT NULL,
+    part_number INTEGER NOT NULL,
+    size INTEGER NOT NULL,
+    etag TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (upload_id, part_number)
+);
+
+CREATE TABLE IF NOT EXISTS resumable_uploads (
+    id TEXT PRIMARY KEY,
+    key TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    upload_length INTEGER NOT NULL,
+    upload_offset INTEGER NOT NULL,
+    created_at TEXT NOT NULL,
+    bucket TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS object_versions (
+    version_id TEXT PRIMARY KEY,
+    key TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    archived_at TEXT NOT NULL,
+    bucket TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_object_versions_key ON object_versions(key);
+
+CREATE TABLE IF NOT EXISTS trash (
+    id TEXT PRIMARY KEY,
+    key TEXT NOT NULL,
+    metadata TEXT NOT NULL,
+    deleted_at TEXT NOT NULL,
+    bucket TEXT NOT NULL DEFAULT ''
+);
+
+CREATE INDEX IF NOT EXISTS idx_trash_key ON trash(key);
+
+CREATE TABLE IF NOT EXISTS buckets (
+    name TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL,
+    quota_bytes INTEGER,
+    default_content_type TEXT,
+    public_read INTEGER NOT NULL DEFAULT 0,
+    versioning INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS tokens (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    token_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL,
+    scope TEXT NOT NULL DEFAULT 'admin',
+    rate_limit_per_second INTEGER,
+    rate_limit_burst INTEGER
+);
+
+CREATE TABLE IF NOT EXISTS sessions (
+    session_hash TEXT PRIMARY KEY,
+    subject TEXT NOT NULL,
+    name TEXT NOT NULL,
+    scope TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE objects ADD COLUMN stored_size INTEGER;
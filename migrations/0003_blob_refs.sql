@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS blob_refs (
+    digest TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    refs INTEGER NOT NULL
+);
@@ -0,0 +1,13 @@
+CREATE TABLE blob_refs_volumes (
+    volume INTEGER NOT NULL DEFAULT 0,
+    digest TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    refs INTEGER NOT NULL,
+    PRIMARY KEY (volume, digest)
+);
+
+INSERT INTO blob_refs_volumes (digest, size, refs) SELECT digest, size, refs FROM blob_refs;
+
+DROP TABLE blob_refs;
+
+ALTER TABLE blob_refs_volumes RENAME TO blob_refs;
@@ -0,0 +1,4 @@
+ALTER TABLE objects ADD COLUMN accessed_at TEXT;
+ALTER TABLE objects ADD COLUMN access_count INTEGER NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_objects_accessed_at ON objects (bucket, accessed_at);
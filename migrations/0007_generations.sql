@@ -0,0 +1,13 @@
+ALTER TABLE objects ADD COLUMN updated_at TEXT;
+ALTER TABLE objects ADD COLUMN generation INTEGER NOT NULL DEFAULT 1;
+
+UPDATE objects SET updated_at = created_at;
+
+CREATE TABLE generations (
+    bucket TEXT NOT NULL DEFAULT '',
+    key TEXT NOT NULL,
+    generation INTEGER NOT NULL,
+    PRIMARY KEY (bucket, key)
+);
+
+INSERT INTO generations (bucket, key, generation) SELECT bucket, key, generation FROM objects;
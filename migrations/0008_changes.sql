@@ -0,0 +1,18 @@
+CREATE TABLE changes (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    bucket TEXT NOT NULL DEFAULT '',
+    op TEXT NOT NULL,
+    key TEXT NOT NULL,
+    etag TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE INDEX idx_changes_bucket_seq ON changes (bucket, seq);
+CREATE INDEX idx_changes_created_at ON changes (created_at);
+
+CREATE TABLE checkpoints (
+    name TEXT PRIMARY KEY,
+    seq INTEGER NOT NULL,
+    updated_at TEXT NOT NULL
+);
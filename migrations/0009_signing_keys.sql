@@ -0,0 +1 @@
+ALTER TABLE tokens ADD COLUMN signing_key TEXT;
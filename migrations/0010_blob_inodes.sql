@@ -0,0 +1,3 @@
+ALTER TABLE blob_refs ADD COLUMN inode INTEGER;
+
+CREATE INDEX idx_blob_refs_inode ON blob_refs (volume, inode);
@@ -0,0 +1,324 @@
+use std::{
+    io,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+    time::Instant,
+};
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderMap, HeaderName, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use http_body_util::BodyExt;
+use serde::Serialize;
+use tokio::{fs, io::AsyncWriteExt, sync::mpsc};
+
+use crate::{
+    models::{AccessLogConfig, AccessLogFormat, ApiToken, LogRotation},
+    request_id::REQUEST_ID_HEADER,
+};
+
+#[derive(Clone)]
+pub struct AccessLog {
+    sender: mpsc::UnboundedSender<AccessEntry>,
+}
+
+#[derive(Serialize)]
+struct AccessEntry {
+    time: DateTime<Utc>,
+    remote_addr: String,
+    user: Option<String>,
+    method: String,
+    uri: String,
+    version: String,
+    status: u16,
+    bytes_received: u64,
+    bytes_sent: u64,
+    duration_ms: u64,
+    referer: Option<String>,
+    user_agent: Option<String>,
+    request_id: Option<String>,
+}
+
+impl AccessEntry {
+    fn combined(&self) -> String {
+        format!(
+            "{} - {} [{}] \"{} {} {}\" {} {} \"{}\" \"{}\"",
+            self.remote_addr,
+            self.user.as_deref().unwrap_or("-"),
+            self.time.format("%d/%b/%Y:%H:%M:%S %z"),
+            self.method,
+            self.uri,
+            self.version,
+            self.status,
+            self.bytes_sent,
+            self.referer.as_deref().unwrap_or("-"),
+            self.user_agent.as_deref().unwrap_or("-"),
+        )
+    }
+}
+
+struct PendingEntry {
+    log: AccessLog,
+    entry: Option<AccessEntry>,
+    started: Instant,
+    bytes_received: Arc<AtomicU64>,
+}
+
+impl Drop for PendingEntry {
+    fn drop(&mut self) {
+        if let Some(mut entry) = self.entry.take() {
+            entry.bytes_received = self.bytes_received.load(Ordering::Relaxed);
+            entry.duration_ms = self.started.elapsed().as_millis() as u64;
+            let _ = self.log.sender.send(entry);
+        }
+    }
+}
+
+struct RotatingFile {
+    path: PathBuf,
+    file: fs::File,
+    size: u64,
+    opened_at: DateTime<Utc>,
+    rotation: LogRotation,
+    max_size: Option<u64>,
+    max_files: usize,
+}
+
+impl AccessLog {
+    pub async fn open(config: &AccessLogConfig) -> io::Result<Self> {
+        let writer = RotatingFile::open(config).await?;
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        tokio::spawn(writer.run(receiver, config.format));
+
+        Ok(Self { sender })
+    }
+}
+
+impl RotatingFile {
+    async fn open(config: &AccessLogConfig) -> io::Result<Self> {
+        let path = PathBuf::from(&config.path);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let (file, size, opened_at) = Self::open_file(&path).await?;
+
+        Ok(Self {
+            path,
+            file,
+            size,
+            opened_at,
+            rotation: config.rotation,
+            max_size: config.max_size_mb.map(|mb| mb * 1024 * 1024),
+            max_files: config.max_files,
+        })
+    }
+
+    async fn open_file(path: &Path) -> io::Result<(fs::File, u64, DateTime<Utc>)> {
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+
+        let metadata = file.metadata().await?;
+        let opened_at = match metadata.len() {
+            0 => Utc::now(),
+            _ => metadata
+                .modified()
+                .map(DateTime::from)
+                .unwrap_or_else(|_| Utc::now()),
+        };
+
+        Ok((file, metadata.len(), opened_at))
+    }
+
+    async fn run(
+        mut self,
+        mut receiver: mpsc::UnboundedReceiver<AccessEntry>,
+        format: AccessLogFormat,
+    ) {
+        while let Some(entry) = receiver.recv().await {
+            let mut line = match format {
+                AccessLogFormat::Combined => entry.combined(),
+                AccessLogFormat::Json => match serde_json::to_string(&entry) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        tracing::warn!("Failed to serialize access log entry: {}", e);
+                        continue;
+                    }
+                },
+            };
+            line.push('\n');
+
+            if let Err(e) = self.write(&line).await {
+                tracing::warn!("Failed to write access log {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    async fn write(&mut self, line: &str) -> io::Result<()> {
+        if self.should_rotate(line.len() as u64) {
+            self.rotate().await?;
+        }
+
+        self.file.write_all(line.as_bytes()).await?;
+        self.size += line.len() as u64;
+
+        Ok(())
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        if self.size == 0 {
+            return false;
+        }
+
+        let oversized = self
+            .max_size
+            .is_some_and(|max_size| self.size + incoming > max_size);
+
+        oversized || period(self.rotation, self.opened_at) != period(self.rotation, Utc::now())
+    }
+
+    async fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush().await?;
+
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", Utc::now().format("%Y%m%dT%H%M%S%.3f")));
+        fs::rename(&self.path, &rotated).await?;
+
+        tracing::info!(
+            "Rotated access log to {}",
+            PathBuf::from(&rotated).display()
+        );
+
+        let (file, size, opened_at) = Self::open_file(&self.path).await?;
+        self.file = file;
+        self.size = size;
+        self.opened_at = opened_at;
+
+        self.prune().await
+    }
+
+    async fn prune(&self) -> io::Result<()> {
+        let (Some(dir), Some(name)) = (self.path.parent(), self.path.file_name()) else {
+            return Ok(());
+        };
+        let prefix = format!("{}.", name.to_string_lossy());
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+
+        let mut rotated = Vec::new();
+        let mut entries = fs::read_dir(dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            if file_name.starts_with(&prefix) {
+                rotated.push(entry.path());
+            }
+        }
+
+        rotated.sort();
+        let excess = rotated.len().saturating_sub(self.max_files);
+
+        for path in rotated.into_iter().take(excess) {
+            tracing::debug!("Removing old access log {}", path.display());
+            fs::remove_file(&path).await?;
+        }
+
+        Ok(())
+    }
+}
+
+pub async fn access_log_middleware(
+    State(log): State<AccessLog>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started = Instant::now();
+    let remote_addr = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_string())
+        .unwrap_or_else(|| "-".to_string());
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+    let version = format!("{:?}", request.version());
+    let referer = header_string(request.headers(), header::REFERER);
+    let user_agent = header_string(request.headers(), header::USER_AGENT);
+    let request_id = header_string(request.headers(), REQUEST_ID_HEADER);
+
+    let bytes_received = Arc::new(AtomicU64::new(0));
+    let counter = bytes_received.clone();
+    let request = request.map(|body| {
+        Body::new(body.map_frame(move |frame| {
+            if let Some(data) = frame.data_ref() {
+                counter.fetch_add(data.len() as u64, Ordering::Relaxed);
+            }
+            frame
+        }))
+    });
+
+    let response = next.run(request).await;
+
+    let mut pending = PendingEntry {
+        log,
+        entry: Some(AccessEntry {
+            time: Utc::now(),
+            remote_addr,
+            user: response
+                .extensions()
+                .get::<ApiToken>()
+                .map(|token| token.name.clone()),
+            method,
+            uri,
+            version,
+            status: response.status().as_u16(),
+            bytes_received: 0,
+            bytes_sent: 0,
+            duration_ms: 0,
+            referer,
+            user_agent,
+            request_id,
+        }),
+        started,
+        bytes_received,
+    };
+
+    response.map(|body| {
+        Body::new(body.map_frame(move |frame| {
+            if let (Some(data), Some(entry)) = (frame.data_ref(), pending.entry.as_mut()) {
+                entry.bytes_sent += data.len() as u64;
+            }
+            frame
+        }))
+    })
+}
+
+fn period(rotation: LogRotation, time: DateTime<Utc>) -> String {
+    match rotation {
+        LogRotation::Hourly => time.format("%Y%m%d%H").to_string(),
+        LogRotation::Daily => time.format("%Y%m%d").to_string(),
+        LogRotation::Never => String::new(),
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
@@ -1,38 +1,300 @@
 use axum::{
     extract::{Request, State},
-    http::HeaderMap,
+    http::{HeaderMap, HeaderValue, Method, header},
     middleware::Next,
     response::Response,
 };
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
 
 use crate::{
     error::{AppError, Result},
     handlers::objects::AppState,
+    models::TokenScope,
+    signing,
 };
 
+pub const OBJECTS_PATH: &str = "/api/v1/objects/";
+pub const BUCKETS_PATH: &str = "/api/v1/buckets/";
+pub const THUMB_PATH: &str = "/api/v1/thumb/";
+pub const CONFIG_TOKEN_ID: &str = "config";
+pub const SESSION_COOKIE: &str = "lila_session";
+const ADMIN_PATH: &str = "/api/v1/admin";
+const READ_ONLY_POSTS: [&str; 3] = [
+    "/api/v1/metadata:batchGet",
+    "/api/v1/presign",
+    "/api/v1/graphql",
+];
+const VERIFY_SUFFIX: &str = "/verify";
+const TOKEN_PREFIX: &str = "lila_";
+
+type HmacSha256 = Hmac<Sha256>;
+
 pub async fn auth_middleware(
     State(state): State<AppState>,
     headers: HeaderMap,
-    request: Request,
+    mut request: Request,
     next: Next,
 ) -> Result<Response> {
-    let token = headers
-        .get("authorization")
-        .and_then(|v| v.to_str().ok())
-        .and_then(|v| v.strip_prefix("Bearer "));
-
-    match token {
-        Some(t) if t == state.auth_token => {
-            tracing::debug!("Authentication successful");
-            Ok(next.run(request).await)
+    let authorization = headers.get("authorization").and_then(|v| v.to_str().ok());
+
+    let read_key = match *request.method() {
+        Method::GET | Method::HEAD => object_key(&request),
+        _ => None,
+    };
+
+    if let Some(ObjectTarget { bucket: None, key }) = &read_key
+        && state
+            .public_prefixes
+            .iter()
+            .any(|prefix| key.starts_with(prefix.as_str()))
+    {
+        tracing::debug!("Serving {} from a public prefix", key);
+        return Ok(next.run(request).await);
+    }
+
+    let mut body_check = None;
+
+    let principal = match authorization {
+        Some(value) if value.starts_with(signing::ALGORITHM) => {
+            let credentials = &value[signing::ALGORITHM.len()..];
+            let (api_token, signed, check) =
+                signing::verify(&state.metadata, &state.replay_guard, credentials, request).await?;
+            request = signed;
+            body_check = Some(check);
+            Some(api_token)
+        }
+        Some(value) => match value.strip_prefix("Bearer ") {
+            Some(t) => match state.metadata.find_token(&hash_token(t)).await? {
+                Some(api_token) => Some(api_token),
+                None => {
+                    tracing::warn!("Authentication failed: invalid token");
+                    return Err(AppError::Unauthorized);
+                }
+            },
+            None => None,
+        },
+        None => match cookie(&headers, SESSION_COOKIE) {
+            Some(session) => state
+                .metadata
+                .find_session(&hash_token(session))
+                .await?
+                .map(|session| session.principal),
+            None => None,
+        },
+    };
+
+    if let Some(api_token) = principal {
+        let required = required_scope(request.method(), request.uri().path());
+        if !api_token.scope.allows(required) {
+            tracing::warn!(
+                "Token {} with {} scope denied {} {}",
+                api_token.name,
+                api_token.scope,
+                request.method(),
+                request.uri().path()
+            );
+            return Err(AppError::Forbidden(format!(
+                "token scope {} does not grant {} access",
+                api_token.scope, required
+            )));
+        }
+
+        tracing::debug!("Authentication successful with token {}", api_token.name);
+        request.extensions_mut().insert(api_token.clone());
+        let mut response = next.run(request).await;
+
+        if body_check.is_some_and(|check| check.failed()) {
+            tracing::warn!("Signed request body did not match its content hash");
+            return Err(AppError::ChecksumMismatch("sha256".to_string()));
         }
-        Some(_) => {
-            tracing::warn!("Authentication failed: invalid token");
-            Err(AppError::Unauthorized)
+
+        response.extensions_mut().insert(api_token);
+        return Ok(response);
+    }
+
+    if verify_presigned(&state.presign_secret, &request) {
+        tracing::debug!("Authentication successful via presigned URL");
+        return Ok(next.run(request).await);
+    }
+
+    if is_public_read(&state, read_key).await? {
+        tracing::debug!("Serving public object without authentication");
+        return Ok(next.run(request).await);
+    }
+
+    tracing::warn!("Authentication failed: no token provided");
+    Err(AppError::Unauthorized)
+}
+
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .find_map(|pair| match pair.trim().split_once('=') {
+            Some((key, value)) if key == name => Some(value),
+            _ => None,
+        })
+}
+
+pub fn session_cookie(value: &str, max_age: i64, secure: bool) -> Result<HeaderValue> {
+    let secure = if secure { "; Secure" } else { "" };
+
+    format!(
+        "{}={}; Path=/; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        SESSION_COOKIE, value, max_age, secure
+    )
+    .parse()
+    .map_err(|_| AppError::Internal)
+}
+
+pub fn required_scope(method: &Method, path: &str) -> TokenScope {
+    if path == ADMIN_PATH || path.starts_with(&format!("{}/", ADMIN_PATH)) {
+        return TokenScope::Admin;
+    }
+
+    if let Some(rest) = path.strip_prefix(BUCKETS_PATH) {
+        match rest.split_once('/') {
+            None | Some((_, "")) if matches!(*method, Method::PUT | Method::DELETE) => {
+                return TokenScope::Admin;
+            }
+            Some((_, "policy")) if *method == Method::PUT => return TokenScope::Admin,
+            Some((_, inner)) => return required_scope(method, &format!("/api/v1/{}", inner)),
+            None => {}
+        }
+    }
+
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => TokenScope::Read,
+        Method::POST if READ_ONLY_POSTS.contains(&path) => TokenScope::Read,
+        Method::POST if path.starts_with(OBJECTS_PATH) && path.ends_with(VERIFY_SUFFIX) => {
+            TokenScope::Read
         }
+        _ => TokenScope::Write,
+    }
+}
+
+pub fn generate_token() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+pub fn sign(
+    secret: &str,
+    method: &Method,
+    bucket: Option<&str>,
+    key: &str,
+    expires: i64,
+) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(string_to_sign(method, bucket, key, expires).as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn string_to_sign(method: &Method, bucket: Option<&str>, key: &str, expires: i64) -> String {
+    match bucket {
+        Some(bucket) => format!("bucket:{}\n{}\n{}\n{}", bucket, method, key, expires),
+        None => format!("{}\n{}\n{}", method, key, expires),
+    }
+}
+
+async fn is_public_read(state: &AppState, target: Option<ObjectTarget>) -> Result<bool> {
+    let Some(target) = target else {
+        return Ok(false);
+    };
+
+    let metadata = match &target.bucket {
+        Some(name) => match state.metadata.get_bucket(name).await? {
+            Some(bucket) if bucket.policy.public_read => return Ok(true),
+            Some(_) => state.metadata.scoped(name),
+            None => return Ok(false),
+        },
+        None => state.metadata.clone(),
+    };
+
+    Ok(metadata
+        .get(&target.key)
+        .await?
+        .is_some_and(|metadata| metadata.public))
+}
+
+struct ObjectTarget {
+    bucket: Option<String>,
+    key: String,
+}
+
+fn object_key(request: &Request) -> Option<ObjectTarget> {
+    let path = request.uri().path();
+
+    let (bucket, encoded_key) = match path
+        .strip_prefix(OBJECTS_PATH)
+        .or_else(|| path.strip_prefix(THUMB_PATH))
+    {
+        Some(encoded_key) => (None, encoded_key),
         None => {
-            tracing::warn!("Authentication failed: no token provided");
-            Err(AppError::Unauthorized)
+            let (bucket, rest) = path.strip_prefix(BUCKETS_PATH)?.split_once('/')?;
+            let encoded_key = rest
+                .strip_prefix("objects/")
+                .or_else(|| rest.strip_prefix("thumb/"))?;
+            (Some(bucket.to_string()), encoded_key)
         }
+    };
+
+    percent_decode_str(encoded_key)
+        .decode_utf8()
+        .ok()
+        .map(|key| ObjectTarget {
+            bucket,
+            key: key.into_owned(),
+        })
+}
+
+fn verify_presigned(secret: &str, request: &Request) -> bool {
+    let Some(ObjectTarget { bucket, key }) = object_key(request) else {
+        return false;
+    };
+
+    let mut expires = None;
+    let mut signature = None;
+    for pair in request.uri().query().unwrap_or("").split('&') {
+        match pair.split_once('=') {
+            Some(("expires", value)) => expires = value.parse::<i64>().ok(),
+            Some(("signature", value)) => signature = hex::decode(value).ok(),
+            _ => {}
+        }
+    }
+
+    let (Some(expires), Some(signature)) = (expires, signature) else {
+        return false;
+    };
+
+    if expires < Utc::now().timestamp() {
+        tracing::warn!("Presigned URL for {} expired at {}", key, expires);
+        return false;
     }
+
+    let method = match *request.method() {
+        Method::HEAD => Method::GET,
+        ref method => method.clone(),
+    };
+
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(string_to_sign(&method, bucket.as_deref(), &key, expires).as_bytes());
+
+    mac.verify_slice(&signature).is_ok()
 }
@@ -0,0 +1,502 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_compression::tokio::{bufread::GzipDecoder, write::GzipEncoder};
+use chrono::Utc;
+use tokio::{
+    fs,
+    io::{AsyncWrite, AsyncWriteExt, BufReader},
+};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    export, fsck,
+    models::{BackupManifest, Config, FsckQuery, RestoreReport},
+    storage::{FileStorage, MetadataStore, SqliteMetadataStore, journal::JOURNAL_DIR},
+};
+
+pub const MANIFEST_NAME: &str = "manifest.json";
+pub const DATABASE_NAME: &str = "metadata.db";
+pub const OBJECTS_DIR: &str = "objects";
+pub const VOLUMES_DIR: &str = "volumes";
+
+const STAGING_DIR: &str = ".backups";
+const SKIPPED_DIRS: [&str; 4] = [STAGING_DIR, ".uploads", ".lost+found", JOURNAL_DIR];
+
+pub struct Snapshot {
+    path: PathBuf,
+    pub manifest: BackupManifest,
+}
+
+impl Snapshot {
+    pub async fn create(metadata: &MetadataStore, storage: &FileStorage) -> Result<Self> {
+        let path = storage
+            .base_path
+            .join(STAGING_DIR)
+            .join(Uuid::new_v4().simple().to_string());
+        fs::create_dir_all(&path).await?;
+
+        tracing::info!("Creating backup snapshot in {}", path.display());
+
+        match stage(metadata, storage, &path).await {
+            Ok(manifest) => {
+                tracing::info!(
+                    "Snapshot holds {} files ({} bytes)",
+                    manifest.files,
+                    manifest.total_size
+                );
+                Ok(Self { path, manifest })
+            }
+            Err(e) => {
+                fs::remove_dir_all(&path).await.ok();
+                Err(e)
+            }
+        }
+    }
+
+    pub async fn write_archive<W>(&self, writer: W) -> Result<()>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut tar = tokio_tar::Builder::new(GzipEncoder::new(writer));
+
+        tar.append_path_with_name(self.path.join(MANIFEST_NAME), MANIFEST_NAME)
+            .await?;
+        tar.append_path_with_name(self.path.join(DATABASE_NAME), DATABASE_NAME)
+            .await?;
+        tar.append_dir_all(OBJECTS_DIR, self.path.join(OBJECTS_DIR))
+            .await?;
+        if fs::try_exists(self.path.join(VOLUMES_DIR)).await? {
+            tar.append_dir_all(VOLUMES_DIR, self.path.join(VOLUMES_DIR))
+                .await?;
+        }
+
+        tar.into_inner().await?.shutdown().await?;
+        Ok(())
+    }
+
+    pub async fn write_to(&self, destination: &Path) -> Result<()> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let file = export::create_new(destination).await?;
+        self.write_archive(file).await
+    }
+
+    pub async fn remove(self) {
+        if let Err(e) = fs::remove_dir_all(&self.path).await {
+            tracing::warn!(
+                "Failed to remove backup snapshot {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+pub async fn restore(config: &Config, archive: &Path, dry_run: bool) -> Result<RestoreReport> {
+    let database = database_path(&config.database_url)?;
+    let staging = primary_volume(config)?
+        .join(STAGING_DIR)
+        .join(format!("restore-{}", Uuid::new_v4().simple()));
+    fs::create_dir_all(&staging).await?;
+
+    let result = restore_from(config, archive, &database, &staging, dry_run).await;
+
+    if let Err(e) = fs::remove_dir_all(&staging).await {
+        tracing::warn!("Failed to remove {}: {}", staging.display(), e);
+    }
+
+    result
+}
+
+async fn restore_from(
+    config: &Config,
+    archive: &Path,
+    database: &Path,
+    staging: &Path,
+    dry_run: bool,
+) -> Result<RestoreReport> {
+    tracing::info!("Unpacking {}", archive.display());
+
+    let file = fs::File::open(archive).await?;
+    tokio_tar::Archive::new(GzipDecoder::new(BufReader::new(file)))
+        .unpack(staging)
+        .await
+        .map_err(invalid)?;
+
+    let (manifest, restored) = validate(config, staging).await?;
+    tracing::info!(
+        "Backup from {} (version {}) holds {} objects",
+        manifest.created_at,
+        manifest.version,
+        restored.len()
+    );
+
+    let current = if fs::try_exists(database).await? {
+        let metadata: MetadataStore =
+            Arc::new(SqliteMetadataStore::new(&config.database_url, &config.database).await?);
+        let inventory = metadata.inventory().await;
+        metadata.close().await;
+        etags(inventory?)
+    } else {
+        HashMap::new()
+    };
+
+    let mut report = RestoreReport {
+        dry_run,
+        objects: restored.len(),
+        added: restored
+            .keys()
+            .filter(|key| !current.contains_key(*key))
+            .count(),
+        removed: current
+            .keys()
+            .filter(|key| !restored.contains_key(*key))
+            .count(),
+        changed: restored
+            .iter()
+            .filter(|(key, etag)| current.get(*key).is_some_and(|current| current != *etag))
+            .count(),
+        blobs: manifest.files,
+        manifest,
+        previous_database: None,
+    };
+
+    if dry_run {
+        return Ok(report);
+    }
+
+    if fs::try_exists(database).await? {
+        let previous = with_suffix(
+            database,
+            &format!(".pre-restore-{}", Utc::now().format("%Y%m%dT%H%M%S")),
+        );
+
+        for suffix in ["", "-wal", "-shm"] {
+            match fs::rename(
+                with_suffix(database, suffix),
+                with_suffix(&previous, suffix),
+            )
+            .await
+            {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+
+        tracing::info!("Moved previous database to {}", previous.display());
+        report.previous_database = Some(previous.display().to_string());
+    } else if let Some(parent) = database.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    fs::copy(staging.join(DATABASE_NAME), database).await?;
+
+    let primary = primary_volume(config)?;
+    let (mut blobs, _) = link_tree(&staging.join(OBJECTS_DIR), primary, true).await?;
+
+    let targets: Vec<&str> = config
+        .storage_path
+        .volumes()
+        .iter()
+        .map(String::as_str)
+        .chain(
+            config
+                .tiering
+                .as_ref()
+                .map(|tiering| tiering.cold_path.as_str()),
+        )
+        .collect();
+
+    for (index, source) in staged_volumes(staging).await? {
+        let target = match targets.get(index) {
+            Some(volume) => Path::new(volume),
+            None => {
+                tracing::warn!(
+                    "Backup volume {} is not configured, restoring its blobs to {}",
+                    index,
+                    primary.display()
+                );
+                primary
+            }
+        };
+        blobs += link_tree(&source, target, true).await?.0;
+    }
+    report.blobs = blobs;
+
+    tracing::info!(
+        "Restored {} objects and {} blobs",
+        report.objects,
+        report.blobs
+    );
+
+    if report.removed > 0 {
+        tracing::info!("Run `lila gc` to remove blobs of objects that are no longer referenced");
+    }
+
+    Ok(report)
+}
+
+async fn validate(
+    config: &Config,
+    staging: &Path,
+) -> Result<(BackupManifest, HashMap<(String, String), String>)> {
+    let manifest = fs::read(staging.join(MANIFEST_NAME))
+        .await
+        .map_err(|_| invalid(format!("missing {}", MANIFEST_NAME)))?;
+    let manifest: BackupManifest = serde_json::from_slice(&manifest).map_err(invalid)?;
+
+    if !fs::try_exists(staging.join(DATABASE_NAME)).await? {
+        return Err(invalid(format!("missing {}", DATABASE_NAME)));
+    }
+
+    let objects = staging.join(OBJECTS_DIR);
+    fs::create_dir_all(&objects).await?;
+
+    let mut volumes = vec![objects];
+    volumes.extend(
+        staged_volumes(staging)
+            .await?
+            .into_iter()
+            .map(|(_, path)| path),
+    );
+
+    let mut count = 0;
+    let mut total_size = 0;
+    for volume in &volumes {
+        for (_, size) in files(volume).await? {
+            count += 1;
+            total_size += size;
+        }
+    }
+
+    if count != manifest.files || total_size != manifest.total_size {
+        return Err(invalid(format!(
+            "manifest lists {} files ({} bytes) but the archive holds {} ({} bytes)",
+            manifest.files, manifest.total_size, count, total_size
+        )));
+    }
+
+    let database_url = format!("sqlite:{}", staging.join(DATABASE_NAME).display());
+    let metadata: MetadataStore =
+        Arc::new(SqliteMetadataStore::new(&database_url, &config.database).await?);
+    let volumes: Vec<String> = volumes
+        .iter()
+        .map(|volume| volume.display().to_string())
+        .collect();
+    let storage = FileStorage::new(&volumes, config.etag_algorithm, 0).await?;
+
+    let result = async {
+        let options = FsckQuery {
+            repair: false,
+            quick: true,
+        };
+        let report = fsck::run(&metadata, &storage, &options).await?;
+        Ok::<_, AppError>((report, metadata.inventory().await?))
+    }
+    .await;
+    metadata.close().await;
+    let (report, inventory) = result?;
+
+    let broken = report.missing_blobs.len() + report.size_mismatches.len();
+    if broken > 0 {
+        return Err(invalid(format!(
+            "{} objects have missing or truncated blobs",
+            broken
+        )));
+    }
+
+    Ok((manifest, etags(inventory)))
+}
+
+async fn staged_volumes(staging: &Path) -> Result<Vec<(usize, PathBuf)>> {
+    let mut volumes = Vec::new();
+
+    let mut entries = match fs::read_dir(staging.join(VOLUMES_DIR)).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(volumes),
+        Err(e) => return Err(e.into()),
+    };
+
+    while let Some(entry) = entries.next_entry().await? {
+        let index = entry
+            .file_name()
+            .to_string_lossy()
+            .parse()
+            .map_err(|_| invalid(format!("unexpected volume {:?}", entry.file_name())))?;
+        volumes.push((index, entry.path()));
+    }
+
+    volumes.sort();
+    Ok(volumes)
+}
+
+fn primary_volume(config: &Config) -> Result<&Path> {
+    config
+        .storage_path
+        .volumes()
+        .first()
+        .map(Path::new)
+        .ok_or_else(|| {
+            AppError::BadRequest("storage_path must name at least one volume".to_string())
+        })
+}
+
+fn etags(inventory: Vec<(String, String, String)>) -> HashMap<(String, String), String> {
+    inventory
+        .into_iter()
+        .map(|(bucket, key, etag)| ((bucket, key), etag))
+        .collect()
+}
+
+fn database_path(url: &str) -> Result<PathBuf> {
+    let path = url
+        .strip_prefix("sqlite://")
+        .or_else(|| url.strip_prefix("sqlite:"))
+        .unwrap_or(url);
+    let path = path.split('?').next().unwrap_or_default();
+
+    if path.is_empty() || path == ":memory:" {
+        return Err(AppError::BadRequest(format!(
+            "database_url {} does not point at a file",
+            url
+        )));
+    }
+
+    Ok(PathBuf::from(path))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+fn invalid(reason: impl std::fmt::Display) -> AppError {
+    AppError::BadRequest(format!("invalid backup archive: {}", reason))
+}
+
+async fn stage(
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    path: &Path,
+) -> Result<BackupManifest> {
+    metadata.backup_to(&path.join(DATABASE_NAME)).await?;
+
+    let created_at = Utc::now();
+    let volumes = storage.volume_paths();
+    let (mut files, mut total_size) =
+        link_tree(&volumes[0], &path.join(OBJECTS_DIR), false).await?;
+
+    for (index, volume) in volumes.iter().enumerate().skip(1) {
+        let (count, size) = link_tree(
+            volume,
+            &path.join(VOLUMES_DIR).join(index.to_string()),
+            false,
+        )
+        .await?;
+        files += count;
+        total_size += size;
+    }
+
+    let manifest = BackupManifest {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at,
+        files,
+        total_size,
+    };
+
+    fs::write(
+        path.join(MANIFEST_NAME),
+        serde_json::to_vec_pretty(&manifest).map_err(std::io::Error::other)?,
+    )
+    .await?;
+
+    Ok(manifest)
+}
+
+async fn link_tree(root: &Path, destination: &Path, replace: bool) -> Result<(usize, u64)> {
+    let mut linked = (0, 0);
+
+    for (name, size) in files(root).await? {
+        let target = destination.join(&name);
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        match link(&root.join(&name), &target, replace).await {
+            Ok(()) => {
+                linked.0 += 1;
+                linked.1 += size;
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                tracing::debug!("Blob {} vanished while linking", name.display());
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Ok(linked)
+}
+
+async fn files(root: &Path) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+
+    while let Some(relative) = pending.pop() {
+        let mut entries = fs::read_dir(root.join(&relative)).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+            let name = relative.join(entry.file_name());
+
+            if file_type.is_dir() {
+                if !SKIPPED_DIRS
+                    .iter()
+                    .any(|skipped| entry.file_name() == *skipped)
+                {
+                    pending.push(name);
+                }
+            } else if file_type.is_file() {
+                match entry.metadata().await {
+                    Ok(metadata) => files.push((name, metadata.len())),
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+async fn link(source: &Path, destination: &Path, replace: bool) -> std::io::Result<()> {
+    if replace {
+        match fs::remove_file(destination).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+    }
+
+    if let Err(e) = fs::hard_link(source, destination).await {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            return Err(e);
+        }
+
+        tracing::debug!(
+            "Hard link failed for {}, copying instead: {}",
+            source.display(),
+            e
+        );
+        fs::copy(source, destination).await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,401 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::{
+    auth, backup,
+    compression::AtRestPolicy,
+    error::AppError,
+    export, fsck,
+    handlers::objects::AppState,
+    import, jobs,
+    models::{Config, FsckQuery},
+    storage::{FileStorage, MetadataStore, SqliteMetadataStore, Upstream},
+};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+
+#[derive(Debug, Parser)]
+#[command(name = "lila", version, about = "A small self-hosted object store")]
+pub struct Cli {
+    #[arg(
+        short,
+        long,
+        global = true,
+        env = "LILA_CONFIG",
+        help = "Path to the config file [default: config.toml]"
+    )]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Default, Subcommand)]
+pub enum Command {
+    #[default]
+    #[command(about = "Run the HTTP server (default)")]
+    Serve,
+    #[command(about = "Validate the configuration and print it with secrets redacted")]
+    CheckConfig,
+    #[command(about = "Cross-check object metadata against blobs on disk")]
+    Fsck {
+        #[arg(
+            long,
+            help = "Drop metadata for missing blobs, fix sizes and etags, and move orphaned blobs to .lost+found"
+        )]
+        repair: bool,
+        #[arg(long, help = "Compare sizes only, skipping etag verification")]
+        quick: bool,
+    },
+    #[command(about = "Import every file under a local directory as objects")]
+    Import {
+        #[arg(long, help = "Directory to import")]
+        path: PathBuf,
+        #[arg(long, default_value = "", help = "Key prefix for imported objects")]
+        prefix: String,
+        #[arg(
+            long,
+            help = "Import into this bucket instead of the default namespace"
+        )]
+        bucket: Option<String>,
+        #[arg(long, help = "Leave objects that already exist untouched")]
+        skip_existing: bool,
+    },
+    #[command(about = "Write objects back out as a directory tree, .zip or .tar.gz")]
+    Export {
+        #[arg(
+            long,
+            default_value = "",
+            help = "Only export keys starting with this prefix"
+        )]
+        prefix: String,
+        #[arg(
+            long,
+            help = "Destination directory, or archive path ending in .zip, .tar.gz or .tgz"
+        )]
+        out: PathBuf,
+        #[arg(
+            long,
+            help = "Export from this bucket instead of the default namespace"
+        )]
+        bucket: Option<String>,
+    },
+    #[command(
+        about = "Restore the database and blobs from a backup archive (stop the server first)"
+    )]
+    Restore {
+        #[arg(help = "Backup archive produced by /api/v1/admin/backup")]
+        archive: PathBuf,
+        #[arg(
+            long,
+            help = "Validate the archive and report changes without applying them"
+        )]
+        dry_run: bool,
+    },
+    #[command(about = "Remove blobs that are not referenced by any object")]
+    Gc {
+        #[arg(long, help = "Report orphaned blobs without removing them")]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "Only remove blobs older than this [default: gc_min_age_minutes]"
+        )]
+        min_age_minutes: Option<u64>,
+    },
+    #[command(about = "List objects on a running server")]
+    Ls {
+        #[arg(
+            default_value = ":",
+            help = "Prefix to list, as PREFIX or BUCKET:PREFIX"
+        )]
+        path: String,
+        #[arg(
+            short,
+            long,
+            help = "List every key under the prefix instead of one level"
+        )]
+        recursive: bool,
+        #[arg(short, long, help = "Show size and modification time")]
+        long: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+    #[command(about = "Copy files to, from or between objects on a running server")]
+    Cp {
+        #[arg(help = "Local path, - for stdin, or [BUCKET]:KEY")]
+        source: String,
+        #[arg(help = "Local path, - for stdout, or [BUCKET]:KEY")]
+        destination: String,
+        #[arg(short, long, help = "Copy a directory or every key under a prefix")]
+        recursive: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+    #[command(about = "Delete objects on a running server")]
+    Rm {
+        #[arg(required = true, help = "Keys to delete, as KEY or BUCKET:KEY")]
+        paths: Vec<String>,
+        #[arg(short, long, help = "Delete every key under each prefix")]
+        recursive: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+    #[command(about = "Print the metadata of an object on a running server")]
+    Stat {
+        #[arg(help = "Key to inspect, as KEY or BUCKET:KEY")]
+        path: String,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+    #[cfg(feature = "fuse")]
+    #[command(about = "Mount the key namespace of a running server as a filesystem")]
+    Mount {
+        #[arg(help = "Directory to mount on")]
+        mountpoint: PathBuf,
+        #[arg(long, help = "Mount this bucket instead of the default namespace")]
+        bucket: Option<String>,
+        #[arg(long, help = "Mount read-only")]
+        read_only: bool,
+        #[arg(
+            long,
+            help = "Let other users access the mount (needs user_allow_other)"
+        )]
+        allow_other: bool,
+        #[command(flatten)]
+        remote: RemoteArgs,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct RemoteArgs {
+    #[arg(
+        long,
+        env = "LILA_ENDPOINT",
+        default_value = "http://localhost:3000",
+        help = "Base URL of the server"
+    )]
+    pub endpoint: String,
+    #[arg(
+        long,
+        env = "LILA_TOKEN",
+        hide_env_values = true,
+        help = "Bearer token used to authenticate"
+    )]
+    pub token: Option<String>,
+}
+
+impl Cli {
+    pub fn config_path(&self) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        match &self.config {
+            Some(path) if !path.exists() => {
+                Err(format!("config file {} does not exist", path.display()).into())
+            }
+            Some(path) => Ok(path.clone()),
+            None => Ok(PathBuf::from(DEFAULT_CONFIG_PATH)),
+        }
+    }
+}
+
+pub fn check_config(path: &Path, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    config.validate()?;
+
+    if path.exists() {
+        println!("Loaded {}", path.display());
+    } else {
+        println!("{} not found, using defaults", path.display());
+    }
+
+    println!("{}", toml::to_string_pretty(&config.sanitized())?);
+    println!("Configuration OK");
+
+    Ok(())
+}
+
+pub async fn fsck(config: &Config, options: FsckQuery) -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+    let (metadata, storage) = open_stores(config).await?;
+
+    let report = fsck::run(&metadata, &storage, &options).await;
+    metadata.close().await;
+    let report = report?;
+
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    if !options.repair && report.problems() > 0 {
+        return Err(format!(
+            "found {} problems, rerun with --repair to fix them",
+            report.problems()
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+pub fn init_tracing() {
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "lila=info".into()),
+        )
+        .init();
+}
+
+pub async fn gc(
+    config: &Config,
+    min_age: Duration,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+    let (metadata, storage) = open_stores(config).await?;
+
+    let report = jobs::gc::collect(&metadata, &storage, min_age, dry_run).await;
+    metadata.close().await;
+
+    println!("{}", serde_json::to_string_pretty(&report?)?);
+
+    Ok(())
+}
+
+pub async fn restore(
+    config: &Config,
+    archive: &Path,
+    dry_run: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+
+    let report = backup::restore(config, archive, dry_run).await?;
+    println!("{}", serde_json::to_string_pretty(&report)?);
+
+    Ok(())
+}
+
+pub async fn import(
+    config: &Config,
+    path: &Path,
+    prefix: &str,
+    bucket: Option<&str>,
+    skip_existing: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+    let (metadata, storage) = open_stores(config).await?;
+    let state = AppState::new(config, metadata.clone(), storage, auth::generate_token());
+
+    let response = async {
+        let state = scope(state, bucket).await?;
+        import::import_directory(&state, path, prefix, skip_existing).await
+    }
+    .await;
+    metadata.close().await;
+    let response = response?;
+
+    println!("{}", serde_json::to_string_pretty(&response)?);
+
+    if !response.failed.is_empty() {
+        return Err(format!("{} files failed to import", response.failed.len()).into());
+    }
+
+    Ok(())
+}
+
+pub async fn export(
+    config: &Config,
+    prefix: &str,
+    out: &Path,
+    bucket: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    init_tracing();
+    let (metadata, storage) = open_stores(config).await?;
+    let state = AppState::new(config, metadata.clone(), storage, auth::generate_token());
+
+    let response = async {
+        let state = scope(state, bucket).await?;
+        export::export(&state, prefix, out).await
+    }
+    .await;
+    metadata.close().await;
+    let response = response?;
+
+    println!("{}", serde_json::to_string_pretty(&response)?);
+
+    if !response.failed.is_empty() {
+        return Err(format!("{} objects failed to export", response.failed.len()).into());
+    }
+
+    Ok(())
+}
+
+async fn scope(state: AppState, bucket: Option<&str>) -> Result<AppState, AppError> {
+    match bucket {
+        Some(name) => match state.metadata.get_bucket(name).await? {
+            Some(bucket) => Ok(state.scoped(bucket)),
+            None => Err(AppError::BucketNotFound(name.to_string())),
+        },
+        None => Ok(state),
+    }
+}
+
+async fn open_stores(
+    config: &Config,
+) -> Result<(MetadataStore, FileStorage), Box<dyn std::error::Error>> {
+    let metadata: MetadataStore =
+        Arc::new(SqliteMetadataStore::new(&config.database_url, &config.database).await?);
+    let mut storage = FileStorage::new(
+        config.storage_path.volumes(),
+        config.etag_algorithm,
+        config.disk_reserve_mb * 1024 * 1024,
+    )
+    .await?
+    .with_durability(config.durability)
+    .with_buffer_sizes(
+        config.streaming.read_buffer_kb * 1024,
+        config.streaming.write_buffer_kb * 1024,
+    );
+
+    if let Some(policy) = AtRestPolicy::new(&config.compression) {
+        tracing::info!("Compressing blobs at rest with zstd level {}", policy.level);
+        storage = storage.with_compression(policy);
+    }
+
+    if config.dedup {
+        tracing::info!("Deduplicating blobs by content hash");
+        storage = storage.with_dedup(metadata.clone());
+    }
+
+    if let Some(chunking) = &config.chunking {
+        if config.upstream.is_some() {
+            tracing::warn!(
+                "Chunked storage is not supported with an upstream mirror, storing objects whole"
+            );
+        } else {
+            tracing::info!(
+                "Splitting objects over {} MB into {} MB chunks",
+                chunking.threshold_mb,
+                chunking.chunk_size_mb
+            );
+            storage = storage.with_chunking(chunking.clone());
+        }
+    }
+
+    if let Some(tiering) = &config.tiering {
+        storage = storage.with_cold_tier(&tiering.cold_path).await?;
+        tracing::info!("Cold storage tier at {}", tiering.cold_path);
+    }
+
+    if let Some(upstream) = &config.upstream {
+        storage = storage.with_upstream(Upstream::new(upstream)?);
+        tracing::info!(
+            "Mirroring blobs to upstream bucket {} at {}",
+            upstream.bucket,
+            upstream.endpoint
+        );
+    }
+
+    Ok((metadata, storage))
+}
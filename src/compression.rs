@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use axum::{
+    body::HttpBody,
+    http::{Response, StatusCode, header},
+};
+use tower_http::compression::Predicate;
+
+use crate::models::CompressionConfig;
+
+#[derive(Clone)]
+pub struct CompressionPolicy {
+    enabled: bool,
+    min_size: u64,
+    skip_content_types: Arc<[String]>,
+}
+
+impl CompressionPolicy {
+    pub fn new(config: &CompressionConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            min_size: config.min_size,
+            skip_content_types: config.skip_content_types.clone().into(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct AtRestPolicy {
+    pub level: i32,
+    min_size: u64,
+    skip_content_types: Arc<[String]>,
+}
+
+impl AtRestPolicy {
+    pub fn new(config: &CompressionConfig) -> Option<Self> {
+        config.at_rest.then(|| Self {
+            level: config.at_rest_level,
+            min_size: config.min_size,
+            skip_content_types: config.skip_content_types.clone().into(),
+        })
+    }
+
+    pub fn applies(&self, content_type: &str, size: u64) -> bool {
+        size >= self.min_size
+            && !self
+                .skip_content_types
+                .iter()
+                .any(|skip| content_type.starts_with(skip.as_str()))
+    }
+}
+
+impl Predicate for CompressionPolicy {
+    fn should_compress<B>(&self, response: &Response<B>) -> bool
+    where
+        B: HttpBody,
+    {
+        if !self.enabled || response.status() == StatusCode::PARTIAL_CONTENT {
+            return false;
+        }
+
+        let size = response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .or_else(|| response.body().size_hint().exact());
+
+        if size.is_some_and(|size| size < self.min_size) {
+            return false;
+        }
+
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("");
+
+        !self
+            .skip_content_types
+            .iter()
+            .any(|skip| content_type.starts_with(skip.as_str()))
+    }
+}
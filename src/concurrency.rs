@@ -0,0 +1,62 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::{
+    error::{AppError, Result},
+    models::ConcurrencyConfig,
+};
+
+#[derive(Clone, Default)]
+pub struct Limits {
+    uploads: Option<Arc<Semaphore>>,
+    bulk: Option<Arc<Semaphore>>,
+    queue_timeout: Duration,
+}
+
+impl Limits {
+    pub fn new(config: &ConcurrencyConfig) -> Self {
+        let semaphore = |limit: Option<usize>| limit.map(|n| Arc::new(Semaphore::new(n.max(1))));
+
+        Self {
+            uploads: semaphore(config.max_uploads),
+            bulk: semaphore(config.max_bulk_operations),
+            queue_timeout: Duration::from_secs(config.queue_timeout_seconds),
+        }
+    }
+
+    pub async fn upload(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        self.acquire(&self.uploads, "upload").await
+    }
+
+    pub async fn bulk(&self) -> Result<Option<OwnedSemaphorePermit>> {
+        self.acquire(&self.bulk, "bulk operation").await
+    }
+
+    async fn acquire(
+        &self,
+        semaphore: &Option<Arc<Semaphore>>,
+        kind: &str,
+    ) -> Result<Option<OwnedSemaphorePermit>> {
+        let Some(semaphore) = semaphore else {
+            return Ok(None);
+        };
+
+        if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            return Ok(Some(permit));
+        }
+
+        tracing::debug!("Waiting for a free {} slot", kind);
+        metrics::counter!("lila_concurrency_queued_total", "kind" => kind.to_string()).increment(1);
+
+        match tokio::time::timeout(self.queue_timeout, semaphore.clone().acquire_owned()).await {
+            Ok(permit) => Ok(Some(permit.map_err(|_| AppError::Internal)?)),
+            Err(_) => {
+                tracing::warn!("No {} slot freed up within {:?}", kind, self.queue_timeout);
+                metrics::counter!("lila_concurrency_rejected_total", "kind" => kind.to_string())
+                    .increment(1);
+                Err(AppError::TooManyRequests(1))
+            }
+        }
+    }
+}
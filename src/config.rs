@@ -1,29 +1,98 @@
-use std::{fs, path::Path};
+use std::path::Path;
 
-use crate::models::Config;
+use figment::{
+    Figment,
+    providers::{Env, Format, Toml},
+};
+
+use crate::{
+    jobs,
+    models::{Config, ListenerConfig, Mode},
+    rate_limit,
+};
+
+const ENV_PREFIX: &str = "LILA_";
 
 const DEFAULT_CONFIG: &str = r#"server_host = "127.0.0.1"
 server_port = 3000
 storage_path = "./data/objects"
 database_url = "sqlite:./data/metadata.db"
-auth_token = "owo"
 max_upload_size_mb = 100
 "#;
 
 impl Config {
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
         dotenvy::dotenv().ok();
 
-        let path = Path::new("config.toml");
+        let mut figment = Figment::from(Toml::string(DEFAULT_CONFIG));
 
-        if !path.exists() {
-            fs::write(path, DEFAULT_CONFIG)?;
-            tracing::info!("Created default config.toml");
+        if path.exists() {
+            figment = figment.merge(Toml::file(path));
         }
 
-        let config_str = fs::read_to_string(path)?;
+        let config = figment
+            .merge(Env::prefixed(ENV_PREFIX).split("__"))
+            .extract()?;
 
-        let config: Config = toml::from_str(&config_str)?;
         Ok(config)
     }
+
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        for limit in [self.rate_limit, self.rate_limit_read, self.rate_limit_write]
+            .iter()
+            .flatten()
+        {
+            rate_limit::validate(limit)?;
+        }
+
+        if self.tls_cert_path.is_some() != self.tls_key_path.is_some() {
+            return Err("tls_cert_path and tls_key_path must be set together".into());
+        }
+
+        if self.tls_redirect_port.is_some() && self.tls_cert_path.is_none() {
+            return Err("tls_redirect_port requires tls_cert_path and tls_key_path".into());
+        }
+
+        if self.mode == Mode::Replica && self.replication.is_some() {
+            return Err("replication cannot be configured in replica mode".into());
+        }
+
+        if let Some(maintenance) = &self.maintenance {
+            jobs::maintenance::parse_schedule(&maintenance.schedule)?;
+        }
+
+        for listener in &self.listeners {
+            if let Some(route) = listener.routes.iter().find(|route| !route.starts_with('/')) {
+                return Err(format!(
+                    "listener {} route {} must start with '/'",
+                    listener.address, route
+                )
+                .into());
+            }
+        }
+
+        if self.s3.enabled && self.s3.secret_key.is_none() && self.auth_token.is_none() {
+            return Err("s3.secret_key must be set when auth_token is not configured".into());
+        }
+
+        if let Some(otlp) = &self.otlp
+            && !(0.0..=1.0).contains(&otlp.sample_ratio)
+        {
+            return Err("otlp.sample_ratio must be between 0 and 1".into());
+        }
+
+        Ok(())
+    }
+
+    pub fn listeners(&self) -> Vec<ListenerConfig> {
+        if !self.listeners.is_empty() {
+            return self.listeners.clone();
+        }
+
+        vec![ListenerConfig {
+            address: format!("{}:{}", self.server_host, self.server_port),
+            routes: Vec::new(),
+            tls: true,
+        }]
+    }
 }
@@ -1,9 +1,9 @@
+use crate::{models::ErrorResponse, request_id};
 use axum::{
     Json,
-    http::StatusCode,
+    http::{StatusCode, header},
     response::{IntoResponse, Response},
 };
-use serde_json::json;
 
 #[derive(Debug, thiserror::Error)]
 pub enum AppError {
@@ -16,24 +16,111 @@ pub enum AppError {
     #[error("Object not found: {0}")]
     NotFound(String),
 
+    #[error("Upload not found: {0}")]
+    UploadNotFound(String),
+
+    #[error("Bucket not found: {0}")]
+    BucketNotFound(String),
+
+    #[error("Token not found: {0}")]
+    TokenNotFound(String),
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Object is under retention until {1}: {0}")]
+    RetentionLocked(String, chrono::DateTime<chrono::Utc>),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
+    #[error("Unsupported media type: {0}")]
+    UnsupportedMediaType(String),
+
     #[error("Unauthorized")]
     Unauthorized,
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Payload exceeds maximum allowed size: {0} bytes")]
     PayloadTooLarge(usize),
 
-    #[allow(dead_code)]
+    #[error("Range not satisfiable for object of {0} bytes")]
+    RangeNotSatisfiable(i64),
+
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+
+    #[error("Checksum mismatch: {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Too many requests, retry in {0} seconds")]
+    TooManyRequests(u64),
+
+    #[error("Insufficient storage: {0}")]
+    InsufficientStorage(String),
+
+    #[error("Server is in read-only mode")]
+    ReadOnly(u64),
+
+    #[error("Upstream error: {0}")]
+    Upstream(String),
+
+    #[error("Request timeout: {0}")]
+    RequestTimeout(String),
+
     #[error("Internal server error")]
     Internal,
 }
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let content_range = match &self {
+            AppError::RangeNotSatisfiable(size) => Some(format!("bytes */{}", size)),
+            _ => None,
+        };
+
+        let retry_after = match &self {
+            AppError::TooManyRequests(seconds) | AppError::ReadOnly(seconds) => {
+                Some(seconds.to_string())
+            }
+            _ => None,
+        };
+
         let (status, message) = match self {
             AppError::NotFound(key) => {
                 (StatusCode::NOT_FOUND, format!("Object not found: {}", key))
             }
+            AppError::UploadNotFound(id) => {
+                (StatusCode::NOT_FOUND, format!("Upload not found: {}", id))
+            }
+            AppError::BucketNotFound(name) => {
+                (StatusCode::NOT_FOUND, format!("Bucket not found: {}", name))
+            }
+            AppError::TokenNotFound(id) => {
+                (StatusCode::NOT_FOUND, format!("Token not found: {}", id))
+            }
+            AppError::BadRequest(reason) => {
+                (StatusCode::BAD_REQUEST, format!("Bad request: {}", reason))
+            }
+            AppError::RetentionLocked(key, until) => (
+                StatusCode::LOCKED,
+                format!(
+                    "Object is under retention until {}: {}",
+                    until.to_rfc3339(),
+                    key
+                ),
+            ),
+            AppError::Conflict(reason) => (StatusCode::CONFLICT, format!("Conflict: {}", reason)),
+            AppError::UnsupportedMediaType(content_type) => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                format!("Unsupported media type: {}", content_type),
+            ),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()),
+            AppError::Forbidden(reason) => {
+                (StatusCode::FORBIDDEN, format!("Forbidden: {}", reason))
+            }
             AppError::Database(e) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 format!("Database error: {}", e),
@@ -46,21 +133,65 @@ impl IntoResponse for AppError {
                 StatusCode::PAYLOAD_TOO_LARGE,
                 format!("Payload exceeds maximum allowed size: {} bytes", limit),
             ),
+            AppError::RangeNotSatisfiable(size) => (
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                format!("Range not satisfiable for object of {} bytes", size),
+            ),
+            AppError::PreconditionFailed(reason) => (
+                StatusCode::PRECONDITION_FAILED,
+                format!("Precondition failed: {}", reason),
+            ),
+            AppError::ChecksumMismatch(algorithm) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Checksum mismatch: uploaded content does not match the supplied {} digest",
+                    algorithm
+                ),
+            ),
+            AppError::TooManyRequests(seconds) => (
+                StatusCode::TOO_MANY_REQUESTS,
+                format!("Too many requests, retry in {} seconds", seconds),
+            ),
+            AppError::InsufficientStorage(reason) => (
+                StatusCode::INSUFFICIENT_STORAGE,
+                format!("Insufficient storage: {}", reason),
+            ),
+            AppError::ReadOnly(_) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Server is in read-only mode".to_string(),
+            ),
+            AppError::Upstream(reason) => (
+                StatusCode::BAD_GATEWAY,
+                format!("Upstream error: {}", reason),
+            ),
+            AppError::RequestTimeout(reason) => (
+                StatusCode::REQUEST_TIMEOUT,
+                format!("Request timeout: {}", reason),
+            ),
             AppError::Internal => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "Internal server error".to_string(),
             ),
         };
 
-        (
-            status,
-            Json(json!({
-                "error": message,
-                "server": "lila",
-                "author": "april"
-            })),
-        )
-            .into_response()
+        let body = ErrorResponse {
+            error: message,
+            server: "lila",
+            author: "april",
+            request_id: request_id::current(),
+        };
+
+        let mut response = (status, Json(body)).into_response();
+
+        if let Some(value) = content_range.and_then(|v| v.parse().ok()) {
+            response.headers_mut().insert(header::CONTENT_RANGE, value);
+        }
+
+        if let Some(value) = retry_after.and_then(|v| v.parse().ok()) {
+            response.headers_mut().insert(header::RETRY_AFTER, value);
+        }
+
+        response
     }
 }
 
@@ -0,0 +1,108 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::broadcast;
+
+use crate::{
+    error::Result,
+    models::{EventOp, ObjectEvent, ObjectMetadata},
+    storage::MetadataStore,
+};
+
+const BACKLOG_SIZE: usize = 4096;
+
+pub struct Replay {
+    pub events: Vec<ObjectEvent>,
+    pub missed: u64,
+    pub receiver: broadcast::Receiver<ObjectEvent>,
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ObjectEvent>,
+    backlog: Arc<Mutex<Backlog>>,
+    order: Arc<tokio::sync::Mutex<()>>,
+}
+
+#[derive(Default)]
+struct Backlog {
+    seq: u64,
+    events: VecDeque<ObjectEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(BACKLOG_SIZE);
+
+        Self {
+            sender,
+            backlog: Arc::default(),
+            order: Arc::default(),
+        }
+    }
+}
+
+impl EventBus {
+    pub fn resume(&self, seq: u64) {
+        self.backlog.lock().unwrap().seq = seq;
+    }
+
+    pub async fn record(
+        &self,
+        metadata: &MetadataStore,
+        op: EventOp,
+        object: &ObjectMetadata,
+    ) -> Result<ObjectEvent> {
+        let _order = self.order.lock().await;
+        let event = metadata.record_change(op, object).await?;
+        self.publish(event.clone());
+        Ok(event)
+    }
+
+    fn publish(&self, event: ObjectEvent) {
+        let mut backlog = self.backlog.lock().unwrap();
+        backlog.seq = event.seq;
+
+        if backlog.events.len() == BACKLOG_SIZE {
+            backlog.events.pop_front();
+        }
+        backlog.events.push_back(event.clone());
+
+        let _ = self.sender.send(event);
+    }
+
+    pub fn seq(&self) -> u64 {
+        self.backlog.lock().unwrap().seq
+    }
+
+    pub fn subscribe(&self, since: Option<u64>) -> Replay {
+        let backlog = self.backlog.lock().unwrap();
+        let receiver = self.sender.subscribe();
+
+        let Some(since) = since else {
+            return Replay {
+                events: Vec::new(),
+                missed: 0,
+                receiver,
+            };
+        };
+
+        let oldest = backlog
+            .events
+            .front()
+            .map_or(backlog.seq + 1, |event| event.seq);
+
+        Replay {
+            events: backlog
+                .events
+                .iter()
+                .filter(|event| event.seq > since)
+                .cloned()
+                .collect(),
+            missed: oldest.saturating_sub(since + 1),
+            receiver,
+        }
+    }
+}
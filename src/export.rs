@@ -0,0 +1,125 @@
+use std::path::Path;
+
+use tokio::{fs, io::AsyncWriteExt};
+
+use crate::{
+    error::{AppError, Result},
+    handlers::{archive, objects::AppState},
+    models::{ExportResponse, ImportFailure, ObjectMetadata},
+};
+
+enum Format {
+    Directory,
+    Zip,
+    TarGz,
+}
+
+impl Format {
+    fn detect(path: &Path) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        if name.ends_with(".zip") {
+            Format::Zip
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Format::TarGz
+        } else {
+            Format::Directory
+        }
+    }
+}
+
+pub async fn export(state: &AppState, prefix: &str, destination: &Path) -> Result<ExportResponse> {
+    tracing::info!("Exporting prefix {:?} to {}", prefix, destination.display());
+
+    let objects = archive::live_objects(state, prefix).await?;
+
+    let mut response = ExportResponse {
+        prefix: prefix.to_string(),
+        destination: destination.display().to_string(),
+        exported: 0,
+        total_size: 0,
+        failed: Vec::new(),
+    };
+
+    match Format::detect(destination) {
+        Format::Directory => {
+            fs::create_dir_all(destination).await?;
+
+            for obj in &objects {
+                match export_object(state, obj, destination).await {
+                    Ok(()) => {
+                        response.exported += 1;
+                        response.total_size += obj.size;
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to export {}: {}", obj.key, e);
+                        response.failed.push(ImportFailure {
+                            path: obj.key.clone(),
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        format => {
+            if let Some(parent) = destination.parent() {
+                fs::create_dir_all(parent).await?;
+            }
+
+            let file = create_new(destination).await?;
+
+            match format {
+                Format::Zip => archive::write_zip(state, "", &objects, file).await?,
+                _ => archive::write_tar_gz(state, "", &objects, file).await?,
+            }
+
+            response.exported = objects.len();
+            response.total_size = objects.iter().map(|obj| obj.size).sum();
+        }
+    }
+
+    tracing::info!(
+        "Exported {} objects ({} bytes), {} failed",
+        response.exported,
+        response.total_size,
+        response.failed.len()
+    );
+
+    Ok(response)
+}
+
+async fn export_object(state: &AppState, obj: &ObjectMetadata, root: &Path) -> Result<()> {
+    let relative = archive::entry_key("", &obj.key)
+        .ok_or_else(|| AppError::BadRequest(format!("{} is not a valid path", obj.key)))?;
+    let path = root.join(relative);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    let mut source = state.storage.reader(obj, 0).await?;
+    let mut file = create_new(&path).await?;
+
+    tokio::io::copy(&mut source, &mut file).await?;
+    file.flush().await?;
+
+    Ok(())
+}
+
+pub async fn create_new(path: &Path) -> Result<fs::File> {
+    match fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .await
+    {
+        Ok(file) => Ok(file),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Err(AppError::Conflict(
+            format!("{} already exists", path.display()),
+        )),
+        Err(e) => Err(AppError::Io(e)),
+    }
+}
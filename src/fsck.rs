@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+
+use crate::{
+    error::Result,
+    models::{FsckIssue, FsckQuery, FsckReport, ListCursor, ObjectMetadata, SortField, SortOrder},
+    storage::{FileStorage, MetadataStore},
+};
+
+const PAGE_SIZE: i64 = 1000;
+
+pub async fn run(
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    options: &FsckQuery,
+) -> Result<FsckReport> {
+    tracing::info!(
+        "Starting consistency check (repair: {}, quick: {})",
+        options.repair,
+        options.quick
+    );
+
+    let mut report = FsckReport::default();
+
+    check_scope("", metadata, storage, options, &mut report).await?;
+
+    for bucket in metadata.list_buckets().await? {
+        check_scope(
+            &bucket.name,
+            &metadata.scoped(&bucket.name),
+            &storage.scoped(&bucket.name),
+            options,
+            &mut report,
+        )
+        .await?;
+    }
+
+    tracing::info!(
+        "Consistency check finished: {} objects, {} blobs, {} problems, {} repaired",
+        report.objects_checked,
+        report.blobs_checked,
+        report.problems(),
+        report.repaired
+    );
+
+    Ok(report)
+}
+
+async fn check_scope(
+    bucket: &str,
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    options: &FsckQuery,
+    report: &mut FsckReport,
+) -> Result<()> {
+    let mut referenced = HashSet::new();
+    let mut cursor = None;
+
+    loop {
+        let page = metadata
+            .list(
+                None,
+                cursor.as_ref(),
+                SortField::Key,
+                SortOrder::Asc,
+                Some(PAGE_SIZE),
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        cursor = Some(ListCursor::after(last, SortField::Key));
+        let exhausted = (page.len() as i64) < PAGE_SIZE;
+
+        for object in page {
+            referenced.insert(storage.get_object_path(&object.key).await);
+            check_object(bucket, object, metadata, storage, options, report).await?;
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    for blob in storage.list_blobs().await? {
+        report.blobs_checked += 1;
+
+        if referenced.contains(&blob) {
+            continue;
+        }
+
+        tracing::warn!("Blob {} is not referenced by any object", blob.display());
+        report.orphaned_blobs.push(blob.display().to_string());
+
+        if options.repair {
+            let destination = storage.quarantine(&blob).await?;
+            tracing::info!("Moved orphaned blob to {}", destination.display());
+            report.repaired += 1;
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_object(
+    bucket: &str,
+    object: ObjectMetadata,
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    options: &FsckQuery,
+    report: &mut FsckReport,
+) -> Result<()> {
+    report.objects_checked += 1;
+
+    let issue = |expected: Option<String>, actual: Option<String>| FsckIssue {
+        bucket: bucket.to_string(),
+        key: object.key.clone(),
+        expected,
+        actual,
+    };
+
+    let Some(size) = storage.blob_size(&object.key).await? else {
+        tracing::warn!("Object {} has no blob on disk", object.key);
+        report.missing_blobs.push(issue(None, None));
+
+        if options.repair {
+            metadata.delete(&object.key).await?;
+            report.repaired += 1;
+        }
+        return Ok(());
+    };
+
+    let expected_size = object.stored_size.unwrap_or(object.size);
+    let size_mismatch = size as i64 != expected_size;
+    if size_mismatch {
+        tracing::warn!(
+            "Object {} is {} bytes on disk but {} in metadata",
+            object.key,
+            size,
+            expected_size
+        );
+        report.size_mismatches.push(issue(
+            Some(expected_size.to_string()),
+            Some(size.to_string()),
+        ));
+    }
+
+    let etag = if options.quick {
+        None
+    } else {
+        Some(storage.compute_etag(&object).await?)
+    };
+
+    let etag_mismatch = etag.as_ref().is_some_and(|etag| *etag != object.etag);
+    if etag_mismatch {
+        tracing::warn!("Object {} does not match its stored etag", object.key);
+        report
+            .etag_mismatches
+            .push(issue(Some(object.etag.clone()), etag.clone()));
+    }
+
+    if options.repair && (size_mismatch || etag_mismatch) {
+        let etag = match etag {
+            Some(etag) => etag,
+            None => storage.compute_etag(&object).await?,
+        };
+
+        let repaired = match object.stored_size {
+            Some(_) => ObjectMetadata {
+                stored_size: Some(size as i64),
+                etag,
+                ..object
+            },
+            None => ObjectMetadata {
+                size: size as i64,
+                etag,
+                ..object
+            },
+        };
+
+        metadata.insert(&repaired).await?;
+        report.repaired += 1;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::sync::LazyLock;
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, InputObject, Schema, SimpleObject,
+    http::GraphiQLSource,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::{
+    Json,
+    extract::{Query, State},
+    response::{Html, IntoResponse},
+};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    handlers::{
+        self,
+        objects::{AppState, ListQuery, SearchQuery},
+    },
+    models::{ObjectMetadata, SortField, SortOrder},
+    storage::EtagAlgorithm,
+};
+
+const MAX_DEPTH: usize = 8;
+const MAX_COMPLEXITY: usize = 1000;
+
+pub type LilaSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: LazyLock<LilaSchema> = LazyLock::new(|| {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+});
+
+pub struct QueryRoot;
+
+#[async_graphql::Object(name = "Query")]
+impl QueryRoot {
+    async fn object(
+        &self,
+        ctx: &Context<'_>,
+        key: String,
+    ) -> async_graphql::Result<Option<Object>> {
+        let state = ctx.data::<AppState>()?;
+        Ok(state.metadata.get(&key).await?.map(Object))
+    }
+
+    async fn objects(
+        &self,
+        ctx: &Context<'_>,
+        prefix: Option<String>,
+        delimiter: Option<String>,
+        first: Option<i64>,
+        after: Option<String>,
+        order_by: Option<ObjectOrder>,
+    ) -> async_graphql::Result<ObjectPage> {
+        let state = ctx.data::<AppState>()?.clone();
+        let query = ListQuery {
+            prefix,
+            limit: first,
+            delimiter,
+            cursor: after,
+            sort: order_by.as_ref().map(|order| order.field),
+            order: order_by.and_then(|order| order.direction),
+        };
+
+        let Json(page) = handlers::objects::list_objects(State(state), Query(query)).await?;
+
+        Ok(ObjectPage {
+            objects: page.objects.into_iter().map(Object).collect(),
+            prefixes: page.prefixes,
+            next_cursor: page.next_cursor,
+        })
+    }
+
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: ObjectFilter,
+        first: Option<i64>,
+        order_by: Option<ObjectOrder>,
+    ) -> async_graphql::Result<Vec<Object>> {
+        let state = ctx.data::<AppState>()?.clone();
+        let query = SearchQuery {
+            key: filter.key,
+            key_glob: filter.key_glob,
+            etag: filter.etag,
+            content_type: filter.content_type,
+            min_size: filter.min_size,
+            max_size: filter.max_size,
+            accessed_before: filter.accessed_before,
+            accessed_after: filter.accessed_after,
+            limit: first,
+            sort: order_by.as_ref().map(|order| order.field),
+            order: order_by.and_then(|order| order.direction),
+        };
+
+        let Json(response) = handlers::objects::search_objects(State(state), Query(query)).await?;
+
+        Ok(response.objects.into_iter().map(Object).collect())
+    }
+
+    async fn stats(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] prefix: String,
+    ) -> async_graphql::Result<Stats> {
+        let state = ctx.data::<AppState>()?;
+        let (total_objects, total_size, largest) = state.metadata.get_prefix_stats(&prefix).await?;
+
+        Ok(Stats {
+            prefix,
+            total_objects,
+            total_size,
+            largest: largest.map(Object),
+        })
+    }
+}
+
+pub struct Object(ObjectMetadata);
+
+#[async_graphql::Object]
+impl Object {
+    async fn id(&self) -> &str {
+        &self.0.id
+    }
+
+    async fn key(&self) -> &str {
+        &self.0.key
+    }
+
+    async fn size(&self) -> i64 {
+        self.0.size
+    }
+
+    async fn content_type(&self) -> &str {
+        &self.0.content_type
+    }
+
+    async fn etag(&self) -> &str {
+        &self.0.etag
+    }
+
+    async fn etag_algorithm(&self) -> EtagAlgorithm {
+        self.0.etag_algorithm
+    }
+
+    async fn public(&self) -> bool {
+        self.0.public
+    }
+
+    async fn generation(&self) -> i64 {
+        self.0.generation
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn updated_at(&self) -> Option<DateTime<Utc>> {
+        self.0.updated_at
+    }
+
+    async fn accessed_at(&self) -> Option<DateTime<Utc>> {
+        self.0.accessed_at
+    }
+
+    async fn access_count(&self) -> i64 {
+        self.0.access_count
+    }
+
+    async fn expires_at(&self) -> Option<DateTime<Utc>> {
+        self.0.expires_at
+    }
+
+    async fn retain_until(&self) -> Option<DateTime<Utc>> {
+        self.0.retain_until
+    }
+
+    async fn cache_control(&self) -> Option<&str> {
+        self.0.cache_control.as_deref()
+    }
+
+    async fn stored_size(&self) -> Option<i64> {
+        self.0.stored_size
+    }
+
+    async fn metadata(&self) -> Vec<MetadataEntry> {
+        self.0
+            .user_metadata
+            .iter()
+            .map(|(name, value)| MetadataEntry {
+                name: name.clone(),
+                value: value.clone(),
+            })
+            .collect()
+    }
+
+    async fn metadata_value(&self, name: String) -> Option<&str> {
+        self.0.user_metadata.get(&name).map(String::as_str)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct ObjectPage {
+    objects: Vec<Object>,
+    prefixes: Vec<String>,
+    next_cursor: Option<String>,
+}
+
+#[derive(SimpleObject)]
+pub struct MetadataEntry {
+    name: String,
+    value: String,
+}
+
+#[derive(Default, InputObject)]
+pub struct ObjectFilter {
+    key: Option<String>,
+    key_glob: Option<String>,
+    etag: Option<String>,
+    content_type: Option<String>,
+    min_size: Option<i64>,
+    max_size: Option<i64>,
+    accessed_before: Option<DateTime<Utc>>,
+    accessed_after: Option<DateTime<Utc>>,
+}
+
+#[derive(InputObject)]
+pub struct ObjectOrder {
+    field: SortField,
+    direction: Option<SortOrder>,
+}
+
+#[derive(SimpleObject)]
+pub struct Stats {
+    prefix: String,
+    total_objects: i64,
+    total_size: i64,
+    largest: Option<Object>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/graphql",
+    tag = "graphql",
+    request_body(content = serde_json::Value, description = "GraphQL request with query, variables and operationName"),
+    responses(
+        (status = 200, description = "GraphQL response with data and errors", body = serde_json::Value),
+    )
+)]
+pub async fn graphql(State(state): State<AppState>, request: GraphQLRequest) -> GraphQLResponse {
+    tracing::info!("GRAPHQL request");
+
+    SCHEMA
+        .execute(request.into_inner().data(state))
+        .await
+        .into()
+}
+
+pub async fn graphiql() -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint("/api/v1/graphql").finish())
+}
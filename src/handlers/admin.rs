@@ -0,0 +1,361 @@
+use std::sync::atomic::Ordering;
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{Query, Request, State},
+    http::{Method, StatusCode, Uri, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    auth, backup,
+    error::{AppError, Result},
+    export, fsck,
+    handlers::{archive::PIPE_CAPACITY, objects::AppState},
+    models::{
+        BackupQuery, BackupResponse, Config, ErrorResponse, ExportRequest, ExportResponse,
+        FsckQuery, FsckReport, HealthCheck, HealthResponse, ListJobsResponse, MaintenanceStatus,
+        ReadOnlyMode, ReplicationStatus, TokenScope,
+    },
+    openapi::Binary,
+};
+
+const ADMIN_PREFIX: &str = "/api/v1/admin/";
+const SESSION_PREFIX: &str = "/auth/";
+const READ_ONLY_PATH: &str = "/api/v1/admin/read-only";
+const FSCK_PATH: &str = "/api/v1/admin/fsck";
+const BACKUP_PATH: &str = "/api/v1/admin/backup";
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/config",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Effective configuration with secrets redacted", body = Object),
+    )
+)]
+pub async fn get_config(State(state): State<AppState>) -> Json<Config> {
+    tracing::info!("GET admin config request");
+
+    Json(state.config.sanitized())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/jobs",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Background jobs and their last run", body = ListJobsResponse),
+    )
+)]
+pub async fn list_jobs(State(state): State<AppState>) -> Json<ListJobsResponse> {
+    tracing::info!("LIST admin jobs request");
+
+    let jobs = state.jobs.list();
+    let total = jobs.len();
+
+    Json(ListJobsResponse { jobs, total })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/replication",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Replication progress and lag", body = ReplicationStatus),
+        (status = 404, description = "Replication is not configured", body = ErrorResponse),
+    )
+)]
+pub async fn replication_status(State(state): State<AppState>) -> Result<Json<ReplicationStatus>> {
+    tracing::info!("GET admin replication request");
+
+    state
+        .replication
+        .status(state.events.seq())
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("replication is not configured".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/maintenance",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Database maintenance schedule and last run", body = MaintenanceStatus),
+        (status = 404, description = "Database maintenance is not configured", body = ErrorResponse),
+    )
+)]
+pub async fn maintenance_status(State(state): State<AppState>) -> Result<Json<MaintenanceStatus>> {
+    tracing::info!("GET admin maintenance request");
+
+    let database = state.metadata.database_stats().await?;
+
+    state
+        .maintenance
+        .status(database)
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound("database maintenance is not configured".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/health",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Database, storage and disk health", body = HealthResponse),
+    )
+)]
+pub async fn health(State(state): State<AppState>) -> Json<HealthResponse> {
+    tracing::info!("GET admin health request");
+
+    let database = check(state.metadata.ping().await);
+    let storage = check(state.storage.probe().await);
+
+    let disk = match state.storage.disk_usage().await {
+        Ok(disk) => Some(disk),
+        Err(e) => {
+            tracing::warn!("Failed to read disk usage: {}", e);
+            None
+        }
+    };
+
+    Json(HealthResponse {
+        healthy: database.ok && storage.ok,
+        read_only: state.is_read_only(),
+        database,
+        storage,
+        disk,
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/fsck",
+    tag = "admin",
+    params(FsckQuery),
+    responses(
+        (status = 200, description = "Consistency report", body = FsckReport),
+    )
+)]
+pub async fn fsck(
+    State(state): State<AppState>,
+    Query(query): Query<FsckQuery>,
+) -> Result<Json<FsckReport>> {
+    tracing::info!("POST admin fsck request");
+
+    let report = fsck::run(&state.metadata, &state.storage, &query).await?;
+
+    Ok(Json(report))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/export",
+    tag = "admin",
+    request_body = ExportRequest,
+    responses(
+        (status = 200, description = "Export summary", body = ExportResponse),
+        (status = 404, description = "Bucket not found", body = ErrorResponse),
+    )
+)]
+pub async fn export(
+    State(state): State<AppState>,
+    Json(request): Json<ExportRequest>,
+) -> Result<Json<ExportResponse>> {
+    tracing::info!("POST admin export request to {}", request.destination);
+
+    let state = match request.bucket {
+        Some(name) => match state.metadata.get_bucket(&name).await? {
+            Some(bucket) => state.scoped(bucket),
+            None => return Err(AppError::BucketNotFound(name)),
+        },
+        None => state,
+    };
+
+    let response = export::export(
+        &state,
+        &request.prefix,
+        std::path::Path::new(&request.destination),
+    )
+    .await?;
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/backup",
+    tag = "admin",
+    params(BackupQuery),
+    responses(
+        (status = 200, description = "Backup written to `destination`, or streamed as a tarball when omitted", content(
+            (BackupResponse = "application/json"),
+            (Binary = "application/gzip"),
+        )),
+    )
+)]
+pub async fn backup(
+    State(state): State<AppState>,
+    Query(query): Query<BackupQuery>,
+) -> Result<Response> {
+    tracing::info!("POST admin backup request");
+
+    let snapshot = backup::Snapshot::create(&state.metadata, &state.storage).await?;
+    let manifest = snapshot.manifest.clone();
+
+    if let Some(destination) = query.destination {
+        let result = snapshot.write_to(std::path::Path::new(&destination)).await;
+        snapshot.remove().await;
+        result?;
+
+        tracing::info!("Backup written to {}", destination);
+        return Ok(Json(BackupResponse {
+            destination,
+            manifest,
+        })
+        .into_response());
+    }
+
+    let (writer, reader) = tokio::io::duplex(PIPE_CAPACITY);
+
+    tokio::spawn(async move {
+        let result = snapshot.write_archive(writer).await;
+        snapshot.remove().await;
+
+        match result {
+            Ok(()) => tracing::info!("Backup stream finished"),
+            Err(e) => tracing::error!("Failed to stream backup: {}", e),
+        }
+    });
+
+    let filename = format!(
+        "lila-backup-{}.tar.gz",
+        manifest.created_at.format("%Y%m%dT%H%M%SZ")
+    );
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/gzip")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/read-only",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Whether writes are rejected", body = ReadOnlyMode),
+    )
+)]
+pub async fn get_read_only(State(state): State<AppState>) -> Json<ReadOnlyMode> {
+    Json(ReadOnlyMode {
+        enabled: state.is_read_only(),
+    })
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/admin/read-only",
+    tag = "admin",
+    request_body = ReadOnlyMode,
+    responses(
+        (status = 200, description = "Updated read-only mode", body = ReadOnlyMode),
+        (status = 403, description = "Read-only mode cannot be disabled on a replica", body = ErrorResponse),
+    )
+)]
+pub async fn put_read_only(
+    State(state): State<AppState>,
+    Json(mode): Json<ReadOnlyMode>,
+) -> Result<Json<ReadOnlyMode>> {
+    if state.is_replica() && !mode.enabled {
+        return Err(AppError::Forbidden(
+            "read-only mode cannot be disabled on a replica".to_string(),
+        ));
+    }
+
+    state.read_only.store(mode.enabled, Ordering::Relaxed);
+
+    if mode.enabled {
+        tracing::warn!("Read-only mode enabled");
+    } else {
+        tracing::warn!("Read-only mode disabled");
+    }
+
+    Ok(Json(mode))
+}
+
+pub async fn read_only_guard(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    if state.is_replica() && is_mutation(request.method(), request.uri()) {
+        tracing::debug!(
+            "Rejecting {} {} on a replica",
+            request.method(),
+            request.uri().path()
+        );
+        return Err(AppError::Forbidden(
+            "this server is a read-only replica".to_string(),
+        ));
+    }
+
+    if state.is_read_only() && is_mutation(request.method(), request.uri()) {
+        tracing::debug!(
+            "Rejecting {} {} in read-only mode",
+            request.method(),
+            request.uri().path()
+        );
+        return Err(AppError::ReadOnly(
+            state.config.read_only_retry_after_seconds,
+        ));
+    }
+
+    Ok(next.run(request).await)
+}
+
+fn is_mutation(method: &Method, uri: &Uri) -> bool {
+    let path = uri.path();
+
+    if path.starts_with(SESSION_PREFIX) || (path == READ_ONLY_PATH && *method == Method::PUT) {
+        return false;
+    }
+
+    if !path.starts_with(ADMIN_PREFIX) {
+        return auth::required_scope(method, path) != TokenScope::Read;
+    }
+
+    match *method {
+        Method::GET | Method::HEAD | Method::OPTIONS => false,
+        Method::POST if path == FSCK_PATH => {
+            Query::<FsckQuery>::try_from_uri(uri).map_or(true, |Query(query)| query.repair)
+        }
+        Method::POST if path == BACKUP_PATH => Query::<BackupQuery>::try_from_uri(uri)
+            .map_or(true, |Query(query)| query.destination.is_some()),
+        _ => true,
+    }
+}
+
+fn check(result: Result<()>) -> HealthCheck {
+    match result {
+        Ok(()) => HealthCheck {
+            ok: true,
+            error: None,
+        },
+        Err(e) => {
+            tracing::error!("Health check failed: {}", e);
+            HealthCheck {
+                ok: false,
+                error: Some(e.to_string()),
+            }
+        }
+    }
+}
@@ -0,0 +1,472 @@
+use std::path::Path as FsPath;
+
+use async_compression::tokio::{bufread::GzipDecoder, write::GzipEncoder};
+use async_zip::{
+    Compression, ZipEntryBuilder,
+    tokio::{read::seek::ZipFileReader, write::ZipFileWriter},
+};
+use axum::{
+    Json,
+    body::{Body, Bytes},
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use chrono::Utc;
+use futures_util::{Stream, StreamExt};
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio_util::{
+    compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt},
+    io::ReaderStream,
+};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::{self, AppState},
+    models::{ErrorResponse, ImportFailure, ImportResponse, ObjectMetadata, SortField, SortOrder},
+    openapi::Binary,
+    storage::ExpectedChecksums,
+};
+
+pub const PIPE_CAPACITY: usize = 64 * 1024;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+enum ImportKind {
+    Zip,
+    Tar,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, ToSchema)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "application/zip",
+            ArchiveFormat::TarGz => "application/gzip",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::TarGz => "tar.gz",
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ArchiveQuery {
+    format: Option<ArchiveFormat>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/archive/{prefix}",
+    tag = "archive",
+    params(("prefix" = String, Path, description = "Key prefix, may contain slashes"), ArchiveQuery),
+    responses(
+        (status = 200, description = "Archive of every object under the prefix", body = Binary, content_type = "application/zip"),
+        (status = 404, description = "No objects under the prefix", body = ErrorResponse),
+    )
+)]
+pub async fn download_archive(
+    State(state): State<AppState>,
+    Path(prefix): Path<String>,
+    Query(params): Query<ArchiveQuery>,
+) -> Result<Response> {
+    tracing::info!("ARCHIVE request for prefix: {}", prefix);
+
+    let prefix = if !prefix.ends_with('/') {
+        format!("{}/", prefix)
+    } else {
+        prefix
+    };
+
+    let objects = live_objects(&state, &prefix).await?;
+
+    if objects.is_empty() {
+        return Err(AppError::NotFound(prefix));
+    }
+
+    let format = params.format.unwrap_or_default();
+    tracing::debug!("Archiving {} objects under {}", objects.len(), prefix);
+
+    let filename = format!(
+        "{}.{}",
+        prefix
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or("archive"),
+        format.extension()
+    );
+
+    let permit = state.limits.bulk().await?;
+    let (writer, reader) = tokio::io::duplex(PIPE_CAPACITY);
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let result = match format {
+            ArchiveFormat::Zip => write_zip(&state, &prefix, &objects, writer).await,
+            ArchiveFormat::TarGz => write_tar_gz(&state, &prefix, &objects, writer).await,
+        };
+
+        match result {
+            Ok(()) => tracing::info!("Archive of {} finished", prefix),
+            Err(e) => tracing::error!("Failed to build archive of {}: {}", prefix, e),
+        }
+    });
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, format.content_type())
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename.replace('"', "_")),
+        )
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap())
+}
+
+pub async fn live_objects(state: &AppState, prefix: &str) -> Result<Vec<ObjectMetadata>> {
+    let now = Utc::now();
+
+    Ok(state
+        .metadata
+        .list(
+            Some(prefix),
+            None,
+            SortField::Key,
+            SortOrder::Asc,
+            Some(i64::MAX),
+        )
+        .await?
+        .into_iter()
+        .filter(|obj| obj.expires_at.is_none_or(|at| at > now))
+        .collect())
+}
+
+pub async fn write_zip<W>(
+    state: &AppState,
+    prefix: &str,
+    objects: &[ObjectMetadata],
+    writer: W,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut zip = ZipFileWriter::with_tokio(writer);
+
+    for obj in objects {
+        let file = state.storage.reader(obj, 0).await?;
+        let entry = ZipEntryBuilder::new(entry_name(prefix, obj).into(), Compression::Deflate);
+
+        let mut entry_writer = zip.write_entry_stream(entry).await.map_err(zip_error)?;
+        futures_util::io::copy(file.compat(), &mut entry_writer).await?;
+        entry_writer.close().await.map_err(zip_error)?;
+    }
+
+    zip.close()
+        .await
+        .map_err(zip_error)?
+        .into_inner()
+        .shutdown()
+        .await?;
+    Ok(())
+}
+
+pub async fn write_tar_gz<W>(
+    state: &AppState,
+    prefix: &str,
+    objects: &[ObjectMetadata],
+    writer: W,
+) -> Result<()>
+where
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let mut tar = tokio_tar::Builder::new(GzipEncoder::new(writer));
+
+    for obj in objects {
+        let file = state.storage.reader(obj, 0).await?;
+
+        let mut header = tokio_tar::Header::new_gnu();
+        header.set_size(obj.size as u64);
+        header.set_mode(0o644);
+        header.set_mtime(obj.created_at.timestamp().max(0) as u64);
+        header.set_cksum();
+
+        tar.append_data(&mut header, entry_name(prefix, obj), file)
+            .await?;
+    }
+
+    tar.into_inner().await?.shutdown().await?;
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/import/{prefix}",
+    tag = "archive",
+    params(("prefix" = String, Path, description = "Key prefix, may contain slashes")),
+    request_body(content = Binary, content_type = "application/zip", description = "Zip or gzipped tar archive"),
+    responses(
+        (status = 200, description = "Import summary", body = ImportResponse),
+        (status = 415, description = "Unsupported archive format", body = ErrorResponse),
+    )
+)]
+pub async fn import_archive(
+    State(state): State<AppState>,
+    Path(prefix): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<ImportResponse>> {
+    tracing::info!("IMPORT request for prefix: {}", prefix);
+
+    let prefix = if !prefix.ends_with('/') {
+        format!("{}/", prefix)
+    } else {
+        prefix
+    };
+
+    let kind = import_kind(&headers)?;
+
+    let _permit = state.limits.bulk().await?;
+    let staging_id = Uuid::new_v4().to_string();
+    let max_size = state.max_upload_size * 1024 * 1024;
+    let path = state
+        .storage
+        .stage_upload(&staging_id, body.into_data_stream(), max_size)
+        .await?;
+
+    tracing::debug!("Archive staged as {}", staging_id);
+
+    let mut response = ImportResponse {
+        prefix: prefix.clone(),
+        imported: 0,
+        skipped: 0,
+        total_size: 0,
+        failed: Vec::new(),
+    };
+
+    let result = match kind {
+        ImportKind::Zip => import_zip(&state, &path, &mut response).await,
+        ImportKind::Tar => import_tar(&state, &path, &mut response).await,
+    };
+
+    state.storage.remove_upload(&staging_id).await?;
+    result?;
+
+    tracing::info!(
+        "Imported {} objects ({} bytes) under {}, {} failed",
+        response.imported,
+        response.total_size,
+        prefix,
+        response.failed.len()
+    );
+
+    Ok(Json(response))
+}
+
+fn import_kind(headers: &HeaderMap) -> Result<ImportKind> {
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    match content_type.split(';').next().unwrap_or("").trim() {
+        "application/zip" | "application/x-zip-compressed" => Ok(ImportKind::Zip),
+        "application/x-tar"
+        | "application/gzip"
+        | "application/x-gzip"
+        | "application/x-gtar"
+        | "application/x-compressed-tar" => Ok(ImportKind::Tar),
+        other => Err(AppError::UnsupportedMediaType(format!(
+            "expected a zip or tar archive, got {:?}",
+            other
+        ))),
+    }
+}
+
+async fn import_zip(state: &AppState, path: &FsPath, response: &mut ImportResponse) -> Result<()> {
+    let file = tokio::fs::File::open(path).await?;
+    let mut zip = ZipFileReader::with_tokio(BufReader::new(file))
+        .await
+        .map_err(|e| AppError::BadRequest(format!("invalid zip archive: {}", e)))?;
+
+    let names: Vec<Option<String>> = zip
+        .file()
+        .entries()
+        .iter()
+        .map(|entry| match entry.dir() {
+            Ok(false) => entry.filename().as_str().ok().map(String::from),
+            _ => None,
+        })
+        .collect();
+
+    for (index, name) in names.into_iter().enumerate() {
+        let Some(name) = name else {
+            continue;
+        };
+
+        let result = match zip.reader_without_entry(index).await {
+            Ok(reader) => {
+                let stream = ReaderStream::new(reader.compat());
+                import_entry(state, &response.prefix, &name, stream).await
+            }
+            Err(e) => Err(zip_error(e)),
+        };
+
+        record(response, name, result);
+    }
+
+    Ok(())
+}
+
+async fn import_tar(state: &AppState, path: &FsPath, response: &mut ImportResponse) -> Result<()> {
+    let mut file = BufReader::new(tokio::fs::File::open(path).await?);
+
+    let gzipped = file.fill_buf().await?.starts_with(&GZIP_MAGIC);
+
+    let reader: Box<dyn AsyncRead + Unpin + Send> = if gzipped {
+        Box::new(GzipDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+
+    let mut archive = tokio_tar::Archive::new(reader);
+    let mut entries = archive
+        .entries()
+        .map_err(|e| AppError::BadRequest(format!("invalid tar archive: {}", e)))?;
+
+    while let Some(entry) = entries.next().await {
+        let mut entry =
+            entry.map_err(|e| AppError::BadRequest(format!("invalid tar archive: {}", e)))?;
+
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let name = match entry.path() {
+            Ok(path) => path.to_string_lossy().into_owned(),
+            Err(e) => {
+                record(response, "<invalid path>".to_string(), Err(AppError::Io(e)));
+                continue;
+            }
+        };
+
+        let stream = ReaderStream::new(&mut entry);
+        let result = import_entry(state, &response.prefix, &name, stream).await;
+
+        record(response, name, result);
+    }
+
+    Ok(())
+}
+
+pub async fn import_entry<S>(
+    state: &AppState,
+    prefix: &str,
+    name: &str,
+    stream: S,
+) -> Result<ObjectMetadata>
+where
+    S: Stream<Item = std::io::Result<Bytes>> + Unpin,
+{
+    let key = entry_key(prefix, name)
+        .ok_or_else(|| AppError::BadRequest(format!("invalid entry path: {}", name)))?;
+
+    objects::ensure_unlocked(state, &key).await?;
+    let generation = state.metadata.next_generation(&key).await?;
+
+    let max_size = state.max_upload_size * 1024 * 1024;
+    let staged = state
+        .storage
+        .stage_stream(&key, stream, max_size, &ExpectedChecksums::default())
+        .await?;
+
+    let content_type = objects::detect_content_type(state, &key, &staged).await;
+    let layout = match state.storage.finalize_staged(&staged, &content_type).await {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let size = staged.size;
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: key.clone(),
+        size,
+        stored_size: layout.stored_size,
+        chunks: layout.chunks,
+        content_type,
+        etag: staged.etag.clone(),
+        etag_algorithm: state.storage.etag_algorithm(),
+        created_at: now,
+        public: false,
+        expires_at: None,
+        retain_until: objects::retention_for(state, &key),
+        user_metadata: Default::default(),
+        cache_control: None,
+        cache_expires: None,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    objects::commit_object(state, staged, &metadata, None).await?;
+    tracing::debug!("Imported {} ({} bytes)", key, size);
+
+    Ok(metadata)
+}
+
+pub fn record(response: &mut ImportResponse, path: String, result: Result<ObjectMetadata>) {
+    match result {
+        Ok(metadata) => {
+            response.imported += 1;
+            response.total_size += metadata.size;
+        }
+        Err(e) => {
+            tracing::warn!("Failed to import {}: {}", path, e);
+            response.failed.push(ImportFailure {
+                path,
+                error: e.to_string(),
+            });
+        }
+    }
+}
+
+pub fn entry_key(prefix: &str, name: &str) -> Option<String> {
+    let segments: Vec<&str> = name
+        .split('/')
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+
+    if segments.is_empty() || segments.contains(&"..") {
+        return None;
+    }
+
+    Some(format!("{}{}", prefix, segments.join("/")))
+}
+
+fn entry_name<'a>(prefix: &str, obj: &'a ObjectMetadata) -> &'a str {
+    obj.key.strip_prefix(prefix).unwrap_or(&obj.key)
+}
+
+fn zip_error(e: async_zip::error::ZipError) -> AppError {
+    AppError::Io(std::io::Error::other(e))
+}
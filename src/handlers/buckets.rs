@@ -0,0 +1,274 @@
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::StatusCode,
+    response::Response,
+};
+use chrono::Utc;
+use tower::ServiceExt;
+
+use crate::{
+    auth::BUCKETS_PATH,
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{ApiToken, Bucket, BucketPolicy, ErrorResponse, ListBucketsResponse},
+};
+
+const MIN_NAME_LENGTH: usize = 3;
+const MAX_NAME_LENGTH: usize = 63;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/buckets",
+    tag = "buckets",
+    responses(
+        (status = 200, description = "All buckets", body = ListBucketsResponse),
+    )
+)]
+pub async fn list_buckets(State(state): State<AppState>) -> Result<Json<ListBucketsResponse>> {
+    tracing::info!("LIST buckets request");
+
+    let buckets = state.metadata.list_buckets().await?;
+    let total = buckets.len();
+
+    Ok(Json(ListBucketsResponse { buckets, total }))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/buckets/{bucket}",
+    tag = "buckets",
+    params(("bucket" = String, Path, description = "Bucket name")),
+    request_body(content = Option<BucketPolicy>, description = "Optional bucket policy"),
+    responses(
+        (status = 201, description = "Bucket created", body = Bucket),
+        (status = 400, description = "Invalid bucket name", body = ErrorResponse),
+        (status = 409, description = "Bucket already exists", body = ErrorResponse),
+    )
+)]
+pub async fn create_bucket(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    policy: Option<Json<BucketPolicy>>,
+) -> Result<(StatusCode, Json<Bucket>)> {
+    tracing::info!("CREATE bucket request: {}", name);
+
+    validate_name(&name)?;
+
+    let policy = policy.map(|Json(policy)| policy).unwrap_or_default();
+    validate_policy(&policy)?;
+
+    let bucket = Bucket {
+        name,
+        created_at: Utc::now(),
+        policy,
+    };
+
+    state.storage.create_bucket(&bucket.name).await?;
+
+    if !state.metadata.create_bucket(&bucket).await? {
+        return Err(AppError::Conflict(format!(
+            "bucket {} already exists",
+            bucket.name
+        )));
+    }
+
+    tracing::info!("Bucket {} created", bucket.name);
+    Ok((StatusCode::CREATED, Json(bucket)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/buckets/{bucket}",
+    tag = "buckets",
+    params(("bucket" = String, Path, description = "Bucket name")),
+    responses(
+        (status = 200, description = "Bucket", body = Bucket),
+        (status = 404, description = "Bucket not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_bucket(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Bucket>> {
+    tracing::info!("GET bucket request: {}", name);
+
+    let bucket = state
+        .metadata
+        .get_bucket(&name)
+        .await?
+        .ok_or(AppError::BucketNotFound(name))?;
+
+    Ok(Json(bucket))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/buckets/{bucket}/policy",
+    tag = "buckets",
+    params(("bucket" = String, Path, description = "Bucket name")),
+    responses(
+        (status = 200, description = "Bucket policy", body = BucketPolicy),
+        (status = 404, description = "Bucket not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_bucket_policy(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<BucketPolicy>> {
+    tracing::info!("GET bucket policy request: {}", name);
+
+    let bucket = state
+        .metadata
+        .get_bucket(&name)
+        .await?
+        .ok_or(AppError::BucketNotFound(name))?;
+
+    Ok(Json(bucket.policy))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/buckets/{bucket}/policy",
+    tag = "buckets",
+    params(("bucket" = String, Path, description = "Bucket name")),
+    request_body = BucketPolicy,
+    responses(
+        (status = 200, description = "Updated bucket policy", body = BucketPolicy),
+        (status = 404, description = "Bucket not found", body = ErrorResponse),
+    )
+)]
+pub async fn put_bucket_policy(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(policy): Json<BucketPolicy>,
+) -> Result<Json<BucketPolicy>> {
+    tracing::info!("PUT bucket policy request: {}", name);
+
+    validate_policy(&policy)?;
+
+    if !state.metadata.set_bucket_policy(&name, &policy).await? {
+        return Err(AppError::BucketNotFound(name));
+    }
+
+    tracing::info!("Policy for bucket {} updated", name);
+    Ok(Json(policy))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/buckets/{bucket}",
+    tag = "buckets",
+    params(("bucket" = String, Path, description = "Bucket name")),
+    responses(
+        (status = 204, description = "Bucket deleted"),
+        (status = 404, description = "Bucket not found", body = ErrorResponse),
+        (status = 409, description = "Bucket is not empty", body = ErrorResponse),
+    )
+)]
+pub async fn delete_bucket(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode> {
+    tracing::info!("DELETE bucket request: {}", name);
+
+    if state.metadata.get_bucket(&name).await?.is_none() {
+        return Err(AppError::BucketNotFound(name));
+    }
+
+    if !state.metadata.delete_bucket(&name).await? {
+        return Err(AppError::Conflict(format!("bucket {} is not empty", name)));
+    }
+
+    state.storage.remove_bucket(&name).await?;
+
+    tracing::info!("Bucket {} deleted", name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn bucket_api(
+    State(state): State<AppState>,
+    Path((name, _)): Path<(String, String)>,
+    request: Request,
+) -> Result<Response> {
+    let bucket = state
+        .metadata
+        .get_bucket(&name)
+        .await?
+        .ok_or(AppError::BucketNotFound(name))?;
+
+    let rest = request
+        .uri()
+        .path()
+        .strip_prefix(BUCKETS_PATH)
+        .and_then(|path| path.split_once('/'))
+        .map(|(_, rest)| rest)
+        .unwrap_or_default();
+
+    let uri = match request.uri().query() {
+        Some(query) => format!("/api/v1/{}?{}", rest, query),
+        None => format!("/api/v1/{}", rest),
+    };
+
+    tracing::debug!("Dispatching {} to bucket {}", uri, bucket.name);
+
+    let uri = uri
+        .parse()
+        .map_err(|_| AppError::BadRequest(format!("invalid request path: {}", uri)))?;
+
+    let (parts, body) = request.into_parts();
+    let mut scoped = Request::new(body);
+    *scoped.method_mut() = parts.method;
+    *scoped.uri_mut() = uri;
+    *scoped.version_mut() = parts.version;
+    *scoped.headers_mut() = parts.headers;
+    if let Some(token) = parts.extensions.get::<ApiToken>() {
+        scoped.extensions_mut().insert(token.clone());
+    }
+
+    let response = crate::api_routes()
+        .with_state(state.scoped(bucket))
+        .oneshot(scoped)
+        .await
+        .unwrap_or_else(|never| match never {});
+
+    Ok(response)
+}
+
+fn validate_policy(policy: &BucketPolicy) -> Result<()> {
+    if policy.quota_bytes.is_some_and(|quota| quota < 0) {
+        return Err(AppError::BadRequest(
+            "quota_bytes must not be negative".to_string(),
+        ));
+    }
+
+    if policy
+        .default_content_type
+        .as_deref()
+        .is_some_and(|ct| ct.parse::<axum::http::HeaderValue>().is_err())
+    {
+        return Err(AppError::BadRequest(
+            "default_content_type is not a valid header value".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<()> {
+    let valid_length = (MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&name.len());
+    let valid_chars = name
+        .bytes()
+        .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'.');
+    let valid_edges = name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.ends_with(|c: char| c.is_ascii_alphanumeric());
+
+    if valid_length && valid_chars && valid_edges && !name.contains("..") {
+        Ok(())
+    } else {
+        Err(AppError::BadRequest(format!(
+            "invalid bucket name {:?}: use {}-{} lowercase letters, digits, '-' or '.'",
+            name, MIN_NAME_LENGTH, MAX_NAME_LENGTH
+        )))
+    }
+}
@@ -0,0 +1,57 @@
+use axum::{
+    Json,
+    extract::{Query, State},
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{error::Result, handlers::objects::AppState, models::ListChangesResponse};
+
+const DEFAULT_CHANGES_LIMIT: i64 = 1000;
+const MAX_CHANGES_LIMIT: i64 = 10_000;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ChangesQuery {
+    #[serde(default)]
+    since_seq: u64,
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/changes",
+    tag = "events",
+    params(ChangesQuery),
+    responses(
+        (status = 200, description = "Change log entries after `since_seq`", body = ListChangesResponse),
+    )
+)]
+pub async fn list_changes(
+    State(state): State<AppState>,
+    Query(params): Query<ChangesQuery>,
+) -> Result<Json<ListChangesResponse>> {
+    tracing::info!("LIST changes request since {}", params.since_seq);
+
+    let limit = params
+        .limit
+        .unwrap_or(DEFAULT_CHANGES_LIMIT)
+        .clamp(1, MAX_CHANGES_LIMIT);
+
+    let mut changes = state
+        .metadata
+        .list_changes(params.since_seq, limit + 1)
+        .await?;
+    let has_more = changes.len() as i64 > limit;
+    changes.truncate(limit as usize);
+
+    let next_since_seq = changes.last().map_or(params.since_seq, |change| change.seq);
+    let latest_seq = state.metadata.latest_change().await?;
+
+    Ok(Json(ListChangesResponse {
+        changes,
+        latest_seq,
+        next_since_seq,
+        has_more,
+    }))
+}
@@ -0,0 +1,142 @@
+use axum::{
+    extract::{
+        State,
+        ws::{Message, WebSocket, WebSocketUpgrade},
+    },
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{events::Replay, handlers::objects::AppState, models::ObjectEvent};
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Subscribe {
+        #[serde(default)]
+        prefixes: Vec<String>,
+        #[serde(default)]
+        bucket: Option<String>,
+        #[serde(default)]
+        since: Option<u64>,
+    },
+    Unsubscribe,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a> {
+    Subscribed { seq: u64 },
+    Unsubscribed,
+    Event(&'a ObjectEvent),
+    Lagged { missed: u64 },
+    Error { message: String },
+}
+
+#[derive(Default)]
+struct Filter {
+    prefixes: Vec<String>,
+    bucket: Option<String>,
+}
+
+impl Filter {
+    fn matches(&self, event: &ObjectEvent) -> bool {
+        if self.bucket.is_some() && self.bucket != event.bucket {
+            return false;
+        }
+
+        self.prefixes.is_empty()
+            || self
+                .prefixes
+                .iter()
+                .any(|prefix| event.key.starts_with(prefix.as_str()))
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/ws",
+    tag = "events",
+    responses(
+        (status = 101, description = "WebSocket stream of object events, filtered by a subscribe message", body = ObjectEvent),
+    )
+)]
+pub async fn subscribe(State(state): State<AppState>, upgrade: WebSocketUpgrade) -> Response {
+    tracing::info!("WebSocket event subscription opened");
+    upgrade.on_upgrade(move |socket| serve(state, socket))
+}
+
+async fn serve(state: AppState, mut socket: WebSocket) {
+    let mut subscription: Option<(Filter, broadcast::Receiver<ObjectEvent>)> = None;
+
+    loop {
+        let next = async {
+            match &mut subscription {
+                Some((_, receiver)) => receiver.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        let sent = tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Subscribe { prefixes, bucket, since }) => {
+                            let Replay { events, missed, receiver } = state.events.subscribe(since);
+                            let filter = Filter { prefixes, bucket };
+                            let mut sent = send(&mut socket, &ServerMessage::Subscribed {
+                                seq: state.events.seq(),
+                            })
+                            .await;
+                            if sent && missed > 0 {
+                                sent = send(&mut socket, &ServerMessage::Lagged { missed }).await;
+                            }
+                            for event in events.iter().filter(|event| filter.matches(event)) {
+                                if !sent {
+                                    break;
+                                }
+                                sent = send(&mut socket, &ServerMessage::Event(event)).await;
+                            }
+                            subscription = Some((filter, receiver));
+                            sent
+                        }
+                        Ok(ClientMessage::Unsubscribe) => {
+                            subscription = None;
+                            send(&mut socket, &ServerMessage::Unsubscribed).await
+                        }
+                        Err(e) => {
+                            send(&mut socket, &ServerMessage::Error { message: e.to_string() }).await
+                        }
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => false,
+                Some(Ok(_)) => true,
+            },
+            event = next => match event {
+                Ok(event) => match &subscription {
+                    Some((filter, _)) if filter.matches(&event) => {
+                        send(&mut socket, &ServerMessage::Event(&event)).await
+                    }
+                    _ => true,
+                },
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("WebSocket subscriber fell behind by {} events", missed);
+                    send(&mut socket, &ServerMessage::Lagged { missed }).await
+                }
+                Err(RecvError::Closed) => false,
+            },
+        };
+
+        if !sent {
+            break;
+        }
+    }
+
+    tracing::debug!("WebSocket event subscription closed");
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage<'_>) -> bool {
+    let text = serde_json::to_string(message).expect("event messages serialize");
+    socket.send(Message::Text(text.into())).await.is_ok()
+}
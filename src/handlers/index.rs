@@ -116,7 +116,7 @@ pub async fn index() -> impl IntoResponse {
         </div>
 
         <div class="footer">
-            <p>Built by <a href="https://github.com/aprlpet">april</a> · <a href="https://github.com/aprlpet/lila">GitHub</a></p>
+            <p><a href="/ui">Browse files</a> · <a href="/api/v1/docs">API</a> · <a href="/api/v1/graphiql">GraphQL</a> · Built by <a href="https://github.com/aprlpet">april</a> · <a href="https://github.com/aprlpet/lila">GitHub</a></p>
         </div>
     </div>
 </body>
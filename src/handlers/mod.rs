@@ -1,3 +1,17 @@
+pub mod admin;
+pub mod archive;
+pub mod buckets;
+pub mod changes;
+pub mod events;
 pub mod index;
 pub mod objects;
+pub mod presign;
+pub mod sessions;
 pub mod stats;
+pub mod thumbnails;
+pub mod tokens;
+pub mod trash;
+pub mod tus;
+pub mod ui;
+pub mod uploads;
+pub mod versions;
@@ -1,90 +1,1465 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use axum::{
+    Json,
+    body::Body,
+    extract::{
+        Path, Query, State,
+        multipart::{Field, Multipart, MultipartError},
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use base64::{
+    Engine,
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+};
+use chrono::{DateTime, Utc};
+use globset::GlobBuilder;
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Deserialize;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::{
+    concurrency::Limits,
+    error::{AppError, Result},
+    events::EventBus,
+    handlers::{trash, versions},
+    images::{self, Variant},
+    jobs::{
+        JobRegistry, access::AccessTracker, maintenance::MaintenanceTracker,
+        replication::ReplicationTracker,
+    },
+    models::{
+        BatchDeleteResponse, BatchDeleteResult, BatchDeleteStatus, BatchGetResponse, Bucket,
+        CacheControlRule, Config, ErrorResponse, EventOp, FormUploadResponse, ListCursor,
+        ListObjectsResponse, Mode, ObjectInfo, ObjectMetadata, QuotaRule, RetentionRule,
+        SearchResponse, SortField, SortOrder, VerifyResponse, VerifyStatus,
+    },
+    openapi::{Binary, FormUpload},
+    signing::ReplayGuard,
+    storage::{BlobStream, ExpectedChecksums, FileStorage, MetadataStore, SearchFilter, Staged},
+};
+
+const MAX_BATCH_SIZE: usize = 1000;
+const DEFAULT_LIST_LIMIT: i64 = 1000;
+pub const USER_METADATA_PREFIX: &str = "x-lila-meta-";
+const IF_GENERATION_MATCH: &str = "x-lila-if-generation-match";
+const ACTIVE_CONTENT_TYPES: [&str; 5] = [
+    "text/html",
+    "application/xhtml+xml",
+    "image/svg+xml",
+    "text/xml",
+    "application/xml",
+];
+const FILENAME: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Clone)]
+pub struct AppState {
+    pub metadata: MetadataStore,
+    pub storage: FileStorage,
+    pub presign_secret: String,
+    pub max_upload_size: usize,
+    pub versioning: bool,
+    pub trash_retention_hours: Option<u64>,
+    pub retention_rules: Vec<RetentionRule>,
+    pub cache_control_rules: Vec<CacheControlRule>,
+    pub quota_bytes: Option<i64>,
+    pub quota_rules: Vec<QuotaRule>,
+    pub public_prefixes: Vec<String>,
+    pub bucket: Option<Bucket>,
+    pub replay_guard: ReplayGuard,
+    pub jobs: JobRegistry,
+    pub access: AccessTracker,
+    pub limits: Limits,
+    pub events: EventBus,
+    pub replication: ReplicationTracker,
+    pub maintenance: MaintenanceTracker,
+    pub read_only: Arc<AtomicBool>,
+    pub config: Arc<Config>,
+}
+
+impl AppState {
+    pub fn new(
+        config: &Config,
+        metadata: MetadataStore,
+        storage: FileStorage,
+        presign_secret: String,
+    ) -> Self {
+        Self {
+            metadata,
+            storage,
+            presign_secret,
+            max_upload_size: config.max_upload_size_mb,
+            versioning: config.versioning,
+            trash_retention_hours: config.trash_retention_hours,
+            retention_rules: config.retention_rules.clone(),
+            cache_control_rules: config.cache_control_rules.clone(),
+            quota_bytes: config.quota_bytes,
+            quota_rules: config.quota_rules.clone(),
+            public_prefixes: config.public_prefixes.clone(),
+            bucket: None,
+            replay_guard: ReplayGuard::default(),
+            jobs: JobRegistry::default(),
+            access: AccessTracker::default(),
+            limits: Limits::new(&config.concurrency),
+            events: EventBus::default(),
+            replication: ReplicationTracker::default(),
+            maintenance: MaintenanceTracker::default(),
+            read_only: Arc::new(AtomicBool::new(config.read_only)),
+            config: Arc::new(config.clone()),
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.is_replica() || self.read_only.load(Ordering::Relaxed)
+    }
+
+    pub fn is_replica(&self) -> bool {
+        self.config.mode == Mode::Replica
+    }
+
+    pub fn scoped(&self, bucket: Bucket) -> Self {
+        Self {
+            metadata: self.metadata.scoped(&bucket.name),
+            storage: self.storage.scoped(&bucket.name),
+            versioning: bucket.policy.versioning.unwrap_or(self.versioning),
+            bucket: Some(bucket),
+            ..self.clone()
+        }
+    }
+
+    pub async fn scopes(&self) -> Result<Vec<AppState>> {
+        let buckets = self.metadata.list_buckets().await?;
+
+        Ok(std::iter::once(self.clone())
+            .chain(buckets.into_iter().map(|bucket| self.scoped(bucket)))
+            .collect())
+    }
+
+    pub fn bucket_name(&self) -> Option<&str> {
+        self.bucket.as_ref().map(|bucket| bucket.name.as_str())
+    }
+
+    pub async fn publish(&self, op: EventOp, metadata: &ObjectMetadata) {
+        if let Err(e) = self.events.record(&self.metadata, op, metadata).await {
+            tracing::error!(
+                "Failed to record {} of {} in the change log: {}",
+                op.as_str(),
+                metadata.key,
+                e
+            );
+        }
+    }
+
+    pub fn default_content_type(&self) -> &str {
+        self.bucket
+            .as_ref()
+            .and_then(|bucket| bucket.policy.default_content_type.as_deref())
+            .unwrap_or("application/octet-stream")
+    }
+
+    pub fn api_path(&self, path: &str) -> String {
+        match self.bucket_name() {
+            Some(bucket) => format!("/api/v1/buckets/{}/{}", bucket, path),
+            None => format!("/api/v1/{}", path),
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListQuery {
+    pub prefix: Option<String>,
+    pub limit: Option<i64>,
+    pub delimiter: Option<String>,
+    pub cursor: Option<String>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreamQuery {
+    prefix: Option<String>,
+    sort: Option<SortField>,
+    order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    pub key: Option<String>,
+    pub key_glob: Option<String>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub accessed_before: Option<DateTime<Utc>>,
+    pub accessed_after: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VersionQuery {
+    version_id: Option<String>,
+}
+
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GetQuery {
+    version_id: Option<String>,
+    download: Option<bool>,
+    filename: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    format: Option<String>,
+    quality: Option<u8>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AppendQuery {
+    append: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MetadataUpdate {
+    public: Option<bool>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CopyRequest {
+    source: String,
+    destination: String,
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/objects/{key}",
+    tag = "objects",
+    params(
+        ("key" = String, Path, description = "Object key, may contain slashes"),
+        ("x-lila-public" = Option<bool>, Header, description = "Serve the object without authentication"),
+        ("x-lila-ttl-seconds" = Option<i64>, Header, description = "Expire the object after this many seconds"),
+        ("x-lila-expires-at" = Option<String>, Header, description = "Expire the object at this RFC 3339 timestamp"),
+        ("x-lila-content-sha256" = Option<String>, Header, description = "Reject the upload unless the body matches this hex SHA-256"),
+        ("x-lila-if-generation-match" = Option<i64>, Header, description = "Only overwrite this generation, 0 to require a new key"),
+        ("If-Match" = Option<String>, Header, description = "Only overwrite an object with this ETag"),
+        ("If-None-Match" = Option<String>, Header, description = "`*` to refuse overwriting an existing object"),
+    ),
+    request_body(content = Binary, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Object stored", body = ObjectMetadata),
+        (status = 400, description = "Invalid headers", body = ErrorResponse),
+        (status = 412, description = "Precondition failed", body = ErrorResponse),
+        (status = 413, description = "Body exceeds the upload limit or quota", body = ErrorResponse),
+        (status = 422, description = "Checksum mismatch", body = ErrorResponse),
+        (status = 423, description = "Object is under retention", body = ErrorResponse),
+        (status = 507, description = "Not enough free disk space", body = ErrorResponse),
+    )
+)]
+pub async fn put_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<ObjectMetadata>> {
+    tracing::info!("PUT request for object: {}", key);
+
+    let declared_type = declared_content_type(&headers);
+
+    let public = headers
+        .get("x-lila-public")
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("true"));
+
+    let expires_at = parse_expiry(&headers)?;
+    let user_metadata = parse_user_metadata(&headers);
+    let checksums = parse_checksums(&headers)?;
+    let cache_control = header_string(&headers, header::CACHE_CONTROL);
+    let cache_expires = header_string(&headers, header::EXPIRES);
+
+    let expected_generation = check_write_preconditions(&state, &key, &headers).await?;
+    ensure_unlocked(&state, &key).await?;
+
+    let replaced = state
+        .metadata
+        .get(&key)
+        .await?
+        .map_or(0, |current| current.size);
+    let headroom = quota_headroom(&state, &key, replaced).await?;
+    check_declared_length(&headers, headroom.as_ref())?;
+
+    state
+        .storage
+        .ensure_space(content_length(&headers).unwrap_or(0) as u64)
+        .await?;
+
+    let max_size = limit_to_headroom(state.max_upload_size * 1024 * 1024, headroom.as_ref());
+    let _permit = state.limits.upload().await?;
+    let stream = body.into_data_stream();
+    let generation = state.metadata.next_generation(&key).await?;
+
+    let staged = state
+        .storage
+        .stage_stream(&key, stream, max_size, &checksums)
+        .await
+        .map_err(|e| quota_error(e, headroom.as_ref()))?;
+
+    tracing::debug!(
+        "File written with ETag: {}, size: {} bytes",
+        staged.etag,
+        staged.size
+    );
+
+    let content_type = match declared_type {
+        Some(content_type) => content_type,
+        None => detect_content_type(&state, &key, &staged).await,
+    };
+
+    tracing::debug!("Content-Type: {}", content_type);
+
+    let layout = match state.storage.finalize_staged(&staged, &content_type).await {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: key.clone(),
+        size: staged.size,
+        stored_size: layout.stored_size,
+        chunks: layout.chunks,
+        content_type,
+        etag: staged.etag.clone(),
+        etag_algorithm: state.storage.etag_algorithm(),
+        created_at: now,
+        public,
+        expires_at,
+        retain_until: retention_for(&state, &key),
+        user_metadata,
+        cache_control,
+        cache_expires,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    commit_object(&state, staged, &metadata, expected_generation).await?;
+    tracing::info!("Object {} stored successfully", key);
+
+    Ok(Json(metadata))
+}
+
+fn declared_content_type(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && !v.starts_with("application/octet-stream"))
+        .map(str::to_string)
+}
+
+pub async fn detect_content_type(state: &AppState, key: &str, staged: &Staged) -> String {
+    match state.storage.sniff(staged).await {
+        Some(content_type) => content_type.to_string(),
+        None => guess_content_type(state, key),
+    }
+}
+
+pub fn guess_content_type(state: &AppState, key: &str) -> String {
+    mime_guess::from_path(key)
+        .first_raw()
+        .unwrap_or(state.default_content_type())
+        .to_string()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/objects:upload",
+    tag = "objects",
+    request_body(content = FormUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Uploaded objects and keys skipped because they already exist", body = FormUploadResponse),
+        (status = 400, description = "Malformed form", body = ErrorResponse),
+        (status = 413, description = "File exceeds the upload limit or quota", body = ErrorResponse),
+        (status = 423, description = "Object is under retention", body = ErrorResponse),
+    )
+)]
+pub async fn upload_form(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    mut multipart: Multipart,
+) -> Result<Json<FormUploadResponse>> {
+    tracing::info!("FORM upload request");
+
+    state
+        .storage
+        .ensure_space(content_length(&headers).unwrap_or(0) as u64)
+        .await?;
+
+    let _permit = state.limits.upload().await?;
+    let mut prefix = String::new();
+    let mut overwrite = false;
+    let mut response = FormUploadResponse::default();
+
+    while let Some(field) = multipart.next_field().await.map_err(form_error)? {
+        match field.name() {
+            Some("prefix") => prefix = field.text().await.map_err(form_error)?,
+            Some("overwrite") => {
+                overwrite = field
+                    .text()
+                    .await
+                    .map_err(form_error)?
+                    .eq_ignore_ascii_case("true")
+            }
+            _ => {
+                let Some(filename) = field.file_name() else {
+                    continue;
+                };
+
+                let key = format!("{}{}", prefix, filename.trim_start_matches('/'));
+                if filename.is_empty() || key.ends_with('/') {
+                    return Err(AppError::BadRequest(format!(
+                        "invalid upload filename: {}",
+                        filename
+                    )));
+                }
+
+                if !overwrite && state.metadata.get(&key).await?.is_some() {
+                    tracing::debug!("Skipping existing object {}", key);
+                    response.conflicts.push(key);
+                    continue;
+                }
+
+                let metadata = store_form_file(&state, &key, field).await?;
+                tracing::info!("Object {} uploaded from form", key);
+                response.uploaded.push(metadata);
+            }
+        }
+    }
+
+    Ok(Json(response))
+}
+
+async fn store_form_file(state: &AppState, key: &str, field: Field<'_>) -> Result<ObjectMetadata> {
+    ensure_unlocked(state, key).await?;
+
+    let declared_type = field
+        .content_type()
+        .filter(|v| !v.is_empty() && !v.starts_with("application/octet-stream"))
+        .map(str::to_string);
+
+    let replaced = state
+        .metadata
+        .get(key)
+        .await?
+        .map_or(0, |current| current.size);
+    let headroom = quota_headroom(state, key, replaced).await?;
+    let max_size = limit_to_headroom(state.max_upload_size * 1024 * 1024, headroom.as_ref());
+    let generation = state.metadata.next_generation(key).await?;
+
+    let staged = state
+        .storage
+        .stage_stream(key, field, max_size, &ExpectedChecksums::default())
+        .await
+        .map_err(|e| quota_error(e, headroom.as_ref()))?;
+
+    let content_type = match declared_type {
+        Some(content_type) => content_type,
+        None => detect_content_type(state, key, &staged).await,
+    };
+
+    let layout = match state.storage.finalize_staged(&staged, &content_type).await {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: key.to_string(),
+        size: staged.size,
+        stored_size: layout.stored_size,
+        chunks: layout.chunks,
+        content_type,
+        etag: staged.etag.clone(),
+        etag_algorithm: state.storage.etag_algorithm(),
+        created_at: now,
+        public: false,
+        expires_at: None,
+        retain_until: retention_for(state, key),
+        user_metadata: BTreeMap::new(),
+        cache_control: None,
+        cache_expires: None,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    commit_object(state, staged, &metadata, None).await?;
+    Ok(metadata)
+}
+
+fn form_error(e: MultipartError) -> AppError {
+    AppError::BadRequest(format!("invalid form upload: {}", e))
+}
+
+pub async fn commit_object(
+    state: &AppState,
+    staged: Staged,
+    metadata: &ObjectMetadata,
+    expected_generation: Option<i64>,
+) -> Result<()> {
+    let previous = match state.metadata.get(&metadata.key).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let archived = match versions::archive_current(state, &metadata.key).await {
+        Ok(archived) => archived,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let inserted = match expected_generation {
+        Some(generation) => match state.metadata.insert_if(metadata, generation).await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(generation_mismatch(&metadata.key)),
+            Err(e) => Err(e),
+        },
+        None => state.metadata.insert(metadata).await,
+    };
+
+    if let Err(e) = inserted {
+        versions::rollback_archive(state, archived).await;
+        state.storage.discard(staged).await;
+        return Err(e);
+    }
+
+    if let Err(e) = state.storage.commit(staged).await {
+        versions::rollback_archive(state, archived).await;
+
+        let restored = match previous {
+            Some(previous) => state.metadata.insert(&previous).await,
+            None => state.metadata.delete(&metadata.key).await.map(|_| ()),
+        };
+        if let Err(e) = restored {
+            tracing::error!("Failed to restore metadata of {}: {}", metadata.key, e);
+        }
+
+        return Err(e);
+    }
+
+    state.storage.publish(&metadata.key).await?;
+    state.publish(EventOp::Put, metadata).await;
+    Ok(())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/objects/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes"), AppendQuery),
+    request_body(content = Binary, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Data appended", body = ObjectMetadata),
+        (status = 400, description = "append=true is required", body = ErrorResponse),
+        (status = 413, description = "Object would exceed the upload limit or quota", body = ErrorResponse),
+        (status = 423, description = "Object is under retention", body = ErrorResponse),
+    )
+)]
+pub async fn append_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(params): Query<AppendQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<ObjectMetadata>> {
+    tracing::info!("APPEND request for object: {}", key);
+
+    if params.append != Some(true) {
+        return Err(AppError::BadRequest(
+            "PATCH on objects requires append=true".to_string(),
+        ));
+    }
+
+    let expected_generation = check_write_preconditions(&state, &key, &headers).await?;
+
+    let existing = state.metadata.get(&key).await?;
+    let observed_generation = existing.as_ref().map_or(0, |current| current.generation);
+
+    if expected_generation.is_some_and(|expected| expected != observed_generation) {
+        return Err(generation_mismatch(&key));
+    }
+
+    if let Some(current) = &existing {
+        check_retention(current)?;
+    }
+
+    let content_type = match &existing {
+        Some(current) => current.content_type.clone(),
+        None => declared_content_type(&headers).unwrap_or_else(|| guess_content_type(&state, &key)),
+    };
+
+    let headroom = quota_headroom(&state, &key, 0).await?;
+    check_declared_length(&headers, headroom.as_ref())?;
+
+    state
+        .storage
+        .ensure_space(content_length(&headers).unwrap_or(0) as u64)
+        .await?;
+
+    let max_size = limit_to_headroom(state.max_upload_size * 1024 * 1024, headroom.as_ref());
+    let _permit = state.limits.upload().await?;
+    let stream = body.into_data_stream();
+
+    let staged = state
+        .storage
+        .stage_append(&key, existing.as_ref(), stream, max_size)
+        .await
+        .map_err(|e| quota_error(e, headroom.as_ref()))?;
+
+    tracing::debug!("Appended to {}, new size: {} bytes", key, staged.size);
+
+    let layout = match state.storage.finalize_staged(&staged, &content_type).await {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let now = Utc::now();
+    let metadata = match &existing {
+        Some(current) => ObjectMetadata {
+            id: if state.versioning {
+                Uuid::new_v4().to_string()
+            } else {
+                current.id.clone()
+            },
+            size: staged.size,
+            stored_size: layout.stored_size,
+            chunks: layout.chunks,
+            etag: staged.etag.clone(),
+            etag_algorithm: state.storage.etag_algorithm(),
+            updated_at: Some(now),
+            generation: state.metadata.next_generation(&key).await?,
+            ..current.clone()
+        },
+        None => ObjectMetadata {
+            id: Uuid::new_v4().to_string(),
+            key: key.clone(),
+            size: staged.size,
+            stored_size: layout.stored_size,
+            chunks: layout.chunks,
+            content_type,
+            etag: staged.etag.clone(),
+            etag_algorithm: state.storage.etag_algorithm(),
+            created_at: now,
+            public: false,
+            expires_at: None,
+            retain_until: retention_for(&state, &key),
+            user_metadata: Default::default(),
+            cache_control: None,
+            cache_expires: None,
+            accessed_at: None,
+            access_count: 0,
+            updated_at: Some(now),
+            generation: state.metadata.next_generation(&key).await?,
+        },
+    };
+
+    commit_object(&state, staged, &metadata, Some(observed_generation)).await?;
+    tracing::info!("Object {} appended successfully", key);
+
+    Ok(Json(metadata))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/copy",
+    tag = "objects",
+    request_body = CopyRequest,
+    responses(
+        (status = 200, description = "Object copied", body = ObjectMetadata),
+        (status = 404, description = "Source object not found", body = ErrorResponse),
+        (status = 412, description = "Precondition failed", body = ErrorResponse),
+    )
+)]
+pub async fn copy_object(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(request): Json<CopyRequest>,
+) -> Result<Json<ObjectMetadata>> {
+    tracing::info!(
+        "COPY request from {} to {}",
+        request.source,
+        request.destination
+    );
+
+    if request.source == request.destination {
+        return Err(AppError::BadRequest(
+            "source and destination must differ".to_string(),
+        ));
+    }
+
+    let source = state
+        .metadata
+        .get(&request.source)
+        .await?
+        .ok_or_else(|| AppError::NotFound(request.source.clone()))?;
+
+    let expected_generation =
+        check_write_preconditions(&state, &request.destination, &headers).await?;
+    ensure_unlocked(&state, &request.destination).await?;
+
+    check_quota(&state, &request.destination, source.size).await?;
+    state.storage.ensure_space(source.size as u64).await?;
+
+    let generation = state.metadata.next_generation(&request.destination).await?;
+    let staged = state
+        .storage
+        .stage_copy(&source, &request.destination)
+        .await?;
+    tracing::debug!("Blob copied to {}", request.destination);
+
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: request.destination.clone(),
+        size: source.size,
+        stored_size: source.stored_size,
+        chunks: source.chunks,
+        content_type: source.content_type,
+        etag: source.etag,
+        etag_algorithm: source.etag_algorithm,
+        created_at: now,
+        public: false,
+        expires_at: None,
+        retain_until: retention_for(&state, &request.destination),
+        user_metadata: source.user_metadata,
+        cache_control: source.cache_control,
+        cache_expires: source.cache_expires,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    commit_object(&state, staged, &metadata, expected_generation).await?;
+    tracing::info!(
+        "Object {} copied to {}",
+        request.source,
+        request.destination
+    );
+
+    Ok(Json(metadata))
+}
+
+enum QuotaScope {
+    Global,
+    Bucket(String),
+    Prefix(String),
+}
+
+struct QuotaHeadroom {
+    bytes: i64,
+    scope: QuotaScope,
+}
+
+impl QuotaHeadroom {
+    fn exceeded(&self) -> AppError {
+        let scope = match &self.scope {
+            QuotaScope::Global => "global quota".to_string(),
+            QuotaScope::Bucket(name) => format!("quota for bucket {}", name),
+            QuotaScope::Prefix(prefix) => format!("quota for prefix {}", prefix),
+        };
+        AppError::InsufficientStorage(format!(
+            "{} exceeded, {} bytes remaining",
+            scope, self.bytes
+        ))
+    }
+}
+
+async fn quota_headroom(
+    state: &AppState,
+    key: &str,
+    replaced: i64,
+) -> Result<Option<QuotaHeadroom>> {
+    let mut limits: Vec<(QuotaScope, i64)> = state
+        .quota_rules
+        .iter()
+        .filter(|rule| key.starts_with(&rule.prefix))
+        .map(|rule| (QuotaScope::Prefix(rule.prefix.clone()), rule.bytes))
+        .collect();
+
+    if let Some(bucket) = &state.bucket
+        && let Some(quota) = bucket.policy.quota_bytes
+    {
+        limits.push((QuotaScope::Bucket(bucket.name.clone()), quota));
+    }
+
+    if let Some(quota) = state.quota_bytes {
+        limits.push((QuotaScope::Global, quota));
+    }
+
+    let mut tightest: Option<QuotaHeadroom> = None;
+
+    for (scope, quota) in limits {
+        let used = match &scope {
+            QuotaScope::Global => state.metadata.get_total_size().await?,
+            QuotaScope::Bucket(_) => state.metadata.get_stats().await?.1,
+            QuotaScope::Prefix(prefix) => state.metadata.get_prefix_stats(prefix).await?.1,
+        };
+
+        let bytes = (quota - (used - replaced)).max(0);
+
+        if tightest
+            .as_ref()
+            .is_none_or(|current| bytes < current.bytes)
+        {
+            tightest = Some(QuotaHeadroom { bytes, scope });
+        }
+    }
+
+    Ok(tightest)
+}
+
+pub async fn check_quota(state: &AppState, key: &str, size: i64) -> Result<()> {
+    let replaced = state
+        .metadata
+        .get(key)
+        .await?
+        .map_or(0, |current| current.size);
+
+    if let Some(headroom) = quota_headroom(state, key, replaced).await?
+        && size > headroom.bytes
+    {
+        tracing::warn!("Write of {} bytes to {} rejected by quota", size, key);
+        return Err(headroom.exceeded());
+    }
+
+    Ok(())
+}
+
+fn content_length(headers: &HeaderMap) -> Option<i64> {
+    headers
+        .get(header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+}
+
+fn check_declared_length(headers: &HeaderMap, headroom: Option<&QuotaHeadroom>) -> Result<()> {
+    match (headroom, content_length(headers)) {
+        (Some(headroom), Some(length)) if length > headroom.bytes => {
+            tracing::warn!("Upload of {} bytes rejected by quota", length);
+            Err(headroom.exceeded())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn limit_to_headroom(max_size: usize, headroom: Option<&QuotaHeadroom>) -> usize {
+    match headroom {
+        Some(headroom) => max_size.min(headroom.bytes as usize),
+        None => max_size,
+    }
+}
+
+fn quota_error(e: AppError, headroom: Option<&QuotaHeadroom>) -> AppError {
+    match (e, headroom) {
+        (AppError::PayloadTooLarge(limit), Some(headroom)) if limit as i64 == headroom.bytes => {
+            headroom.exceeded()
+        }
+        (e, _) => e,
+    }
+}
+
+pub fn retention_for(state: &AppState, key: &str) -> Option<DateTime<Utc>> {
+    state
+        .retention_rules
+        .iter()
+        .filter(|rule| key.starts_with(&rule.prefix))
+        .map(|rule| rule.days)
+        .max()
+        .map(|days| Utc::now() + chrono::Duration::days(days as i64))
+}
+
+pub async fn ensure_unlocked(state: &AppState, key: &str) -> Result<()> {
+    match state.metadata.get(key).await? {
+        Some(current) => check_retention(&current),
+        None => Ok(()),
+    }
+}
+
+fn check_retention(metadata: &ObjectMetadata) -> Result<()> {
+    match metadata.retain_until {
+        Some(until) if until > Utc::now() => {
+            tracing::warn!("Object {} is retained until {}", metadata.key, until);
+            Err(AppError::RetentionLocked(metadata.key.clone(), until))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn parse_user_metadata(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let name = name.as_str().strip_prefix(USER_METADATA_PREFIX)?;
+            let value = value.to_str().ok()?;
+            (!name.is_empty()).then(|| (name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_checksums(headers: &HeaderMap) -> Result<ExpectedChecksums> {
+    let sha256 = headers
+        .get("x-lila-content-sha256")
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| hex::decode(v.trim()).ok())
+                .filter(|digest| digest.len() == 32)
+                .ok_or_else(|| {
+                    AppError::BadRequest("x-lila-content-sha256 must be a hex sha256".to_string())
+                })
+        })
+        .transpose()?;
+
+    let md5 = headers
+        .get("content-md5")
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| STANDARD.decode(v.trim()).ok())
+                .filter(|digest| digest.len() == 16)
+                .ok_or_else(|| AppError::BadRequest("Content-MD5 must be a base64 md5".to_string()))
+        })
+        .transpose()?;
+
+    Ok(ExpectedChecksums { sha256, md5 })
+}
+
+fn parse_expiry(headers: &HeaderMap) -> Result<Option<DateTime<Utc>>> {
+    let expires_at = if let Some(value) = headers.get("x-lila-expires-at") {
+        let value = value.to_str().unwrap_or("");
+        let parsed = DateTime::parse_from_rfc3339(value)
+            .or_else(|_| DateTime::parse_from_rfc2822(value))
+            .map_err(|_| AppError::BadRequest(format!("invalid x-lila-expires-at: {}", value)))?;
+        parsed.with_timezone(&Utc)
+    } else if let Some(value) = headers.get("x-lila-ttl-seconds") {
+        let seconds = value
+            .to_str()
+            .ok()
+            .and_then(|v| v.parse::<i64>().ok())
+            .filter(|v| *v > 0)
+            .ok_or_else(|| AppError::BadRequest("invalid x-lila-ttl-seconds".to_string()))?;
+        Utc::now() + chrono::Duration::seconds(seconds)
+    } else {
+        return Ok(None);
+    };
+
+    if expires_at <= Utc::now() {
+        return Err(AppError::BadRequest(
+            "expiration must be in the future".to_string(),
+        ));
+    }
+
+    Ok(Some(expires_at))
+}
+
+async fn check_write_preconditions(
+    state: &AppState,
+    key: &str,
+    headers: &HeaderMap,
+) -> Result<Option<i64>> {
+    let if_match = headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok());
+    let if_none_match = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok());
+
+    let if_generation = generation_condition(headers)?;
+
+    if if_match.is_none() && if_none_match.is_none() && if_generation.is_none() {
+        return Ok(None);
+    }
+
+    let existing = state.metadata.get(key).await?;
+
+    if let Some(expected) = if_generation {
+        check_generation(key, existing.as_ref(), expected)?;
+    }
+
+    if let Some(condition) = if_match {
+        check_if_match(key, existing.as_ref(), condition)?;
+    }
+
+    if let Some(condition) = if_none_match
+        && let Some(current) = &existing
+        && etag_matches(condition, &current.etag)
+    {
+        tracing::warn!("If-None-Match precondition failed for {}", key);
+        return Err(AppError::PreconditionFailed(format!(
+            "{} already exists",
+            key
+        )));
+    }
+
+    Ok(Some(existing.map_or(0, |current| current.generation)))
+}
+
+fn check_if_match(key: &str, current: Option<&ObjectMetadata>, condition: &str) -> Result<()> {
+    if !current.is_some_and(|current| etag_matches(condition, &current.etag)) {
+        tracing::warn!("If-Match precondition failed for {}", key);
+        return Err(AppError::PreconditionFailed(format!(
+            "If-Match does not match current etag of {}",
+            key
+        )));
+    }
+
+    Ok(())
+}
+
+pub fn generation_condition(headers: &HeaderMap) -> Result<Option<i64>> {
+    headers
+        .get(IF_GENERATION_MATCH)
+        .map(|v| {
+            v.to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .filter(|generation| *generation >= 0)
+                .ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "{} must be a generation number",
+                        IF_GENERATION_MATCH
+                    ))
+                })
+        })
+        .transpose()
+}
+
+fn check_generation(key: &str, current: Option<&ObjectMetadata>, expected: i64) -> Result<()> {
+    if current.map_or(0, |current| current.generation) != expected {
+        return Err(generation_mismatch(key));
+    }
+
+    Ok(())
+}
+
+fn generation_mismatch(key: &str) -> AppError {
+    tracing::warn!("Generation precondition failed for {}", key);
+    AppError::PreconditionFailed(format!(
+        "{} does not match current generation of {}",
+        IF_GENERATION_MATCH, key
+    ))
+}
+
+fn etag_matches(condition: &str, etag: &str) -> bool {
+    condition.split(',').map(str::trim).any(|candidate| {
+        candidate == "*"
+            || candidate
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .eq_ignore_ascii_case(etag)
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/objects/{key}",
+    tag = "objects",
+    params(
+        ("key" = String, Path, description = "Object key, may contain slashes"),
+        GetQuery,
+        ("Range" = Option<String>, Header, description = "Byte range to return"),
+        ("If-None-Match" = Option<String>, Header, description = "Return 304 when the ETag matches"),
+        ("If-Modified-Since" = Option<String>, Header, description = "Return 304 when unchanged since this date"),
+    ),
+    responses(
+        (status = 200, description = "Object content", body = Binary, content_type = "application/octet-stream"),
+        (status = 206, description = "Requested byte range", body = Binary, content_type = "application/octet-stream"),
+        (status = 304, description = "Not modified"),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+        (status = 416, description = "Range not satisfiable", body = ErrorResponse),
+    ),
+    security((), ("bearer" = []))
+)]
+pub async fn get_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(params): Query<GetQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    tracing::info!("GET request for object: {}", key);
+
+    let (metadata, archived) = resolve_version(&state, &key, params.version_id.as_deref()).await?;
+
+    tracing::debug!("Found metadata for {}: {} bytes", key, metadata.size);
+
+    if let Some(expected) = generation_condition(&headers)? {
+        check_generation(&key, Some(&metadata), expected)?;
+    }
+
+    if params.width.is_some()
+        || params.height.is_some()
+        || params.format.is_some()
+        || params.quality.is_some()
+    {
+        let variant = Variant {
+            width: params.width,
+            height: params.height,
+            format: match &params.format {
+                Some(format) => images::parse_format(format)?,
+                None => images::source_format(&metadata)?,
+            },
+            quality: params
+                .quality
+                .unwrap_or(state.config.images.default_quality),
+        };
+
+        return images::serve(&state, &metadata, archived, variant, &headers).await;
+    }
+
+    let modified = metadata.updated_at.unwrap_or(metadata.created_at);
+    let last_modified = http_date(&modified);
+
+    let not_modified = match headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+    {
+        Some(condition) => etag_matches(condition, &metadata.etag),
+        None => headers
+            .get(header::IF_MODIFIED_SINCE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+            .is_some_and(|since| modified.timestamp() <= since.timestamp()),
+    };
+
+    if not_modified {
+        tracing::debug!("Object {} not modified", key);
+        let builder = Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, &metadata.etag)
+            .header(header::LAST_MODIFIED, last_modified);
+
+        return Ok(cache_headers(&state, &metadata, builder)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let range = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+        Some(value) => parse_range(value, metadata.size)?,
+        None => None,
+    };
+
+    let mut builder = object_response(&state, &metadata);
+
+    if params.download == Some(true) || is_active_content(&metadata.content_type) {
+        let filename = params
+            .filename
+            .as_deref()
+            .unwrap_or_else(|| key.rsplit('/').next().unwrap_or(&key));
+        builder = builder.header(header::CONTENT_DISPOSITION, content_disposition(filename));
+    }
+
+    let response = match range {
+        Some((start, end)) => {
+            let length = end - start + 1;
+            tracing::debug!("Serving range {}-{} ({} bytes)", start, end, length);
+
+            let body =
+                Body::from_stream(open_stream(&state, &metadata, archived, start, length).await?);
+
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, metadata.size),
+                )
+                .header(header::CONTENT_LENGTH, length.to_string())
+                .body(body)
+                .unwrap()
+        }
+        None => {
+            let size = metadata.size as u64;
+            let body = Body::from_stream(open_stream(&state, &metadata, archived, 0, size).await?);
+            tracing::debug!("Opened file for streaming");
+
+            builder
+                .header(header::CONTENT_LENGTH, metadata.size.to_string())
+                .body(body)
+                .unwrap()
+        }
+    };
+
+    tracing::info!("Object {} streaming started", key);
+    Ok(response)
+}
+
+#[utoipa::path(
+    head,
+    path = "/api/v1/objects/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes"), VersionQuery),
+    responses(
+        (status = 200, description = "Object headers without content"),
+        (status = 404, description = "Object not found"),
+    ),
+    security((), ("bearer" = []))
+)]
+pub async fn head_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(params): Query<VersionQuery>,
+) -> Result<Response> {
+    tracing::info!("HEAD request for object: {}", key);
 
-use axum::{
-    Json,
-    body::Body,
-    extract::{Path, Query, State},
-    http::HeaderMap,
-    response::Response,
-};
-use chrono::Utc;
-use serde::Deserialize;
-use tokio_util::io::ReaderStream;
-use uuid::Uuid;
+    let (metadata, _) = resolve_version(&state, &key, params.version_id.as_deref()).await?;
 
-use crate::{
-    error::{AppError, Result},
-    models::{ListObjectsResponse, ObjectInfo, ObjectMetadata, SearchResponse},
-    storage::{FileStorage, MetadataStore},
-};
+    let mut builder = object_response(&state, &metadata);
 
-#[derive(Clone)]
-pub struct AppState {
-    pub metadata: MetadataStore,
-    pub storage: FileStorage,
-    pub auth_token: String,
-    pub max_upload_size: usize,
+    if is_active_content(&metadata.content_type) {
+        let filename = key.rsplit('/').next().unwrap_or(&key);
+        builder = builder.header(header::CONTENT_DISPOSITION, content_disposition(filename));
+    }
+
+    Ok(builder
+        .header(header::CONTENT_LENGTH, metadata.size.to_string())
+        .body(Body::empty())
+        .unwrap())
 }
 
-#[derive(Deserialize)]
-pub struct ListQuery {
-    prefix: Option<String>,
-    limit: Option<i64>,
-    delimiter: Option<String>,
+async fn resolve_version(
+    state: &AppState,
+    key: &str,
+    version_id: Option<&str>,
+) -> Result<(ObjectMetadata, bool)> {
+    let current = state.metadata.get(key).await?;
+
+    let current = current.filter(|metadata| {
+        metadata
+            .expires_at
+            .is_none_or(|expires_at| expires_at > Utc::now())
+    });
+
+    match (current, version_id) {
+        (Some(current), None) => Ok((current, false)),
+        (Some(current), Some(version_id)) if current.id == version_id => Ok((current, false)),
+        (None, None) => Err(AppError::NotFound(key.to_string())),
+        (_, Some(version_id)) => {
+            let version = state
+                .metadata
+                .get_version(key, version_id)
+                .await?
+                .ok_or_else(|| AppError::NotFound(format!("{}?version_id={}", key, version_id)))?;
+            Ok((version, true))
+        }
+    }
 }
 
-#[derive(Deserialize)]
-pub struct SearchQuery {
-    key: Option<String>,
-    content_type: Option<String>,
-    min_size: Option<i64>,
-    max_size: Option<i64>,
-    limit: Option<i64>,
+async fn open_stream(
+    state: &AppState,
+    metadata: &ObjectMetadata,
+    archived: bool,
+    offset: u64,
+    length: u64,
+) -> Result<BlobStream> {
+    if archived {
+        return state.storage.version_stream(metadata, offset, length).await;
+    }
+
+    let stream = state.storage.stream(metadata, offset, length).await?;
+    state
+        .access
+        .record(state.bucket_name().unwrap_or_default(), metadata);
+    Ok(stream)
 }
 
-pub async fn put_object(
-    State(state): State<AppState>,
-    Path(key): Path<String>,
-    headers: HeaderMap,
-    body: Body,
-) -> Result<Json<ObjectMetadata>> {
-    tracing::info!("PUT request for object: {}", key);
+fn object_response(state: &AppState, metadata: &ObjectMetadata) -> axum::http::response::Builder {
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, &metadata.content_type)
+        .header(header::ETAG, &metadata.etag)
+        .header(
+            header::LAST_MODIFIED,
+            http_date(&metadata.updated_at.unwrap_or(metadata.created_at)),
+        )
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header("x-lila-generation", metadata.generation.to_string());
+    let builder = if is_active_content(&metadata.content_type) {
+        builder.header(header::CONTENT_SECURITY_POLICY, "sandbox")
+    } else {
+        builder
+    };
+    let builder = cache_headers(state, metadata, builder);
+
+    metadata
+        .user_metadata
+        .iter()
+        .fold(builder, |builder, (name, value)| {
+            builder.header(format!("{}{}", USER_METADATA_PREFIX, name), value)
+        })
+}
+
+fn is_active_content(content_type: &str) -> bool {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+
+    ACTIVE_CONTENT_TYPES.contains(&essence.as_str()) || essence.ends_with("+xml")
+}
+
+fn content_disposition(filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect();
+
+    format!(
+        "attachment; filename=\"{}\"; filename*=UTF-8''{}",
+        fallback,
+        utf8_percent_encode(filename, FILENAME)
+    )
+}
+
+pub fn cache_headers(
+    state: &AppState,
+    metadata: &ObjectMetadata,
+    builder: axum::http::response::Builder,
+) -> axum::http::response::Builder {
+    let cache_control = metadata.cache_control.as_deref().or_else(|| {
+        state
+            .cache_control_rules
+            .iter()
+            .filter(|rule| metadata.content_type.starts_with(&rule.content_type))
+            .max_by_key(|rule| rule.content_type.len())
+            .map(|rule| rule.value.as_str())
+    });
+
+    let builder = match cache_control {
+        Some(value) => builder.header(header::CACHE_CONTROL, value),
+        None => builder,
+    };
 
-    let content_type = headers
-        .get("content-type")
+    match &metadata.cache_expires {
+        Some(value) => builder.header(header::EXPIRES, value),
+        None => builder,
+    }
+}
+
+fn header_string(headers: &HeaderMap, name: header::HeaderName) -> Option<String> {
+    headers
+        .get(name)
         .and_then(|v| v.to_str().ok())
-        .unwrap_or("application/octet-stream")
-        .to_string();
+        .map(String::from)
+}
 
-    tracing::debug!("Content-Type: {}", content_type);
+fn http_date(time: &DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
 
-    let max_size = state.max_upload_size * 1024 * 1024;
-    let stream = body.into_data_stream();
+fn parse_range(value: &str, size: i64) -> Result<Option<(u64, u64)>> {
+    let size = size as u64;
 
-    let (etag, size) = state.storage.write_stream(&key, stream, max_size).await?;
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return Ok(None);
+    };
 
-    tracing::debug!("File written with ETag: {}, size: {} bytes", etag, size);
+    if spec.contains(',') {
+        tracing::debug!("Multiple ranges requested, serving full object");
+        return Ok(None);
+    }
 
-    let metadata = ObjectMetadata {
-        id: Uuid::new_v4().to_string(),
-        key: key.clone(),
-        size,
-        content_type,
-        etag,
-        created_at: Utc::now(),
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return Ok(None);
     };
 
-    state.metadata.insert(&metadata).await?;
-    tracing::info!("Object {} stored successfully", key);
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return Ok(None),
+        ("", suffix) => {
+            let Ok(suffix) = suffix.parse::<u64>() else {
+                return Ok(None);
+            };
+            if suffix == 0 || size == 0 {
+                return Err(AppError::RangeNotSatisfiable(size as i64));
+            }
+            (size.saturating_sub(suffix), size - 1)
+        }
+        (start, end) => {
+            let Ok(start) = start.parse::<u64>() else {
+                return Ok(None);
+            };
+            let end = match end {
+                "" => size.saturating_sub(1),
+                end => match end.parse::<u64>() {
+                    Ok(end) if end >= start => end.min(size.saturating_sub(1)),
+                    _ => return Ok(None),
+                },
+            };
+            if start >= size {
+                return Err(AppError::RangeNotSatisfiable(size as i64));
+            }
+            (start, end)
+        }
+    };
 
-    Ok(Json(metadata))
+    Ok(Some((start, end)))
 }
 
-pub async fn get_object(
+#[utoipa::path(
+    get,
+    path = "/api/v1/metadata/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Object metadata", body = ObjectMetadata),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_object_metadata(
     State(state): State<AppState>,
     Path(key): Path<String>,
-) -> Result<Response> {
-    tracing::info!("GET request for object: {}", key);
+) -> Result<Json<ObjectMetadata>> {
+    tracing::info!("METADATA request for object: {}", key);
 
     let metadata = state
         .metadata
@@ -92,30 +1467,72 @@ pub async fn get_object(
         .await?
         .ok_or_else(|| AppError::NotFound(key.clone()))?;
 
-    tracing::debug!("Found metadata for {}: {} bytes", key, metadata.size);
+    tracing::debug!("Found metadata for {}", key);
+    Ok(Json(metadata))
+}
 
-    let file = state.storage.open(&key).await?;
-    tracing::debug!("Opened file for streaming");
+#[utoipa::path(
+    post,
+    path = "/api/v1/metadata:batchGet",
+    tag = "objects",
+    request_body(content = Vec<String>, description = "Keys to look up"),
+    responses(
+        (status = 200, description = "Metadata for existing keys and the keys that were not found", body = BatchGetResponse),
+        (status = 400, description = "Too many keys", body = ErrorResponse),
+    )
+)]
+pub async fn batch_get_metadata(
+    State(state): State<AppState>,
+    Json(keys): Json<Vec<String>>,
+) -> Result<Json<BatchGetResponse>> {
+    tracing::info!("BATCH METADATA request for {} objects", keys.len());
 
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+    if keys.len() > MAX_BATCH_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "at most {} keys may be requested per request",
+            MAX_BATCH_SIZE
+        )));
+    }
 
-    let response = Response::builder()
-        .header("content-type", metadata.content_type)
-        .header("etag", metadata.etag)
-        .header("content-length", metadata.size.to_string())
-        .body(body)
-        .unwrap();
+    let objects = state.metadata.get_many(&keys).await?;
 
-    tracing::info!("Object {} streaming started", key);
-    Ok(response)
+    let found: HashSet<&str> = objects.iter().map(|o| o.key.as_str()).collect();
+    let mut missing: Vec<String> = keys
+        .iter()
+        .filter(|k| !found.contains(k.as_str()))
+        .cloned()
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    tracing::info!("Found {} objects, {} missing", objects.len(), missing.len());
+
+    Ok(Json(BatchGetResponse { objects, missing }))
 }
 
-pub async fn get_object_metadata(
+#[utoipa::path(
+    patch,
+    path = "/api/v1/metadata/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    request_body = MetadataUpdate,
+    responses(
+        (status = 200, description = "Updated metadata", body = ObjectMetadata),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+    )
+)]
+pub async fn update_object_metadata(
     State(state): State<AppState>,
     Path(key): Path<String>,
+    Json(update): Json<MetadataUpdate>,
 ) -> Result<Json<ObjectMetadata>> {
-    tracing::info!("HEAD request for object: {}", key);
+    tracing::info!("METADATA update request for object: {}", key);
+
+    if let Some(public) = update.public
+        && !state.metadata.set_public(&key, public).await?
+    {
+        return Err(AppError::NotFound(key));
+    }
 
     let metadata = state
         .metadata
@@ -123,41 +1540,92 @@ pub async fn get_object_metadata(
         .await?
         .ok_or_else(|| AppError::NotFound(key.clone()))?;
 
-    tracing::debug!("Found metadata for {}", key);
+    tracing::info!("Metadata for {} updated", key);
     Ok(Json(metadata))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/objects",
+    tag = "objects",
+    params(ListQuery),
+    responses(
+        (status = 200, description = "A page of objects and common prefixes", body = ListObjectsResponse),
+        (status = 400, description = "Invalid cursor or parameters", body = ErrorResponse),
+    )
+)]
 pub async fn list_objects(
     State(state): State<AppState>,
     Query(params): Query<ListQuery>,
 ) -> Result<Json<ListObjectsResponse>> {
     tracing::info!("LIST request with prefix: {:?}", params.prefix);
 
-    let objects = state
-        .metadata
-        .list(params.prefix.as_deref(), params.limit)
-        .await?;
-
+    let limit = params.limit.unwrap_or(DEFAULT_LIST_LIMIT).max(1);
+    let sort = params.sort.unwrap_or_default();
+    let order = params.order.unwrap_or_default();
     let delimiter = params.delimiter.unwrap_or_else(|| "/".to_string());
     let prefix = params.prefix.as_deref().unwrap_or("");
 
-    let mut prefixes = HashSet::new();
+    let mut cursor = params.cursor.as_deref().map(decode_cursor).transpose()?;
     let mut filtered_objects = Vec::new();
+    let mut prefixes = BTreeSet::new();
+    let mut next_cursor = None;
 
-    for obj in objects {
-        if let Some(rest) = obj.key.strip_prefix(prefix) {
-            if let Some(idx) = rest.find(&delimiter) {
-                let folder = format!("{}{}{}", prefix, &rest[..idx], delimiter);
-                prefixes.insert(folder);
-            } else {
-                filtered_objects.push(obj);
+    'pages: loop {
+        let page = state
+            .metadata
+            .list(
+                params.prefix.as_deref(),
+                cursor.as_ref(),
+                sort,
+                order,
+                Some(limit + 1),
+            )
+            .await?;
+
+        let exhausted = page.len() as i64 <= limit;
+
+        for obj in page {
+            let Some(rest) = obj.key.strip_prefix(prefix) else {
+                cursor = Some(ListCursor::after(&obj, sort));
+                continue;
+            };
+
+            let folder = rest
+                .find(&delimiter)
+                .map(|idx| format!("{}{}{}", prefix, &rest[..idx], delimiter));
+
+            if folder
+                .as_ref()
+                .is_none_or(|folder| !prefixes.contains(folder))
+                && filtered_objects.len() + prefixes.len() >= limit as usize
+            {
+                next_cursor = cursor.as_ref().map(encode_cursor);
+                break 'pages;
+            }
+
+            match folder {
+                Some(folder) => {
+                    cursor = Some(match sort {
+                        SortField::Key => skip_prefix(&folder, &obj.key, order),
+                        _ => ListCursor::after(&obj, sort),
+                    });
+                    prefixes.insert(folder);
+                }
+                None => {
+                    cursor = Some(ListCursor::after(&obj, sort));
+                    filtered_objects.push(obj);
+                }
             }
         }
+
+        if exhausted {
+            break;
+        }
     }
 
     let total = filtered_objects.len();
-    let mut prefix_vec: Vec<String> = prefixes.into_iter().collect();
-    prefix_vec.sort();
+    let prefix_vec: Vec<String> = prefixes.into_iter().collect();
 
     tracing::info!("Found {} objects and {} prefixes", total, prefix_vec.len());
 
@@ -165,28 +1633,127 @@ pub async fn list_objects(
         objects: filtered_objects,
         total,
         prefixes: prefix_vec,
+        next_cursor,
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/objects.ndjson",
+    tag = "objects",
+    params(StreamQuery),
+    responses(
+        (status = 200, description = "Every matching object as newline-delimited JSON", body = ObjectMetadata, content_type = "application/x-ndjson"),
+    )
+)]
+pub async fn stream_objects(
+    State(state): State<AppState>,
+    Query(params): Query<StreamQuery>,
+) -> Result<Response> {
+    tracing::info!("STREAM LIST request with prefix: {:?}", params.prefix);
+
+    let rows = state.metadata.stream(
+        params.prefix,
+        params.sort.unwrap_or_default(),
+        params.order.unwrap_or_default(),
+    );
+
+    let lines = futures_util::stream::unfold(rows, |mut rows| async move {
+        let item = rows.recv().await?;
+        let line = item.map(|metadata| {
+            let mut line = serde_json::to_vec(&metadata).unwrap();
+            line.push(b'\n');
+            line
+        });
+        Some((line, rows))
+    });
+
+    Ok(Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(lines))
+        .unwrap())
+}
+
+fn skip_prefix(folder: &str, key: &str, order: SortOrder) -> ListCursor {
+    let key = match order {
+        SortOrder::Asc => format!("{}{}", folder, char::MAX).max(key.to_string()),
+        SortOrder::Desc => folder.to_string(),
+    };
+
+    ListCursor { key, value: None }
+}
+
+fn encode_cursor(cursor: &ListCursor) -> String {
+    URL_SAFE_NO_PAD.encode(serde_json::to_vec(cursor).unwrap())
+}
+
+fn decode_cursor(cursor: &str) -> Result<ListCursor> {
+    URL_SAFE_NO_PAD
+        .decode(cursor)
+        .ok()
+        .and_then(|cursor| serde_json::from_slice(&cursor).ok())
+        .ok_or_else(|| AppError::BadRequest("invalid cursor".to_string()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "objects",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching objects", body = SearchResponse),
+        (status = 400, description = "Invalid filter", body = ErrorResponse),
+    )
+)]
 pub async fn search_objects(
     State(state): State<AppState>,
     Query(params): Query<SearchQuery>,
 ) -> Result<Json<SearchResponse>> {
     tracing::info!(
-        "SEARCH request with params: key={:?}, content_type={:?}, min_size={:?}, max_size={:?}",
+        "SEARCH request with params: key={:?}, key_glob={:?}, etag={:?}, content_type={:?}, min_size={:?}, max_size={:?}, accessed_before={:?}, accessed_after={:?}",
         params.key,
+        params.key_glob,
+        params.etag,
         params.content_type,
         params.min_size,
-        params.max_size
+        params.max_size,
+        params.accessed_before,
+        params.accessed_after
     );
 
+    let key_glob = params
+        .key_glob
+        .as_deref()
+        .map(|pattern| {
+            GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| AppError::BadRequest(format!("invalid key_glob: {}", e)))
+        })
+        .transpose()?;
+
+    let etag = params
+        .etag
+        .as_deref()
+        .map(|etag| etag.trim_matches('"').to_ascii_lowercase());
+
+    let filter = SearchFilter {
+        key_pattern: params.key.as_deref(),
+        key_glob: key_glob.as_ref(),
+        etag: etag.as_deref(),
+        content_type: params.content_type.as_deref(),
+        min_size: params.min_size,
+        max_size: params.max_size,
+        accessed_before: params.accessed_before,
+        accessed_after: params.accessed_after,
+    };
+
     let objects = state
         .metadata
         .search(
-            params.key.as_deref(),
-            params.content_type.as_deref(),
-            params.min_size,
-            params.max_size,
+            &filter,
+            params.sort.unwrap_or(SortField::CreatedAt),
+            params.order.unwrap_or(SortOrder::Desc),
             params.limit,
         )
         .await?;
@@ -198,26 +1765,222 @@ pub async fn search_objects(
     Ok(Json(SearchResponse { objects, total }))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/objects/{key}",
+    tag = "objects",
+    params(
+        ("key" = String, Path, description = "Object key, may contain slashes"),
+        VersionQuery,
+        ("If-Match" = Option<String>, Header, description = "Only delete an object with this ETag"),
+        ("x-lila-if-generation-match" = Option<i64>, Header, description = "Only delete this generation"),
+    ),
+    responses(
+        (status = 200, description = "Object deleted", body = Object, example = json!({"success": true})),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+        (status = 412, description = "Precondition failed", body = ErrorResponse),
+        (status = 423, description = "Object is under retention", body = ErrorResponse),
+    )
+)]
 pub async fn delete_object(
     State(state): State<AppState>,
     Path(key): Path<String>,
+    Query(params): Query<VersionQuery>,
+    headers: HeaderMap,
 ) -> Result<Json<serde_json::Value>> {
     tracing::info!("DELETE request for object: {}", key);
 
-    state.storage.delete(&key).await?;
-    tracing::debug!("File deleted from storage");
+    let if_match = headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok());
+    let if_generation = generation_condition(&headers)?;
 
-    let deleted = state.metadata.delete(&key).await?;
+    if if_match.is_some() || if_generation.is_some() {
+        let current = state.metadata.get(&key).await?;
 
-    if !deleted {
-        tracing::warn!("Metadata for {} not found", key);
-        return Err(AppError::NotFound(key));
+        if let Some(expected) = if_generation {
+            check_generation(&key, current.as_ref(), expected)?;
+        }
+
+        if let Some(condition) = if_match {
+            check_if_match(&key, current.as_ref(), condition)?;
+        }
+    }
+
+    if let Some(version_id) = params.version_id {
+        return delete_version(&state, &key, &version_id).await;
     }
 
+    delete_key(&state, &key).await?;
+
     tracing::info!("Object {} deleted successfully", key);
     Ok(Json(serde_json::json!({ "success": true })))
 }
 
+async fn delete_version(
+    state: &AppState,
+    key: &str,
+    version_id: &str,
+) -> Result<Json<serde_json::Value>> {
+    let current = state
+        .metadata
+        .get(key)
+        .await?
+        .filter(|current| current.id == version_id);
+
+    if let Some(current) = current {
+        check_retention(&current)?;
+        state.metadata.delete(key).await?;
+        match state.storage.delete(key).await {
+            Ok(()) => {}
+            Err(AppError::NotFound(_)) => tracing::warn!("Blob of {} was already missing", key),
+            Err(e) => {
+                restore_metadata(state, &current).await;
+                return Err(e);
+            }
+        }
+        state.publish(EventOp::Delete, &current).await;
+        let promoted = versions::promote_latest(state, key).await?;
+
+        tracing::info!("Current version {} of {} deleted", version_id, key);
+        return Ok(Json(serde_json::json!({
+            "success": true,
+            "promoted_version_id": promoted.map(|v| v.id)
+        })));
+    }
+
+    if let Some(version) = state.metadata.get_version(key, version_id).await? {
+        check_retention(&version)?;
+    }
+
+    if !state.metadata.delete_version(key, version_id).await? {
+        return Err(AppError::NotFound(format!(
+            "{}?version_id={}",
+            key, version_id
+        )));
+    }
+    state.storage.delete_version(key, version_id).await?;
+
+    tracing::info!("Version {} of {} deleted", version_id, key);
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+async fn delete_key(state: &AppState, key: &str) -> Result<()> {
+    ensure_unlocked(state, key).await?;
+
+    let current = state.metadata.get(key).await?;
+    let removed = match &current {
+        Some(current) => remove_object(state, current).await?,
+        None => false,
+    };
+
+    if !removed {
+        tracing::warn!("Metadata for {} not found", key);
+        return Err(AppError::NotFound(key.to_string()));
+    }
+
+    tracing::debug!("File deleted from storage");
+    Ok(())
+}
+
+async fn remove_object(state: &AppState, current: &ObjectMetadata) -> Result<bool> {
+    if !state.metadata.delete(&current.key).await? {
+        return Ok(false);
+    }
+
+    if let Err(e) = remove_blob(state, current).await {
+        restore_metadata(state, current).await;
+        return Err(e);
+    }
+
+    state.publish(EventOp::Delete, current).await;
+    Ok(true)
+}
+
+async fn remove_blob(state: &AppState, current: &ObjectMetadata) -> Result<()> {
+    let result = async {
+        if trash::move_to_trash(state, current).await? {
+            return Ok(());
+        }
+
+        if versions::archive(state, current).await? {
+            return Ok(());
+        }
+
+        state.storage.delete(&current.key).await
+    }
+    .await;
+
+    match result {
+        Err(AppError::NotFound(_)) => {
+            tracing::warn!("Blob of {} was already missing", current.key);
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+async fn restore_metadata(state: &AppState, current: &ObjectMetadata) {
+    if let Err(e) = state.metadata.insert(current).await {
+        tracing::error!("Failed to restore metadata of {}: {}", current.key, e);
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/objects:batchDelete",
+    tag = "objects",
+    request_body(content = Vec<String>, description = "Keys to delete"),
+    responses(
+        (status = 200, description = "Per-key deletion results", body = BatchDeleteResponse),
+        (status = 400, description = "Too many keys", body = ErrorResponse),
+    )
+)]
+pub async fn batch_delete(
+    State(state): State<AppState>,
+    Json(keys): Json<Vec<String>>,
+) -> Result<Json<BatchDeleteResponse>> {
+    tracing::info!("BATCH DELETE request for {} objects", keys.len());
+
+    if keys.len() > MAX_BATCH_SIZE {
+        return Err(AppError::BadRequest(format!(
+            "at most {} keys may be deleted per request",
+            MAX_BATCH_SIZE
+        )));
+    }
+
+    let mut results = Vec::with_capacity(keys.len());
+    let mut deleted = 0;
+
+    for key in keys {
+        let (status, error) = match delete_key(&state, &key).await {
+            Ok(()) => {
+                deleted += 1;
+                (BatchDeleteStatus::Deleted, None)
+            }
+            Err(AppError::NotFound(_)) => (BatchDeleteStatus::NotFound, None),
+            Err(e) => {
+                tracing::error!("Failed to delete {}: {}", key, e);
+                (BatchDeleteStatus::Error, Some(e.to_string()))
+            }
+        };
+
+        results.push(BatchDeleteResult { key, status, error });
+    }
+
+    tracing::info!("Batch deleted {} of {} objects", deleted, results.len());
+
+    Ok(Json(BatchDeleteResponse { results, deleted }))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/folders/{prefix}",
+    tag = "objects",
+    params(("prefix" = String, Path, description = "Key prefix, may contain slashes")),
+    responses(
+        (status = 200, description = "Objects under the prefix deleted", body = Object, example = json!({"success": true, "deleted": 3})),
+        (status = 423, description = "An object is under retention", body = ErrorResponse),
+    )
+)]
 pub async fn delete_folder(
     State(state): State<AppState>,
     Path(prefix): Path<String>,
@@ -230,13 +1993,41 @@ pub async fn delete_folder(
         prefix
     };
 
-    let objects = state.metadata.list(Some(&prefix), None).await?;
-
-    for obj in &objects {
-        state.storage.delete(&obj.key).await?;
+    if let Some(retained) = state.metadata.first_retained(&prefix, Utc::now()).await? {
+        check_retention(&retained)?;
     }
 
-    let deleted = state.metadata.delete_by_prefix(&prefix).await?;
+    let _permit = state.limits.bulk().await?;
+    let mut cursor = None;
+    let mut deleted = 0;
+
+    loop {
+        let page = state
+            .metadata
+            .list(
+                Some(&prefix),
+                cursor.as_ref(),
+                SortField::Key,
+                SortOrder::Asc,
+                Some(DEFAULT_LIST_LIMIT),
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        cursor = Some(ListCursor::after(last, SortField::Key));
+        let exhausted = (page.len() as i64) < DEFAULT_LIST_LIMIT;
+
+        for obj in &page {
+            if remove_object(&state, obj).await? {
+                deleted += 1;
+            }
+        }
+
+        if exhausted {
+            break;
+        }
+    }
 
     tracing::info!("Deleted {} objects with prefix {}", deleted, prefix);
     Ok(Json(serde_json::json!({
@@ -245,6 +2036,83 @@ pub async fn delete_folder(
     })))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/objects/{key}/verify",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Whether the blob on disk still matches the stored etag", body = VerifyResponse),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+    )
+)]
+pub async fn verify_object(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+) -> Result<Json<VerifyResponse>> {
+    let key = path
+        .strip_suffix("/verify")
+        .ok_or_else(|| AppError::NotFound(path.clone()))?;
+
+    tracing::info!("VERIFY request for object: {}", key);
+
+    let metadata = state
+        .metadata
+        .get(key)
+        .await?
+        .ok_or_else(|| AppError::NotFound(key.to_string()))?;
+
+    let started = Instant::now();
+
+    let actual_etag = match state.storage.blob_size(key).await? {
+        Some(_) => match state.storage.compute_etag(&metadata).await {
+            Ok(etag) => Some(etag),
+            Err(AppError::Io(e)) => {
+                tracing::warn!("Failed to read blob for {}: {}", key, e);
+                None
+            }
+            Err(e) => return Err(e),
+        },
+        None => None,
+    };
+
+    let status = if actual_etag.as_deref() == Some(metadata.etag.as_str()) {
+        VerifyStatus::Ok
+    } else {
+        tracing::warn!(
+            "Object {} is corrupt: expected etag {}, found {:?}",
+            key,
+            metadata.etag,
+            actual_etag
+        );
+        VerifyStatus::Corrupt
+    };
+
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    tracing::info!("Verified {} in {} ms: {:?}", key, duration_ms, status);
+
+    Ok(Json(VerifyResponse {
+        key: key.to_string(),
+        status,
+        etag_algorithm: metadata.etag_algorithm,
+        expected_etag: metadata.etag,
+        actual_etag,
+        size: metadata.size,
+        duration_ms,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/info/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Object metadata and its storage path", body = ObjectInfo),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+    )
+)]
 pub async fn get_object_info(
     State(state): State<AppState>,
     Path(key): Path<String>,
@@ -257,7 +2125,175 @@ pub async fn get_object_info(
         .await?
         .ok_or_else(|| AppError::NotFound(key.clone()))?;
 
-    let path = state.storage.get_object_path_string(&key);
+    let path = state.storage.get_object_path_string(&key).await;
 
     Ok(Json(ObjectInfo { metadata, path }))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path as FsPath, PathBuf};
+
+    use axum::body::Bytes;
+    use sqlx::{Connection, SqliteConnection};
+    use tokio::io::AsyncReadExt;
+
+    use super::*;
+    use crate::storage::SqliteMetadataStore;
+
+    const KEY: &str = "reports/q3.txt";
+
+    struct Fixture {
+        _dir: tempfile::TempDir,
+        state: AppState,
+        database_url: String,
+        data: PathBuf,
+    }
+
+    async fn fixture() -> Fixture {
+        let dir = tempfile::tempdir().unwrap();
+        let data = dir.path().join("data");
+        let database_url = format!("sqlite:{}", dir.path().join("metadata.db").display());
+        let config: Config = toml::from_str(&format!(
+            "server_host = \"127.0.0.1\"\nserver_port = 0\nstorage_path = {:?}\ndatabase_url = {:?}\n",
+            data.display().to_string(),
+            database_url,
+        ))
+        .unwrap();
+
+        let metadata: MetadataStore = Arc::new(
+            SqliteMetadataStore::new(&database_url, &config.database)
+                .await
+                .unwrap(),
+        );
+        let storage = FileStorage::new(config.storage_path.volumes(), Default::default(), 0)
+            .await
+            .unwrap();
+
+        Fixture {
+            state: AppState::new(&config, metadata, storage, "secret".to_string()),
+            _dir: dir,
+            database_url,
+            data,
+        }
+    }
+
+    async fn stage(state: &AppState, body: &'static [u8]) -> (Staged, ObjectMetadata) {
+        let stream =
+            futures_util::stream::iter([Ok::<_, std::io::Error>(Bytes::from_static(body))]);
+        let staged = state
+            .storage
+            .stage_stream(KEY, stream, usize::MAX, &ExpectedChecksums::default())
+            .await
+            .unwrap();
+        state
+            .storage
+            .finalize_staged(&staged, "text/plain")
+            .await
+            .unwrap();
+
+        let now = Utc::now();
+        let metadata = ObjectMetadata {
+            id: Uuid::new_v4().to_string(),
+            key: KEY.to_string(),
+            size: staged.size,
+            content_type: "text/plain".to_string(),
+            etag: staged.etag.clone(),
+            etag_algorithm: state.storage.etag_algorithm(),
+            created_at: now,
+            public: false,
+            expires_at: None,
+            retain_until: None,
+            user_metadata: BTreeMap::new(),
+            cache_control: None,
+            cache_expires: None,
+            stored_size: None,
+            chunks: None,
+            accessed_at: None,
+            access_count: 0,
+            updated_at: Some(now),
+            generation: state.metadata.next_generation(KEY).await.unwrap(),
+        };
+
+        (staged, metadata)
+    }
+
+    async fn stored_body(state: &AppState) -> Vec<u8> {
+        let mut body = Vec::new();
+        state
+            .storage
+            .open(KEY)
+            .await
+            .unwrap()
+            .read_to_end(&mut body)
+            .await
+            .unwrap();
+        body
+    }
+
+    fn files(root: &FsPath) -> Vec<PathBuf> {
+        let mut found = Vec::new();
+        for entry in std::fs::read_dir(root).unwrap() {
+            let path = entry.unwrap().path();
+            if path.is_dir() {
+                found.extend(files(&path));
+            } else {
+                found.push(path);
+            }
+        }
+        found
+    }
+
+    async fn assert_rolled_back(fixture: &Fixture, original: &ObjectMetadata) {
+        let current = fixture.state.metadata.get(KEY).await.unwrap().unwrap();
+        assert_eq!(current.etag, original.etag);
+        assert_eq!(current.generation, original.generation);
+        assert_eq!(stored_body(&fixture.state).await, b"original");
+
+        let blob = fixture.state.storage.get_object_path(KEY).await;
+        assert_eq!(files(&fixture.data), vec![blob]);
+    }
+
+    #[tokio::test]
+    async fn stale_generation_discards_the_staged_blob() {
+        let fixture = fixture().await;
+        let state = &fixture.state;
+
+        let (staged, original) = stage(state, b"original").await;
+        commit_object(state, staged, &original, None).await.unwrap();
+
+        let (staged, replacement) = stage(state, b"replacement").await;
+        let result = commit_object(state, staged, &replacement, Some(0)).await;
+
+        assert!(matches!(result, Err(AppError::PreconditionFailed(_))));
+        assert_rolled_back(&fixture, &original).await;
+    }
+
+    #[tokio::test]
+    async fn metadata_failure_keeps_the_previous_object() {
+        let fixture = fixture().await;
+        let state = &fixture.state;
+
+        let (staged, original) = stage(state, b"original").await;
+        commit_object(state, staged, &original, None).await.unwrap();
+
+        let mut conn = SqliteConnection::connect(&fixture.database_url)
+            .await
+            .unwrap();
+        for event in ["INSERT", "UPDATE"] {
+            sqlx::query(&format!(
+                "CREATE TRIGGER reject_{event} BEFORE {event} ON objects
+                 BEGIN SELECT RAISE(ABORT, 'metadata unavailable'); END"
+            ))
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        }
+
+        let (staged, replacement) = stage(state, b"replacement").await;
+        let result = commit_object(state, staged, &replacement, None).await;
+
+        assert!(matches!(result, Err(AppError::Database(_))));
+        assert_rolled_back(&fixture, &original).await;
+    }
+}
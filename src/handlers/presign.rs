@@ -0,0 +1,109 @@
+use axum::{Extension, Json, extract::State, http::Method};
+use chrono::{Duration, Utc};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use serde::Deserialize;
+use utoipa::ToSchema;
+
+use crate::{
+    auth,
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{ApiToken, ErrorResponse, PresignResponse, TokenScope},
+};
+
+const DEFAULT_EXPIRES_IN: i64 = 3600;
+const MAX_EXPIRES_IN: i64 = 7 * 24 * 3600;
+
+const KEY_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Deserialize, ToSchema)]
+pub struct PresignRequest {
+    key: String,
+    method: Option<String>,
+    expires_in: Option<i64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/presign",
+    tag = "objects",
+    request_body = PresignRequest,
+    responses(
+        (status = 200, description = "Presigned URL", body = PresignResponse),
+        (status = 400, description = "Invalid method or expiry", body = ErrorResponse),
+    )
+)]
+pub async fn presign(
+    State(state): State<AppState>,
+    token: Option<Extension<ApiToken>>,
+    Json(request): Json<PresignRequest>,
+) -> Result<Json<PresignResponse>> {
+    let method = match request.method.as_deref().unwrap_or("GET") {
+        m if m.eq_ignore_ascii_case("GET") => Method::GET,
+        m if m.eq_ignore_ascii_case("PUT") => Method::PUT,
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "cannot presign method {}",
+                other
+            )));
+        }
+    };
+
+    if method != Method::GET
+        && let Some(Extension(token)) = &token
+        && !token.scope.allows(TokenScope::Write)
+    {
+        return Err(AppError::Forbidden(format!(
+            "token scope {} cannot presign {} requests",
+            token.scope, method
+        )));
+    }
+
+    let expires_in = request.expires_in.unwrap_or(DEFAULT_EXPIRES_IN);
+    if !(1..=MAX_EXPIRES_IN).contains(&expires_in) {
+        return Err(AppError::BadRequest(format!(
+            "expires_in must be between 1 and {} seconds",
+            MAX_EXPIRES_IN
+        )));
+    }
+
+    tracing::info!(
+        "PRESIGN request for {} {} ({}s)",
+        method,
+        request.key,
+        expires_in
+    );
+
+    let expires_at = Utc::now() + Duration::seconds(expires_in);
+    let signature = auth::sign(
+        &state.presign_secret,
+        &method,
+        state.bucket_name(),
+        &request.key,
+        expires_at.timestamp(),
+    );
+
+    let encoded_key = request
+        .key
+        .split('/')
+        .map(|segment| utf8_percent_encode(segment, KEY_SEGMENT).to_string())
+        .collect::<Vec<_>>()
+        .join("/");
+
+    let url = format!(
+        "{}?expires={}&signature={}",
+        state.api_path(&format!("objects/{}", encoded_key)),
+        expires_at.timestamp(),
+        signature
+    );
+
+    Ok(Json(PresignResponse {
+        url,
+        method: method.to_string(),
+        expires_at,
+    }))
+}
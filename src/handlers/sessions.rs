@@ -0,0 +1,132 @@
+use axum::{
+    Json,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{Duration, Utc};
+
+use crate::{
+    auth::{self, SESSION_COOKIE},
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{ApiToken, ErrorResponse, SessionInfo, TokenLoginRequest},
+};
+
+const TOKEN_SESSION_HOURS: i64 = 12;
+
+#[utoipa::path(
+    post,
+    path = "/auth/token",
+    tag = "auth",
+    request_body = TokenLoginRequest,
+    responses(
+        (status = 204, description = "Session started, the session cookie is set"),
+        (status = 401, description = "Token not accepted", body = ErrorResponse),
+    ),
+    security(())
+)]
+pub async fn token_login(
+    State(state): State<AppState>,
+    Json(request): Json<TokenLoginRequest>,
+) -> Result<Response> {
+    let api_token = state
+        .metadata
+        .find_token(&auth::hash_token(request.token.trim()))
+        .await?
+        .ok_or_else(|| {
+            tracing::warn!("Token sign-in failed: invalid token");
+            AppError::Unauthorized
+        })?;
+
+    let principal = ApiToken {
+        created_at: Utc::now(),
+        ..api_token
+    };
+    let expires_at = principal.created_at + Duration::hours(TOKEN_SESSION_HOURS);
+    let session = auth::generate_token();
+
+    state
+        .metadata
+        .create_session(
+            &auth::hash_token(&session),
+            &format!("token:{}", principal.id),
+            &principal,
+            expires_at,
+        )
+        .await?;
+
+    tracing::info!(
+        "Session started for token {} with {} scope",
+        principal.name,
+        principal.scope
+    );
+
+    let cookie =
+        auth::session_cookie(&session, TOKEN_SESSION_HOURS * 3600, secure_cookies(&state))?;
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    response.headers_mut().insert(header::SET_COOKIE, cookie);
+    Ok(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/session",
+    tag = "auth",
+    responses(
+        (status = 200, description = "Current session", body = SessionInfo),
+        (status = 401, description = "No valid session", body = ErrorResponse),
+    ),
+    security(("session" = []))
+)]
+pub async fn session(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<SessionInfo>> {
+    let session = auth::cookie(&headers, SESSION_COOKIE).ok_or(AppError::Unauthorized)?;
+
+    let info = state
+        .metadata
+        .find_session(&auth::hash_token(session))
+        .await?
+        .ok_or(AppError::Unauthorized)?;
+
+    Ok(Json(info))
+}
+
+#[utoipa::path(
+    post,
+    path = "/auth/logout",
+    tag = "auth",
+    responses(
+        (status = 204, description = "Session ended"),
+    ),
+    security(("session" = []))
+)]
+pub async fn logout(State(state): State<AppState>, headers: HeaderMap) -> Result<Response> {
+    if let Some(session) = auth::cookie(&headers, SESSION_COOKIE)
+        && state
+            .metadata
+            .delete_session(&auth::hash_token(session))
+            .await?
+    {
+        tracing::info!("Session ended");
+    }
+
+    let mut response = StatusCode::NO_CONTENT.into_response();
+    response.headers_mut().insert(
+        header::SET_COOKIE,
+        auth::session_cookie("", 0, secure_cookies(&state))?,
+    );
+    Ok(response)
+}
+
+fn secure_cookies(state: &AppState) -> bool {
+    state.config.tls_cert_path.is_some()
+        || state
+            .config
+            .oidc
+            .as_ref()
+            .is_some_and(|oidc| oidc.redirect_url.starts_with("https://"))
+}
@@ -1,7 +1,22 @@
-use axum::{Json, extract::State};
+use axum::{
+    Json,
+    extract::{Path, State},
+};
 
-use crate::{error::Result, handlers::objects::AppState, models::StatsResponse};
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::{PrefixStatsResponse, StatsResponse},
+};
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats",
+    tag = "stats",
+    responses(
+        (status = 200, description = "Object count and total size", body = StatsResponse),
+    )
+)]
 pub async fn get_stats(State(state): State<AppState>) -> Result<Json<StatsResponse>> {
     tracing::info!("GET request for stats");
 
@@ -31,3 +46,35 @@ pub async fn get_stats(State(state): State<AppState>) -> Result<Json<StatsRespon
         }
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stats/prefix/{prefix}",
+    tag = "stats",
+    params(("prefix" = String, Path, description = "Key prefix, may contain slashes")),
+    responses(
+        (status = 200, description = "Object count, total size and largest object under the prefix", body = PrefixStatsResponse),
+    )
+)]
+pub async fn get_prefix_stats(
+    State(state): State<AppState>,
+    Path(prefix): Path<String>,
+) -> Result<Json<PrefixStatsResponse>> {
+    tracing::info!("GET request for stats of prefix: {}", prefix);
+
+    let (total_objects, total_size, largest) = state.metadata.get_prefix_stats(&prefix).await?;
+
+    tracing::info!(
+        "Prefix {} holds {} objects, {} bytes",
+        prefix,
+        total_objects,
+        total_size
+    );
+
+    Ok(Json(PrefixStatsResponse {
+        prefix,
+        total_objects,
+        total_size,
+        largest,
+    }))
+}
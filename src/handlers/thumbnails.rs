@@ -0,0 +1,76 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::Response,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    images::{self, Variant},
+    models::ErrorResponse,
+    openapi::Binary,
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ThumbnailQuery {
+    w: Option<u32>,
+    h: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/thumb/{key}",
+    tag = "objects",
+    params(
+        ("key" = String, Path, description = "Object key, may contain slashes"),
+        ThumbnailQuery,
+        ("If-None-Match" = Option<String>, Header, description = "Return 304 when the ETag matches"),
+    ),
+    responses(
+        (status = 200, description = "Thumbnail in the source image format", body = Binary, content_type = "image/*"),
+        (status = 304, description = "Not modified"),
+        (status = 400, description = "Invalid dimensions", body = ErrorResponse),
+        (status = 404, description = "Object not found", body = ErrorResponse),
+        (status = 415, description = "Object is not a supported image", body = ErrorResponse),
+    ),
+    security((), ("bearer" = []))
+)]
+pub async fn get_thumbnail(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+    Query(params): Query<ThumbnailQuery>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    tracing::info!("THUMBNAIL request for object: {}", key);
+
+    let metadata = state
+        .metadata
+        .get(&key)
+        .await?
+        .filter(|metadata| {
+            metadata
+                .expires_at
+                .is_none_or(|expires_at| expires_at > Utc::now())
+        })
+        .ok_or_else(|| AppError::NotFound(key.clone()))?;
+
+    let size = state.config.images.thumbnail_size;
+    let (width, height) = match (params.w, params.h) {
+        (None, None) => (Some(size), Some(size)),
+        dimensions => dimensions,
+    };
+
+    let variant = Variant {
+        width,
+        height,
+        format: images::source_format(&metadata)?,
+        quality: state.config.images.default_quality,
+    };
+
+    images::serve(&state, &metadata, false, variant, &headers).await
+}
@@ -0,0 +1,163 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    auth::{self, CONFIG_TOKEN_ID},
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{
+        ApiToken, CreateTokenRequest, CreatedToken, ErrorResponse, ListTokensResponse,
+        UpdateTokenRequest,
+    },
+    rate_limit, signing,
+};
+
+const MAX_NAME_LENGTH: usize = 128;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/tokens",
+    tag = "tokens",
+    responses(
+        (status = 200, description = "API tokens", body = ListTokensResponse),
+    )
+)]
+pub async fn list_tokens(State(state): State<AppState>) -> Result<Json<ListTokensResponse>> {
+    tracing::info!("LIST tokens request");
+
+    let tokens = state.metadata.list_tokens().await?;
+    let total = tokens.len();
+
+    Ok(Json(ListTokensResponse { tokens, total }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/admin/tokens",
+    tag = "tokens",
+    request_body = CreateTokenRequest,
+    responses(
+        (status = 201, description = "Token created, the secret is only returned once", body = CreatedToken),
+        (status = 400, description = "Invalid name or rate limit", body = ErrorResponse),
+    )
+)]
+pub async fn create_token(
+    State(state): State<AppState>,
+    Json(request): Json<CreateTokenRequest>,
+) -> Result<(StatusCode, Json<CreatedToken>)> {
+    tracing::info!("CREATE token request: {} ({})", request.name, request.scope);
+
+    let name = request.name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "token name must be between 1 and {} characters",
+            MAX_NAME_LENGTH
+        )));
+    }
+
+    if let Some(rate_limit) = &request.rate_limit {
+        rate_limit::validate(rate_limit)?;
+    }
+
+    let token = ApiToken {
+        id: Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        scope: request.scope,
+        rate_limit: request.rate_limit,
+        created_at: Utc::now(),
+    };
+    let secret = auth::generate_token();
+
+    state
+        .metadata
+        .create_token(
+            &token,
+            &auth::hash_token(&secret),
+            &signing::derive_key(&secret),
+        )
+        .await?;
+
+    tracing::info!("Token {} ({}) created", token.id, token.name);
+    Ok((StatusCode::CREATED, Json(CreatedToken { token, secret })))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/admin/tokens/{id}",
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token id")),
+    request_body = UpdateTokenRequest,
+    responses(
+        (status = 200, description = "Updated token", body = ApiToken),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+    )
+)]
+pub async fn update_token(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateTokenRequest>,
+) -> Result<Json<ApiToken>> {
+    tracing::info!("UPDATE token request: {}", id);
+
+    if let Some(rate_limit) = &request.rate_limit {
+        rate_limit::validate(rate_limit)?;
+    }
+
+    if !state
+        .metadata
+        .set_token_rate_limit(&id, request.rate_limit)
+        .await?
+    {
+        return Err(AppError::TokenNotFound(id));
+    }
+
+    let token = state
+        .metadata
+        .find_token_by_id(&id)
+        .await?
+        .map(|(token, _)| token)
+        .ok_or(AppError::TokenNotFound(id))?;
+
+    tracing::info!(
+        "Rate limit for token {} set to {:?}",
+        token.id,
+        token.rate_limit
+    );
+    Ok(Json(token))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/admin/tokens/{id}",
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token id")),
+    responses(
+        (status = 204, description = "Token revoked"),
+        (status = 403, description = "The configured token cannot be revoked", body = ErrorResponse),
+        (status = 404, description = "Token not found", body = ErrorResponse),
+    )
+)]
+pub async fn delete_token(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<StatusCode> {
+    tracing::info!("DELETE token request: {}", id);
+
+    if id == CONFIG_TOKEN_ID {
+        return Err(AppError::Conflict(
+            "the config token is managed through the auth_token setting".to_string(),
+        ));
+    }
+
+    if !state.metadata.delete_token(&id).await? {
+        return Err(AppError::TokenNotFound(id));
+    }
+
+    tracing::info!("Token {} revoked", id);
+    Ok(StatusCode::NO_CONTENT)
+}
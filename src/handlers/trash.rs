@@ -0,0 +1,140 @@
+use axum::{
+    Json,
+    extract::{Path, Query, State},
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{ErrorResponse, EventOp, ListTrashResponse, ObjectMetadata},
+};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrashQuery {
+    limit: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trash",
+    tag = "trash",
+    params(TrashQuery),
+    responses(
+        (status = 200, description = "Deleted objects awaiting purge", body = ListTrashResponse),
+    )
+)]
+pub async fn list_trash(
+    State(state): State<AppState>,
+    Query(params): Query<TrashQuery>,
+) -> Result<Json<ListTrashResponse>> {
+    tracing::info!("LIST TRASH request");
+
+    let objects = state.metadata.list_trash(params.limit).await?;
+    let total = objects.len();
+
+    tracing::info!("Found {} objects in trash", total);
+    Ok(Json(ListTrashResponse { objects, total }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/trash/{key}/restore",
+    tag = "trash",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Object restored", body = ObjectMetadata),
+        (status = 404, description = "Object not in trash", body = ErrorResponse),
+        (status = 409, description = "A live object already exists at the key", body = ErrorResponse),
+    )
+)]
+pub async fn trash_action(
+    State(state): State<AppState>,
+    Path(path): Path<String>,
+) -> Result<Json<ObjectMetadata>> {
+    let Some(key) = path.strip_suffix("/restore") else {
+        return Err(AppError::BadRequest(format!(
+            "unknown trash action for {}",
+            path
+        )));
+    };
+
+    tracing::info!("RESTORE request for object: {}", key);
+
+    let entry = state
+        .metadata
+        .get_trash(key)
+        .await?
+        .ok_or_else(|| AppError::NotFound(key.to_string()))?;
+
+    if state.metadata.get(key).await?.is_some() {
+        return Err(AppError::Conflict(format!(
+            "{} already exists, delete it before restoring",
+            key
+        )));
+    }
+
+    let metadata = ObjectMetadata {
+        updated_at: Some(Utc::now()),
+        generation: state.metadata.next_generation(key).await?,
+        ..entry.metadata
+    };
+
+    state.storage.restore_from_trash(key, &metadata.id).await?;
+    state.metadata.insert(&metadata).await?;
+    state.metadata.delete_trash(&metadata.id).await?;
+    state.publish(EventOp::Put, &metadata).await;
+
+    tracing::info!("Object {} restored from trash", key);
+    Ok(Json(metadata))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/trash/{key}",
+    tag = "trash",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Object purged", body = Object, example = json!({"success": true})),
+        (status = 404, description = "Object not in trash", body = ErrorResponse),
+    )
+)]
+pub async fn purge_object(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!("PURGE request for object: {}", key);
+
+    let entry = state
+        .metadata
+        .get_trash(&key)
+        .await?
+        .ok_or_else(|| AppError::NotFound(key.clone()))?;
+
+    state.metadata.delete_trash(&entry.metadata.id).await?;
+    state
+        .storage
+        .delete_trashed(&key, &entry.metadata.id)
+        .await?;
+
+    tracing::info!("Object {} purged from trash", key);
+    Ok(Json(serde_json::json!({ "success": true })))
+}
+
+pub async fn move_to_trash(state: &AppState, current: &ObjectMetadata) -> Result<bool> {
+    if state.trash_retention_hours.is_none() {
+        return Ok(false);
+    }
+
+    state
+        .storage
+        .move_to_trash(&current.key, &current.id)
+        .await?;
+    state.metadata.insert_trash(current).await?;
+
+    tracing::debug!("Moved {} to trash", current.key);
+    Ok(true)
+}
@@ -0,0 +1,393 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use chrono::Utc;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::{self, AppState},
+    models::{ErrorResponse, ObjectMetadata, ResumableUpload},
+    openapi::Binary,
+    storage::Staged,
+};
+
+const TUS_VERSION: &str = "1.0.0";
+const TUS_EXTENSIONS: &str = "creation,termination";
+
+#[utoipa::path(
+    options,
+    path = "/api/v1/tus",
+    tag = "tus",
+    responses(
+        (status = 204, description = "Supported tus version and extensions", headers(
+            ("tus-version" = String),
+            ("tus-extension" = String),
+        )),
+    ),
+    security(())
+)]
+pub async fn options() -> Response {
+    Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("tus-resumable", TUS_VERSION)
+        .header("tus-version", TUS_VERSION)
+        .header("tus-extension", TUS_EXTENSIONS)
+        .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, "*")
+        .header(header::ACCESS_CONTROL_ALLOW_METHODS, "*")
+        .header(header::ACCESS_CONTROL_ALLOW_HEADERS, "*")
+        .header(header::ACCESS_CONTROL_EXPOSE_HEADERS, "*")
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/tus",
+    tag = "tus",
+    params(
+        ("tus-resumable" = String, Header, description = "Protocol version, 1.0.0"),
+        ("upload-length" = i64, Header, description = "Total upload size in bytes"),
+        ("upload-metadata" = Option<String>, Header, description = "Comma-separated base64 metadata, `filename` sets the key"),
+    ),
+    responses(
+        (status = 201, description = "Upload created", headers(("location" = String), ("upload-offset" = i64))),
+        (status = 400, description = "Missing or invalid headers", body = ErrorResponse),
+        (status = 413, description = "Upload exceeds the size limit", body = ErrorResponse),
+        (status = 507, description = "Upload exceeds a quota or the free disk space", body = ErrorResponse),
+    )
+)]
+pub async fn create_upload(State(state): State<AppState>, headers: HeaderMap) -> Result<Response> {
+    check_version(&headers)?;
+
+    let length = headers
+        .get("upload-length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|v| *v >= 0)
+        .ok_or_else(|| AppError::BadRequest("missing or invalid Upload-Length".to_string()))?;
+
+    let max_size = state.max_upload_size * 1024 * 1024;
+    if length as u64 > max_size as u64 {
+        tracing::warn!("TUS upload of {} bytes exceeds the upload limit", length);
+        return Err(AppError::PayloadTooLarge(max_size));
+    }
+
+    let metadata = headers
+        .get("upload-metadata")
+        .and_then(|v| v.to_str().ok())
+        .map(parse_upload_metadata)
+        .unwrap_or_default();
+
+    let key = metadata
+        .iter()
+        .find(|(k, _)| k == "key" || k == "filename")
+        .map(|(_, v)| v.clone())
+        .filter(|v| !v.is_empty())
+        .ok_or_else(|| {
+            AppError::BadRequest("Upload-Metadata must include a key or filename".to_string())
+        })?;
+
+    let content_type = metadata
+        .iter()
+        .find(|(k, _)| k == "content_type" || k == "filetype")
+        .map(|(_, v)| v.clone())
+        .unwrap_or_else(|| objects::guess_content_type(&state, &key));
+
+    tracing::info!("TUS create request for object: {} ({} bytes)", key, length);
+
+    objects::check_quota(&state, &key, length).await?;
+    state.storage.ensure_space(length as u64).await?;
+
+    let upload = ResumableUpload {
+        id: Uuid::new_v4().to_string(),
+        key,
+        content_type,
+        length,
+        offset: 0,
+        created_at: Utc::now(),
+    };
+
+    state.storage.create_resumable(&upload.id).await?;
+    state.metadata.create_resumable(&upload).await?;
+
+    if upload.length == 0 {
+        finish_upload(&state, &upload).await?;
+    }
+
+    tracing::info!("TUS upload {} created for {}", upload.id, upload.key);
+
+    Ok(Response::builder()
+        .status(StatusCode::CREATED)
+        .header("tus-resumable", TUS_VERSION)
+        .header(
+            header::LOCATION,
+            state.api_path(&format!("tus/{}", upload.id)),
+        )
+        .header("upload-offset", "0")
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[utoipa::path(
+    head,
+    path = "/api/v1/tus/{id}",
+    tag = "tus",
+    params(
+        ("id" = String, Path, description = "Upload id"),
+        ("tus-resumable" = String, Header, description = "Protocol version, 1.0.0"),
+    ),
+    responses(
+        (status = 200, description = "Current offset", headers(("upload-offset" = i64), ("upload-length" = i64))),
+        (status = 404, description = "Upload not found"),
+    )
+)]
+pub async fn get_offset(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    check_version(&headers)?;
+
+    tracing::info!("TUS offset request for upload: {}", upload_id);
+
+    let upload = state
+        .metadata
+        .get_resumable(&upload_id)
+        .await?
+        .ok_or_else(|| AppError::UploadNotFound(upload_id.clone()))?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header("tus-resumable", TUS_VERSION)
+        .header("upload-offset", upload.offset.to_string())
+        .header("upload-length", upload.length.to_string())
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/tus/{id}",
+    tag = "tus",
+    params(
+        ("id" = String, Path, description = "Upload id"),
+        ("tus-resumable" = String, Header, description = "Protocol version, 1.0.0"),
+        ("upload-offset" = i64, Header, description = "Offset the body starts at"),
+    ),
+    request_body(content = Binary, content_type = "application/offset+octet-stream"),
+    responses(
+        (status = 204, description = "Chunk accepted", headers(("upload-offset" = i64))),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+        (status = 409, description = "Offset does not match", body = ErrorResponse),
+        (status = 507, description = "Not enough free disk space", body = ErrorResponse),
+    )
+)]
+pub async fn patch_upload(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Response> {
+    check_version(&headers)?;
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+
+    if content_type != "application/offset+octet-stream" {
+        return Err(AppError::UnsupportedMediaType(content_type.to_string()));
+    }
+
+    let offset = headers
+        .get("upload-offset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .ok_or_else(|| AppError::BadRequest("missing or invalid Upload-Offset".to_string()))?;
+
+    tracing::info!("TUS patch for upload: {} at offset {}", upload_id, offset);
+
+    let upload = state
+        .metadata
+        .get_resumable(&upload_id)
+        .await?
+        .ok_or_else(|| AppError::UploadNotFound(upload_id.clone()))?;
+
+    if offset != upload.offset {
+        return Err(AppError::Conflict(format!(
+            "Upload-Offset {} does not match current offset {}",
+            offset, upload.offset
+        )));
+    }
+
+    let remaining = (upload.length - upload.offset) as usize;
+    state.storage.ensure_space(remaining as u64).await?;
+
+    let max_size = remaining.min(state.max_upload_size * 1024 * 1024);
+    let _permit = state.limits.upload().await?;
+    let stream = body.into_data_stream();
+
+    let new_offset = state
+        .storage
+        .append_resumable(&upload_id, offset, stream, max_size)
+        .await?;
+
+    state
+        .metadata
+        .update_resumable_offset(&upload_id, new_offset)
+        .await?;
+
+    tracing::debug!(
+        "TUS upload {} advanced to {} of {} bytes",
+        upload_id,
+        new_offset,
+        upload.length
+    );
+
+    if new_offset == upload.length {
+        finish_upload(&state, &upload).await?;
+    }
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("tus-resumable", TUS_VERSION)
+        .header("upload-offset", new_offset.to_string())
+        .body(Body::empty())
+        .unwrap())
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tus/{id}",
+    tag = "tus",
+    params(
+        ("id" = String, Path, description = "Upload id"),
+        ("tus-resumable" = String, Header, description = "Protocol version, 1.0.0"),
+    ),
+    responses(
+        (status = 204, description = "Upload terminated"),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+    )
+)]
+pub async fn terminate_upload(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response> {
+    check_version(&headers)?;
+
+    tracing::info!("TUS terminate request for upload: {}", upload_id);
+
+    if !state.metadata.delete_resumable(&upload_id).await? {
+        return Err(AppError::UploadNotFound(upload_id));
+    }
+
+    state.storage.remove_upload(&upload_id).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::NO_CONTENT)
+        .header("tus-resumable", TUS_VERSION)
+        .body(Body::empty())
+        .unwrap())
+}
+
+async fn finish_upload(state: &AppState, upload: &ResumableUpload) -> Result<()> {
+    objects::ensure_unlocked(state, &upload.key).await?;
+    objects::check_quota(state, &upload.key, upload.length).await?;
+
+    let generation = state.metadata.next_generation(&upload.key).await?;
+    let staged = state
+        .storage
+        .stage_resumable(&upload.id, &upload.key)
+        .await?;
+
+    let committed = commit_upload(state, upload, staged, generation).await;
+
+    state.metadata.delete_resumable(&upload.id).await?;
+    state.storage.remove_upload(&upload.id).await?;
+    committed?;
+
+    tracing::info!("TUS upload {} completed as {}", upload.id, upload.key);
+    Ok(())
+}
+
+async fn commit_upload(
+    state: &AppState,
+    upload: &ResumableUpload,
+    staged: Staged,
+    generation: i64,
+) -> Result<()> {
+    let layout = match state
+        .storage
+        .finalize_staged(&staged, &upload.content_type)
+        .await
+    {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: upload.key.clone(),
+        size: staged.size,
+        stored_size: layout.stored_size,
+        chunks: layout.chunks,
+        content_type: upload.content_type.clone(),
+        etag: staged.etag.clone(),
+        etag_algorithm: state.storage.etag_algorithm(),
+        created_at: now,
+        public: false,
+        expires_at: None,
+        retain_until: objects::retention_for(state, &upload.key),
+        user_metadata: Default::default(),
+        cache_control: None,
+        cache_expires: None,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    objects::commit_object(state, staged, &metadata, None).await
+}
+
+fn check_version(headers: &HeaderMap) -> Result<()> {
+    match headers.get("tus-resumable").and_then(|v| v.to_str().ok()) {
+        Some(TUS_VERSION) => Ok(()),
+        Some(other) => Err(AppError::PreconditionFailed(format!(
+            "unsupported Tus-Resumable version {}",
+            other
+        ))),
+        None => Err(AppError::BadRequest(
+            "missing Tus-Resumable header".to_string(),
+        )),
+    }
+}
+
+fn parse_upload_metadata(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|pair| {
+            let mut parts = pair.trim().splitn(2, ' ');
+            let key = parts.next()?.trim();
+            if key.is_empty() {
+                return None;
+            }
+            let value = match parts.next() {
+                Some(encoded) => String::from_utf8(STANDARD.decode(encoded.trim()).ok()?).ok()?,
+                None => String::new(),
+            };
+            Some((key.to_string(), value))
+        })
+        .collect()
+}
@@ -0,0 +1,1150 @@
+use axum::{
+    extract::State,
+    response::{Html, IntoResponse},
+};
+
+use crate::handlers::objects::AppState;
+
+pub async fn ui(State(state): State<AppState>) -> impl IntoResponse {
+    let sso = state.config.oidc.is_some();
+
+    Html(PAGE.replace("{sso}", &sso.to_string()))
+}
+
+pub async fn upload_page(State(state): State<AppState>) -> impl IntoResponse {
+    let sso = state.config.oidc.is_some();
+
+    Html(UPLOAD_PAGE.replace("{sso}", &sso.to_string()))
+}
+
+const PAGE: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>lila · browser</title>
+    <style>
+        @font-face {
+            font-family: 'Iosevka Term';
+            src: url('https://aprl.pet/_astro/Iosevka.B08RWT9K.ttf') format('truetype');
+            font-weight: 400;
+            font-display: swap;
+        }
+
+        :root {
+            --color-bg: #100F0F;
+            --color-bg-2: #1C1B1A;
+            --color-ui: #282726;
+            --color-ui-2: #343331;
+            --color-ui-3: #403E3C;
+            --color-tx-3: #575653;
+            --color-tx-2: #878580;
+            --color-tx: #CECDC3;
+            --color-re: #D14D41;
+            --color-cy: #3AA99F;
+        }
+
+        * {
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }
+
+        body {
+            font-family: 'Iosevka Term', monospace;
+            font-weight: 400;
+            background: var(--color-bg);
+            color: var(--color-tx);
+            line-height: 1.6;
+            padding: 2rem;
+        }
+
+        .container {
+            max-width: 1100px;
+            margin: 0 auto;
+        }
+
+        header {
+            display: flex;
+            align-items: baseline;
+            justify-content: space-between;
+            gap: 1rem;
+            margin-bottom: 1.5rem;
+        }
+
+        h1 {
+            font-size: 2rem;
+            font-weight: 400;
+        }
+
+        a, button.link {
+            color: var(--color-cy);
+            text-decoration: none;
+            background: none;
+            border: none;
+            font: inherit;
+            cursor: pointer;
+        }
+
+        a:hover, button.link:hover {
+            opacity: 0.7;
+        }
+
+        button.danger {
+            color: var(--color-re);
+        }
+
+        input, select, button.action {
+            font: inherit;
+            color: var(--color-tx);
+            background: var(--color-bg-2);
+            border: 1px solid var(--color-ui-2);
+            padding: 0.4rem 0.6rem;
+        }
+
+        button.action {
+            cursor: pointer;
+        }
+
+        button.action:hover {
+            border-color: var(--color-ui-3);
+        }
+
+        .toolbar {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.75rem;
+            align-items: center;
+            margin-bottom: 1rem;
+        }
+
+        .toolbar input[type=search] {
+            flex: 1;
+            min-width: 12rem;
+        }
+
+        .crumbs {
+            color: var(--color-tx-2);
+            margin-bottom: 1rem;
+            word-break: break-all;
+        }
+
+        table {
+            width: 100%;
+            border-collapse: collapse;
+        }
+
+        th, td {
+            text-align: left;
+            padding: 0.4rem 0.6rem;
+            border-bottom: 1px solid var(--color-ui);
+            vertical-align: top;
+        }
+
+        th {
+            color: var(--color-tx-2);
+            font-weight: 400;
+        }
+
+        td.name {
+            word-break: break-all;
+        }
+
+        td.size, td.modified, td.type {
+            color: var(--color-tx-2);
+            white-space: nowrap;
+        }
+
+        td.actions {
+            white-space: nowrap;
+            text-align: right;
+        }
+
+        .muted {
+            color: var(--color-tx-3);
+        }
+
+        .status {
+            color: var(--color-tx-2);
+            margin: 1rem 0;
+        }
+
+        .error {
+            color: var(--color-re);
+        }
+
+        #login {
+            max-width: 420px;
+            margin: 4rem auto;
+        }
+
+        #login form {
+            display: flex;
+            gap: 0.5rem;
+            margin: 1rem 0;
+        }
+
+        #login input {
+            flex: 1;
+        }
+
+        #preview {
+            position: fixed;
+            inset: 0;
+            background: rgba(16, 15, 15, 0.92);
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            padding: 2rem;
+        }
+
+        #preview .frame {
+            background: var(--color-bg-2);
+            border: 1px solid var(--color-ui-2);
+            max-width: 100%;
+            max-height: 100%;
+            display: flex;
+            flex-direction: column;
+        }
+
+        #preview .bar {
+            display: flex;
+            justify-content: space-between;
+            gap: 1rem;
+            padding: 0.5rem 0.75rem;
+            border-bottom: 1px solid var(--color-ui);
+        }
+
+        #preview .body {
+            overflow: auto;
+            padding: 0.75rem;
+        }
+
+        #preview img, #preview video {
+            max-width: 80vw;
+            max-height: 75vh;
+            display: block;
+        }
+
+        #preview iframe {
+            width: 80vw;
+            height: 75vh;
+            border: none;
+            background: white;
+        }
+
+        #preview pre {
+            white-space: pre-wrap;
+            word-break: break-all;
+            max-width: 80vw;
+            max-height: 75vh;
+        }
+
+        [hidden] {
+            display: none !important;
+        }
+
+        @media (max-width: 768px) {
+            body {
+                padding: 1.5rem 1rem;
+            }
+
+            td.type, th.type, td.modified, th.modified {
+                display: none;
+            }
+        }
+    </style>
+</head>
+<body data-sso="{sso}">
+    <div class="container">
+        <header>
+            <h1><a href="/">lila</a></h1>
+            <button class="link" id="logout" hidden>sign out</button>
+        </header>
+
+        <div id="login" hidden>
+            <p>Sign in with an API token to browse this server.</p>
+            <form id="login-form">
+                <input type="password" id="token" placeholder="lila_..." autocomplete="off" required>
+                <button class="action" type="submit">sign in</button>
+            </form>
+            <p id="sso" hidden><a href="/auth/login?return_to=/ui">sign in with single sign-on</a></p>
+            <p class="error" id="login-error"></p>
+        </div>
+
+        <div id="browser" hidden>
+            <div class="toolbar">
+                <select id="bucket"></select>
+                <input type="search" id="search" placeholder="search this folder">
+                <button class="action" id="refresh">refresh</button>
+                <button class="action" id="upload">upload</button>
+            </div>
+            <div class="crumbs" id="crumbs"></div>
+            <table>
+                <thead>
+                    <tr>
+                        <th>name</th>
+                        <th class="size">size</th>
+                        <th class="type">type</th>
+                        <th class="modified">modified</th>
+                        <th></th>
+                    </tr>
+                </thead>
+                <tbody id="entries"></tbody>
+            </table>
+            <p class="status" id="status"></p>
+            <button class="action" id="more" hidden>load more</button>
+        </div>
+    </div>
+
+    <div id="preview" hidden>
+        <div class="frame">
+            <div class="bar">
+                <span id="preview-name"></span>
+                <span>
+                    <button class="link" id="preview-download">download</button>
+                    <button class="link" id="preview-close">close</button>
+                </span>
+            </div>
+            <div class="body" id="preview-body"></div>
+        </div>
+    </div>
+
+    <script>
+        const PAGE_SIZE = 200;
+        const TEXT_PREVIEW_BYTES = 65536;
+
+        const view = {
+            bucket: '',
+            prefix: '',
+            cursor: null,
+            search: '',
+        };
+
+        const $ = (id) => document.getElementById(id);
+
+        function base() {
+            return view.bucket
+                ? `/api/v1/buckets/${encodeURIComponent(view.bucket)}/`
+                : '/api/v1/';
+        }
+
+        function encodeKey(key) {
+            return key.split('/').map(encodeURIComponent).join('/');
+        }
+
+        function escape(text) {
+            const div = document.createElement('div');
+            div.textContent = text;
+            return div.innerHTML;
+        }
+
+        function formatSize(bytes) {
+            const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+            let size = bytes;
+            let unit = 0;
+            while (size >= 1024 && unit < units.length - 1) {
+                size /= 1024;
+                unit += 1;
+            }
+            return `${unit === 0 ? size : size.toFixed(1)} ${units[unit]}`;
+        }
+
+        async function api(path, options = {}) {
+            const response = await fetch(path, { ...options, credentials: 'same-origin' });
+            if (response.status === 401) {
+                showLogin();
+                throw new Error('authentication required');
+            }
+            if (!response.ok) {
+                let message = response.statusText;
+                try {
+                    message = (await response.json()).error || message;
+                } catch (_) {}
+                throw new Error(message);
+            }
+            return response;
+        }
+
+        async function presign(key) {
+            const response = await api(`${base()}presign`, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ key, expires_in: 300 }),
+            });
+            return (await response.json()).url;
+        }
+
+        function showLogin(error = '') {
+            $('login-error').textContent = error;
+            $('browser').hidden = true;
+            $('logout').hidden = true;
+            $('login').hidden = false;
+
+            $('sso').hidden = document.body.dataset.sso !== 'true';
+        }
+
+        async function start() {
+            let buckets = [];
+            try {
+                const response = await api('/api/v1/buckets');
+                buckets = (await response.json()).buckets;
+            } catch (e) {
+                if (e.message === 'authentication required') {
+                    return;
+                }
+            }
+
+            $('bucket').innerHTML = '<option value="">(default)</option>' + buckets
+                .map((bucket) => `<option value="${escape(bucket.name)}">${escape(bucket.name)}</option>`)
+                .join('');
+            $('bucket').value = view.bucket;
+
+            $('login').hidden = true;
+            $('browser').hidden = false;
+            $('logout').hidden = false;
+            load(true);
+        }
+
+        function renderCrumbs() {
+            const parts = view.prefix.split('/').filter(Boolean);
+            const crumbs = [`<button class="link" data-prefix="">${escape(view.bucket || 'root')}</button>`];
+            let path = '';
+            for (const part of parts) {
+                path += `${part}/`;
+                crumbs.push(`<button class="link" data-prefix="${escape(path)}">${escape(part)}</button>`);
+            }
+            $('crumbs').innerHTML = crumbs.join(' / ');
+        }
+
+        function folderRow(prefix) {
+            const name = prefix.slice(view.prefix.length);
+            return `<tr>
+                <td class="name"><button class="link" data-prefix="${escape(prefix)}">${escape(name)}</button></td>
+                <td class="size muted">—</td>
+                <td class="type muted">folder</td>
+                <td class="modified"></td>
+                <td class="actions"></td>
+            </tr>`;
+        }
+
+        function objectRow(object) {
+            const name = object.key.startsWith(view.prefix)
+                ? object.key.slice(view.prefix.length)
+                : object.key;
+            const key = escape(object.key);
+            return `<tr>
+                <td class="name"><button class="link" data-preview="${key}" data-type="${escape(object.content_type)}" data-size="${object.size}">${escape(name)}</button></td>
+                <td class="size">${formatSize(object.size)}</td>
+                <td class="type">${escape(object.content_type)}</td>
+                <td class="modified">${new Date(object.updated_at || object.created_at).toLocaleString()}</td>
+                <td class="actions">
+                    <button class="link" data-download="${key}">download</button>
+                    <button class="link danger" data-delete="${key}">delete</button>
+                </td>
+            </tr>`;
+        }
+
+        async function load(reset) {
+            if (reset) {
+                view.cursor = null;
+                $('entries').innerHTML = '';
+                renderCrumbs();
+            }
+
+            $('status').textContent = 'loading…';
+            $('status').classList.remove('error');
+            $('more').hidden = true;
+
+            try {
+                let objects;
+                let prefixes = [];
+
+                if (view.search) {
+                    const params = new URLSearchParams({
+                        key_glob: `${view.prefix}*${view.search}*`,
+                        limit: PAGE_SIZE,
+                    });
+                    const response = await api(`${base()}search?${params}`);
+                    objects = (await response.json()).objects;
+                    view.cursor = null;
+                } else {
+                    const params = new URLSearchParams({
+                        prefix: view.prefix,
+                        delimiter: '/',
+                        limit: PAGE_SIZE,
+                    });
+                    if (view.cursor) {
+                        params.set('cursor', view.cursor);
+                    }
+                    const response = await api(`${base()}objects?${params}`);
+                    const page = await response.json();
+                    objects = page.objects;
+                    prefixes = reset ? page.prefixes : [];
+                    view.cursor = page.next_cursor || null;
+                }
+
+                $('entries').insertAdjacentHTML(
+                    'beforeend',
+                    prefixes.map(folderRow).join('') + objects.map(objectRow).join(''),
+                );
+
+                const count = $('entries').children.length;
+                $('status').textContent = count === 0 ? 'nothing here yet' : '';
+                $('more').hidden = !view.cursor;
+            } catch (e) {
+                if (e.message !== 'authentication required') {
+                    $('status').textContent = e.message;
+                    $('status').classList.add('error');
+                }
+            }
+        }
+
+        function navigate(prefix) {
+            view.prefix = prefix;
+            view.search = '';
+            $('search').value = '';
+            load(true);
+        }
+
+        async function download(key) {
+            try {
+                const url = await presign(key);
+                window.location.href = `${url}&download=true`;
+            } catch (e) {
+                alert(`download failed: ${e.message}`);
+            }
+        }
+
+        async function remove(key) {
+            if (!confirm(`delete ${key}?`)) {
+                return;
+            }
+            try {
+                await api(`${base()}objects/${encodeKey(key)}`, { method: 'DELETE' });
+                load(true);
+            } catch (e) {
+                alert(`delete failed: ${e.message}`);
+            }
+        }
+
+        async function preview(key, type, size) {
+            $('preview-name').textContent = key;
+            $('preview-download').dataset.download = key;
+            $('preview-body').innerHTML = '<p class="muted">loading…</p>';
+            $('preview').hidden = false;
+
+            try {
+                const url = await presign(key);
+                const body = $('preview-body');
+
+                if (/^image\/(jpeg|png|webp)/.test(type)) {
+                    body.innerHTML = `<img src="${escape(url)}&width=1600" alt="">`;
+                } else if (type.startsWith('image/')) {
+                    body.innerHTML = `<img src="${escape(url)}" alt="">`;
+                } else if (type.startsWith('video/')) {
+                    body.innerHTML = `<video src="${escape(url)}" controls></video>`;
+                } else if (type.startsWith('audio/')) {
+                    body.innerHTML = `<audio src="${escape(url)}" controls></audio>`;
+                } else if (type === 'application/pdf') {
+                    body.innerHTML = `<iframe src="${escape(url)}"></iframe>`;
+                } else if (type.startsWith('text/') || /json|xml|javascript|yaml|toml/.test(type)) {
+                    const response = await fetch(url, {
+                        headers: { Range: `bytes=0-${TEXT_PREVIEW_BYTES - 1}` },
+                    });
+                    const text = await response.text();
+                    const truncated = size > TEXT_PREVIEW_BYTES ? '\n\n… truncated' : '';
+                    body.innerHTML = `<pre>${escape(text + truncated)}</pre>`;
+                } else {
+                    body.innerHTML = '<p class="muted">no preview available for this type</p>';
+                }
+            } catch (e) {
+                $('preview-body').innerHTML = `<p class="error">${escape(e.message)}</p>`;
+            }
+        }
+
+        function closePreview() {
+            $('preview').hidden = true;
+            $('preview-body').innerHTML = '';
+        }
+
+        document.addEventListener('click', (event) => {
+            const target = event.target.closest('button');
+            if (!target) {
+                return;
+            }
+            if (target.dataset.prefix !== undefined) {
+                navigate(target.dataset.prefix);
+            } else if (target.dataset.preview) {
+                preview(target.dataset.preview, target.dataset.type, Number(target.dataset.size));
+            } else if (target.dataset.download) {
+                download(target.dataset.download);
+            } else if (target.dataset.delete) {
+                remove(target.dataset.delete);
+            }
+        });
+
+        $('login-form').addEventListener('submit', async (event) => {
+            event.preventDefault();
+            const response = await fetch('/auth/token', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ token: $('token').value.trim() }),
+                credentials: 'same-origin',
+            }).catch(() => null);
+            $('token').value = '';
+
+            if (!response || !response.ok) {
+                showLogin('that token was not accepted');
+                return;
+            }
+            start();
+        });
+
+        $('logout').addEventListener('click', async () => {
+            await fetch('/auth/logout', { method: 'POST', credentials: 'same-origin' }).catch(() => {});
+            showLogin();
+        });
+
+        $('bucket').addEventListener('change', (event) => {
+            view.bucket = event.target.value;
+            navigate('');
+        });
+
+        let searchTimer;
+        $('search').addEventListener('input', (event) => {
+            clearTimeout(searchTimer);
+            searchTimer = setTimeout(() => {
+                view.search = event.target.value.trim();
+                load(true);
+            }, 250);
+        });
+
+        $('refresh').addEventListener('click', () => load(true));
+        $('upload').addEventListener('click', () => {
+            const params = new URLSearchParams({ bucket: view.bucket, prefix: view.prefix });
+            window.location.href = `/ui/upload?${params}`;
+        });
+        $('more').addEventListener('click', () => load(false));
+        $('preview-close').addEventListener('click', closePreview);
+        $('preview').addEventListener('click', (event) => {
+            if (event.target === $('preview')) {
+                closePreview();
+            }
+        });
+        document.addEventListener('keydown', (event) => {
+            if (event.key === 'Escape') {
+                closePreview();
+            }
+        });
+
+        start();
+    </script>
+</body>
+</html>
+"#;
+
+const UPLOAD_PAGE: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>lila · upload</title>
+    <style>
+        @font-face {
+            font-family: 'Iosevka Term';
+            src: url('https://aprl.pet/_astro/Iosevka.B08RWT9K.ttf') format('truetype');
+            font-weight: 400;
+            font-display: swap;
+        }
+
+        :root {
+            --color-bg: #100F0F;
+            --color-bg-2: #1C1B1A;
+            --color-ui: #282726;
+            --color-ui-2: #343331;
+            --color-ui-3: #403E3C;
+            --color-tx-3: #575653;
+            --color-tx-2: #878580;
+            --color-tx: #CECDC3;
+            --color-re: #D14D41;
+            --color-cy: #3AA99F;
+        }
+
+        * {
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }
+
+        body {
+            font-family: 'Iosevka Term', monospace;
+            font-weight: 400;
+            background: var(--color-bg);
+            color: var(--color-tx);
+            line-height: 1.6;
+            padding: 2rem;
+        }
+
+        .container {
+            max-width: 900px;
+            margin: 0 auto;
+        }
+
+        header {
+            display: flex;
+            align-items: baseline;
+            justify-content: space-between;
+            gap: 1rem;
+            margin-bottom: 1.5rem;
+        }
+
+        h1 {
+            font-size: 2rem;
+            font-weight: 400;
+        }
+
+        a, button.link {
+            color: var(--color-cy);
+            text-decoration: none;
+            background: none;
+            border: none;
+            font: inherit;
+            cursor: pointer;
+        }
+
+        a:hover, button.link:hover {
+            opacity: 0.7;
+        }
+
+        input, select, button.action {
+            font: inherit;
+            color: var(--color-tx);
+            background: var(--color-bg-2);
+            border: 1px solid var(--color-ui-2);
+            padding: 0.4rem 0.6rem;
+        }
+
+        button.action {
+            cursor: pointer;
+        }
+
+        button.action:hover {
+            border-color: var(--color-ui-3);
+        }
+
+        button.action:disabled {
+            color: var(--color-tx-3);
+            cursor: default;
+        }
+
+        .toolbar {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.75rem;
+            align-items: center;
+            margin-bottom: 1rem;
+        }
+
+        .toolbar input[type=text] {
+            flex: 1;
+            min-width: 12rem;
+        }
+
+        #drop {
+            border: 1px dashed var(--color-ui-3);
+            padding: 3rem 1rem;
+            text-align: center;
+            color: var(--color-tx-2);
+            cursor: pointer;
+            margin-bottom: 1rem;
+        }
+
+        #drop.over {
+            border-color: var(--color-cy);
+            color: var(--color-tx);
+        }
+
+        ul {
+            list-style: none;
+        }
+
+        li {
+            display: flex;
+            justify-content: space-between;
+            gap: 1rem;
+            padding: 0.4rem 0.6rem;
+            border-bottom: 1px solid var(--color-ui);
+            word-break: break-all;
+        }
+
+        li .size {
+            color: var(--color-tx-2);
+            white-space: nowrap;
+        }
+
+        progress {
+            width: 100%;
+            height: 0.5rem;
+            margin: 1rem 0 0.5rem;
+            accent-color: var(--color-cy);
+        }
+
+        .muted {
+            color: var(--color-tx-3);
+        }
+
+        .status {
+            color: var(--color-tx-2);
+            margin: 1rem 0;
+        }
+
+        .error {
+            color: var(--color-re);
+        }
+
+        #login {
+            max-width: 420px;
+            margin: 4rem auto;
+        }
+
+        #login form {
+            display: flex;
+            gap: 0.5rem;
+            margin: 1rem 0;
+        }
+
+        #login input {
+            flex: 1;
+        }
+
+        [hidden] {
+            display: none !important;
+        }
+
+        @media (max-width: 768px) {
+            body {
+                padding: 1.5rem 1rem;
+            }
+        }
+    </style>
+</head>
+<body data-sso="{sso}">
+    <div class="container">
+        <header>
+            <h1><a href="/">lila</a></h1>
+            <span>
+                <a href="/ui" id="browse">browse</a>
+                <button class="link" id="logout" hidden>sign out</button>
+            </span>
+        </header>
+
+        <div id="login" hidden>
+            <p>Sign in with an API token to upload to this server.</p>
+            <form id="login-form">
+                <input type="password" id="token" placeholder="lila_..." autocomplete="off" required>
+                <button class="action" type="submit">sign in</button>
+            </form>
+            <p id="sso" hidden><a href="/auth/login?return_to=/ui/upload">sign in with single sign-on</a></p>
+            <p class="error" id="login-error"></p>
+        </div>
+
+        <div id="uploader" hidden>
+            <div class="toolbar">
+                <select id="bucket"></select>
+                <input type="text" id="prefix" placeholder="target folder, e.g. photos/2024/">
+            </div>
+            <div id="drop">drop files here or click to choose</div>
+            <input type="file" id="files" multiple hidden>
+            <ul id="queue"></ul>
+            <progress id="progress" max="1" value="0" hidden></progress>
+            <p class="status" id="status"></p>
+            <div class="toolbar">
+                <button class="action" id="start" disabled>upload</button>
+                <button class="action" id="clear" disabled>clear</button>
+            </div>
+        </div>
+    </div>
+
+    <script>
+        const BATCH_SIZE = 1000;
+
+        const params = new URLSearchParams(window.location.search);
+        let queue = [];
+        let uploading = false;
+
+        const $ = (id) => document.getElementById(id);
+
+        function base() {
+            const bucket = $('bucket').value;
+            return bucket
+                ? `/api/v1/buckets/${encodeURIComponent(bucket)}/`
+                : '/api/v1/';
+        }
+
+        function prefix() {
+            const value = $('prefix').value.trim().replace(/^\/+/, '');
+            return value && !value.endsWith('/') ? `${value}/` : value;
+        }
+
+        function escape(text) {
+            const div = document.createElement('div');
+            div.textContent = text;
+            return div.innerHTML;
+        }
+
+        function formatSize(bytes) {
+            const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+            let size = bytes;
+            let unit = 0;
+            while (size >= 1024 && unit < units.length - 1) {
+                size /= 1024;
+                unit += 1;
+            }
+            return `${unit === 0 ? size : size.toFixed(1)} ${units[unit]}`;
+        }
+
+        function setStatus(message, error = false) {
+            $('status').textContent = message;
+            $('status').classList.toggle('error', error);
+        }
+
+        async function api(path, options = {}) {
+            const response = await fetch(path, { ...options, credentials: 'same-origin' });
+            if (response.status === 401) {
+                showLogin();
+                throw new Error('authentication required');
+            }
+            if (!response.ok) {
+                let message = response.statusText;
+                try {
+                    message = (await response.json()).error || message;
+                } catch (_) {}
+                throw new Error(message);
+            }
+            return response;
+        }
+
+        function showLogin(error = '') {
+            $('login-error').textContent = error;
+            $('uploader').hidden = true;
+            $('logout').hidden = true;
+            $('login').hidden = false;
+
+            $('sso').hidden = document.body.dataset.sso !== 'true';
+        }
+
+        async function start() {
+            let buckets = [];
+            try {
+                const response = await api('/api/v1/buckets');
+                buckets = (await response.json()).buckets;
+            } catch (e) {
+                if (e.message === 'authentication required') {
+                    return;
+                }
+            }
+
+            $('bucket').innerHTML = '<option value="">(default)</option>' + buckets
+                .map((bucket) => `<option value="${escape(bucket.name)}">${escape(bucket.name)}</option>`)
+                .join('');
+            $('bucket').value = params.get('bucket') || '';
+            $('prefix').value = params.get('prefix') || '';
+
+            $('login').hidden = true;
+            $('uploader').hidden = false;
+            $('logout').hidden = false;
+            renderQueue();
+        }
+
+        function renderQueue() {
+            $('queue').innerHTML = queue
+                .map((file) => `<li><span>${escape(prefix() + file.name)}</span><span class="size">${formatSize(file.size)}</span></li>`)
+                .join('');
+            $('start').disabled = uploading || queue.length === 0;
+            $('clear').disabled = uploading || queue.length === 0;
+
+            const browse = new URLSearchParams({ bucket: $('bucket').value, prefix: prefix() });
+            $('browse').href = `/ui?${browse}`;
+        }
+
+        function enqueue(files) {
+            if (uploading) {
+                return;
+            }
+            for (const file of files) {
+                queue = queue.filter((queued) => queued.name !== file.name);
+                queue.push(file);
+            }
+            setStatus('');
+            renderQueue();
+        }
+
+        async function existing(keys) {
+            const found = new Set();
+            for (let i = 0; i < keys.length; i += BATCH_SIZE) {
+                const response = await api(`${base()}metadata:batchGet`, {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify(keys.slice(i, i + BATCH_SIZE)),
+                });
+                for (const object of (await response.json()).objects) {
+                    found.add(object.key);
+                }
+            }
+            return found;
+        }
+
+        function send(files, overwrite) {
+            const form = new FormData();
+            form.append('prefix', prefix());
+            form.append('overwrite', overwrite ? 'true' : 'false');
+            for (const file of files) {
+                form.append('file', file, file.name);
+            }
+
+            return new Promise((resolve, reject) => {
+                const xhr = new XMLHttpRequest();
+                xhr.open('POST', `${base()}objects:upload`);
+                xhr.withCredentials = true;
+
+                xhr.upload.addEventListener('progress', (event) => {
+                    if (event.lengthComputable) {
+                        $('progress').max = event.total;
+                        $('progress').value = event.loaded;
+                        setStatus(`uploading… ${formatSize(event.loaded)} of ${formatSize(event.total)}`);
+                    }
+                });
+
+                xhr.addEventListener('load', () => {
+                    if (xhr.status === 401) {
+                        showLogin();
+                        reject(new Error('authentication required'));
+                        return;
+                    }
+                    let body = {};
+                    try {
+                        body = JSON.parse(xhr.responseText);
+                    } catch (_) {}
+                    if (xhr.status >= 200 && xhr.status < 300) {
+                        resolve(body);
+                    } else {
+                        reject(new Error(body.error || xhr.statusText || `upload failed (${xhr.status})`));
+                    }
+                });
+                xhr.addEventListener('error', () => reject(new Error('network error')));
+                xhr.addEventListener('abort', () => reject(new Error('upload aborted')));
+
+                xhr.send(form);
+            });
+        }
+
+        async function upload() {
+            if (uploading || queue.length === 0) {
+                return;
+            }
+
+            uploading = true;
+            renderQueue();
+            setStatus('checking for existing files…');
+
+            try {
+                const target = prefix();
+                const found = await existing(queue.map((file) => target + file.name));
+
+                let files = queue;
+                let overwrite = false;
+                if (found.size > 0) {
+                    const names = [...found].slice(0, 10).join('\n');
+                    const more = found.size > 10 ? `\n… and ${found.size - 10} more` : '';
+                    overwrite = confirm(`${found.size} file(s) already exist:\n\n${names}${more}\n\nOverwrite them?`);
+                    if (!overwrite) {
+                        files = queue.filter((file) => !found.has(target + file.name));
+                    }
+                }
+
+                if (files.length === 0) {
+                    setStatus('nothing to upload');
+                    return;
+                }
+
+                $('progress').value = 0;
+                $('progress').hidden = false;
+                const result = await send(files, overwrite);
+
+                const uploaded = result.uploaded || [];
+                const conflicts = result.conflicts || [];
+                const skipped = queue.length - files.length + conflicts.length;
+                queue = queue.filter((file) => !uploaded.some((object) => object.key === target + file.name));
+                setStatus(`uploaded ${uploaded.length} file(s)` + (skipped > 0 ? `, skipped ${skipped} existing` : ''));
+            } catch (e) {
+                if (e.message !== 'authentication required') {
+                    setStatus(`upload failed: ${e.message}`, true);
+                }
+            } finally {
+                uploading = false;
+                $('progress').hidden = true;
+                renderQueue();
+            }
+        }
+
+        $('drop').addEventListener('click', () => $('files').click());
+        $('drop').addEventListener('dragover', (event) => {
+            event.preventDefault();
+            $('drop').classList.add('over');
+        });
+        $('drop').addEventListener('dragleave', () => $('drop').classList.remove('over'));
+        $('drop').addEventListener('drop', (event) => {
+            event.preventDefault();
+            $('drop').classList.remove('over');
+            enqueue(event.dataTransfer.files);
+        });
+        $('files').addEventListener('change', (event) => {
+            enqueue(event.target.files);
+            event.target.value = '';
+        });
+
+        $('prefix').addEventListener('input', renderQueue);
+        $('bucket').addEventListener('change', renderQueue);
+        $('start').addEventListener('click', upload);
+        $('clear').addEventListener('click', () => {
+            queue = [];
+            setStatus('');
+            renderQueue();
+        });
+
+        $('login-form').addEventListener('submit', async (event) => {
+            event.preventDefault();
+            const response = await fetch('/auth/token', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ token: $('token').value.trim() }),
+                credentials: 'same-origin',
+            }).catch(() => null);
+            $('token').value = '';
+
+            if (!response || !response.ok) {
+                showLogin('that token was not accepted');
+                return;
+            }
+            start();
+        });
+
+        $('logout').addEventListener('click', async () => {
+            await fetch('/auth/logout', { method: 'POST', credentials: 'same-origin' }).catch(() => {});
+            showLogin();
+        });
+
+        window.addEventListener('beforeunload', (event) => {
+            if (uploading) {
+                event.preventDefault();
+            }
+        });
+
+        start();
+    </script>
+</body>
+</html>
+"#;
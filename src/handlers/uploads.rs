@@ -0,0 +1,279 @@
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, State},
+    http::HeaderMap,
+};
+use chrono::Utc;
+use serde::Deserialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::{self, AppState},
+    models::{ErrorResponse, ObjectMetadata, UploadPart, UploadSession, UploadStatusResponse},
+    openapi::Binary,
+};
+
+const MAX_PART_NUMBER: i64 = 10_000;
+
+#[derive(Deserialize, ToSchema)]
+pub struct InitiateUploadRequest {
+    key: String,
+    content_type: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/uploads",
+    tag = "uploads",
+    request_body = InitiateUploadRequest,
+    responses(
+        (status = 200, description = "Multipart upload started", body = UploadSession),
+        (status = 400, description = "Missing key", body = ErrorResponse),
+    )
+)]
+pub async fn initiate_upload(
+    State(state): State<AppState>,
+    Json(request): Json<InitiateUploadRequest>,
+) -> Result<Json<UploadSession>> {
+    tracing::info!("INITIATE upload request for object: {}", request.key);
+
+    if request.key.is_empty() {
+        return Err(AppError::BadRequest("key must not be empty".to_string()));
+    }
+
+    let content_type = request
+        .content_type
+        .unwrap_or_else(|| objects::guess_content_type(&state, &request.key));
+
+    let upload = UploadSession {
+        id: Uuid::new_v4().to_string(),
+        key: request.key,
+        content_type,
+        created_at: Utc::now(),
+    };
+
+    state.metadata.create_upload(&upload).await?;
+    tracing::info!("Upload {} initiated for {}", upload.id, upload.key);
+
+    Ok(Json(upload))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/uploads/{id}/parts/{part_number}",
+    tag = "uploads",
+    params(
+        ("id" = String, Path, description = "Upload id"),
+        ("part_number" = i64, Path, description = "Part number, starting at 1"),
+    ),
+    request_body(content = Binary, content_type = "application/octet-stream"),
+    responses(
+        (status = 200, description = "Part stored", body = UploadPart),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+        (status = 413, description = "Part exceeds the upload limit", body = ErrorResponse),
+    )
+)]
+pub async fn upload_part(
+    State(state): State<AppState>,
+    Path((upload_id, part_number)): Path<(String, i64)>,
+    body: Body,
+) -> Result<Json<UploadPart>> {
+    tracing::info!("PUT part {} for upload: {}", part_number, upload_id);
+
+    if !(1..=MAX_PART_NUMBER).contains(&part_number) {
+        return Err(AppError::BadRequest(format!(
+            "part number must be between 1 and {}",
+            MAX_PART_NUMBER
+        )));
+    }
+
+    state
+        .metadata
+        .get_upload(&upload_id)
+        .await?
+        .ok_or_else(|| AppError::UploadNotFound(upload_id.clone()))?;
+
+    let max_size = state.max_upload_size * 1024 * 1024;
+    let _permit = state.limits.upload().await?;
+    let stream = body.into_data_stream();
+
+    let (etag, size) = state
+        .storage
+        .write_part(&upload_id, part_number, stream, max_size)
+        .await?;
+
+    let part = UploadPart {
+        part_number,
+        size,
+        etag,
+        created_at: Utc::now(),
+    };
+
+    state.metadata.put_part(&upload_id, &part).await?;
+    tracing::debug!(
+        "Part {} of upload {} stored: {} bytes",
+        part_number,
+        upload_id,
+        size
+    );
+
+    Ok(Json(part))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/uploads/{id}",
+    tag = "uploads",
+    params(("id" = String, Path, description = "Upload id")),
+    responses(
+        (status = 200, description = "Upload and its parts", body = UploadStatusResponse),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_upload(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+) -> Result<Json<UploadStatusResponse>> {
+    tracing::info!("GET upload status: {}", upload_id);
+
+    let upload = state
+        .metadata
+        .get_upload(&upload_id)
+        .await?
+        .ok_or_else(|| AppError::UploadNotFound(upload_id.clone()))?;
+
+    let parts = state.metadata.list_parts(&upload_id).await?;
+
+    Ok(Json(UploadStatusResponse { upload, parts }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/uploads/{id}/complete",
+    tag = "uploads",
+    params(
+        ("id" = String, Path, description = "Upload id"),
+        ("x-lila-if-generation-match" = Option<i64>, Header, description = "Only overwrite this generation, 0 to require a new key"),
+    ),
+    responses(
+        (status = 200, description = "Parts assembled into an object", body = ObjectMetadata),
+        (status = 400, description = "Upload has no parts", body = ErrorResponse),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+        (status = 412, description = "Precondition failed", body = ErrorResponse),
+        (status = 507, description = "Upload exceeds a quota or the free disk space", body = ErrorResponse),
+    )
+)]
+pub async fn complete_upload(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<ObjectMetadata>> {
+    tracing::info!("COMPLETE request for upload: {}", upload_id);
+
+    let upload = state
+        .metadata
+        .get_upload(&upload_id)
+        .await?
+        .ok_or_else(|| AppError::UploadNotFound(upload_id.clone()))?;
+
+    let parts = state.metadata.list_parts(&upload_id).await?;
+
+    if parts.is_empty() {
+        return Err(AppError::BadRequest(
+            "upload has no parts to complete".to_string(),
+        ));
+    }
+
+    let part_numbers: Vec<i64> = parts.iter().map(|p| p.part_number).collect();
+
+    let expected_generation = objects::generation_condition(&headers)?;
+    objects::ensure_unlocked(&state, &upload.key).await?;
+
+    let size = parts.iter().map(|part| part.size).sum();
+    objects::check_quota(&state, &upload.key, size).await?;
+    state.storage.ensure_space(size as u64).await?;
+
+    let generation = state.metadata.next_generation(&upload.key).await?;
+    let staged = state
+        .storage
+        .stage_parts(&upload_id, &upload.key, &part_numbers)
+        .await?;
+
+    tracing::debug!(
+        "Assembled {} parts for {}: {} bytes",
+        part_numbers.len(),
+        upload.key,
+        staged.size
+    );
+
+    let layout = match state
+        .storage
+        .finalize_staged(&staged, &upload.content_type)
+        .await
+    {
+        Ok(layout) => layout,
+        Err(e) => {
+            state.storage.discard(staged).await;
+            return Err(e);
+        }
+    };
+
+    let now = Utc::now();
+    let metadata = ObjectMetadata {
+        id: Uuid::new_v4().to_string(),
+        key: upload.key.clone(),
+        size: staged.size,
+        stored_size: layout.stored_size,
+        chunks: layout.chunks,
+        content_type: upload.content_type,
+        etag: staged.etag.clone(),
+        etag_algorithm: state.storage.etag_algorithm(),
+        created_at: now,
+        public: false,
+        expires_at: None,
+        retain_until: objects::retention_for(&state, &upload.key),
+        user_metadata: Default::default(),
+        cache_control: None,
+        cache_expires: None,
+        accessed_at: None,
+        access_count: 0,
+        updated_at: Some(now),
+        generation,
+    };
+
+    objects::commit_object(&state, staged, &metadata, expected_generation).await?;
+    state.metadata.delete_upload(&upload_id).await?;
+    state.storage.remove_upload(&upload_id).await?;
+
+    tracing::info!("Upload {} completed as {}", upload_id, upload.key);
+    Ok(Json(metadata))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/uploads/{id}",
+    tag = "uploads",
+    params(("id" = String, Path, description = "Upload id")),
+    responses(
+        (status = 200, description = "Upload aborted", body = Object, example = json!({"success": true})),
+        (status = 404, description = "Upload not found", body = ErrorResponse),
+    )
+)]
+pub async fn abort_upload(
+    State(state): State<AppState>,
+    Path(upload_id): Path<String>,
+) -> Result<Json<serde_json::Value>> {
+    tracing::info!("ABORT request for upload: {}", upload_id);
+
+    if !state.metadata.delete_upload(&upload_id).await? {
+        return Err(AppError::UploadNotFound(upload_id));
+    }
+
+    state.storage.remove_upload(&upload_id).await?;
+
+    tracing::info!("Upload {} aborted", upload_id);
+    Ok(Json(serde_json::json!({ "success": true })))
+}
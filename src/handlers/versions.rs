@@ -0,0 +1,113 @@
+use axum::{
+    Json,
+    extract::{Path, State},
+};
+
+use chrono::Utc;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{EventOp, ListVersionsResponse, ObjectMetadata},
+};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/versions/{key}",
+    tag = "objects",
+    params(("key" = String, Path, description = "Object key, may contain slashes")),
+    responses(
+        (status = 200, description = "Current object and archived versions, newest first", body = ListVersionsResponse),
+    )
+)]
+pub async fn list_versions(
+    State(state): State<AppState>,
+    Path(key): Path<String>,
+) -> Result<Json<ListVersionsResponse>> {
+    tracing::info!("LIST VERSIONS request for object: {}", key);
+
+    let current = state.metadata.get(&key).await?;
+    let versions = state.metadata.list_versions(&key).await?;
+
+    if current.is_none() && versions.is_empty() {
+        return Err(AppError::NotFound(key));
+    }
+
+    tracing::info!("Found {} archived versions of {}", versions.len(), key);
+
+    Ok(Json(ListVersionsResponse {
+        key,
+        current,
+        versions,
+    }))
+}
+
+pub async fn archive_current(state: &AppState, key: &str) -> Result<Option<ObjectMetadata>> {
+    if !state.versioning {
+        return Ok(None);
+    }
+
+    let Some(current) = state.metadata.get(key).await? else {
+        return Ok(None);
+    };
+
+    archive(state, &current).await?;
+    Ok(Some(current))
+}
+
+pub async fn archive(state: &AppState, current: &ObjectMetadata) -> Result<bool> {
+    if !state.versioning {
+        return Ok(false);
+    }
+
+    state
+        .storage
+        .archive_version(&current.key, &current.id)
+        .await?;
+    state.metadata.insert_version(current).await?;
+
+    tracing::debug!("Archived version {} of {}", current.id, current.key);
+    Ok(true)
+}
+
+pub async fn rollback_archive(state: &AppState, archived: Option<ObjectMetadata>) {
+    let Some(archived) = archived else {
+        return;
+    };
+
+    if let Err(e) = state
+        .storage
+        .restore_version(&archived.key, &archived.id)
+        .await
+    {
+        tracing::error!("Failed to restore blob of {}: {}", archived.key, e);
+        return;
+    }
+
+    if let Err(e) = state
+        .metadata
+        .delete_version(&archived.key, &archived.id)
+        .await
+    {
+        tracing::error!("Failed to drop version record of {}: {}", archived.key, e);
+    }
+}
+
+pub async fn promote_latest(state: &AppState, key: &str) -> Result<Option<ObjectMetadata>> {
+    let Some(latest) = state.metadata.list_versions(key).await?.into_iter().next() else {
+        return Ok(None);
+    };
+
+    state.storage.restore_version(key, &latest.id).await?;
+    let latest = ObjectMetadata {
+        updated_at: Some(Utc::now()),
+        generation: state.metadata.next_generation(key).await?,
+        ..latest
+    };
+    state.metadata.insert(&latest).await?;
+    state.metadata.delete_version(key, &latest.id).await?;
+    state.publish(EventOp::Put, &latest).await;
+
+    tracing::debug!("Promoted version {} of {} to current", latest.id, key);
+    Ok(Some(latest))
+}
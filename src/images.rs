@@ -0,0 +1,213 @@
+use std::io::Cursor;
+
+use axum::{
+    body::Body,
+    http::{HeaderMap, StatusCode, header},
+    response::Response,
+};
+use futures_util::TryStreamExt;
+use image::{DynamicImage, ImageFormat, codecs::jpeg::JpegEncoder};
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::{self, AppState},
+    models::ObjectMetadata,
+};
+
+pub struct Variant {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub format: ImageFormat,
+    pub quality: u8,
+}
+
+impl Variant {
+    fn name(&self, etag: &str) -> String {
+        let quality = match self.format {
+            ImageFormat::Png => 0,
+            _ => self.quality,
+        };
+
+        format!(
+            "{}-{}x{}-q{}.{}",
+            etag,
+            self.width.unwrap_or(0),
+            self.height.unwrap_or(0),
+            quality,
+            self.format.extensions_str()[0]
+        )
+    }
+}
+
+pub fn source_format(metadata: &ObjectMetadata) -> Result<ImageFormat> {
+    match metadata.content_type.split(';').next().unwrap_or("").trim() {
+        "image/jpeg" => Ok(ImageFormat::Jpeg),
+        "image/png" => Ok(ImageFormat::Png),
+        "image/webp" => Ok(ImageFormat::WebP),
+        other => Err(AppError::UnsupportedMediaType(other.to_string())),
+    }
+}
+
+pub fn parse_format(format: &str) -> Result<ImageFormat> {
+    match format.to_ascii_lowercase().as_str() {
+        "jpeg" | "jpg" => Ok(ImageFormat::Jpeg),
+        "png" => Ok(ImageFormat::Png),
+        "webp" => Ok(ImageFormat::WebP),
+        other => Err(AppError::BadRequest(format!(
+            "unsupported image format: {}",
+            other
+        ))),
+    }
+}
+
+pub async fn serve(
+    state: &AppState,
+    metadata: &ObjectMetadata,
+    archived: bool,
+    variant: Variant,
+    headers: &HeaderMap,
+) -> Result<Response> {
+    let config = &state.config.images;
+    let source = source_format(metadata)?;
+
+    for dimension in [variant.width, variant.height].into_iter().flatten() {
+        if dimension == 0 || dimension > config.max_size {
+            return Err(AppError::BadRequest(format!(
+                "image dimensions must be between 1 and {}",
+                config.max_size
+            )));
+        }
+    }
+
+    if !(1..=100).contains(&variant.quality) {
+        return Err(AppError::BadRequest(
+            "image quality must be between 1 and 100".to_string(),
+        ));
+    }
+
+    let name = variant.name(&metadata.etag);
+    let etag = format!("\"{}\"", name);
+
+    if headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag))
+    {
+        tracing::debug!("Image variant {} not modified", name);
+        let builder = Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, &etag);
+        return Ok(objects::cache_headers(state, metadata, builder)
+            .body(Body::empty())
+            .unwrap());
+    }
+
+    let data = match state.storage.read_variant(&metadata.key, &name).await? {
+        Some(data) => {
+            tracing::debug!("Serving cached image variant {}", name);
+            data
+        }
+        None => {
+            let data = render(state, metadata, archived, source, &variant).await?;
+            if let Err(e) = state
+                .storage
+                .store_variant(&metadata.key, &name, &data)
+                .await
+            {
+                tracing::warn!("Failed to cache image variant {}: {}", name, e);
+            }
+            data
+        }
+    };
+
+    let builder = Response::builder()
+        .header(header::CONTENT_TYPE, variant.format.to_mime_type())
+        .header(header::CONTENT_LENGTH, data.len().to_string())
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::ETAG, etag);
+
+    Ok(objects::cache_headers(state, metadata, builder)
+        .body(Body::from(data))
+        .unwrap())
+}
+
+async fn render(
+    state: &AppState,
+    metadata: &ObjectMetadata,
+    archived: bool,
+    source: ImageFormat,
+    variant: &Variant,
+) -> Result<Vec<u8>> {
+    let config = &state.config.images;
+    let max_source = config.max_source_mb * 1024 * 1024;
+    if metadata.size as usize > max_source {
+        return Err(AppError::PayloadTooLarge(max_source));
+    }
+
+    tracing::debug!(
+        "Rendering {:?}x{:?} {:?} variant of {} ({} bytes)",
+        variant.width,
+        variant.height,
+        variant.format,
+        metadata.key,
+        metadata.size
+    );
+
+    let size = metadata.size as u64;
+    let stream = if archived {
+        state.storage.version_stream(metadata, 0, size).await?
+    } else {
+        state.storage.stream(metadata, 0, size).await?
+    };
+
+    let data: Vec<u8> = stream
+        .try_fold(
+            Vec::with_capacity(metadata.size as usize),
+            |mut data, chunk| async move {
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            },
+        )
+        .await?;
+
+    let width = variant.width.unwrap_or(config.max_size);
+    let height = variant.height.unwrap_or(config.max_size);
+    let (format, quality) = (variant.format, variant.quality);
+
+    tokio::task::spawn_blocking(move || {
+        let image = image::load_from_memory_with_format(&data, source)
+            .map_err(|e| AppError::BadRequest(format!("failed to decode image: {}", e)))?;
+
+        let image = if image.width() <= width && image.height() <= height {
+            image
+        } else {
+            image.thumbnail(width, height)
+        };
+
+        encode(image, format, quality)
+    })
+    .await
+    .map_err(|_| AppError::Internal)?
+}
+
+fn encode(image: DynamicImage, format: ImageFormat, quality: u8) -> Result<Vec<u8>> {
+    let mut data = Cursor::new(Vec::new());
+
+    let result = match format {
+        ImageFormat::Jpeg => JpegEncoder::new_with_quality(&mut data, quality)
+            .encode_image(&DynamicImage::ImageRgb8(image.into_rgb8())),
+        ImageFormat::WebP => {
+            let image = image.into_rgba8();
+            let encoded = webp::Encoder::from_rgba(&image, image.width(), image.height())
+                .encode(quality as f32);
+            return Ok(encoded.to_vec());
+        }
+        _ => image.write_to(&mut data, format),
+    };
+
+    result.map_err(|e| {
+        tracing::error!("Failed to encode {:?} image: {}", format, e);
+        AppError::Internal
+    })?;
+    Ok(data.into_inner())
+}
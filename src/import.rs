@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use tokio::fs;
+use tokio_util::io::ReaderStream;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::{archive, objects::AppState},
+    models::{ImportResponse, ObjectMetadata},
+};
+
+pub async fn import_directory(
+    state: &AppState,
+    root: &Path,
+    prefix: &str,
+    skip_existing: bool,
+) -> Result<ImportResponse> {
+    tracing::info!("Importing {} under prefix {:?}", root.display(), prefix);
+
+    let mut response = ImportResponse {
+        prefix: prefix.to_string(),
+        imported: 0,
+        skipped: 0,
+        total_size: 0,
+        failed: Vec::new(),
+    };
+
+    for path in walk(root).await? {
+        let name = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if skip_existing
+            && let Some(key) = archive::entry_key(prefix, &name)
+            && state.metadata.get(&key).await?.is_some()
+        {
+            tracing::debug!("Skipping existing object {}", key);
+            response.skipped += 1;
+            continue;
+        }
+
+        let result = import_file(state, prefix, &name, &path).await;
+        archive::record(&mut response, name, result);
+    }
+
+    tracing::info!(
+        "Imported {} files ({} bytes), skipped {}, {} failed",
+        response.imported,
+        response.total_size,
+        response.skipped,
+        response.failed.len()
+    );
+
+    Ok(response)
+}
+
+async fn import_file(
+    state: &AppState,
+    prefix: &str,
+    name: &str,
+    path: &Path,
+) -> Result<ObjectMetadata> {
+    let file = fs::File::open(path).await?;
+    state
+        .storage
+        .ensure_space(file.metadata().await?.len())
+        .await?;
+
+    archive::import_entry(state, prefix, name, ReaderStream::new(file)).await
+}
+
+pub(crate) async fn walk(root: &Path) -> Result<Vec<PathBuf>> {
+    if !fs::metadata(root).await?.is_dir() {
+        return Err(AppError::BadRequest(format!(
+            "{} is not a directory",
+            root.display()
+        )));
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let file_type = entry.file_type().await?;
+
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            } else {
+                tracing::warn!("Skipping {}: not a regular file", entry.path().display());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
@@ -0,0 +1,77 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::ObjectMetadata,
+    storage::{Access, MetadataStore},
+};
+
+const JOB_NAME: &str = "access_flush";
+const FLUSH_INTERVAL: Duration = Duration::from_secs(10);
+
+type Pending = HashMap<(String, String, String), (DateTime<Utc>, i64)>;
+
+#[derive(Clone, Default)]
+pub struct AccessTracker {
+    pending: Arc<Mutex<Pending>>,
+}
+
+impl AccessTracker {
+    pub fn record(&self, bucket: &str, metadata: &ObjectMetadata) {
+        let mut pending = self.pending.lock().unwrap();
+        let entry = pending
+            .entry((
+                bucket.to_string(),
+                metadata.key.clone(),
+                metadata.id.clone(),
+            ))
+            .or_insert((Utc::now(), 0));
+        entry.0 = Utc::now();
+        entry.1 += 1;
+    }
+
+    pub async fn flush(&self, metadata: &MetadataStore) -> Result<usize> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let flushed = pending.len();
+
+        let mut buckets: BTreeMap<String, Vec<Access>> = BTreeMap::new();
+        for ((bucket, key, id), (at, count)) in pending {
+            buckets
+                .entry(bucket)
+                .or_default()
+                .push(Access { key, id, at, count });
+        }
+
+        for (bucket, accesses) in buckets {
+            metadata.scoped(&bucket).record_access(&accesses).await?;
+        }
+
+        Ok(flushed)
+    }
+}
+
+pub async fn run(state: AppState) {
+    state.jobs.register(JOB_NAME, FLUSH_INTERVAL);
+    let mut interval = tokio::time::interval(FLUSH_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match state
+            .jobs
+            .track(JOB_NAME, state.access.flush(&state.metadata))
+            .await
+        {
+            Ok(0) => {}
+            Ok(flushed) => tracing::debug!("Recorded access times for {} objects", flushed),
+            Err(e) => tracing::error!("Failed to record access times: {}", e),
+        }
+    }
+}
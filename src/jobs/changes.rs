@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::handlers::objects::AppState;
+
+const JOB_NAME: &str = "changes_prune";
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+pub async fn run(state: AppState, retention_hours: u64) {
+    tracing::info!(
+        "Change log pruning started with {} hour retention",
+        retention_hours
+    );
+
+    state.jobs.register(JOB_NAME, PRUNE_INTERVAL);
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping change log pruning in read-only mode");
+            continue;
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::hours(retention_hours as i64);
+        let result = state
+            .jobs
+            .track(JOB_NAME, async {
+                Ok(state.metadata.prune_changes(cutoff).await? as usize)
+            })
+            .await;
+
+        match result {
+            Ok(0) => tracing::debug!("No change log entries to prune"),
+            Ok(pruned) => tracing::info!("Pruned {} change log entries", pruned),
+            Err(e) => tracing::error!("Change log pruning failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::EventOp,
+};
+
+const JOB_NAME: &str = "expiry";
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+const SWEEP_BATCH_SIZE: i64 = 500;
+
+pub async fn run(state: AppState) {
+    tracing::info!("Expiry sweeper started");
+
+    state.jobs.register(JOB_NAME, SWEEP_INTERVAL);
+    let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping expiry sweep in read-only mode");
+            continue;
+        }
+
+        match state.jobs.track(JOB_NAME, sweep_all(&state)).await {
+            Ok(0) => tracing::debug!("No expired objects found"),
+            Ok(removed) => tracing::info!("Removed {} expired objects", removed),
+            Err(e) => tracing::error!("Expiry sweep failed: {}", e),
+        }
+    }
+}
+
+async fn sweep_all(state: &AppState) -> Result<usize> {
+    let mut removed = 0;
+
+    for scope in state.scopes().await? {
+        removed += sweep_expired(&scope).await?;
+    }
+
+    Ok(removed)
+}
+
+pub async fn sweep_expired(state: &AppState) -> Result<usize> {
+    let mut removed = 0;
+
+    loop {
+        let expired = state
+            .metadata
+            .list_expired(Utc::now(), SWEEP_BATCH_SIZE)
+            .await?;
+
+        if expired.is_empty() {
+            return Ok(removed);
+        }
+
+        for metadata in expired {
+            if !state.metadata.delete(&metadata.key).await? {
+                continue;
+            }
+
+            state.publish(EventOp::Delete, &metadata).await;
+            removed += 1;
+
+            match state.storage.delete(&metadata.key).await {
+                Ok(()) | Err(AppError::NotFound(_)) => {
+                    tracing::debug!("Expired object {} removed", metadata.key)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to remove expired blob {}: {}", metadata.key, e)
+                }
+            }
+        }
+    }
+}
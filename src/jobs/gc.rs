@@ -0,0 +1,151 @@
+use std::{
+    collections::HashSet,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::GcReport,
+    storage::{FileStorage, MetadataStore},
+};
+
+const JOB_NAME: &str = "gc";
+
+pub async fn run(state: AppState, interval_hours: u64, min_age: Duration) {
+    tracing::info!(
+        "Blob garbage collector started, running every {} hours",
+        interval_hours
+    );
+
+    let period = Duration::from_secs(interval_hours * 3600);
+    state.jobs.register(JOB_NAME, period);
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping garbage collection in read-only mode");
+            continue;
+        }
+
+        let result = state
+            .jobs
+            .track(JOB_NAME, async {
+                collect(&state.metadata, &state.storage, min_age, false)
+                    .await
+                    .map(|report| report.removed)
+            })
+            .await;
+
+        match result {
+            Ok(0) => tracing::debug!("No orphaned blobs found"),
+            Ok(removed) => tracing::info!("Removed {} orphaned blobs", removed),
+            Err(e) => tracing::error!("Garbage collection failed: {}", e),
+        }
+    }
+}
+
+pub async fn collect(
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    min_age: Duration,
+    dry_run: bool,
+) -> Result<GcReport> {
+    let mut report = GcReport::default();
+
+    collect_scope(metadata, storage, min_age, dry_run, &mut report).await?;
+
+    for bucket in metadata.list_buckets().await? {
+        collect_scope(
+            &metadata.scoped(&bucket.name),
+            &storage.scoped(&bucket.name),
+            min_age,
+            dry_run,
+            &mut report,
+        )
+        .await?;
+    }
+
+    for blob in storage.list_unreferenced().await? {
+        report.blobs_scanned += 1;
+        sweep(storage, &blob, min_age, dry_run, &mut report).await?;
+    }
+
+    tracing::info!(
+        "Garbage collection scanned {} blobs: {} orphaned, {} too recent, {} removed ({} bytes)",
+        report.blobs_scanned,
+        report.orphaned,
+        report.skipped_recent,
+        report.removed,
+        report.bytes_reclaimed
+    );
+
+    Ok(report)
+}
+
+async fn collect_scope(
+    metadata: &MetadataStore,
+    storage: &FileStorage,
+    min_age: Duration,
+    dry_run: bool,
+    report: &mut GcReport,
+) -> Result<()> {
+    let blobs = storage.list_blobs().await?;
+    let mut referenced = HashSet::new();
+    for key in metadata.list_keys().await? {
+        referenced.insert(storage.get_object_path(&key).await);
+    }
+
+    for blob in blobs {
+        report.blobs_scanned += 1;
+
+        if referenced.contains(&blob) {
+            continue;
+        }
+
+        sweep(storage, &blob, min_age, dry_run, report).await?;
+    }
+
+    Ok(())
+}
+
+async fn sweep(
+    storage: &FileStorage,
+    blob: &Path,
+    min_age: Duration,
+    dry_run: bool,
+    report: &mut GcReport,
+) -> Result<()> {
+    let Ok(file) = tokio::fs::metadata(blob).await else {
+        return Ok(());
+    };
+
+    report.orphaned += 1;
+
+    let age = file
+        .modified()
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+        .unwrap_or_default();
+
+    if age < min_age {
+        tracing::debug!("Keeping recent unreferenced blob {}", blob.display());
+        report.skipped_recent += 1;
+        return Ok(());
+    }
+
+    if dry_run {
+        tracing::info!("Would remove orphaned blob {}", blob.display());
+        return Ok(());
+    }
+
+    storage.remove_blob(blob).await?;
+    tracing::debug!("Removed orphaned blob {}", blob.display());
+    report.removed += 1;
+    report.bytes_reclaimed += file.len();
+
+    Ok(())
+}
@@ -0,0 +1,162 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use croner::Cron;
+
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::{DatabaseStats, MaintenanceConfig, MaintenanceRun, MaintenanceStatus},
+};
+
+const JOB_NAME: &str = "db_maintenance";
+
+#[derive(Clone, Default)]
+pub struct MaintenanceTracker {
+    progress: Arc<Mutex<Option<Progress>>>,
+}
+
+struct Progress {
+    schedule: String,
+    next_run_at: Option<DateTime<Utc>>,
+    last_run: Option<MaintenanceRun>,
+    last_error: Option<String>,
+}
+
+impl MaintenanceTracker {
+    pub fn status(&self, database: DatabaseStats) -> Option<MaintenanceStatus> {
+        let progress = self.progress.lock().unwrap();
+        let progress = progress.as_ref()?;
+
+        Some(MaintenanceStatus {
+            schedule: progress.schedule.clone(),
+            next_run_at: progress.next_run_at,
+            database,
+            last_run: progress.last_run.clone(),
+            last_error: progress.last_error.clone(),
+        })
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut Progress)) {
+        if let Some(progress) = self.progress.lock().unwrap().as_mut() {
+            apply(progress);
+        }
+    }
+}
+
+pub fn parse_schedule(schedule: &str) -> std::result::Result<Cron, String> {
+    Cron::new(schedule)
+        .parse()
+        .map_err(|e| format!("invalid maintenance schedule '{}': {}", schedule, e))
+}
+
+pub async fn run(state: AppState, config: MaintenanceConfig) {
+    let schedule = match parse_schedule(&config.schedule) {
+        Ok(schedule) => schedule,
+        Err(e) => {
+            tracing::error!("Database maintenance disabled: {}", e);
+            return;
+        }
+    };
+
+    tracing::info!("Database maintenance scheduled for '{}'", config.schedule);
+
+    let now = Utc::now();
+    let interval = schedule
+        .find_next_occurrence(&now, false)
+        .and_then(|first| {
+            schedule
+                .find_next_occurrence(&first, false)
+                .map(|second| second - first)
+        })
+        .ok()
+        .and_then(|gap| gap.to_std().ok())
+        .unwrap_or(Duration::ZERO);
+    state.jobs.register(JOB_NAME, interval);
+
+    *state.maintenance.progress.lock().unwrap() = Some(Progress {
+        schedule: config.schedule.clone(),
+        next_run_at: None,
+        last_run: None,
+        last_error: None,
+    });
+
+    loop {
+        let next = match schedule.find_next_occurrence(&Utc::now(), false) {
+            Ok(next) => next,
+            Err(e) => {
+                tracing::error!("No further database maintenance runs scheduled: {}", e);
+                state
+                    .maintenance
+                    .update(|progress| progress.next_run_at = None);
+                return;
+            }
+        };
+
+        tracing::debug!("Next database maintenance at {}", next);
+        state
+            .maintenance
+            .update(|progress| progress.next_run_at = Some(next));
+        tokio::time::sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping database maintenance in read-only mode");
+            continue;
+        }
+
+        let result = state
+            .jobs
+            .track(JOB_NAME, async {
+                let run = maintain(&state, &config).await?;
+                let reclaimed = run.reclaimed_bytes as usize;
+                state.maintenance.update(|progress| {
+                    progress.last_run = Some(run);
+                    progress.last_error = None;
+                });
+                Ok(reclaimed)
+            })
+            .await;
+
+        match result {
+            Ok(reclaimed) => tracing::info!(
+                "Database maintenance finished, reclaimed {} bytes",
+                reclaimed
+            ),
+            Err(e) => {
+                tracing::error!("Database maintenance failed: {}", e);
+                state
+                    .maintenance
+                    .update(|progress| progress.last_error = Some(e.to_string()));
+            }
+        }
+    }
+}
+
+async fn maintain(state: &AppState, config: &MaintenanceConfig) -> Result<MaintenanceRun> {
+    let started_at = Utc::now();
+    let started = Instant::now();
+
+    let before = state.metadata.database_stats().await?;
+    if config.vacuum_pages > 0 && !before.incremental_vacuum {
+        tracing::debug!("Incremental vacuum is not enabled for the metadata database");
+    }
+
+    state
+        .metadata
+        .optimize(config.vacuum_pages, config.analyze)
+        .await?;
+
+    let after = state.metadata.database_stats().await?;
+
+    Ok(MaintenanceRun {
+        started_at,
+        duration_ms: started.elapsed().as_millis() as u64,
+        analyzed: config.analyze,
+        size_before_bytes: before.size_bytes,
+        size_after_bytes: after.size_bytes,
+        reclaimed_bytes: before.size_bytes.saturating_sub(after.size_bytes),
+    })
+}
@@ -0,0 +1,78 @@
+pub mod access;
+pub mod changes;
+pub mod expiry;
+pub mod gc;
+pub mod maintenance;
+pub mod reconcile;
+pub mod replication;
+pub mod tiering;
+pub mod trash;
+pub mod variants;
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+
+use crate::{error::Result, models::JobStatus};
+
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    jobs: Arc<Mutex<BTreeMap<&'static str, JobStatus>>>,
+}
+
+impl JobRegistry {
+    pub fn register(&self, name: &'static str, interval: Duration) {
+        self.jobs.lock().unwrap().insert(
+            name,
+            JobStatus {
+                name: name.to_string(),
+                interval_seconds: interval.as_secs(),
+                runs: 0,
+                running: false,
+                last_started_at: None,
+                last_duration_ms: None,
+                last_processed: None,
+                last_error: None,
+            },
+        );
+    }
+
+    pub async fn track<F>(&self, name: &'static str, run: F) -> Result<usize>
+    where
+        F: Future<Output = Result<usize>>,
+    {
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(name) {
+            job.running = true;
+            job.last_started_at = Some(Utc::now());
+        }
+
+        let started = Instant::now();
+        let result = run.await;
+
+        if let Some(job) = self.jobs.lock().unwrap().get_mut(name) {
+            job.running = false;
+            job.runs += 1;
+            job.last_duration_ms = Some(started.elapsed().as_millis() as u64);
+            match &result {
+                Ok(processed) => {
+                    job.last_processed = Some(*processed);
+                    job.last_error = None;
+                }
+                Err(e) => {
+                    job.last_processed = None;
+                    job.last_error = Some(e.to_string());
+                }
+            }
+        }
+
+        result
+    }
+
+    pub fn list(&self) -> Vec<JobStatus> {
+        self.jobs.lock().unwrap().values().cloned().collect()
+    }
+}
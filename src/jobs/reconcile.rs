@@ -0,0 +1,142 @@
+use std::{collections::HashSet, time::Duration};
+
+use chrono::Utc;
+
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::{EventOp, ObjectMetadata, ReconcileConfig},
+};
+
+const JOB_NAME: &str = "reconcile";
+
+#[derive(Default)]
+struct Drift {
+    checked: usize,
+    dangling: usize,
+    orphaned: usize,
+    healed: usize,
+}
+
+pub async fn run(state: AppState, policy: ReconcileConfig) {
+    tracing::info!(
+        "Reconciliation started, sampling {} objects every {} minutes",
+        policy.sample_size,
+        policy.interval_minutes
+    );
+
+    let period = Duration::from_secs(policy.interval_minutes.max(1) * 60);
+    state.jobs.register(JOB_NAME, period);
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        let result = state
+            .jobs
+            .track(JOB_NAME, async {
+                let drift = reconcile_all(&state, &policy).await?;
+
+                metrics::counter!("lila_reconcile_objects_checked_total")
+                    .increment(drift.checked as u64);
+                metrics::counter!("lila_reconcile_healed_total").increment(drift.healed as u64);
+                metrics::gauge!("lila_reconcile_dangling_objects").set(drift.dangling as f64);
+                metrics::gauge!("lila_reconcile_orphaned_blobs").set(drift.orphaned as f64);
+
+                if drift.dangling > 0 || drift.orphaned > 0 {
+                    tracing::warn!(
+                        "Reconciliation found {} objects without blobs ({} healed) and {} unreferenced blobs",
+                        drift.dangling,
+                        drift.healed,
+                        drift.orphaned
+                    );
+                }
+
+                Ok(drift.checked)
+            })
+            .await;
+
+        match result {
+            Ok(checked) => tracing::debug!("Reconciled {} sampled objects", checked),
+            Err(e) => tracing::error!("Reconciliation failed: {}", e),
+        }
+    }
+}
+
+async fn reconcile_all(state: &AppState, policy: &ReconcileConfig) -> Result<Drift> {
+    let mut drift = Drift::default();
+
+    for scope in state.scopes().await? {
+        reconcile(&scope, policy, &mut drift).await?;
+    }
+
+    drift.orphaned += state.storage.list_unreferenced().await?.len();
+
+    Ok(drift)
+}
+
+async fn reconcile(state: &AppState, policy: &ReconcileConfig, drift: &mut Drift) -> Result<()> {
+    let cutoff = Utc::now() - chrono::Duration::minutes(policy.grace_minutes as i64);
+
+    for obj in state.metadata.sample(policy.sample_size).await? {
+        if obj.created_at > cutoff {
+            continue;
+        }
+
+        drift.checked += 1;
+
+        if state.storage.blob_size(&obj.key).await?.is_some() {
+            continue;
+        }
+
+        tracing::warn!("Object {} has no blob on disk", obj.key);
+        drift.dangling += 1;
+
+        if policy.heal && !state.is_read_only() && tombstone(state, &obj).await? {
+            drift.healed += 1;
+        }
+    }
+
+    let mut referenced = HashSet::new();
+    for key in state.metadata.list_keys().await? {
+        referenced.insert(state.storage.get_object_path(&key).await);
+    }
+
+    for blob in state.storage.list_blobs().await? {
+        if !referenced.contains(&blob) {
+            tracing::debug!("Blob {} is not referenced by any object", blob.display());
+            drift.orphaned += 1;
+        }
+    }
+
+    Ok(())
+}
+
+async fn tombstone(state: &AppState, obj: &ObjectMetadata) -> Result<bool> {
+    match state.metadata.get(&obj.key).await? {
+        Some(current) if current.id == obj.id => {}
+        _ => return Ok(false),
+    }
+
+    if state.storage.blob_size(&obj.key).await?.is_some() {
+        return Ok(false);
+    }
+
+    if state.trash_retention_hours.is_some() {
+        state.metadata.insert_trash(obj).await?;
+    }
+
+    if !state.metadata.delete(&obj.key).await? {
+        return Ok(false);
+    }
+    state.publish(EventOp::Delete, obj).await;
+
+    tracing::warn!(
+        "Tombstoned {} (etag {}, {} bytes) whose blob is missing",
+        obj.key,
+        obj.etag,
+        obj.size
+    );
+
+    Ok(true)
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use percent_encoding::utf8_percent_encode;
+use reqwest::{Method, StatusCode, header};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    error::{AppError, Result},
+    handlers::objects::{AppState, USER_METADATA_PREFIX},
+    models::{EventOp, ObjectEvent, ReplicationConfig, ReplicationStatus},
+    s3::auth::UNRESERVED,
+};
+
+const CHECKPOINT: &str = "replication";
+const CATCH_UP_BATCH: i64 = 500;
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Default)]
+pub struct ReplicationTracker {
+    progress: Arc<Mutex<Option<Progress>>>,
+}
+
+struct Progress {
+    endpoint: String,
+    replicated_seq: u64,
+    replicated_at: Option<DateTime<Utc>>,
+    pending_since: Option<DateTime<Utc>>,
+    failures: u64,
+    dropped: u64,
+    last_error: Option<String>,
+}
+
+impl ReplicationTracker {
+    pub fn status(&self, latest_seq: u64) -> Option<ReplicationStatus> {
+        let progress = self.progress.lock().unwrap();
+        let progress = progress.as_ref()?;
+
+        Some(ReplicationStatus {
+            endpoint: progress.endpoint.clone(),
+            latest_seq,
+            replicated_seq: progress.replicated_seq,
+            lag_events: latest_seq.saturating_sub(progress.replicated_seq),
+            lag_seconds: progress
+                .pending_since
+                .map(|since| (Utc::now() - since).num_milliseconds().max(0) as f64 / 1000.0)
+                .unwrap_or(0.0),
+            replicated_at: progress.replicated_at,
+            failures: progress.failures,
+            dropped_events: progress.dropped,
+            last_error: progress.last_error.clone(),
+        })
+    }
+
+    fn update(&self, apply: impl FnOnce(&mut Progress)) {
+        if let Some(progress) = self.progress.lock().unwrap().as_mut() {
+            apply(progress);
+        }
+    }
+}
+
+struct Replica {
+    client: reqwest::Client,
+    endpoint: String,
+    token: String,
+    buckets: HashSet<String>,
+}
+
+pub async fn run(state: AppState, config: ReplicationConfig) {
+    tracing::info!("Replicating object changes to {}", config.endpoint);
+
+    let mut receiver = state.events.subscribe(None).receiver;
+    let mut position = match state.metadata.get_checkpoint(CHECKPOINT).await {
+        Ok(seq) => seq.unwrap_or(0),
+        Err(e) => {
+            tracing::error!("Failed to read replication checkpoint: {}", e);
+            0
+        }
+    };
+    tracing::info!("Resuming replication after change {}", position);
+
+    *state.replication.progress.lock().unwrap() = Some(Progress {
+        endpoint: config.endpoint.clone(),
+        replicated_seq: position,
+        replicated_at: None,
+        pending_since: None,
+        failures: 0,
+        dropped: 0,
+        last_error: None,
+    });
+
+    let mut replica = Replica {
+        client: reqwest::Client::new(),
+        endpoint: config.endpoint.trim_end_matches('/').to_string(),
+        token: config.token,
+        buckets: HashSet::new(),
+    };
+
+    let mut catching_up = true;
+
+    loop {
+        if catching_up {
+            match state.metadata.list_changes(position, CATCH_UP_BATCH).await {
+                Ok(changes) if changes.is_empty() => catching_up = false,
+                Ok(changes) => {
+                    let missed = changes[0].seq.saturating_sub(position + 1);
+                    if missed > 0 {
+                        tracing::error!(
+                            "{} changes were pruned before they could be replicated",
+                            missed
+                        );
+                        metrics::counter!("lila_replication_dropped_total").increment(missed);
+                        state
+                            .replication
+                            .update(|progress| progress.dropped += missed);
+                    }
+
+                    for change in &changes {
+                        deliver(&state, &mut replica, change).await;
+                        position = change.seq;
+                    }
+                }
+                Err(e) => {
+                    tracing::error!("Failed to read the change log: {}", e);
+                    tokio::time::sleep(MAX_BACKOFF).await;
+                }
+            }
+            continue;
+        }
+
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                tracing::warn!(
+                    "Replication fell {} events behind, catching up from the change log",
+                    missed
+                );
+                catching_up = true;
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        if event.seq <= position {
+            continue;
+        }
+        if event.seq > position + 1 {
+            catching_up = true;
+            continue;
+        }
+
+        deliver(&state, &mut replica, &event).await;
+        position = event.seq;
+    }
+}
+
+async fn deliver(state: &AppState, replica: &mut Replica, event: &ObjectEvent) {
+    state
+        .replication
+        .update(|progress| progress.pending_since = Some(event.timestamp));
+
+    let mut backoff = INITIAL_BACKOFF;
+    while let Err(e) = replicate(state, replica, event).await {
+        tracing::warn!(
+            "Failed to replicate {} of {}, retrying in {:?}: {}",
+            event.op.as_str(),
+            event.key,
+            backoff,
+            e
+        );
+        metrics::counter!("lila_replication_failures_total").increment(1);
+        state.replication.update(|progress| {
+            progress.failures += 1;
+            progress.last_error = Some(e.to_string());
+        });
+
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+
+    metrics::counter!("lila_replicated_events_total").increment(1);
+    state.replication.update(|progress| {
+        progress.replicated_seq = event.seq;
+        progress.replicated_at = Some(Utc::now());
+        progress.pending_since = None;
+        progress.last_error = None;
+    });
+
+    if let Err(e) = state.metadata.set_checkpoint(CHECKPOINT, event.seq).await {
+        tracing::warn!("Failed to save replication checkpoint: {}", e);
+    }
+}
+
+async fn replicate(state: &AppState, replica: &mut Replica, event: &ObjectEvent) -> Result<()> {
+    let state = match &event.bucket {
+        Some(name) => match state.metadata.get_bucket(name).await? {
+            Some(bucket) => state.scoped(bucket),
+            None => {
+                tracing::debug!("Bucket {} is gone, skipping {}", name, event.key);
+                return Ok(());
+            }
+        },
+        None => state.clone(),
+    };
+
+    if let Some(bucket) = &event.bucket {
+        ensure_bucket(replica, bucket).await?;
+    }
+
+    let url = object_url(replica, event);
+
+    match event.op {
+        EventOp::Put => {
+            let Some(metadata) = state.metadata.get(&event.key).await? else {
+                tracing::debug!("{} was deleted before it could be replicated", event.key);
+                return Ok(());
+            };
+            if metadata.etag != event.etag {
+                tracing::debug!("{} changed before it could be replicated", event.key);
+                return Ok(());
+            }
+
+            let stream = state
+                .storage
+                .stream(&metadata, 0, metadata.size as u64)
+                .await?;
+
+            let mut request = replica
+                .request(Method::PUT, &url)
+                .header(header::CONTENT_TYPE, &metadata.content_type)
+                .header(header::CONTENT_LENGTH, metadata.size)
+                .header("x-lila-public", metadata.public.to_string());
+            if let Some(expires_at) = metadata.expires_at {
+                request = request.header("x-lila-expires-at", expires_at.to_rfc3339());
+            }
+            if let Some(cache_control) = &metadata.cache_control {
+                request = request.header(header::CACHE_CONTROL, cache_control);
+            }
+            if let Some(expires) = &metadata.cache_expires {
+                request = request.header(header::EXPIRES, expires);
+            }
+            for (name, value) in &metadata.user_metadata {
+                request = request.header(format!("{}{}", USER_METADATA_PREFIX, name), value);
+            }
+
+            let response = request
+                .body(reqwest::Body::wrap_stream(stream))
+                .send()
+                .await
+                .map_err(|e| AppError::Upstream(format!("replica PUT failed: {}", e)))?;
+            check(response, &event.key, &[]).await?;
+            tracing::debug!("Replicated {} ({} bytes)", event.key, metadata.size);
+        }
+        EventOp::Delete => {
+            let response = replica
+                .request(Method::DELETE, &url)
+                .send()
+                .await
+                .map_err(|e| AppError::Upstream(format!("replica DELETE failed: {}", e)))?;
+            check(response, &event.key, &[StatusCode::NOT_FOUND]).await?;
+            tracing::debug!("Replicated deletion of {}", event.key);
+        }
+    }
+
+    Ok(())
+}
+
+async fn ensure_bucket(replica: &mut Replica, bucket: &str) -> Result<()> {
+    if replica.buckets.contains(bucket) {
+        return Ok(());
+    }
+
+    let url = format!("{}/api/v1/buckets/{}", replica.endpoint, encode(bucket));
+    let response = replica
+        .request(Method::PUT, &url)
+        .send()
+        .await
+        .map_err(|e| AppError::Upstream(format!("replica bucket creation failed: {}", e)))?;
+    check(response, bucket, &[StatusCode::CONFLICT]).await?;
+
+    replica.buckets.insert(bucket.to_string());
+    Ok(())
+}
+
+impl Replica {
+    fn request(&self, method: Method, url: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, url).bearer_auth(&self.token)
+    }
+}
+
+fn object_url(replica: &Replica, event: &ObjectEvent) -> String {
+    let key = event
+        .key
+        .split('/')
+        .map(encode)
+        .collect::<Vec<_>>()
+        .join("/");
+
+    match &event.bucket {
+        Some(bucket) => format!(
+            "{}/api/v1/buckets/{}/objects/{}",
+            replica.endpoint,
+            encode(bucket),
+            key
+        ),
+        None => format!("{}/api/v1/objects/{}", replica.endpoint, key),
+    }
+}
+
+fn encode(segment: &str) -> String {
+    utf8_percent_encode(segment, UNRESERVED).to_string()
+}
+
+async fn check(response: reqwest::Response, name: &str, tolerated: &[StatusCode]) -> Result<()> {
+    let status = response.status();
+
+    if status.is_success() || tolerated.contains(&status) {
+        return Ok(());
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(AppError::Upstream(format!(
+        "replica returned {} for {}: {}",
+        status,
+        name,
+        body.trim()
+    )))
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{
+    error::Result,
+    handlers::objects::AppState,
+    models::{SortField, SortOrder, TieringConfig},
+};
+
+const JOB_NAME: &str = "tiering";
+
+pub async fn run(state: AppState, policy: TieringConfig) {
+    tracing::info!(
+        "Cold tier migration started, running every {} hours",
+        policy.interval_hours
+    );
+
+    let period = Duration::from_secs(policy.interval_hours.max(1) * 3600);
+    state.jobs.register(JOB_NAME, period);
+    let mut interval = tokio::time::interval(period);
+
+    loop {
+        interval.tick().await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping cold tier migration in read-only mode");
+            continue;
+        }
+
+        match state
+            .jobs
+            .track(JOB_NAME, migrate_all(&state, &policy))
+            .await
+        {
+            Ok(0) => tracing::debug!("No objects due for the cold tier"),
+            Ok(moved) => tracing::info!("Moved {} objects to the cold tier", moved),
+            Err(e) => tracing::error!("Cold tier migration failed: {}", e),
+        }
+    }
+}
+
+async fn migrate_all(state: &AppState, policy: &TieringConfig) -> Result<usize> {
+    let mut moved = 0;
+
+    for scope in state.scopes().await? {
+        moved += migrate(&scope, policy).await?;
+    }
+
+    Ok(moved)
+}
+
+async fn migrate(state: &AppState, policy: &TieringConfig) -> Result<usize> {
+    let now = Utc::now();
+    let created_before = policy
+        .min_age_days
+        .map(|days| now - chrono::Duration::days(days as i64));
+    let accessed_before = policy
+        .idle_days
+        .map(|days| now - chrono::Duration::days(days as i64));
+
+    let mut rows = state
+        .metadata
+        .stream(None, SortField::CreatedAt, SortOrder::Asc);
+    let mut moved = 0;
+
+    while let Some(row) = rows.recv().await {
+        let obj = row?;
+
+        if created_before.is_some_and(|cutoff| obj.created_at > cutoff) {
+            break;
+        }
+
+        if accessed_before.is_some_and(|cutoff| obj.accessed_at.unwrap_or(obj.created_at) > cutoff)
+        {
+            continue;
+        }
+
+        match state.storage.demote(&obj.key).await {
+            Ok(true) => moved += 1,
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to move {} to the cold tier: {}", obj.key, e),
+        }
+    }
+
+    Ok(moved)
+}
@@ -0,0 +1,71 @@
+use std::time::Duration;
+
+use chrono::Utc;
+
+use crate::{error::Result, handlers::objects::AppState};
+
+const JOB_NAME: &str = "trash_purge";
+const PURGE_INTERVAL: Duration = Duration::from_secs(600);
+
+pub async fn run(state: AppState, retention_hours: u64) {
+    tracing::info!(
+        "Trash purge job started with {} hour retention",
+        retention_hours
+    );
+
+    state.jobs.register(JOB_NAME, PURGE_INTERVAL);
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        if state.is_read_only() {
+            tracing::debug!("Skipping trash purge in read-only mode");
+            continue;
+        }
+
+        match state
+            .jobs
+            .track(JOB_NAME, purge_all(&state, retention_hours))
+            .await
+        {
+            Ok(0) => tracing::debug!("No expired objects in trash"),
+            Ok(purged) => tracing::info!("Purged {} expired objects from trash", purged),
+            Err(e) => tracing::error!("Trash purge failed: {}", e),
+        }
+    }
+}
+
+async fn purge_all(state: &AppState, retention_hours: u64) -> Result<usize> {
+    let mut purged = 0;
+
+    for scope in state.scopes().await? {
+        purged += purge_expired(&scope, retention_hours).await?;
+    }
+
+    Ok(purged)
+}
+
+pub async fn purge_expired(state: &AppState, retention_hours: u64) -> Result<usize> {
+    let cutoff = Utc::now() - chrono::Duration::hours(retention_hours as i64);
+    let expired = state.metadata.list_trash_before(cutoff).await?;
+
+    let mut purged = 0;
+    for entry in expired {
+        let metadata = entry.metadata;
+
+        if let Err(e) = state
+            .storage
+            .delete_trashed(&metadata.key, &metadata.id)
+            .await
+        {
+            tracing::warn!("Failed to remove trashed blob for {}: {}", metadata.key, e);
+        }
+
+        if state.metadata.delete_trash(&metadata.id).await? {
+            purged += 1;
+        }
+    }
+
+    Ok(purged)
+}
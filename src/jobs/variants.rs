@@ -0,0 +1,27 @@
+use std::time::Duration;
+
+use crate::handlers::objects::AppState;
+
+const JOB_NAME: &str = "variant_cache_trim";
+const TRIM_INTERVAL: Duration = Duration::from_secs(300);
+
+pub async fn run(state: AppState, max_mb: u64) {
+    tracing::info!("Image variant cache limited to {} MB", max_mb);
+
+    state.jobs.register(JOB_NAME, TRIM_INTERVAL);
+    let mut interval = tokio::time::interval(TRIM_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match state
+            .jobs
+            .track(JOB_NAME, state.storage.trim_variants(max_mb * 1024 * 1024))
+            .await
+        {
+            Ok(0) => tracing::debug!("Image variant cache within limits"),
+            Ok(removed) => tracing::info!("Evicted {} cached image variants", removed),
+            Err(e) => tracing::error!("Image variant cache trim failed: {}", e),
+        }
+    }
+}
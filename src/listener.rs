@@ -0,0 +1,81 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+};
+use axum_server::tls_rustls::RustlsConfig;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::models::ListenerConfig;
+
+pub async fn spawn(
+    config: &ListenerConfig,
+    app: Router,
+    tls: Option<RustlsConfig>,
+    shutdown: CancellationToken,
+) -> io::Result<JoinHandle<io::Result<()>>> {
+    let listener = tokio::net::TcpListener::bind(&config.address).await?;
+
+    let app = if config.routes.is_empty() {
+        app
+    } else {
+        let routes: Arc<[String]> = config.routes.clone().into();
+        app.layer(middleware::from_fn_with_state(routes, restrict))
+    };
+    let service = app.into_make_service_with_connect_info::<SocketAddr>();
+
+    let tls = tls.filter(|_| config.tls);
+    match &tls {
+        Some(_) => tracing::info!("Serving HTTPS on {}", config.address),
+        None => tracing::info!("Serving HTTP on {}", config.address),
+    }
+    if !config.routes.is_empty() {
+        tracing::info!(
+            "Listener {} restricted to {}",
+            config.address,
+            config.routes.join(", ")
+        );
+    }
+
+    let Some(tls) = tls else {
+        return Ok(tokio::spawn(
+            axum::serve(listener, service)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .into_future(),
+        ));
+    };
+
+    let handle = axum_server::Handle::new();
+    tokio::spawn({
+        let handle = handle.clone();
+        async move {
+            shutdown.cancelled().await;
+            handle.graceful_shutdown(None);
+        }
+    });
+
+    Ok(tokio::spawn(
+        axum_server::from_tcp_rustls(listener.into_std()?, tls)
+            .handle(handle)
+            .serve(service),
+    ))
+}
+
+async fn restrict(State(routes): State<Arc<[String]>>, request: Request, next: Next) -> Response {
+    let path = request.uri().path();
+    let allowed = routes.iter().any(|route| {
+        path.strip_prefix(route.trim_end_matches('/'))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    });
+
+    if !allowed {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    next.run(request).await
+}
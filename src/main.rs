@@ -1,107 +1,744 @@
+mod access_log;
 mod auth;
+mod backup;
+mod cli;
+mod compression;
+mod concurrency;
 mod config;
 mod error;
+mod events;
+mod export;
+mod fsck;
+mod graphql;
 mod handlers;
+mod images;
+mod import;
+mod jobs;
+mod listener;
 mod models;
+#[cfg(feature = "fuse")]
+mod mount;
+mod oidc;
+mod openapi;
+mod publish;
+mod rate_limit;
+mod remote;
+mod request_id;
+mod s3;
+mod signing;
 mod storage;
+mod telemetry;
+mod timeout;
+mod tls;
+
+use std::{sync::Arc, time::Duration};
 
 use axum::{
-    Router, middleware,
-    routing::{delete, get, put},
+    Router,
+    extract::DefaultBodyLimit,
+    middleware,
+    routing::{any, delete, get, head, options, patch, post, put},
 };
+use chrono::Utc;
+use clap::Parser;
+use cli::{Cli, Command};
+use compression::AtRestPolicy;
 use handlers::objects::AppState;
-use storage::{FileStorage, MetadataStore};
-use tower_http::{
-    cors::CorsLayer,
-    trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer},
-};
-use tracing::Level;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use rate_limit::RequestClass;
+use storage::{FileStorage, MetadataStore, SqliteMetadataStore, Upstream};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tower_http::{compression::CompressionLayer, cors::CorsLayer, trace::TraceLayer};
+use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Some(Command::Ls {
+            path,
+            recursive,
+            long,
+            remote,
+        }) => return remote::ls(&remote, &path, recursive, long).await,
+        Some(Command::Cp {
+            source,
+            destination,
+            recursive,
+            remote,
+        }) => return remote::cp(&remote, &source, &destination, recursive).await,
+        Some(Command::Rm {
+            paths,
+            recursive,
+            remote,
+        }) => return remote::rm(&remote, &paths, recursive).await,
+        Some(Command::Stat { path, remote }) => return remote::stat(&remote, &path).await,
+        #[cfg(feature = "fuse")]
+        Some(Command::Mount {
+            mountpoint,
+            bucket,
+            read_only,
+            allow_other,
+            remote,
+        }) => {
+            cli::init_tracing();
+            let client = remote.client()?;
+            let client = match bucket {
+                Some(bucket) => client.bucket(bucket),
+                None => client,
+            };
+            return mount::mount(client, &mountpoint, read_only, allow_other).await;
+        }
+        _ => {}
+    }
+
+    let config_path = cli.config_path()?;
+    let config = models::Config::load(&config_path)?;
+
+    match cli.command.unwrap_or_default() {
+        Command::Serve => serve(config).await,
+        Command::CheckConfig => cli::check_config(&config_path, &config),
+        Command::Fsck { repair, quick } => {
+            cli::fsck(&config, models::FsckQuery { repair, quick }).await
+        }
+        Command::Import {
+            path,
+            prefix,
+            bucket,
+            skip_existing,
+        } => cli::import(&config, &path, &prefix, bucket.as_deref(), skip_existing).await,
+        Command::Export {
+            prefix,
+            out,
+            bucket,
+        } => cli::export(&config, &prefix, &out, bucket.as_deref()).await,
+        Command::Restore { archive, dry_run } => cli::restore(&config, &archive, dry_run).await,
+        Command::Gc {
+            dry_run,
+            min_age_minutes,
+        } => {
+            let min_age = min_age_minutes.unwrap_or(config.gc_min_age_minutes);
+            cli::gc(&config, Duration::from_secs(min_age * 60), dry_run).await
+        }
+        Command::Ls { .. } | Command::Cp { .. } | Command::Rm { .. } | Command::Stat { .. } => {
+            unreachable!()
+        }
+        #[cfg(feature = "fuse")]
+        Command::Mount { .. } => unreachable!(),
+    }
+}
+
+fn init_tracing(
+    config: &models::Config,
+) -> Result<Option<SdkTracerProvider>, Box<dyn std::error::Error>> {
+    let (otlp_layer, tracer_provider) = match &config.otlp {
+        Some(otlp) => {
+            let (layer, provider) = telemetry::otlp_layer(otlp)?;
+            (Some(layer), Some(provider))
+        }
+        None => (None, None),
+    };
+
     tracing_subscriber::registry()
         .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "lila=debug,tower_http=debug,axum=debug".into()),
+            tracing_subscriber::fmt::layer().with_filter(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| "lila=debug,tower_http=debug,axum=debug".into()),
+            ),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(otlp_layer)
         .init();
 
+    Ok(tracer_provider)
+}
+
+async fn serve(config: models::Config) -> Result<(), Box<dyn std::error::Error>> {
+    config.validate()?;
+    let tracer_provider = init_tracing(&config)?;
+
     tracing::info!("Starting lila");
     tracing::info!("Created by april");
-
-    let config = models::Config::load()?;
     tracing::info!("Configuration loaded successfully");
+    if let Some(otlp) = &config.otlp {
+        tracing::info!(
+            "Exporting traces over OTLP to {}",
+            otlp.endpoint.as_deref().unwrap_or("the default endpoint")
+        );
+    }
     tracing::debug!(
         "Server will bind to {}:{}",
         config.server_host,
         config.server_port
     );
-    tracing::debug!("Storage path: {}", config.storage_path);
+    tracing::debug!("Storage path: {}", config.storage_path.volumes().join(", "));
     tracing::debug!("Database URL: {}", config.database_url);
     tracing::debug!("Max upload size: {} MB", config.max_upload_size_mb);
+    tracing::debug!("Versioning enabled: {}", config.versioning);
+    tracing::debug!("ETag algorithm: {}", config.etag_algorithm);
+    tracing::debug!("Trash retention: {:?} hours", config.trash_retention_hours);
+    tracing::debug!(
+        "Garbage collection interval: {:?} hours",
+        config.gc_interval_hours
+    );
+    tracing::debug!("Compression enabled: {}", config.compression.enabled);
+    tracing::debug!("Read-only mode: {}", config.read_only);
+    if config.mode == models::Mode::Replica {
+        tracing::info!("Running as a read-only replica");
+    }
+    tracing::debug!("Storage quota: {:?} bytes", config.quota_bytes);
+    let read_limit = config.rate_limit_read.or(config.rate_limit);
+    let write_limit = config.rate_limit_write.or(config.rate_limit);
+    tracing::debug!(
+        "Rate limits: read {:?}, write {:?}",
+        read_limit,
+        write_limit
+    );
+    for rule in &config.quota_rules {
+        tracing::debug!(
+            "Quota rule: {} limited to {} bytes",
+            rule.prefix,
+            rule.bytes
+        );
+    }
+    for prefix in &config.public_prefixes {
+        tracing::debug!("Public prefix: {}", prefix);
+    }
+    for rule in &config.retention_rules {
+        tracing::debug!("Retention rule: {} for {} days", rule.prefix, rule.days);
+    }
 
-    let metadata = MetadataStore::new(&config.database_url).await?;
+    let metadata: MetadataStore =
+        Arc::new(SqliteMetadataStore::new(&config.database_url, &config.database).await?);
     tracing::info!("Metadata store initialized");
 
-    let storage = FileStorage::new(&config.storage_path).await?;
+    match &config.auth_token {
+        Some(token) => {
+            let config_token = models::ApiToken {
+                id: auth::CONFIG_TOKEN_ID.to_string(),
+                name: "config.toml".to_string(),
+                scope: models::TokenScope::Admin,
+                rate_limit: None,
+                created_at: Utc::now(),
+            };
+            metadata
+                .create_token(
+                    &config_token,
+                    &auth::hash_token(token),
+                    &signing::derive_key(token),
+                )
+                .await?;
+            tracing::debug!("Registered auth_token from configuration");
+        }
+        None => {
+            metadata.delete_token(auth::CONFIG_TOKEN_ID).await?;
+        }
+    }
+
+    if metadata.count_tokens().await? == 0 {
+        let bootstrap = models::ApiToken {
+            id: Uuid::new_v4().to_string(),
+            name: "bootstrap".to_string(),
+            scope: models::TokenScope::Admin,
+            rate_limit: None,
+            created_at: Utc::now(),
+        };
+        let secret = auth::generate_token();
+        metadata
+            .create_token(
+                &bootstrap,
+                &auth::hash_token(&secret),
+                &signing::derive_key(&secret),
+            )
+            .await?;
+        eprintln!("Bootstrap API token (shown once, store it now): {}", secret);
+        tracing::warn!(
+            "No API tokens configured, created bootstrap token {}",
+            bootstrap.id
+        );
+    }
+
+    let mut storage = FileStorage::new(
+        config.storage_path.volumes(),
+        config.etag_algorithm,
+        config.disk_reserve_mb * 1024 * 1024,
+    )
+    .await?
+    .with_durability(config.durability)
+    .with_buffer_sizes(
+        config.streaming.read_buffer_kb * 1024,
+        config.streaming.write_buffer_kb * 1024,
+    );
+
+    if let Some(policy) = AtRestPolicy::new(&config.compression) {
+        tracing::info!("Compressing blobs at rest with zstd level {}", policy.level);
+        storage = storage.with_compression(policy);
+    }
+
+    if config.dedup {
+        tracing::info!("Deduplicating blobs by content hash");
+        storage = storage.with_dedup(metadata.clone());
+    }
+
+    if let Some(chunking) = &config.chunking {
+        if config.upstream.is_some() {
+            tracing::warn!(
+                "Chunked storage is not supported with an upstream mirror, storing objects whole"
+            );
+        } else {
+            tracing::info!(
+                "Splitting objects over {} MB into {} MB chunks",
+                chunking.threshold_mb,
+                chunking.chunk_size_mb
+            );
+            storage = storage.with_chunking(chunking.clone());
+        }
+    }
+
+    if let Some(tiering) = &config.tiering {
+        storage = storage.with_cold_tier(&tiering.cold_path).await?;
+        tracing::info!("Cold storage tier at {}", tiering.cold_path);
+    }
+
+    if let Some(upstream) = &config.upstream {
+        storage = storage.with_upstream(Upstream::new(upstream)?);
+        tracing::info!(
+            "Mirroring blobs to upstream bucket {} at {}",
+            upstream.bucket,
+            upstream.endpoint
+        );
+    }
     tracing::info!("File storage initialized");
 
-    let state = AppState {
-        metadata,
-        storage,
-        auth_token: config.auth_token.clone(),
-        max_upload_size: config.max_upload_size_mb,
+    let discarded = storage.recover().await?;
+    if discarded > 0 {
+        tracing::info!("Cleaned up {} interrupted writes", discarded);
+    }
+
+    let presign_secret = match config.presign_secret.clone().or(config.auth_token.clone()) {
+        Some(secret) => secret,
+        None => {
+            tracing::warn!("presign_secret is not set, presigned URLs will not survive a restart");
+            auth::generate_token()
+        }
     };
 
+    let state = AppState::new(&config, metadata, storage, presign_secret);
+    state.events.resume(state.metadata.latest_change().await?);
+
+    tokio::spawn(jobs::expiry::run(state.clone()));
+    tokio::spawn(jobs::access::run(state.clone()));
+    tokio::spawn(jobs::variants::run(
+        state.clone(),
+        config.images.cache_max_mb,
+    ));
+    tokio::spawn(jobs::changes::run(
+        state.clone(),
+        config.changes_retention_hours,
+    ));
+
+    if let Some(retention_hours) = config.trash_retention_hours {
+        tokio::spawn(jobs::trash::run(state.clone(), retention_hours));
+    }
+
+    if let Some(interval_hours) = config.gc_interval_hours {
+        tokio::spawn(jobs::gc::run(
+            state.clone(),
+            interval_hours,
+            Duration::from_secs(config.gc_min_age_minutes * 60),
+        ));
+    }
+
+    if let Some(tiering) = &config.tiering {
+        if tiering.min_age_days.is_none() && tiering.idle_days.is_none() {
+            tracing::warn!(
+                "Cold tier has no min_age_days or idle_days rule, objects will not be migrated"
+            );
+        } else {
+            tokio::spawn(jobs::tiering::run(state.clone(), tiering.clone()));
+        }
+    }
+
+    if let Some(reconcile) = &config.reconcile {
+        tokio::spawn(jobs::reconcile::run(state.clone(), reconcile.clone()));
+    }
+
+    if let Some(maintenance) = &config.maintenance {
+        tokio::spawn(jobs::maintenance::run(state.clone(), maintenance.clone()));
+    }
+
+    if let Some(replication) = &config.replication {
+        tokio::spawn(jobs::replication::run(state.clone(), replication.clone()));
+    }
+
+    for publisher in &config.publishers {
+        let publisher = publish::connect(publisher)
+            .await
+            .map_err(|e| format!("Failed to connect {} publisher: {}", publisher.kind(), e))?;
+        tokio::spawn(publish::run(state.clone(), publisher));
+    }
+
     let cors = CorsLayer::permissive();
 
-    let protected_routes = Router::new()
-        .route("/api/v1/objects", get(handlers::objects::list_objects))
-        .route("/api/v1/objects/{*key}", put(handlers::objects::put_object))
-        .route("/api/v1/objects/{*key}", get(handlers::objects::get_object))
-        .route(
-            "/api/v1/objects/{*key}",
-            delete(handlers::objects::delete_object),
-        )
+    let compression = CompressionLayer::new()
+        .compress_when(compression::CompressionPolicy::new(&config.compression));
+
+    let protected_routes = api_routes()
+        .merge(admin_routes())
+        .route("/api/v1/buckets", get(handlers::buckets::list_buckets))
         .route(
-            "/api/v1/metadata/{*key}",
-            get(handlers::objects::get_object_metadata),
+            "/api/v1/buckets/{bucket}",
+            put(handlers::buckets::create_bucket)
+                .get(handlers::buckets::get_bucket)
+                .delete(handlers::buckets::delete_bucket),
         )
         .route(
-            "/api/v1/info/{*key}",
-            get(handlers::objects::get_object_info),
+            "/api/v1/buckets/{bucket}/policy",
+            get(handlers::buckets::get_bucket_policy).put(handlers::buckets::put_bucket_policy),
         )
         .route(
-            "/api/v1/folders/{*prefix}",
-            delete(handlers::objects::delete_folder),
+            "/api/v1/buckets/{bucket}/{*path}",
+            any(handlers::buckets::bucket_api),
         )
-        .route("/api/v1/stats", get(handlers::stats::get_stats))
-        .route("/api/v1/search", get(handlers::objects::search_objects))
+        .layer(middleware::from_fn_with_state(
+            rate_limit::RateLimiter::new(RequestClass::Read, read_limit),
+            rate_limit::rate_limit_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            rate_limit::RateLimiter::new(RequestClass::Write, write_limit),
+            rate_limit::rate_limit_middleware,
+        ))
         .layer(middleware::from_fn_with_state(
             state.clone(),
             auth::auth_middleware,
         ));
 
-    let app = Router::new()
+    let mut app = Router::new()
         .route("/", get(handlers::index::index))
         .route("/favicon.ico", get(handlers::index::favicon))
         .route("/github", get(handlers::index::github_redirect))
-        .merge(protected_routes)
+        .route("/ui", get(handlers::ui::ui))
+        .route("/ui/upload", get(handlers::ui::upload_page))
+        .route("/api/v1/openapi.json", get(openapi::spec))
+        .route("/api/v1/docs", get(openapi::docs))
+        .route("/api/v1/graphiql", get(graphql::graphiql))
+        .route("/auth/token", post(handlers::sessions::token_login))
+        .route("/auth/session", get(handlers::sessions::session))
+        .route("/auth/logout", post(handlers::sessions::logout))
+        .merge(protected_routes);
+
+    if config.s3.enabled {
+        tracing::info!("S3 API enabled at {}", config.s3.path);
+        let secret_key = config
+            .s3
+            .secret_key
+            .clone()
+            .or(config.auth_token.clone())
+            .ok_or("s3.secret_key must be set when auth_token is not configured")?;
+        app = app.merge(s3::router(state.clone(), &config.s3, secret_key));
+    }
+
+    if let Some(oidc) = &config.oidc {
+        tracing::info!("OIDC login enabled for issuer {}", oidc.issuer);
+        app = app.merge(oidc::router(state.clone(), oidc));
+    }
+
+    if config.metrics.enabled {
+        let handle = telemetry::install()?;
+        let metadata = state.metadata.clone();
+
+        match &config.metrics.listen {
+            Some(listen) => {
+                let metrics = telemetry::router(handle, metadata, &config.metrics.path);
+                let listener = tokio::net::TcpListener::bind(listen).await?;
+                tracing::info!("Metrics available on {}{}", listen, config.metrics.path);
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(listener, metrics).await {
+                        tracing::error!("Metrics listener failed: {}", e);
+                    }
+                });
+            }
+            None => {
+                tracing::info!("Metrics available at {}", config.metrics.path);
+                app = app.merge(telemetry::router(handle, metadata, &config.metrics.path));
+            }
+        }
+    }
+
+    let mut app = app
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            handlers::admin::read_only_guard,
+        ))
         .layer(cors)
+        .route("/api/v1/tus", options(handlers::tus::options))
+        .layer(compression)
+        .layer(middleware::from_fn_with_state(
+            Arc::new(config.timeouts.clone()),
+            timeout::timeout_middleware,
+        ))
         .layer(
             TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
-                .on_response(DefaultOnResponse::new().level(Level::INFO)),
-        )
-        .with_state(state);
+                .make_span_with(telemetry::request_span)
+                .on_response(telemetry::record_response),
+        );
+
+    if config.metrics.enabled {
+        app = app.layer(middleware::from_fn(telemetry::track));
+    }
+
+    if let Some(access_log) = &config.access_log {
+        tracing::info!(
+            "Writing {:?} access log to {}",
+            access_log.format,
+            access_log.path
+        );
+        app = app.layer(middleware::from_fn_with_state(
+            access_log::AccessLog::open(access_log).await?,
+            access_log::access_log_middleware,
+        ));
+    }
+
+    app = app.layer(middleware::from_fn(request_id::request_id_middleware));
+
+    let metadata = state.metadata.clone();
+    let access = state.access.clone();
+    let app = app.with_state(state);
 
-    let addr = format!("{}:{}", config.server_host, config.server_port);
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
     tracing::info!("GitHub: https://github.com/aprlpet/lila");
 
-    axum::serve(listener, app).await?;
+    let shutdown = CancellationToken::new();
+    tokio::spawn(shutdown_signal(shutdown.clone()));
+
+    let tls = tls::load(&config).await?;
+    if tls.is_some()
+        && let Some(port) = config.tls_redirect_port
+    {
+        let listen = format!("{}:{}", config.server_host, port);
+        let listener = tokio::net::TcpListener::bind(&listen).await?;
+        let redirect = tls::redirect_router(config.server_port);
+        let shutdown = shutdown.clone();
+        tracing::info!("Redirecting HTTP on {} to HTTPS", listen);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, redirect)
+                .with_graceful_shutdown(shutdown.cancelled_owned())
+                .await
+            {
+                tracing::error!("HTTP redirect listener failed: {}", e);
+            }
+        });
+    }
+
+    let mut servers = JoinSet::new();
+    for listener in config.listeners() {
+        let server = listener::spawn(&listener, app.clone(), tls.clone(), shutdown.clone()).await?;
+        servers.spawn(async move { server.await? });
+    }
+
+    tokio::select! {
+        Some(result) = servers.join_next() => result??,
+        _ = shutdown.cancelled() => {
+            let drain = Duration::from_secs(config.shutdown_timeout_seconds);
+            tracing::info!("Draining in-flight requests for up to {:?}", drain);
+
+            let drained = tokio::time::timeout(drain, async {
+                while let Some(result) = servers.join_next().await {
+                    result??;
+                }
+                Ok::<_, Box<dyn std::error::Error>>(())
+            })
+            .await;
+
+            match drained {
+                Ok(result) => result?,
+                Err(_) => {
+                    tracing::warn!("Drain timeout elapsed, aborting remaining requests");
+                    servers.abort_all();
+                }
+            }
+        }
+    }
+
+    if let Err(e) = access.flush(&metadata).await {
+        tracing::warn!("Failed to record access times: {}", e);
+    }
+
+    metadata.close().await;
+    tracing::info!("Metadata store closed");
+
+    if let Some(provider) = tracer_provider
+        && let Err(e) = provider.shutdown()
+    {
+        tracing::warn!("Failed to flush OTLP traces: {}", e);
+    }
 
     Ok(())
 }
+
+async fn shutdown_signal(shutdown: CancellationToken) {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for ctrl-c: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received SIGINT, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+
+    shutdown.cancel();
+}
+
+fn admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/v1/admin/config", get(handlers::admin::get_config))
+        .route("/api/v1/admin/jobs", get(handlers::admin::list_jobs))
+        .route(
+            "/api/v1/admin/replication",
+            get(handlers::admin::replication_status),
+        )
+        .route(
+            "/api/v1/admin/maintenance",
+            get(handlers::admin::maintenance_status),
+        )
+        .route("/api/v1/admin/health", get(handlers::admin::health))
+        .route("/api/v1/admin/fsck", post(handlers::admin::fsck))
+        .route("/api/v1/admin/export", post(handlers::admin::export))
+        .route("/api/v1/admin/backup", post(handlers::admin::backup))
+        .route(
+            "/api/v1/admin/read-only",
+            get(handlers::admin::get_read_only).put(handlers::admin::put_read_only),
+        )
+        .route(
+            "/api/v1/admin/tokens",
+            get(handlers::tokens::list_tokens).post(handlers::tokens::create_token),
+        )
+        .route(
+            "/api/v1/admin/tokens/{id}",
+            patch(handlers::tokens::update_token).delete(handlers::tokens::delete_token),
+        )
+}
+
+pub fn api_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/v1/objects", get(handlers::objects::list_objects))
+        .route(
+            "/api/v1/objects.ndjson",
+            get(handlers::objects::stream_objects),
+        )
+        .route(
+            "/api/v1/objects:batchDelete",
+            post(handlers::objects::batch_delete),
+        )
+        .route(
+            "/api/v1/objects:upload",
+            post(handlers::objects::upload_form).layer(DefaultBodyLimit::disable()),
+        )
+        .route("/api/v1/objects/{*key}", put(handlers::objects::put_object))
+        .route("/api/v1/objects/{*key}", get(handlers::objects::get_object))
+        .route(
+            "/api/v1/thumb/{*key}",
+            get(handlers::thumbnails::get_thumbnail),
+        )
+        .route(
+            "/api/v1/objects/{*key}",
+            head(handlers::objects::head_object),
+        )
+        .route(
+            "/api/v1/objects/{*key}",
+            delete(handlers::objects::delete_object),
+        )
+        .route(
+            "/api/v1/objects/{*key}",
+            patch(handlers::objects::append_object),
+        )
+        .route(
+            "/api/v1/objects/{*key}",
+            post(handlers::objects::verify_object),
+        )
+        .route(
+            "/api/v1/metadata/{*key}",
+            get(handlers::objects::get_object_metadata)
+                .patch(handlers::objects::update_object_metadata),
+        )
+        .route(
+            "/api/v1/metadata:batchGet",
+            post(handlers::objects::batch_get_metadata),
+        )
+        .route(
+            "/api/v1/info/{*key}",
+            get(handlers::objects::get_object_info),
+        )
+        .route(
+            "/api/v1/folders/{*prefix}",
+            delete(handlers::objects::delete_folder),
+        )
+        .route(
+            "/api/v1/archive/{*prefix}",
+            get(handlers::archive::download_archive),
+        )
+        .route(
+            "/api/v1/import/{*prefix}",
+            post(handlers::archive::import_archive),
+        )
+        .route("/api/v1/presign", post(handlers::presign::presign))
+        .route("/api/v1/copy", post(handlers::objects::copy_object))
+        .route("/api/v1/uploads", post(handlers::uploads::initiate_upload))
+        .route(
+            "/api/v1/uploads/{id}",
+            get(handlers::uploads::get_upload).delete(handlers::uploads::abort_upload),
+        )
+        .route(
+            "/api/v1/uploads/{id}/parts/{part_number}",
+            put(handlers::uploads::upload_part),
+        )
+        .route(
+            "/api/v1/uploads/{id}/complete",
+            post(handlers::uploads::complete_upload),
+        )
+        .route("/api/v1/tus", post(handlers::tus::create_upload))
+        .route(
+            "/api/v1/tus/{id}",
+            head(handlers::tus::get_offset)
+                .patch(handlers::tus::patch_upload)
+                .delete(handlers::tus::terminate_upload),
+        )
+        .route(
+            "/api/v1/versions/{*key}",
+            get(handlers::versions::list_versions),
+        )
+        .route("/api/v1/trash", get(handlers::trash::list_trash))
+        .route(
+            "/api/v1/trash/{*key}",
+            post(handlers::trash::trash_action).delete(handlers::trash::purge_object),
+        )
+        .route("/api/v1/stats", get(handlers::stats::get_stats))
+        .route(
+            "/api/v1/stats/prefix/{*prefix}",
+            get(handlers::stats::get_prefix_stats),
+        )
+        .route("/api/v1/search", get(handlers::objects::search_objects))
+        .route("/api/v1/events/ws", get(handlers::events::subscribe))
+        .route("/api/v1/changes", get(handlers::changes::list_changes))
+        .route("/api/v1/graphql", post(graphql::graphql))
+}
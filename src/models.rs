@@ -1,53 +1,1258 @@
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::storage::EtagAlgorithm;
+
+const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ObjectMetadata {
     pub id: String,
     pub key: String,
     pub size: i64,
     pub content_type: String,
     pub etag: String,
+    #[serde(default)]
+    pub etag_algorithm: EtagAlgorithm,
     pub created_at: DateTime<Utc>,
+    pub public: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retain_until: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub user_metadata: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_expires: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stored_size: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<Vec<Chunk>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accessed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub access_count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub generation: i64,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Chunk {
+    pub size: i64,
+    pub digest: String,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema, async_graphql::Enum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    Key,
+    Size,
+    CreatedAt,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema, async_graphql::Enum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListCursor {
+    pub key: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+}
+
+impl ListCursor {
+    pub fn after(metadata: &ObjectMetadata, sort: SortField) -> Self {
+        let value = match sort {
+            SortField::Key => None,
+            SortField::Size => Some(metadata.size.to_string()),
+            SortField::CreatedAt => Some(metadata.created_at.to_rfc3339()),
+        };
+
+        Self {
+            key: metadata.key.clone(),
+            value,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub server: &'static str,
+    pub author: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct StatsResponse {
     pub total_objects: i64,
     pub total_size: i64,
     pub storage_path: String,
 }
 
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PrefixStatsResponse {
+    pub prefix: String,
+    pub total_objects: i64,
+    pub total_size: i64,
+    pub largest: Option<ObjectMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Bucket {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub policy: BucketPolicy,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct BucketPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quota_bytes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_content_type: Option<String>,
+    pub public_read: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub versioning: Option<bool>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListBucketsResponse {
+    pub buckets: Vec<Bucket>,
+    pub total: usize,
+}
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum TokenScope {
+    #[default]
+    Read,
+    Write,
+    Admin,
+}
+
+impl TokenScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenScope::Read => "read",
+            TokenScope::Write => "write",
+            TokenScope::Admin => "admin",
+        }
+    }
+
+    pub fn allows(self, required: TokenScope) -> bool {
+        self >= required
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(TokenScope::Read),
+            "write" => Ok(TokenScope::Write),
+            "admin" => Ok(TokenScope::Admin),
+            other => Err(format!("unknown token scope: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RateLimit {
+    pub per_second: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub burst: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SessionInfo {
+    #[serde(flatten)]
+    pub principal: ApiToken,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TokenLoginRequest {
+    pub token: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateTokenRequest {
+    pub name: String,
+    #[serde(default)]
+    pub scope: TokenScope,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateTokenRequest {
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CreatedToken {
+    #[serde(flatten)]
+    pub token: ApiToken,
+    pub secret: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListTokensResponse {
+    pub tokens: Vec<ApiToken>,
+    pub total: usize,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct JobStatus {
+    pub name: String,
+    pub interval_seconds: u64,
+    pub runs: u64,
+    pub running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_started_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_processed: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct FsckReport {
+    pub objects_checked: usize,
+    pub blobs_checked: usize,
+    pub missing_blobs: Vec<FsckIssue>,
+    pub orphaned_blobs: Vec<String>,
+    pub size_mismatches: Vec<FsckIssue>,
+    pub etag_mismatches: Vec<FsckIssue>,
+    pub repaired: usize,
+}
+
+impl FsckReport {
+    pub fn problems(&self) -> usize {
+        self.missing_blobs.len()
+            + self.orphaned_blobs.len()
+            + self.size_mismatches.len()
+            + self.etag_mismatches.len()
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct FsckIssue {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub bucket: String,
+    pub key: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actual: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct GcReport {
+    pub blobs_scanned: usize,
+    pub orphaned: usize,
+    pub skipped_recent: usize,
+    pub removed: usize,
+    pub bytes_reclaimed: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BackupManifest {
+    pub version: String,
+    pub created_at: DateTime<Utc>,
+    pub files: usize,
+    pub total_size: u64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BackupQuery {
+    pub destination: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BackupResponse {
+    pub destination: String,
+    #[serde(flatten)]
+    pub manifest: BackupManifest,
+}
+
 #[derive(Debug, Serialize)]
+pub struct RestoreReport {
+    pub dry_run: bool,
+    pub manifest: BackupManifest,
+    pub objects: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub blobs: usize,
+    pub previous_database: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FsckQuery {
+    #[serde(default)]
+    pub repair: bool,
+    #[serde(default)]
+    pub quick: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReplicationStatus {
+    pub endpoint: String,
+    pub latest_seq: u64,
+    pub replicated_seq: u64,
+    pub lag_events: u64,
+    pub lag_seconds: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replicated_at: Option<DateTime<Utc>>,
+    pub failures: u64,
+    pub dropped_events: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DatabaseStats {
+    pub size_bytes: u64,
+    pub free_bytes: u64,
+    pub incremental_vacuum: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct MaintenanceRun {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub analyzed: bool,
+    pub size_before_bytes: u64,
+    pub size_after_bytes: u64,
+    pub reclaimed_bytes: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MaintenanceStatus {
+    pub schedule: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub database: DatabaseStats,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_run: Option<MaintenanceRun>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListJobsResponse {
+    pub jobs: Vec<JobStatus>,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthCheck {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DiskUsage {
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub reserve_bytes: u64,
+    pub volumes: Vec<VolumeUsage>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VolumeUsage {
+    pub path: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+    pub cold: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthResponse {
+    pub healthy: bool,
+    pub read_only: bool,
+    pub database: HealthCheck,
+    pub storage: HealthCheck,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub disk: Option<DiskUsage>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ReadOnlyMode {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ListObjectsResponse {
     pub objects: Vec<ObjectMetadata>,
     pub total: usize,
     pub prefixes: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct ObjectInfo {
     pub metadata: ObjectMetadata,
     pub path: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum VerifyStatus {
+    Ok,
+    Corrupt,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VerifyResponse {
+    pub key: String,
+    pub status: VerifyStatus,
+    pub etag_algorithm: EtagAlgorithm,
+    pub expected_etag: String,
+    pub actual_etag: Option<String>,
+    pub size: i64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SearchResponse {
     pub objects: Vec<ObjectMetadata>,
     pub total: usize,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchDeleteStatus {
+    Deleted,
+    NotFound,
+    Error,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchDeleteResult {
+    pub key: String,
+    pub status: BatchDeleteStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct FormUploadResponse {
+    pub uploaded: Vec<ObjectMetadata>,
+    pub conflicts: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchDeleteResponse {
+    pub results: Vec<BatchDeleteResult>,
+    pub deleted: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportResponse {
+    pub prefix: String,
+    pub imported: usize,
+    pub skipped: usize,
+    pub total_size: i64,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExportRequest {
+    #[serde(default)]
+    pub prefix: String,
+    pub destination: String,
+    pub bucket: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ExportResponse {
+    pub prefix: String,
+    pub destination: String,
+    pub exported: usize,
+    pub total_size: i64,
+    pub failed: Vec<ImportFailure>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchGetResponse {
+    pub objects: Vec<ObjectMetadata>,
+    pub missing: Vec<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListVersionsResponse {
+    pub key: String,
+    pub current: Option<ObjectMetadata>,
+    pub versions: Vec<ObjectMetadata>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EventOp {
+    Put,
+    Delete,
+}
+
+impl EventOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EventOp::Put => "put",
+            EventOp::Delete => "delete",
+        }
+    }
+}
+
+impl FromStr for EventOp {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "put" => Ok(EventOp::Put),
+            "delete" => Ok(EventOp::Delete),
+            other => Err(format!("unknown event op: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ObjectEvent {
+    pub seq: u64,
+    pub op: EventOp,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bucket: Option<String>,
+    pub key: String,
+    pub etag: String,
+    pub size: i64,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListChangesResponse {
+    pub changes: Vec<ObjectEvent>,
+    pub latest_seq: u64,
+    pub next_since_seq: u64,
+    pub has_more: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TrashEntry {
+    #[serde(flatten)]
+    pub metadata: ObjectMetadata,
+    pub deleted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ListTrashResponse {
+    pub objects: Vec<TrashEntry>,
+    pub total: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PresignResponse {
+    pub url: String,
+    pub method: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UploadSession {
+    pub id: String,
+    pub key: String,
+    pub content_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct UploadPart {
+    pub part_number: i64,
+    pub size: i64,
+    pub etag: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UploadStatusResponse {
+    pub upload: UploadSession,
+    pub parts: Vec<UploadPart>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ResumableUpload {
+    pub id: String,
+    pub key: String,
+    pub content_type: String,
+    pub length: i64,
+    pub offset: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub server_host: String,
     pub server_port: u16,
-    pub storage_path: String,
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    #[serde(default)]
+    pub tls_redirect_port: Option<u16>,
+    #[serde(default)]
+    pub listeners: Vec<ListenerConfig>,
+    pub storage_path: StoragePath,
     pub database_url: String,
-    pub auth_token: String,
+    #[serde(default)]
+    pub database: DatabaseConfig,
+    #[serde(default)]
+    pub auth_token: Option<String>,
+    #[serde(default)]
+    pub presign_secret: Option<String>,
     #[serde(default = "default_max_upload_size")]
     pub max_upload_size_mb: usize,
+    #[serde(default)]
+    pub versioning: bool,
+    #[serde(default)]
+    pub trash_retention_hours: Option<u64>,
+    #[serde(default)]
+    pub gc_interval_hours: Option<u64>,
+    #[serde(default = "default_gc_min_age")]
+    pub gc_min_age_minutes: u64,
+    #[serde(default)]
+    pub retention_rules: Vec<RetentionRule>,
+    #[serde(default)]
+    pub compression: CompressionConfig,
+    #[serde(default)]
+    pub etag_algorithm: EtagAlgorithm,
+    #[serde(default)]
+    pub cache_control_rules: Vec<CacheControlRule>,
+    #[serde(default)]
+    pub quota_bytes: Option<i64>,
+    #[serde(default)]
+    pub quota_rules: Vec<QuotaRule>,
+    #[serde(default = "default_disk_reserve")]
+    pub disk_reserve_mb: u64,
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout_seconds: u64,
+    #[serde(default)]
+    pub s3: S3Config,
+    #[serde(default)]
+    pub oidc: Option<OidcConfig>,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
+    #[serde(default)]
+    pub rate_limit_read: Option<RateLimit>,
+    #[serde(default)]
+    pub rate_limit_write: Option<RateLimit>,
+    #[serde(default)]
+    pub public_prefixes: Vec<String>,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default = "default_read_only_retry_after")]
+    pub read_only_retry_after_seconds: u64,
+    #[serde(default)]
+    pub mode: Mode,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub otlp: Option<OtlpConfig>,
+    #[serde(default)]
+    pub access_log: Option<AccessLogConfig>,
+    #[serde(default)]
+    pub upstream: Option<UpstreamConfig>,
+    #[serde(default)]
+    pub dedup: bool,
+    #[serde(default)]
+    pub chunking: Option<ChunkingConfig>,
+    #[serde(default)]
+    pub tiering: Option<TieringConfig>,
+    #[serde(default)]
+    pub durability: Durability,
+    #[serde(default)]
+    pub reconcile: Option<ReconcileConfig>,
+    #[serde(default)]
+    pub streaming: StreamingConfig,
+    #[serde(default)]
+    pub images: ImageConfig,
+    #[serde(default)]
+    pub timeouts: TimeoutConfig,
+    #[serde(default)]
+    pub concurrency: ConcurrencyConfig,
+    #[serde(default)]
+    pub publishers: Vec<PublisherConfig>,
+    #[serde(default)]
+    pub replication: Option<ReplicationConfig>,
+    #[serde(default = "default_changes_retention")]
+    pub changes_retention_hours: u64,
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerConfig {
+    pub address: String,
+    #[serde(default)]
+    pub routes: Vec<String>,
+    #[serde(default = "default_listener_tls")]
+    pub tls: bool,
+}
+
+fn default_listener_tls() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    pub path: String,
+    pub listen: Option<String>,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/metrics".to_string(),
+            listen: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub journal_mode: String,
+    pub synchronous: String,
+    pub auto_vacuum: String,
+    pub busy_timeout_ms: u64,
+    pub cache_size_kb: Option<u64>,
+    pub page_size: Option<u32>,
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_seconds: u64,
+    pub idle_timeout_seconds: Option<u64>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            journal_mode: "wal".to_string(),
+            synchronous: "normal".to_string(),
+            auto_vacuum: "incremental".to_string(),
+            busy_timeout_ms: 5000,
+            cache_size_kb: None,
+            page_size: None,
+            max_connections: 10,
+            min_connections: 0,
+            acquire_timeout_seconds: 30,
+            idle_timeout_seconds: Some(600),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageConfig {
+    pub thumbnail_size: u32,
+    pub max_size: u32,
+    pub max_source_mb: usize,
+    pub default_quality: u8,
+    pub cache_max_mb: u64,
+}
+
+impl Default for ImageConfig {
+    fn default() -> Self {
+        Self {
+            thumbnail_size: 256,
+            max_size: 2048,
+            max_source_mb: 50,
+            default_quality: 80,
+            cache_max_mb: 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingConfig {
+    pub read_buffer_kb: usize,
+    pub write_buffer_kb: usize,
+}
+
+impl Default for StreamingConfig {
+    fn default() -> Self {
+        Self {
+            read_buffer_kb: 256,
+            write_buffer_kb: 256,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeoutConfig {
+    pub upload_seconds: Option<u64>,
+    pub download_seconds: Option<u64>,
+    pub idle_body_seconds: Option<u64>,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            upload_seconds: None,
+            download_seconds: None,
+            idle_body_seconds: Some(60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    pub max_uploads: Option<usize>,
+    pub max_bulk_operations: Option<usize>,
+    pub queue_timeout_seconds: u64,
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_uploads: None,
+            max_bulk_operations: Some(4),
+            queue_timeout_seconds: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplicationConfig {
+    pub endpoint: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum PublisherConfig {
+    Nats {
+        url: String,
+        subject: String,
+    },
+    Kafka {
+        brokers: String,
+        topic: String,
+    },
+    Redis {
+        url: String,
+        stream: String,
+        #[serde(default)]
+        max_len: Option<usize>,
+    },
+}
+
+impl PublisherConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PublisherConfig::Nats { .. } => "nats",
+            PublisherConfig::Kafka { .. } => "kafka",
+            PublisherConfig::Redis { .. } => "redis",
+        }
+    }
+}
+
+impl Config {
+    pub fn sanitized(&self) -> Config {
+        let redact = |secret: &Option<String>| secret.as_ref().map(|_| REDACTED.to_string());
+
+        let mut config = self.clone();
+        config.auth_token = redact(&self.auth_token);
+        config.presign_secret = redact(&self.presign_secret);
+        config.s3.secret_key = redact(&self.s3.secret_key);
+        if let Some(oidc) = config.oidc.as_mut() {
+            oidc.client_secret = REDACTED.to_string();
+        }
+        if let Some(upstream) = config.upstream.as_mut() {
+            upstream.secret_key = REDACTED.to_string();
+        }
+        if let Some(otlp) = config.otlp.as_mut() {
+            otlp.headers
+                .values_mut()
+                .for_each(|value| *value = REDACTED.to_string());
+        }
+        if let Some(replication) = config.replication.as_mut() {
+            replication.token = REDACTED.to_string();
+        }
+        for publisher in config.publishers.iter_mut() {
+            match publisher {
+                PublisherConfig::Nats { url, .. } | PublisherConfig::Redis { url, .. } => {
+                    *url = REDACTED.to_string()
+                }
+                PublisherConfig::Kafka { .. } => {}
+            }
+        }
+        config
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+    #[serde(default = "default_session_hours")]
+    pub session_hours: u64,
+    #[serde(default)]
+    pub session_scope: TokenScope,
+    #[serde(default)]
+    pub allowed_emails: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpstreamConfig {
+    pub endpoint: String,
+    pub bucket: String,
+    #[serde(default = "default_upstream_region")]
+    pub region: String,
+    pub access_key: String,
+    pub secret_key: String,
+    #[serde(default)]
+    pub prefix: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum StoragePath {
+    Single(String),
+    Volumes(Vec<String>),
+}
+
+impl StoragePath {
+    pub fn volumes(&self) -> &[String] {
+        match self {
+            StoragePath::Single(path) => std::slice::from_ref(path),
+            StoragePath::Volumes(paths) => paths,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkingConfig {
+    #[serde(default = "default_chunking_threshold")]
+    pub threshold_mb: u64,
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size_mb: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TieringConfig {
+    pub cold_path: String,
+    #[serde(default)]
+    pub min_age_days: Option<u64>,
+    #[serde(default)]
+    pub idle_days: Option<u64>,
+    #[serde(default = "default_tiering_interval")]
+    pub interval_hours: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceConfig {
+    #[serde(default = "default_maintenance_schedule")]
+    pub schedule: String,
+    #[serde(default = "default_vacuum_pages")]
+    pub vacuum_pages: u32,
+    #[serde(default)]
+    pub analyze: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconcileConfig {
+    #[serde(default = "default_reconcile_interval")]
+    pub interval_minutes: u64,
+    #[serde(default = "default_reconcile_sample_size")]
+    pub sample_size: i64,
+    #[serde(default = "default_reconcile_grace")]
+    pub grace_minutes: u64,
+    #[serde(default)]
+    pub heal: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+    #[serde(default = "default_otlp_filter")]
+    pub filter: String,
+    #[serde(default = "default_otlp_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessLogConfig {
+    pub path: String,
+    #[serde(default)]
+    pub format: AccessLogFormat,
+    #[serde(default)]
+    pub rotation: LogRotation,
+    #[serde(default)]
+    pub max_size_mb: Option<u64>,
+    #[serde(default = "default_access_log_max_files")]
+    pub max_files: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    #[default]
+    Primary,
+    Replica,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Durability {
+    None,
+    #[default]
+    Flush,
+    Fsync,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessLogFormat {
+    #[default]
+    Combined,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct S3Config {
+    pub enabled: bool,
+    pub path: String,
+    pub access_key: String,
+    pub secret_key: Option<String>,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/s3".to_string(),
+            access_key: "lila".to_string(),
+            secret_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionRule {
+    pub prefix: String,
+    pub days: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuotaRule {
+    pub prefix: String,
+    pub bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheControlRule {
+    pub content_type: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    pub min_size: u64,
+    pub skip_content_types: Vec<String>,
+    pub at_rest: bool,
+    pub at_rest_level: i32,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_size: 1024,
+            skip_content_types: [
+                "image/",
+                "video/",
+                "audio/",
+                "font/woff",
+                "application/zip",
+                "application/gzip",
+                "application/x-gzip",
+                "application/zstd",
+                "application/x-bzip2",
+                "application/x-xz",
+                "application/x-7z-compressed",
+                "application/vnd.rar",
+                "application/pdf",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+            at_rest: false,
+            at_rest_level: 3,
+        }
+    }
 }
 
 fn default_max_upload_size() -> usize {
     100
 }
+
+fn default_disk_reserve() -> u64 {
+    256
+}
+
+fn default_changes_retention() -> u64 {
+    168
+}
+
+fn default_read_only_retry_after() -> u64 {
+    60
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_gc_min_age() -> u64 {
+    60
+}
+
+fn default_oidc_scopes() -> Vec<String> {
+    ["openid", "email", "profile"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_session_hours() -> u64 {
+    12
+}
+
+fn default_upstream_region() -> String {
+    "us-east-1".to_string()
+}
+
+fn default_chunking_threshold() -> u64 {
+    256
+}
+
+fn default_chunk_size() -> u64 {
+    64
+}
+
+fn default_tiering_interval() -> u64 {
+    6
+}
+
+fn default_maintenance_schedule() -> String {
+    "0 3 * * *".to_string()
+}
+
+fn default_vacuum_pages() -> u32 {
+    1000
+}
+
+fn default_reconcile_interval() -> u64 {
+    60
+}
+
+fn default_reconcile_sample_size() -> i64 {
+    1000
+}
+
+fn default_reconcile_grace() -> u64 {
+    10
+}
+
+fn default_otlp_service_name() -> String {
+    "lila".to_string()
+}
+
+fn default_otlp_filter() -> String {
+    "lila=debug,tower_http=debug,sqlx::query=debug".to_string()
+}
+
+fn default_otlp_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_access_log_max_files() -> usize {
+    14
+}
@@ -0,0 +1,806 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsStr,
+    future::Future,
+    os::unix::fs::MetadataExt,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use fuser::{
+    FUSE_ROOT_ID, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyCreate, ReplyData,
+    ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, ReplyWrite, Request, Session, TimeOrNow,
+};
+use futures_util::TryStreamExt;
+use libc::c_int;
+use lila_client::{Client, ListOptions, ObjectMetadata, PutOptions, StatusCode};
+use tokio::runtime::Handle;
+
+const TTL: Duration = Duration::from_secs(1);
+const BLOCK_SIZE: u32 = 4096;
+
+pub async fn mount(
+    client: Client,
+    mountpoint: &Path,
+    read_only: bool,
+    allow_other: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let owner = std::fs::metadata(mountpoint)?;
+    let filesystem = LilaFs::new(client.clone(), owner.uid(), owner.gid(), read_only);
+
+    let mut options = vec![
+        MountOption::FSName(client.endpoint().to_string()),
+        MountOption::Subtype("lila".to_string()),
+        MountOption::DefaultPermissions,
+        MountOption::NoAtime,
+        if read_only {
+            MountOption::RO
+        } else {
+            MountOption::RW
+        },
+    ];
+    if allow_other {
+        options.push(MountOption::AllowOther);
+    }
+
+    let mut session = Session::new(filesystem, mountpoint, &options)?;
+    let mut unmounter = session.unmount_callable();
+    let mut running = tokio::task::spawn_blocking(move || session.run());
+
+    tracing::info!(
+        "Mounted {} at {}, press Ctrl-C to unmount",
+        client.endpoint(),
+        mountpoint.display()
+    );
+
+    tokio::select! {
+        result = &mut running => result??,
+        _ = tokio::signal::ctrl_c() => {
+            tracing::info!("Unmounting {}", mountpoint.display());
+            unmounter.unmount()?;
+            running.await??;
+        }
+    }
+
+    Ok(())
+}
+
+enum Node {
+    File { size: u64, modified: SystemTime },
+    Directory,
+}
+
+impl From<ObjectMetadata> for Node {
+    fn from(metadata: ObjectMetadata) -> Self {
+        Node::File {
+            size: metadata.size as u64,
+            modified: metadata.updated_at.unwrap_or(metadata.created_at).into(),
+        }
+    }
+}
+
+struct OpenFile {
+    key: String,
+    buffer: Option<Vec<u8>>,
+    dirty: bool,
+}
+
+struct LilaFs {
+    client: Client,
+    runtime: Handle,
+    uid: u32,
+    gid: u32,
+    read_only: bool,
+    mounted_at: SystemTime,
+    paths: HashMap<u64, String>,
+    inodes: HashMap<String, u64>,
+    next_ino: u64,
+    dirs: HashSet<String>,
+    files: HashMap<u64, OpenFile>,
+    listings: HashMap<u64, Vec<(u64, FileType, String)>>,
+    next_fh: u64,
+}
+
+impl LilaFs {
+    fn new(client: Client, uid: u32, gid: u32, read_only: bool) -> Self {
+        Self {
+            client,
+            runtime: Handle::current(),
+            uid,
+            gid,
+            read_only,
+            mounted_at: SystemTime::now(),
+            paths: HashMap::from([(FUSE_ROOT_ID, String::new())]),
+            inodes: HashMap::from([(String::new(), FUSE_ROOT_ID)]),
+            next_ino: FUSE_ROOT_ID + 1,
+            dirs: HashSet::new(),
+            files: HashMap::new(),
+            listings: HashMap::new(),
+            next_fh: 1,
+        }
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    fn ino(&mut self, path: &str) -> u64 {
+        if let Some(ino) = self.inodes.get(path) {
+            return *ino;
+        }
+
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.paths.insert(ino, path.to_string());
+        self.inodes.insert(path.to_string(), ino);
+        ino
+    }
+
+    fn path(&self, ino: u64) -> Result<String, c_int> {
+        self.paths.get(&ino).cloned().ok_or(libc::ENOENT)
+    }
+
+    fn child(&self, parent: u64, name: &OsStr) -> Result<String, c_int> {
+        let name = name.to_str().ok_or(libc::EINVAL)?;
+        let parent = self.path(parent)?;
+
+        Ok(if parent.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", parent, name)
+        })
+    }
+
+    fn writable(&self) -> Result<(), c_int> {
+        if self.read_only {
+            Err(libc::EROFS)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn node(&self, path: &str) -> Result<Option<Node>, c_int> {
+        if path.is_empty() {
+            return Ok(Some(Node::Directory));
+        }
+
+        match self.block_on(self.client.metadata(path)) {
+            Ok(metadata) => Ok(Some(metadata.into())),
+            Err(e) if e.is_not_found() => match self.is_directory(path)? {
+                true => Ok(Some(Node::Directory)),
+                false => Ok(None),
+            },
+            Err(e) => Err(errno(&e)),
+        }
+    }
+
+    fn is_directory(&self, path: &str) -> Result<bool, c_int> {
+        Ok(self.dirs.contains(path) || self.has_children(path)?)
+    }
+
+    fn has_children(&self, path: &str) -> Result<bool, c_int> {
+        let options = ListOptions {
+            prefix: Some(dir_prefix(path)),
+            limit: Some(1),
+            ..Default::default()
+        };
+
+        let page = self
+            .block_on(self.client.list_objects(&options))
+            .map_err(|e| errno(&e))?;
+        Ok(!page.objects.is_empty() || !page.prefixes.is_empty())
+    }
+
+    fn attr(&self, ino: u64, node: &Node) -> FileAttr {
+        match node {
+            Node::File { size, modified } => self.file_attr(ino, *size, *modified),
+            Node::Directory => self.dir_attr(ino),
+        }
+    }
+
+    fn file_attr(&self, ino: u64, size: u64, modified: SystemTime) -> FileAttr {
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: modified,
+            mtime: modified,
+            ctime: modified,
+            crtime: modified,
+            kind: FileType::RegularFile,
+            perm: if self.read_only { 0o444 } else { 0o644 },
+            nlink: 1,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    fn dir_attr(&self, ino: u64) -> FileAttr {
+        FileAttr {
+            ino,
+            size: 0,
+            blocks: 0,
+            atime: self.mounted_at,
+            mtime: self.mounted_at,
+            ctime: self.mounted_at,
+            crtime: self.mounted_at,
+            kind: FileType::Directory,
+            perm: if self.read_only { 0o555 } else { 0o755 },
+            nlink: 2,
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: BLOCK_SIZE,
+            flags: 0,
+        }
+    }
+
+    fn open_buffer(&self, path: &str) -> Option<u64> {
+        self.files
+            .values()
+            .filter(|file| file.key == path)
+            .find_map(|file| file.buffer.as_ref())
+            .map(|buffer| buffer.len() as u64)
+    }
+
+    fn stat(&mut self, path: &str) -> Result<FileAttr, c_int> {
+        let ino = self.ino(path);
+
+        if let Some(size) = self.open_buffer(path) {
+            return Ok(self.file_attr(ino, size, SystemTime::now()));
+        }
+
+        match self.node(path)? {
+            Some(node) => Ok(self.attr(ino, &node)),
+            None => Err(libc::ENOENT),
+        }
+    }
+
+    fn list_directory(&mut self, ino: u64) -> Result<Vec<(u64, FileType, String)>, c_int> {
+        let path = self.path(ino)?;
+        let prefix = dir_prefix(&path);
+        let parent = match path.rsplit_once('/') {
+            Some((parent, _)) => self.ino(parent),
+            None => FUSE_ROOT_ID,
+        };
+
+        let mut children = BTreeMap::new();
+        let mut options = ListOptions {
+            prefix: Some(prefix.clone()),
+            delimiter: Some("/".to_string()),
+            ..Default::default()
+        };
+
+        loop {
+            let page = self
+                .block_on(self.client.list_objects(&options))
+                .map_err(|e| errno(&e))?;
+
+            for folder in &page.prefixes {
+                let name = folder[prefix.len()..].trim_end_matches('/');
+                if !name.is_empty() {
+                    children.insert(name.to_string(), FileType::Directory);
+                }
+            }
+            for object in &page.objects {
+                let name = &object.key[prefix.len()..];
+                if !name.is_empty() {
+                    children
+                        .entry(name.to_string())
+                        .or_insert(FileType::RegularFile);
+                }
+            }
+
+            match page.next_cursor {
+                Some(cursor) => options.cursor = Some(cursor),
+                None => break,
+            }
+        }
+
+        for dir in &self.dirs {
+            if let Some(name) = dir.strip_prefix(&prefix)
+                && !name.contains('/')
+            {
+                children.insert(name.to_string(), FileType::Directory);
+            }
+        }
+
+        let mut entries = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (parent, FileType::Directory, "..".to_string()),
+        ];
+        for (name, kind) in children {
+            let child = self.ino(&format!("{}{}", prefix, name));
+            entries.push((child, kind, name));
+        }
+
+        Ok(entries)
+    }
+
+    fn open_file(&mut self, ino: u64, flags: i32) -> Result<u64, c_int> {
+        let key = self.path(ino)?;
+        let write = flags & libc::O_ACCMODE != libc::O_RDONLY;
+
+        let (buffer, dirty) = if !write {
+            (None, false)
+        } else if flags & libc::O_TRUNC != 0 {
+            self.writable()?;
+            (Some(Vec::new()), true)
+        } else {
+            self.writable()?;
+            let data = self
+                .block_on(self.client.get_bytes(&key))
+                .map_err(|e| errno(&e))?;
+            (Some(data.to_vec()), false)
+        };
+
+        Ok(self.insert_file(key, buffer, dirty))
+    }
+
+    fn insert_file(&mut self, key: String, buffer: Option<Vec<u8>>, dirty: bool) -> u64 {
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        self.files.insert(fh, OpenFile { key, buffer, dirty });
+        fh
+    }
+
+    fn read_file(&self, fh: u64, offset: u64, size: u32) -> Result<Vec<u8>, c_int> {
+        let file = self.files.get(&fh).ok_or(libc::EBADF)?;
+
+        if let Some(buffer) = &file.buffer {
+            let start = (offset as usize).min(buffer.len());
+            let end = (start + size as usize).min(buffer.len());
+            return Ok(buffer[start..end].to_vec());
+        }
+
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let range = offset..offset + size as u64;
+        let data =
+            self.block_on(async { self.client.get_range(&file.key, range).await?.bytes().await });
+
+        match data {
+            Ok(data) => Ok(data.to_vec()),
+            Err(e) if e.status() == Some(StatusCode::RANGE_NOT_SATISFIABLE) => Ok(Vec::new()),
+            Err(e) => Err(errno(&e)),
+        }
+    }
+
+    fn write_file(&mut self, fh: u64, offset: u64, data: &[u8]) -> Result<u32, c_int> {
+        let file = self.files.get_mut(&fh).ok_or(libc::EBADF)?;
+        let buffer = file.buffer.as_mut().ok_or(libc::EBADF)?;
+
+        let offset = offset as usize;
+        let end = offset + data.len();
+        if buffer.len() < end {
+            buffer.resize(end, 0);
+        }
+        buffer[offset..end].copy_from_slice(data);
+        file.dirty = true;
+
+        Ok(data.len() as u32)
+    }
+
+    fn sync_file(&mut self, fh: u64) -> Result<(), c_int> {
+        let file = self.files.get(&fh).ok_or(libc::EBADF)?;
+        let (true, Some(buffer)) = (file.dirty, &file.buffer) else {
+            return Ok(());
+        };
+
+        self.block_on(
+            self.client
+                .put_bytes(&file.key, buffer.clone(), &PutOptions::default()),
+        )
+        .map_err(|e| errno(&e))?;
+
+        if let Some(file) = self.files.get_mut(&fh) {
+            file.dirty = false;
+        }
+        Ok(())
+    }
+
+    fn truncate(&mut self, ino: u64, fh: Option<u64>, size: u64) -> Result<(), c_int> {
+        self.writable()?;
+
+        if let Some(file) = fh.and_then(|fh| self.files.get_mut(&fh))
+            && let Some(buffer) = file.buffer.as_mut()
+        {
+            buffer.resize(size as usize, 0);
+            file.dirty = true;
+            return Ok(());
+        }
+
+        let key = self.path(ino)?;
+        let mut data = match size {
+            0 => Vec::new(),
+            _ => self
+                .block_on(self.client.get_bytes(&key))
+                .map_err(|e| errno(&e))?
+                .to_vec(),
+        };
+        data.resize(size as usize, 0);
+
+        self.block_on(self.client.put_bytes(&key, data, &PutOptions::default()))
+            .map_err(|e| errno(&e))?;
+        Ok(())
+    }
+
+    fn create_file(&mut self, parent: u64, name: &OsStr) -> Result<(FileAttr, u64), c_int> {
+        self.writable()?;
+        let key = self.child(parent, name)?;
+
+        let metadata = self
+            .block_on(
+                self.client
+                    .put_bytes(&key, Vec::new(), &PutOptions::default()),
+            )
+            .map_err(|e| errno(&e))?;
+
+        let ino = self.ino(&key);
+        let attr = self.attr(ino, &metadata.into());
+        let fh = self.insert_file(key, Some(Vec::new()), false);
+        Ok((attr, fh))
+    }
+
+    fn make_dir(&mut self, parent: u64, name: &OsStr) -> Result<FileAttr, c_int> {
+        self.writable()?;
+        let path = self.child(parent, name)?;
+
+        if self.node(&path)?.is_some() {
+            return Err(libc::EEXIST);
+        }
+
+        self.dirs.insert(path.clone());
+        let ino = self.ino(&path);
+        Ok(self.dir_attr(ino))
+    }
+
+    fn remove_file(&mut self, parent: u64, name: &OsStr) -> Result<(), c_int> {
+        self.writable()?;
+        let key = self.child(parent, name)?;
+
+        self.block_on(self.client.delete_object(&key))
+            .map_err(|e| errno(&e))
+    }
+
+    fn remove_dir(&mut self, parent: u64, name: &OsStr) -> Result<(), c_int> {
+        self.writable()?;
+        let path = self.child(parent, name)?;
+
+        if self.has_children(&path)? {
+            return Err(libc::ENOTEMPTY);
+        }
+
+        self.dirs.remove(&path);
+        Ok(())
+    }
+
+    fn rename_node(
+        &mut self,
+        parent: u64,
+        name: &OsStr,
+        new_parent: u64,
+        new_name: &OsStr,
+    ) -> Result<(), c_int> {
+        self.writable()?;
+        let from = self.child(parent, name)?;
+        let to = self.child(new_parent, new_name)?;
+
+        let moves = match self.node(&from)?.ok_or(libc::ENOENT)? {
+            Node::File { .. } => vec![(from.clone(), to.clone())],
+            Node::Directory => {
+                let prefix = dir_prefix(&from);
+                let objects: Vec<ObjectMetadata> = self
+                    .block_on(self.client.stream_objects(&prefix).try_collect())
+                    .map_err(|e| errno(&e))?;
+
+                objects
+                    .into_iter()
+                    .map(|object| {
+                        let key = format!("{}/{}", to, &object.key[prefix.len()..]);
+                        (object.key, key)
+                    })
+                    .collect()
+            }
+        };
+
+        for (source, destination) in &moves {
+            self.block_on(async {
+                self.client.copy_object(source, destination).await?;
+                self.client.delete_object(source).await
+            })
+            .map_err(|e| errno(&e))?;
+        }
+
+        let renamed = |path: &str| match path.strip_prefix(&from) {
+            Some("") => Some(to.clone()),
+            Some(rest) if rest.starts_with('/') => Some(format!("{}{}", to, rest)),
+            _ => None,
+        };
+
+        self.dirs = self
+            .dirs
+            .iter()
+            .map(|dir| renamed(dir).unwrap_or_else(|| dir.clone()))
+            .collect();
+
+        for (ino, path) in self.paths.iter_mut() {
+            if let Some(new_path) = renamed(path) {
+                self.inodes.remove(path);
+                self.inodes.insert(new_path.clone(), *ino);
+                *path = new_path;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Filesystem for LilaFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        match self.child(parent, name).and_then(|path| self.stat(&path)) {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.path(ino).and_then(|path| self.stat(&path)) {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn setattr(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _mode: Option<u32>,
+        _uid: Option<u32>,
+        _gid: Option<u32>,
+        size: Option<u64>,
+        _atime: Option<TimeOrNow>,
+        _mtime: Option<TimeOrNow>,
+        _ctime: Option<SystemTime>,
+        fh: Option<u64>,
+        _crtime: Option<SystemTime>,
+        _chgtime: Option<SystemTime>,
+        _bkuptime: Option<SystemTime>,
+        _flags: Option<u32>,
+        reply: ReplyAttr,
+    ) {
+        let result = match size {
+            Some(size) => self.truncate(ino, fh, size),
+            None => Ok(()),
+        };
+
+        match result
+            .and_then(|()| self.path(ino))
+            .and_then(|path| self.stat(&path))
+        {
+            Ok(attr) => reply.attr(&TTL, &attr),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn mkdir(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        reply: ReplyEntry,
+    ) {
+        match self.make_dir(parent, name) {
+            Ok(attr) => reply.entry(&TTL, &attr, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn unlink(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        match self.remove_file(parent, name) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn rmdir(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEmpty) {
+        match self.remove_dir(parent, name) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn rename(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        newparent: u64,
+        newname: &OsStr,
+        _flags: u32,
+        reply: ReplyEmpty,
+    ) {
+        match self.rename_node(parent, name, newparent, newname) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        match self.open_file(ino, flags) {
+            Ok(fh) => reply.opened(fh, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        match self.read_file(fh, offset.max(0) as u64, size) {
+            Ok(data) => reply.data(&data),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn write(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        data: &[u8],
+        _write_flags: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyWrite,
+    ) {
+        match self.write_file(fh, offset.max(0) as u64, data) {
+            Ok(written) => reply.written(written),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn flush(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _lock_owner: u64,
+        reply: ReplyEmpty,
+    ) {
+        match self.sync_file(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        let result = self.sync_file(fh);
+        self.files.remove(&fh);
+
+        match result {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn fsync(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _datasync: bool,
+        reply: ReplyEmpty,
+    ) {
+        match self.sync_file(fh) {
+            Ok(()) => reply.ok(),
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn opendir(&mut self, _req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
+        match self.list_directory(ino) {
+            Ok(entries) => {
+                let fh = self.next_fh;
+                self.next_fh += 1;
+                self.listings.insert(fh, entries);
+                reply.opened(fh, 0);
+            }
+            Err(e) => reply.error(e),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(entries) = self.listings.get(&fh) else {
+            reply.error(libc::EBADF);
+            return;
+        };
+
+        for (i, (ino, kind, name)) in entries.iter().enumerate().skip(offset.max(0) as usize) {
+            if reply.add(*ino, i as i64 + 1, *kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+
+    fn releasedir(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        reply: ReplyEmpty,
+    ) {
+        self.listings.remove(&fh);
+        reply.ok();
+    }
+
+    fn create(
+        &mut self,
+        _req: &Request<'_>,
+        parent: u64,
+        name: &OsStr,
+        _mode: u32,
+        _umask: u32,
+        _flags: i32,
+        reply: ReplyCreate,
+    ) {
+        match self.create_file(parent, name) {
+            Ok((attr, fh)) => reply.created(&TTL, &attr, 0, fh, 0),
+            Err(e) => reply.error(e),
+        }
+    }
+}
+
+fn dir_prefix(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!("{}/", path)
+    }
+}
+
+fn errno(e: &lila_client::Error) -> c_int {
+    match e.status() {
+        Some(StatusCode::NOT_FOUND) => libc::ENOENT,
+        Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN) => libc::EACCES,
+        Some(StatusCode::PAYLOAD_TOO_LARGE | StatusCode::INSUFFICIENT_STORAGE) => libc::ENOSPC,
+        Some(StatusCode::CONFLICT | StatusCode::LOCKED) => libc::EBUSY,
+        _ => {
+            tracing::warn!("Request failed: {}", e);
+            libc::EIO
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    Router,
+    extract::{Query, State},
+    http::header,
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Duration, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+use utoipa::IntoParams;
+
+use crate::{
+    auth,
+    error::{AppError, Result},
+    handlers::objects::AppState,
+    models::{ApiToken, ErrorResponse, OidcConfig},
+    storage::MetadataStore,
+};
+
+const LOGIN_TIMEOUT_MINUTES: i64 = 10;
+
+#[derive(Clone)]
+pub struct OidcState {
+    metadata: MetadataStore,
+    config: Arc<OidcConfig>,
+    client: reqwest::Client,
+    provider: Arc<OnceCell<Provider>>,
+    pending: Arc<Mutex<HashMap<String, PendingLogin>>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Provider {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+}
+
+struct PendingLogin {
+    nonce: String,
+    verifier: String,
+    return_to: String,
+    started_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LoginQuery {
+    return_to: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl Audience {
+    fn contains(&self, client_id: &str) -> bool {
+        match self {
+            Audience::One(aud) => aud == client_id,
+            Audience::Many(auds) => auds.iter().any(|aud| aud == client_id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct IdClaims {
+    iss: String,
+    aud: Audience,
+    exp: i64,
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    email_verified: Option<bool>,
+    name: Option<String>,
+}
+
+pub fn router(state: AppState, config: &OidcConfig) -> Router<AppState> {
+    let oidc_state = OidcState {
+        metadata: state.metadata,
+        config: Arc::new(config.clone()),
+        client: reqwest::Client::new(),
+        provider: Arc::new(OnceCell::new()),
+        pending: Arc::new(Mutex::new(HashMap::new())),
+    };
+
+    Router::new()
+        .route("/auth/login", get(login))
+        .route("/auth/callback", get(callback))
+        .with_state(oidc_state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/login",
+    tag = "auth",
+    params(LoginQuery),
+    responses(
+        (status = 303, description = "Redirect to the identity provider"),
+    ),
+    security(())
+)]
+async fn login(
+    State(state): State<OidcState>,
+    Query(query): Query<LoginQuery>,
+) -> Result<Redirect> {
+    let provider = state.provider().await?;
+
+    let return_to = query
+        .return_to
+        .filter(|path| path.starts_with('/') && !path.starts_with("//"))
+        .unwrap_or_else(|| "/".to_string());
+
+    let login_state = auth::generate_token();
+    let nonce = auth::generate_token();
+    let verifier = auth::generate_token();
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+
+    {
+        let mut pending = state.pending.lock().unwrap();
+        let cutoff = Utc::now() - Duration::minutes(LOGIN_TIMEOUT_MINUTES);
+        pending.retain(|_, login| login.started_at > cutoff);
+        pending.insert(
+            login_state.clone(),
+            PendingLogin {
+                nonce: nonce.clone(),
+                verifier,
+                return_to,
+                started_at: Utc::now(),
+            },
+        );
+    }
+
+    let mut url = reqwest::Url::parse(&provider.authorization_endpoint)
+        .map_err(|e| AppError::Upstream(format!("invalid authorization endpoint: {}", e)))?;
+    url.query_pairs_mut()
+        .append_pair("response_type", "code")
+        .append_pair("client_id", &state.config.client_id)
+        .append_pair("redirect_uri", &state.config.redirect_url)
+        .append_pair("scope", &state.config.scopes.join(" "))
+        .append_pair("state", &login_state)
+        .append_pair("nonce", &nonce)
+        .append_pair("code_challenge", &challenge)
+        .append_pair("code_challenge_method", "S256");
+
+    tracing::info!("Redirecting to OIDC provider {}", provider.issuer);
+    Ok(Redirect::to(url.as_str()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/auth/callback",
+    tag = "auth",
+    params(CallbackQuery),
+    responses(
+        (status = 303, description = "Session cookie set, redirect to `return_to`"),
+        (status = 401, description = "Login failed", body = ErrorResponse),
+    ),
+    security(())
+)]
+async fn callback(
+    State(state): State<OidcState>,
+    Query(query): Query<CallbackQuery>,
+) -> Result<Response> {
+    if let Some(error) = query.error {
+        tracing::warn!(
+            "OIDC provider returned error: {} {}",
+            error,
+            query.error_description.unwrap_or_default()
+        );
+        return Err(AppError::Unauthorized);
+    }
+
+    let (Some(code), Some(login_state)) = (query.code, query.state) else {
+        return Err(AppError::BadRequest(
+            "callback requires code and state parameters".to_string(),
+        ));
+    };
+
+    let login = state
+        .pending
+        .lock()
+        .unwrap()
+        .remove(&login_state)
+        .filter(|login| login.started_at > Utc::now() - Duration::minutes(LOGIN_TIMEOUT_MINUTES))
+        .ok_or_else(|| AppError::BadRequest("unknown or expired login state".to_string()))?;
+
+    let provider = state.provider().await?;
+
+    let response = state
+        .client
+        .post(&provider.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code.as_str()),
+            ("redirect_uri", state.config.redirect_url.as_str()),
+            ("client_id", state.config.client_id.as_str()),
+            ("client_secret", state.config.client_secret.as_str()),
+            ("code_verifier", login.verifier.as_str()),
+        ])
+        .send()
+        .await
+        .map_err(|e| AppError::Upstream(format!("token request failed: {}", e)))?;
+
+    if !response.status().is_success() {
+        tracing::warn!("OIDC token endpoint returned {}", response.status());
+        return Err(AppError::Upstream(format!(
+            "token endpoint returned {}",
+            response.status()
+        )));
+    }
+
+    let tokens: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::Upstream(format!("invalid token response: {}", e)))?;
+
+    let claims = state.verify_claims(provider, &tokens.id_token, &login.nonce)?;
+
+    let name = claims
+        .email
+        .clone()
+        .or(claims.name.clone())
+        .unwrap_or_else(|| claims.sub.clone());
+
+    if !state.config.allowed_emails.is_empty() {
+        let allowed = claims.email_verified != Some(false)
+            && claims.email.as_ref().is_some_and(|email| {
+                state
+                    .config
+                    .allowed_emails
+                    .iter()
+                    .any(|allowed| allowed.eq_ignore_ascii_case(email))
+            });
+
+        if !allowed {
+            tracing::warn!("OIDC login for {} is not in allowed_emails", name);
+            return Err(AppError::Forbidden(format!(
+                "{} is not allowed to sign in",
+                name
+            )));
+        }
+    }
+
+    let principal = ApiToken {
+        id: format!("session:{}", claims.sub),
+        name,
+        scope: state.config.session_scope,
+        rate_limit: None,
+        created_at: Utc::now(),
+    };
+    let expires_at = principal.created_at + Duration::hours(state.config.session_hours as i64);
+    let session = auth::generate_token();
+
+    state
+        .metadata
+        .create_session(
+            &auth::hash_token(&session),
+            &claims.sub,
+            &principal,
+            expires_at,
+        )
+        .await?;
+
+    tracing::info!(
+        "OIDC session started for {} with {} scope",
+        principal.name,
+        principal.scope
+    );
+
+    let cookie = auth::session_cookie(
+        &session,
+        (state.config.session_hours * 3600) as i64,
+        state.config.redirect_url.starts_with("https://"),
+    )?;
+
+    let mut response = Redirect::to(&login.return_to).into_response();
+    response.headers_mut().insert(header::SET_COOKIE, cookie);
+    Ok(response)
+}
+
+impl OidcState {
+    async fn provider(&self) -> Result<&Provider> {
+        self.provider
+            .get_or_try_init(|| async {
+                let url = format!(
+                    "{}/.well-known/openid-configuration",
+                    self.config.issuer.trim_end_matches('/')
+                );
+
+                tracing::debug!("Fetching OIDC discovery document from {}", url);
+
+                let response = self
+                    .client
+                    .get(&url)
+                    .send()
+                    .await
+                    .and_then(|response| response.error_for_status())
+                    .map_err(|e| AppError::Upstream(format!("OIDC discovery failed: {}", e)))?;
+
+                response
+                    .json::<Provider>()
+                    .await
+                    .map_err(|e| AppError::Upstream(format!("invalid discovery document: {}", e)))
+            })
+            .await
+    }
+
+    fn verify_claims(&self, provider: &Provider, id_token: &str, nonce: &str) -> Result<IdClaims> {
+        let invalid = |reason: &str| AppError::Upstream(format!("invalid ID token: {}", reason));
+
+        let payload = id_token
+            .split('.')
+            .nth(1)
+            .ok_or_else(|| invalid("malformed JWT"))?;
+
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|_| invalid("payload is not base64url"))?;
+
+        let claims: IdClaims =
+            serde_json::from_slice(&payload).map_err(|_| invalid("unreadable claims"))?;
+
+        if claims.iss.trim_end_matches('/') != provider.issuer.trim_end_matches('/') {
+            return Err(invalid("issuer mismatch"));
+        }
+
+        if !claims.aud.contains(&self.config.client_id) {
+            return Err(invalid("audience mismatch"));
+        }
+
+        if claims.exp < Utc::now().timestamp() {
+            return Err(invalid("token expired"));
+        }
+
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(invalid("nonce mismatch"));
+        }
+
+        Ok(claims)
+    }
+}
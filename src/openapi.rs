@@ -0,0 +1,201 @@
+use axum::{
+    Json,
+    response::{Html, IntoResponse},
+};
+use utoipa::{
+    Modify, OpenApi, PartialSchema, ToSchema,
+    openapi::{
+        self, ArrayBuilder, KnownFormat, ObjectBuilder, RefOr, Schema, SchemaFormat, Type,
+        security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme},
+    },
+};
+
+use crate::{
+    auth::SESSION_COOKIE,
+    graphql,
+    handlers::{self, archive::ArchiveFormat},
+    models::{SortField, SortOrder},
+    oidc,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "lila",
+        description = "Object storage API. Every route under `/api/v1/` except `/api/v1/buckets` \
+                       and `/api/v1/admin` is also served per bucket under \
+                       `/api/v1/buckets/{bucket}/`. Object reads accept presigned URLs in place \
+                       of a token."
+    ),
+    paths(
+        handlers::objects::list_objects,
+        handlers::objects::stream_objects,
+        handlers::objects::batch_delete,
+        handlers::objects::upload_form,
+        handlers::objects::put_object,
+        handlers::objects::get_object,
+        handlers::objects::head_object,
+        handlers::objects::delete_object,
+        handlers::objects::append_object,
+        handlers::objects::get_object_metadata,
+        handlers::objects::update_object_metadata,
+        handlers::objects::batch_get_metadata,
+        handlers::objects::get_object_info,
+        handlers::objects::verify_object,
+        handlers::objects::delete_folder,
+        handlers::objects::copy_object,
+        handlers::objects::search_objects,
+        handlers::thumbnails::get_thumbnail,
+        handlers::versions::list_versions,
+        handlers::presign::presign,
+        handlers::archive::download_archive,
+        handlers::archive::import_archive,
+        handlers::uploads::initiate_upload,
+        handlers::uploads::get_upload,
+        handlers::uploads::abort_upload,
+        handlers::uploads::upload_part,
+        handlers::uploads::complete_upload,
+        handlers::tus::options,
+        handlers::tus::create_upload,
+        handlers::tus::get_offset,
+        handlers::tus::patch_upload,
+        handlers::tus::terminate_upload,
+        handlers::trash::list_trash,
+        handlers::trash::trash_action,
+        handlers::trash::purge_object,
+        handlers::stats::get_stats,
+        handlers::stats::get_prefix_stats,
+        handlers::events::subscribe,
+        handlers::changes::list_changes,
+        graphql::graphql,
+        handlers::buckets::list_buckets,
+        handlers::buckets::create_bucket,
+        handlers::buckets::get_bucket,
+        handlers::buckets::delete_bucket,
+        handlers::buckets::get_bucket_policy,
+        handlers::buckets::put_bucket_policy,
+        handlers::admin::get_config,
+        handlers::admin::list_jobs,
+        handlers::admin::replication_status,
+        handlers::admin::maintenance_status,
+        handlers::admin::health,
+        handlers::admin::fsck,
+        handlers::admin::export,
+        handlers::admin::backup,
+        handlers::admin::get_read_only,
+        handlers::admin::put_read_only,
+        handlers::tokens::list_tokens,
+        handlers::tokens::create_token,
+        handlers::tokens::update_token,
+        handlers::tokens::delete_token,
+        oidc::login,
+        oidc::callback,
+        handlers::sessions::token_login,
+        handlers::sessions::session,
+        handlers::sessions::logout,
+    ),
+    components(schemas(ArchiveFormat, SortField, SortOrder)),
+    modifiers(&SecuritySchemes),
+    security(("bearer" = []), ("session" = [])),
+    tags(
+        (name = "objects", description = "Store, read and query objects"),
+        (name = "archive", description = "Download and import prefixes as archives"),
+        (name = "uploads", description = "Multipart uploads"),
+        (name = "tus", description = "Resumable uploads using the tus 1.0 protocol"),
+        (name = "trash", description = "Soft-deleted objects"),
+        (name = "stats", description = "Storage usage"),
+        (name = "events", description = "Object change notifications"),
+        (name = "buckets", description = "Bucket management"),
+        (name = "admin", description = "Server administration, requires an admin token"),
+        (name = "tokens", description = "API token management, requires an admin token"),
+        (name = "auth", description = "Browser sessions, started with an API token or single sign-on when OIDC is configured"),
+    )
+)]
+struct ApiDoc;
+
+pub struct Binary;
+
+impl PartialSchema for Binary {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::String)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Binary)))
+            .into()
+    }
+}
+
+impl ToSchema for Binary {}
+
+pub struct FormUpload;
+
+impl PartialSchema for FormUpload {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .property(
+                "prefix",
+                ObjectBuilder::new()
+                    .schema_type(Type::String)
+                    .description(Some("Key prefix for every file, sent before the files")),
+            )
+            .property(
+                "overwrite",
+                ObjectBuilder::new()
+                    .schema_type(Type::Boolean)
+                    .description(Some("Replace existing objects instead of skipping them")),
+            )
+            .property("file", ArrayBuilder::new().items(Binary::schema()))
+            .required("file")
+            .into()
+    }
+}
+
+impl ToSchema for FormUpload {}
+
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::new(SESSION_COOKIE))),
+        );
+    }
+}
+
+pub async fn spec() -> Json<openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+pub async fn docs() -> impl IntoResponse {
+    Html(DOCS_PAGE)
+}
+
+const DOCS_PAGE: &str = r#"
+<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>lila · api</title>
+    <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+    <div id="swagger-ui"></div>
+    <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+    <script>
+        window.ui = SwaggerUIBundle({
+            url: '/api/v1/openapi.json',
+            dom_id: '#swagger-ui',
+            deepLinking: true,
+            persistAuthorization: true,
+        });
+    </script>
+</body>
+</html>
+"#;
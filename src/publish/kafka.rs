@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use rdkafka::{
+    ClientConfig,
+    producer::{FutureProducer, FutureRecord},
+};
+
+use super::{EventPublisher, PublishError};
+use crate::models::ObjectEvent;
+
+const MESSAGE_TIMEOUT_MS: &str = "30000";
+
+pub struct KafkaPublisher {
+    producer: FutureProducer,
+    topic: String,
+}
+
+impl KafkaPublisher {
+    pub fn connect(brokers: &str, topic: &str) -> Result<Self, PublishError> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", MESSAGE_TIMEOUT_MS)
+            .create()?;
+
+        Ok(Self {
+            producer,
+            topic: topic.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for KafkaPublisher {
+    fn name(&self) -> &str {
+        "kafka"
+    }
+
+    async fn publish(&self, event: &ObjectEvent) -> Result<(), PublishError> {
+        let payload = serde_json::to_vec(event)?;
+        let record = FutureRecord::to(&self.topic)
+            .key(&event.key)
+            .payload(&payload);
+
+        self.producer
+            .send(record, Duration::ZERO)
+            .await
+            .map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "redis")]
+mod redis;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    handlers::objects::AppState,
+    models::{ObjectEvent, PublisherConfig},
+};
+
+pub type PublishError = Box<dyn std::error::Error + Send + Sync>;
+
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn publish(&self, event: &ObjectEvent) -> Result<(), PublishError>;
+}
+
+pub async fn connect(config: &PublisherConfig) -> Result<Arc<dyn EventPublisher>, PublishError> {
+    match config {
+        #[cfg(feature = "nats")]
+        PublisherConfig::Nats { url, subject } => {
+            Ok(Arc::new(nats::NatsPublisher::connect(url, subject).await?))
+        }
+        #[cfg(feature = "kafka")]
+        PublisherConfig::Kafka { brokers, topic } => {
+            Ok(Arc::new(kafka::KafkaPublisher::connect(brokers, topic)?))
+        }
+        #[cfg(feature = "redis")]
+        PublisherConfig::Redis {
+            url,
+            stream,
+            max_len,
+        } => Ok(Arc::new(
+            redis::RedisPublisher::connect(url, stream, *max_len).await?,
+        )),
+        #[allow(unreachable_patterns)]
+        other => Err(format!(
+            "lila was built without support for {} publishers",
+            other.kind()
+        )
+        .into()),
+    }
+}
+
+pub async fn run(state: AppState, publisher: Arc<dyn EventPublisher>) {
+    tracing::info!("Publishing object events to {}", publisher.name());
+    let mut receiver = state.events.subscribe(None).receiver;
+
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(missed)) => {
+                tracing::warn!(
+                    "Publisher {} fell behind and dropped {} events",
+                    publisher.name(),
+                    missed
+                );
+                metrics::counter!("lila_events_dropped_total", "publisher" => publisher.name().to_string())
+                    .increment(missed);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        match publisher.publish(&event).await {
+            Ok(()) => {
+                metrics::counter!("lila_events_published_total", "publisher" => publisher.name().to_string())
+                    .increment(1);
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to publish event {} for {} to {}: {}",
+                    event.seq,
+                    event.key,
+                    publisher.name(),
+                    e
+                );
+                metrics::counter!("lila_events_publish_failures_total", "publisher" => publisher.name().to_string())
+                    .increment(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use async_trait::async_trait;
+
+use super::{EventPublisher, PublishError};
+use crate::models::ObjectEvent;
+
+pub struct NatsPublisher {
+    client: async_nats::Client,
+    subject: String,
+}
+
+impl NatsPublisher {
+    pub async fn connect(url: &str, subject: &str) -> Result<Self, PublishError> {
+        Ok(Self {
+            client: async_nats::connect(url).await?,
+            subject: subject.to_string(),
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for NatsPublisher {
+    fn name(&self) -> &str {
+        "nats"
+    }
+
+    async fn publish(&self, event: &ObjectEvent) -> Result<(), PublishError> {
+        let payload = serde_json::to_vec(event)?;
+        self.client
+            .publish(self.subject.clone(), payload.into())
+            .await?;
+        Ok(())
+    }
+}
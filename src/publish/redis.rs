@@ -0,0 +1,62 @@
+use async_trait::async_trait;
+use redis::aio::ConnectionManager;
+
+use super::{EventPublisher, PublishError};
+use crate::models::ObjectEvent;
+
+pub struct RedisPublisher {
+    connection: ConnectionManager,
+    stream: String,
+    max_len: Option<usize>,
+}
+
+impl RedisPublisher {
+    pub async fn connect(
+        url: &str,
+        stream: &str,
+        max_len: Option<usize>,
+    ) -> Result<Self, PublishError> {
+        let client = redis::Client::open(url)?;
+
+        Ok(Self {
+            connection: client.get_connection_manager().await?,
+            stream: stream.to_string(),
+            max_len,
+        })
+    }
+}
+
+#[async_trait]
+impl EventPublisher for RedisPublisher {
+    fn name(&self) -> &str {
+        "redis"
+    }
+
+    async fn publish(&self, event: &ObjectEvent) -> Result<(), PublishError> {
+        let mut command = redis::cmd("XADD");
+        command.arg(&self.stream);
+        if let Some(max_len) = self.max_len {
+            command.arg("MAXLEN").arg("~").arg(max_len);
+        }
+        command
+            .arg("*")
+            .arg("seq")
+            .arg(event.seq)
+            .arg("op")
+            .arg(event.op.as_str())
+            .arg("bucket")
+            .arg(event.bucket.as_deref().unwrap_or(""))
+            .arg("key")
+            .arg(&event.key)
+            .arg("etag")
+            .arg(&event.etag)
+            .arg("size")
+            .arg(event.size)
+            .arg("timestamp")
+            .arg(event.timestamp.to_rfc3339());
+
+        let mut connection = self.connection.clone();
+        let _: String = command.query_async(&mut connection).await?;
+        Ok(())
+    }
+}
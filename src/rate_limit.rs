@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    num::NonZeroU32,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use governor::{
+    DefaultDirectRateLimiter, DefaultKeyedRateLimiter, Quota,
+    clock::{Clock, DefaultClock},
+};
+
+use crate::{
+    auth,
+    error::{AppError, Result},
+    models::{ApiToken, RateLimit, TokenScope},
+};
+
+const RETAIN_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestClass {
+    Read,
+    Write,
+}
+
+impl RequestClass {
+    fn of(request: &Request) -> Self {
+        match auth::required_scope(request.method(), request.uri().path()) {
+            TokenScope::Read => RequestClass::Read,
+            TokenScope::Write | TokenScope::Admin => RequestClass::Write,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    class: RequestClass,
+    default: Option<Arc<DefaultKeyedRateLimiter<String>>>,
+    overrides: Arc<Mutex<HashMap<String, TokenLimiter>>>,
+}
+
+struct TokenLimiter {
+    limit: RateLimit,
+    limiter: Arc<DefaultDirectRateLimiter>,
+}
+
+impl TokenLimiter {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            limiter: Arc::new(DefaultDirectRateLimiter::direct(quota(limit))),
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(class: RequestClass, default: Option<RateLimit>) -> Self {
+        Self {
+            class,
+            default: default.map(|limit| Arc::new(DefaultKeyedRateLimiter::keyed(quota(limit)))),
+            overrides: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn check(&self, key: String, rate_limit: Option<RateLimit>) -> Result<()> {
+        let outcome = match rate_limit {
+            Some(limit) => {
+                let limiter = {
+                    let mut overrides = self.overrides.lock().unwrap();
+                    let entry = overrides
+                        .entry(key.clone())
+                        .or_insert_with(|| TokenLimiter::new(limit));
+
+                    if entry.limit != limit {
+                        *entry = TokenLimiter::new(limit);
+                    }
+
+                    entry.limiter.clone()
+                };
+                limiter.check()
+            }
+            None => {
+                let Some(limiter) = &self.default else {
+                    return Ok(());
+                };
+
+                if limiter.len() > RETAIN_THRESHOLD {
+                    limiter.retain_recent();
+                }
+
+                limiter.check_key(&key)
+            }
+        };
+
+        outcome.map_err(|not_until| {
+            let wait = not_until.wait_time_from(DefaultClock::default().now());
+            tracing::info!(
+                "{:?} rate limit exceeded for {}, retry in {:?}",
+                self.class,
+                key,
+                wait
+            );
+            AppError::TooManyRequests(wait.as_secs().max(1))
+        })
+    }
+}
+
+pub fn validate(rate_limit: &RateLimit) -> Result<()> {
+    if rate_limit.per_second == 0 || rate_limit.burst == Some(0) {
+        return Err(AppError::BadRequest(
+            "rate_limit per_second and burst must be greater than zero".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn rate_limit_middleware(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Result<Response> {
+    if RequestClass::of(&request) != limiter.class {
+        return Ok(next.run(request).await);
+    }
+
+    let (key, rate_limit) = match request.extensions().get::<ApiToken>() {
+        Some(token) => (format!("token:{}", token.id), token.rate_limit),
+        None => {
+            let peer = request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+                .unwrap_or_else(|| "unknown".to_string());
+            (format!("ip:{}", peer), None)
+        }
+    };
+
+    limiter.check(key, rate_limit)?;
+    Ok(next.run(request).await)
+}
+
+fn quota(limit: RateLimit) -> Quota {
+    let per_second = NonZeroU32::new(limit.per_second).unwrap_or(NonZeroU32::MIN);
+    let burst = limit.burst.and_then(NonZeroU32::new).unwrap_or(per_second);
+
+    Quota::per_second(per_second).allow_burst(burst)
+}
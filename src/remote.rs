@@ -0,0 +1,422 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use futures_util::TryStreamExt;
+use lila_client::{Client, ListOptions, ObjectMetadata, PutOptions};
+use tokio::{fs, io};
+
+use crate::{cli::RemoteArgs, handlers::archive, import};
+
+const DELETE_BATCH_SIZE: usize = 1000;
+
+enum Location {
+    Local(PathBuf),
+    Stdio,
+    Remote(Remote),
+}
+
+impl Location {
+    fn parse(arg: &str) -> Self {
+        if arg == "-" {
+            return Location::Stdio;
+        }
+
+        match Remote::parse(arg) {
+            Some(remote) => Location::Remote(remote),
+            None => Location::Local(PathBuf::from(arg)),
+        }
+    }
+}
+
+struct Remote {
+    bucket: Option<String>,
+    key: String,
+}
+
+impl Remote {
+    fn parse(arg: &str) -> Option<Self> {
+        let (bucket, key) = arg.split_once(':')?;
+        if bucket.contains('/') {
+            return None;
+        }
+
+        Some(Remote {
+            bucket: (!bucket.is_empty()).then(|| bucket.to_string()),
+            key: key.to_string(),
+        })
+    }
+
+    fn parse_or_key(arg: &str) -> Self {
+        Self::parse(arg).unwrap_or_else(|| Remote {
+            bucket: None,
+            key: arg.to_string(),
+        })
+    }
+
+    fn client(&self, args: &RemoteArgs) -> Result<Client, lila_client::Error> {
+        let client = args.client()?;
+        Ok(match &self.bucket {
+            Some(bucket) => client.bucket(bucket),
+            None => client,
+        })
+    }
+
+    fn child(&self, name: &str) -> Remote {
+        Remote {
+            bucket: self.bucket.clone(),
+            key: join_key(&self.key, name),
+        }
+    }
+
+    fn child_key(&self, key: &str) -> Remote {
+        Remote {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+        }
+    }
+
+    fn same_bucket(&self, other: &Remote) -> bool {
+        self.bucket == other.bucket
+    }
+}
+
+impl RemoteArgs {
+    pub fn client(&self) -> Result<Client, lila_client::Error> {
+        let mut builder = Client::builder(&self.endpoint);
+        if let Some(token) = &self.token {
+            builder = builder.token(token);
+        }
+
+        builder.build()
+    }
+}
+
+impl fmt::Display for Remote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.bucket.as_deref().unwrap_or(""), self.key)
+    }
+}
+
+pub async fn ls(
+    args: &RemoteArgs,
+    path: &str,
+    recursive: bool,
+    long: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = Remote::parse_or_key(path);
+    let client = target.client(args)?;
+
+    if recursive {
+        let mut objects = Box::pin(client.stream_objects(&target.key));
+        while let Some(object) = objects.try_next().await? {
+            print_object(&object, long);
+        }
+        return Ok(());
+    }
+
+    let mut options = ListOptions {
+        prefix: Some(target.key.clone()),
+        delimiter: Some("/".to_string()),
+        ..Default::default()
+    };
+    let mut prefixes = BTreeSet::new();
+    let mut objects = Vec::new();
+
+    loop {
+        let page = client.list_objects(&options).await?;
+        prefixes.extend(page.prefixes);
+        objects.extend(page.objects);
+
+        match page.next_cursor {
+            Some(cursor) => options.cursor = Some(cursor),
+            None => break,
+        }
+    }
+
+    for prefix in prefixes {
+        if long {
+            println!("{:>12}  {:19}  {}", "DIR", "", prefix);
+        } else {
+            println!("{}", prefix);
+        }
+    }
+    for object in &objects {
+        print_object(object, long);
+    }
+
+    Ok(())
+}
+
+fn print_object(object: &ObjectMetadata, long: bool) {
+    if long {
+        let modified = object.updated_at.unwrap_or(object.created_at);
+        println!(
+            "{:>12}  {}  {}",
+            object.size,
+            modified.format("%Y-%m-%d %H:%M:%S"),
+            object.key
+        );
+    } else {
+        println!("{}", object.key);
+    }
+}
+
+pub async fn cp(
+    args: &RemoteArgs,
+    source: &str,
+    destination: &str,
+    recursive: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match (Location::parse(source), Location::parse(destination)) {
+        (Location::Local(path), Location::Remote(target)) => {
+            let client = target.client(args)?;
+
+            if fs::metadata(&path).await?.is_dir() {
+                if !recursive {
+                    return Err(
+                        format!("{} is a directory, use -r to copy it", path.display()).into(),
+                    );
+                }
+
+                for file in import::walk(&path).await? {
+                    let name = relative_name(&path, &file);
+                    upload(&client, &file, &target.child(&name)).await?;
+                }
+                return Ok(());
+            }
+
+            let target = match file_name(&path) {
+                Some(name) if is_directory_key(&target.key) => target.child(&name),
+                _ => target,
+            };
+            upload(&client, &path, &target).await
+        }
+        (Location::Stdio, Location::Remote(target)) => {
+            if is_directory_key(&target.key) {
+                return Err("a key is required when copying from stdin".into());
+            }
+
+            let client = target.client(args)?;
+            client
+                .put_reader(&target.key, io::stdin(), &PutOptions::default())
+                .await?;
+            Ok(())
+        }
+        (Location::Remote(source), Location::Local(path)) => {
+            let client = source.client(args)?;
+
+            if recursive {
+                for object in list_under(&client, &source.key).await? {
+                    let Some(relative) =
+                        archive::entry_key("", object_name(&source.key, &object.key))
+                    else {
+                        eprintln!("skipping {}: not a valid path", object.key);
+                        continue;
+                    };
+
+                    let file = path.join(relative);
+                    download(&client, &source.child_key(&object.key), &file).await?;
+                }
+                return Ok(());
+            }
+
+            let file = if fs::metadata(&path).await.is_ok_and(|m| m.is_dir())
+                || destination.ends_with(std::path::MAIN_SEPARATOR)
+            {
+                path.join(base_name(&source.key))
+            } else {
+                path
+            };
+            download(&client, &source, &file).await
+        }
+        (Location::Remote(source), Location::Stdio) => {
+            let client = source.client(args)?;
+            let mut stdout = io::stdout();
+            client
+                .get_object(&source.key)
+                .await?
+                .write_to(&mut stdout)
+                .await?;
+            Ok(())
+        }
+        (Location::Remote(source), Location::Remote(target)) => {
+            let from = source.client(args)?;
+            let to = target.client(args)?;
+
+            if recursive {
+                for object in list_under(&from, &source.key).await? {
+                    let name = object_name(&source.key, &object.key);
+                    copy(
+                        &from,
+                        &to,
+                        &source.child_key(&object.key),
+                        &target.child(name),
+                    )
+                    .await?;
+                }
+                return Ok(());
+            }
+
+            let target = if is_directory_key(&target.key) {
+                target.child(base_name(&source.key))
+            } else {
+                target
+            };
+            copy(&from, &to, &source, &target).await
+        }
+        _ => Err("one of source or destination must be remote, written as [BUCKET]:KEY".into()),
+    }
+}
+
+async fn upload(
+    client: &Client,
+    path: &Path,
+    target: &Remote,
+) -> Result<(), Box<dyn std::error::Error>> {
+    client
+        .put_file(&target.key, path, &PutOptions::default())
+        .await?;
+    println!("upload: {} -> {}", path.display(), target);
+    Ok(())
+}
+
+async fn download(
+    client: &Client,
+    source: &Remote,
+    path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+
+    client.download_to_file(&source.key, path).await?;
+    println!("download: {} -> {}", source, path.display());
+    Ok(())
+}
+
+async fn copy(
+    from: &Client,
+    to: &Client,
+    source: &Remote,
+    target: &Remote,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if source.same_bucket(target) {
+        from.copy_object(&source.key, &target.key).await?;
+    } else {
+        let download = from.get_object(&source.key).await?;
+        let options = PutOptions {
+            content_type: download.content_type().map(str::to_string),
+            content_length: download.content_length(),
+            ..Default::default()
+        };
+        to.put_stream(&target.key, download.into_stream(), &options)
+            .await?;
+    }
+
+    println!("copy: {} -> {}", source, target);
+    Ok(())
+}
+
+pub async fn rm(
+    args: &RemoteArgs,
+    paths: &[String],
+    recursive: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failed = 0;
+
+    for path in paths {
+        let target = Remote::parse_or_key(path);
+        let client = target.client(args)?;
+
+        if !recursive {
+            client.delete_object(&target.key).await?;
+            println!("delete: {}", target);
+            continue;
+        }
+
+        let keys: Vec<String> = list_under(&client, &target.key)
+            .await?
+            .into_iter()
+            .map(|object| object.key)
+            .collect();
+
+        for batch in keys.chunks(DELETE_BATCH_SIZE) {
+            let response = client.delete_objects(batch).await?;
+            for result in response.results {
+                match result.error {
+                    Some(error) => {
+                        eprintln!("failed to delete {}: {}", result.key, error);
+                        failed += 1;
+                    }
+                    None => println!("delete: {}", target.child_key(&result.key)),
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} objects failed to delete", failed).into());
+    }
+
+    Ok(())
+}
+
+pub async fn stat(args: &RemoteArgs, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let target = Remote::parse_or_key(path);
+    let metadata = target.client(args)?.metadata(&target.key).await?;
+
+    println!("{}", serde_json::to_string_pretty(&metadata)?);
+
+    Ok(())
+}
+
+async fn list_under(
+    client: &Client,
+    prefix: &str,
+) -> Result<Vec<ObjectMetadata>, lila_client::Error> {
+    client.stream_objects(prefix).try_collect().await
+}
+
+fn join_key(prefix: &str, name: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with('/') {
+        format!("{}{}", prefix, name)
+    } else {
+        format!("{}/{}", prefix, name)
+    }
+}
+
+fn is_directory_key(key: &str) -> bool {
+    key.is_empty() || key.ends_with('/')
+}
+
+fn base_name(key: &str) -> &str {
+    key.rsplit('/').next().unwrap_or(key)
+}
+
+fn object_name<'a>(prefix: &str, key: &'a str) -> &'a str {
+    let name = key
+        .strip_prefix(prefix)
+        .unwrap_or(key)
+        .trim_start_matches('/');
+    if name.is_empty() {
+        base_name(key)
+    } else {
+        name
+    }
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    Some(path.file_name()?.to_string_lossy().into_owned())
+}
+
+fn relative_name(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
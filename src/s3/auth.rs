@@ -0,0 +1,324 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use axum::{
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{NaiveDateTime, Utc};
+use hmac::{Hmac, Mac};
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, percent_decode_str, utf8_percent_encode};
+use sha2::{Digest, Sha256};
+
+use crate::s3::{S3State, xml::S3Error};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const CHUNK_ALGORITHM: &str = "AWS4-HMAC-SHA256-PAYLOAD";
+const EMPTY_SHA256: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+const REQUIRED_SIGNED_HEADERS: [&str; 2] = ["host", "x-amz-date"];
+const MAX_CLOCK_SKEW_MINUTES: i64 = 15;
+
+pub const UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+type HmacSha256 = Hmac<Sha256>;
+
+struct Credentials<'a> {
+    access_key: &'a str,
+    date: &'a str,
+    region: &'a str,
+    service: &'a str,
+    signed_headers: Vec<&'a str>,
+    signature: Vec<u8>,
+}
+
+#[derive(Clone)]
+pub struct ChunkSigner {
+    key: Vec<u8>,
+    amz_date: String,
+    scope: String,
+    seed: Vec<u8>,
+    mismatch: Arc<AtomicBool>,
+}
+
+impl ChunkSigner {
+    pub fn seed(&self) -> &[u8] {
+        &self.seed
+    }
+
+    pub fn verify(&self, previous: &[u8], payload_hash: &[u8], signature: &[u8]) -> bool {
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            CHUNK_ALGORITHM,
+            self.amz_date,
+            self.scope,
+            hex::encode(previous),
+            EMPTY_SHA256,
+            hex::encode(payload_hash)
+        );
+
+        let mut mac =
+            HmacSha256::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(string_to_sign.as_bytes());
+
+        let verified = mac.verify_slice(signature).is_ok();
+        if !verified {
+            self.mismatch.store(true, Ordering::Relaxed);
+        }
+        verified
+    }
+}
+
+pub async fn verify_signature(
+    State(state): State<S3State>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, S3Error> {
+    let headers = request.headers();
+
+    let authorization = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| S3Error::access_denied("missing Authorization header"))?;
+
+    let credentials = parse_authorization(authorization)?;
+
+    if let Some(missing) = REQUIRED_SIGNED_HEADERS
+        .iter()
+        .find(|name| !credentials.signed_headers.contains(name))
+    {
+        return Err(S3Error::access_denied(format!(
+            "{} must be included in SignedHeaders",
+            missing
+        )));
+    }
+
+    if credentials.access_key != state.access_key {
+        tracing::warn!(
+            "S3 request with unknown access key {}",
+            credentials.access_key
+        );
+        return Err(S3Error::new(
+            StatusCode::FORBIDDEN,
+            "InvalidAccessKeyId",
+            "The access key you provided does not exist",
+        ));
+    }
+
+    let amz_date = header_value(headers, "x-amz-date")
+        .ok_or_else(|| S3Error::access_denied("missing x-amz-date header"))?;
+
+    let signed_at = NaiveDateTime::parse_from_str(amz_date, "%Y%m%dT%H%M%SZ")
+        .map_err(|_| S3Error::access_denied("invalid x-amz-date header"))?
+        .and_utc();
+
+    if (Utc::now() - signed_at).num_minutes().abs() > MAX_CLOCK_SKEW_MINUTES {
+        return Err(S3Error::new(
+            StatusCode::FORBIDDEN,
+            "RequestTimeTooSkewed",
+            "The difference between the request time and the server's time is too large",
+        ));
+    }
+
+    if !amz_date.starts_with(credentials.date) {
+        return Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "AuthorizationHeaderMalformed",
+            "credential scope date does not match x-amz-date",
+        ));
+    }
+
+    let payload_hash = header_value(headers, "x-amz-content-sha256")
+        .ok_or_else(|| S3Error::invalid_argument("missing x-amz-content-sha256 header"))?;
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method(),
+        canonical_uri(request.uri().path()),
+        canonical_query(request.uri().query().unwrap_or("")),
+        canonical_headers(headers, &credentials.signed_headers),
+        credentials.signed_headers.join(";"),
+        payload_hash
+    );
+
+    let scope = format!(
+        "{}/{}/{}/aws4_request",
+        credentials.date, credentials.region, credentials.service
+    );
+
+    let string_to_sign = format!(
+        "{}\n{}\n{}\n{}",
+        ALGORITHM,
+        amz_date,
+        scope,
+        hex::encode(Sha256::digest(canonical_request.as_bytes()))
+    );
+
+    let mut signing_key = format!("AWS4{}", state.secret_key).into_bytes();
+    for part in [
+        credentials.date,
+        credentials.region,
+        credentials.service,
+        "aws4_request",
+    ] {
+        signing_key = hmac(&signing_key, part);
+    }
+
+    let mut mac =
+        HmacSha256::new_from_slice(&signing_key).expect("HMAC accepts keys of any length");
+    mac.update(string_to_sign.as_bytes());
+
+    if mac.verify_slice(&credentials.signature).is_err() {
+        tracing::warn!("S3 signature mismatch for {}", request.uri().path());
+        tracing::debug!("Canonical request:\n{}", canonical_request);
+        return Err(signature_mismatch());
+    }
+
+    tracing::debug!("S3 signature verified for {}", credentials.access_key);
+
+    let signer = ChunkSigner {
+        key: signing_key,
+        amz_date: amz_date.to_string(),
+        scope,
+        seed: credentials.signature,
+        mismatch: Arc::new(AtomicBool::new(false)),
+    };
+    let mismatch = signer.mismatch.clone();
+    request.extensions_mut().insert(signer);
+
+    let response = next.run(request).await;
+
+    if mismatch.load(Ordering::Relaxed) {
+        tracing::warn!("S3 chunk signature mismatch");
+        return Err(signature_mismatch());
+    }
+
+    Ok(response)
+}
+
+fn signature_mismatch() -> S3Error {
+    S3Error::new(
+        StatusCode::FORBIDDEN,
+        "SignatureDoesNotMatch",
+        "The request signature we calculated does not match the signature you provided",
+    )
+}
+
+fn parse_authorization(value: &str) -> Result<Credentials<'_>, S3Error> {
+    let malformed = || {
+        S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "AuthorizationHeaderMalformed",
+            "expected an AWS4-HMAC-SHA256 Authorization header",
+        )
+    };
+
+    let fields = value.strip_prefix(ALGORITHM).ok_or_else(malformed)?;
+
+    let mut credential = None;
+    let mut signed_headers = None;
+    let mut signature = None;
+
+    for field in fields.split(',') {
+        match field.trim().split_once('=') {
+            Some(("Credential", v)) => credential = Some(v),
+            Some(("SignedHeaders", v)) => signed_headers = Some(v),
+            Some(("Signature", v)) => signature = hex::decode(v).ok(),
+            _ => {}
+        }
+    }
+
+    let mut scope = credential.ok_or_else(malformed)?.split('/');
+    let (Some(access_key), Some(date), Some(region), Some(service), Some("aws4_request")) = (
+        scope.next(),
+        scope.next(),
+        scope.next(),
+        scope.next(),
+        scope.next(),
+    ) else {
+        return Err(malformed());
+    };
+
+    if service != "s3" {
+        return Err(malformed());
+    }
+
+    Ok(Credentials {
+        access_key,
+        date,
+        region,
+        service,
+        signed_headers: signed_headers.ok_or_else(malformed)?.split(';').collect(),
+        signature: signature.ok_or_else(malformed)?,
+    })
+}
+
+fn canonical_uri(path: &str) -> String {
+    path.split('/')
+        .map(|segment| {
+            let decoded = percent_decode_str(segment).decode_utf8_lossy();
+            utf8_percent_encode(&decoded, UNRESERVED).to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn canonical_query(query: &str) -> String {
+    let mut pairs: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (encode_component(name), encode_component(value))
+        })
+        .collect();
+
+    pairs.sort();
+
+    pairs
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn canonical_headers(headers: &HeaderMap, signed_headers: &[&str]) -> String {
+    signed_headers
+        .iter()
+        .map(|name| {
+            let values: Vec<String> = headers
+                .get_all(*name)
+                .iter()
+                .map(|value| {
+                    String::from_utf8_lossy(value.as_bytes())
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+            format!("{}:{}\n", name, values.join(","))
+        })
+        .collect()
+}
+
+fn encode_component(value: &str) -> String {
+    let decoded = percent_decode_str(value).decode_utf8_lossy();
+    utf8_percent_encode(&decoded, UNRESERVED).to_string()
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
+
+pub fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
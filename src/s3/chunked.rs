@@ -0,0 +1,171 @@
+use std::io;
+
+use axum::body::Bytes;
+use futures_util::{Stream, StreamExt, stream};
+use sha2::{Digest, Sha256};
+
+use crate::s3::auth::ChunkSigner;
+
+const SIGNATURE_EXTENSION: &str = "chunk-signature=";
+
+enum Phase {
+    Header,
+    Data(usize),
+    Trailer,
+    Done,
+}
+
+struct Signatures {
+    signer: ChunkSigner,
+    previous: Vec<u8>,
+    expected: Vec<u8>,
+    hasher: Sha256,
+}
+
+impl Signatures {
+    fn verify(&mut self) -> io::Result<()> {
+        let payload_hash = std::mem::take(&mut self.hasher).finalize();
+
+        if !self
+            .signer
+            .verify(&self.previous, &payload_hash, &self.expected)
+        {
+            return Err(invalid("aws-chunked chunk signature does not match"));
+        }
+
+        self.previous = std::mem::take(&mut self.expected);
+        Ok(())
+    }
+}
+
+struct Decoder<S> {
+    stream: S,
+    buffer: Vec<u8>,
+    phase: Phase,
+    signatures: Option<Signatures>,
+}
+
+pub fn decode<S>(stream: S, signer: Option<ChunkSigner>) -> impl Stream<Item = io::Result<Bytes>>
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin,
+{
+    let decoder = Decoder {
+        stream,
+        buffer: Vec::new(),
+        phase: Phase::Header,
+        signatures: signer.map(|signer| Signatures {
+            previous: signer.seed().to_vec(),
+            signer,
+            expected: Vec::new(),
+            hasher: Sha256::new(),
+        }),
+    };
+
+    stream::try_unfold(decoder, |mut decoder| async move {
+        let chunk = decoder.next_chunk().await?;
+        Ok(chunk.map(|chunk| (chunk, decoder)))
+    })
+}
+
+impl<S> Decoder<S>
+where
+    S: Stream<Item = Result<Bytes, axum::Error>> + Unpin,
+{
+    async fn next_chunk(&mut self) -> io::Result<Option<Bytes>> {
+        loop {
+            match self.phase {
+                Phase::Header => {
+                    let Some(end) = find_line_end(&self.buffer) else {
+                        self.fill().await?;
+                        continue;
+                    };
+
+                    let line = String::from_utf8_lossy(&self.buffer[..end]).into_owned();
+                    self.buffer.drain(..end + 2);
+
+                    let mut fields = line.split(';');
+                    let size = fields.next().unwrap_or("").trim();
+                    let size = usize::from_str_radix(size, 16).map_err(|_| {
+                        invalid(format!("invalid aws-chunked size line: {:?}", line))
+                    })?;
+
+                    if let Some(signatures) = self.signatures.as_mut() {
+                        signatures.expected = fields
+                            .find_map(|field| field.trim().strip_prefix(SIGNATURE_EXTENSION))
+                            .and_then(|signature| hex::decode(signature).ok())
+                            .ok_or_else(|| invalid("aws-chunked chunk is missing its signature"))?;
+                    }
+
+                    self.phase = match size {
+                        0 => {
+                            if let Some(signatures) = self.signatures.as_mut() {
+                                signatures.verify()?;
+                            }
+                            Phase::Done
+                        }
+                        size => Phase::Data(size),
+                    };
+                }
+                Phase::Data(remaining) => {
+                    if self.buffer.is_empty() {
+                        self.fill().await?;
+                        continue;
+                    }
+
+                    let take = remaining.min(self.buffer.len());
+                    let chunk = Bytes::from(self.buffer.drain(..take).collect::<Vec<u8>>());
+
+                    if let Some(signatures) = self.signatures.as_mut() {
+                        signatures.hasher.update(&chunk);
+                        if take == remaining {
+                            signatures.verify()?;
+                        }
+                    }
+
+                    self.phase = match remaining - take {
+                        0 => Phase::Trailer,
+                        remaining => Phase::Data(remaining),
+                    };
+
+                    return Ok(Some(chunk));
+                }
+                Phase::Trailer => {
+                    if self.buffer.len() < 2 {
+                        self.fill().await?;
+                        continue;
+                    }
+
+                    if &self.buffer[..2] != b"\r\n" {
+                        return Err(invalid("aws-chunked data is not terminated by CRLF"));
+                    }
+
+                    self.buffer.drain(..2);
+                    self.phase = Phase::Header;
+                }
+                Phase::Done => return Ok(None),
+            }
+        }
+    }
+
+    async fn fill(&mut self) -> io::Result<()> {
+        match self.stream.next().await {
+            Some(Ok(bytes)) => {
+                self.buffer.extend_from_slice(&bytes);
+                Ok(())
+            }
+            Some(Err(e)) => Err(io::Error::other(e)),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "aws-chunked body ended before the final chunk",
+            )),
+        }
+    }
+}
+
+fn find_line_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(2).position(|window| window == b"\r\n")
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
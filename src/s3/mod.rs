@@ -0,0 +1,501 @@
+pub mod auth;
+mod chunked;
+mod xml;
+
+use axum::{
+    Json, Router,
+    body::Body,
+    extract::{Extension, Path, Query, RawQuery, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{
+    error::AppError,
+    handlers::{
+        buckets,
+        objects::{self, AppState, GetQuery, USER_METADATA_PREFIX, VersionQuery},
+    },
+    models::{ListCursor, S3Config, SortField, SortOrder},
+};
+use xml::{ListedBucket, Listing, ListingStyle, S3Error};
+
+const AMZ_META_PREFIX: &str = "x-amz-meta-";
+const STREAMING_SIGNED: &str = "STREAMING-AWS4-HMAC-SHA256-PAYLOAD";
+const STREAMING_UNSIGNED: &str = "STREAMING-UNSIGNED-PAYLOAD-TRAILER";
+const DEFAULT_MAX_KEYS: usize = 1000;
+const LIST_PAGE_SIZE: i64 = 1000;
+const UNSUPPORTED_SUBRESOURCES: &[&str] = &[
+    "acl",
+    "cors",
+    "delete",
+    "lifecycle",
+    "policy",
+    "tagging",
+    "uploadId",
+    "uploads",
+    "versioning",
+    "versions",
+];
+
+type S3Result<T> = std::result::Result<T, S3Error>;
+
+#[derive(Clone)]
+pub struct S3State {
+    app: AppState,
+    access_key: String,
+    secret_key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ListObjectsQuery {
+    list_type: Option<u8>,
+    prefix: Option<String>,
+    delimiter: Option<String>,
+    max_keys: Option<usize>,
+    marker: Option<String>,
+    continuation_token: Option<String>,
+    start_after: Option<String>,
+}
+
+pub fn router(state: AppState, config: &S3Config, secret_key: String) -> Router<AppState> {
+    let s3_state = S3State {
+        access_key: config.access_key.clone(),
+        secret_key,
+        app: state,
+    };
+
+    let base = config.path.trim_end_matches('/');
+
+    Router::new()
+        .route(base, get(list_buckets))
+        .route(&format!("{}/", base), get(list_buckets))
+        .route(
+            &format!("{}/{{bucket}}", base),
+            get(list_objects).head(head_bucket).put(create_bucket),
+        )
+        .route(
+            &format!("{}/{{bucket}}/", base),
+            get(list_objects).head(head_bucket).put(create_bucket),
+        )
+        .route(
+            &format!("{}/{{bucket}}/{{*key}}", base),
+            get(get_object)
+                .head(head_object)
+                .put(put_object)
+                .delete(delete_object)
+                .post(post_object),
+        )
+        .layer(middleware::from_fn_with_state(
+            s3_state.clone(),
+            auth::verify_signature,
+        ))
+        .with_state(s3_state)
+}
+
+async fn list_buckets(State(state): State<S3State>) -> S3Result<Response> {
+    tracing::info!("S3 ListBuckets request");
+
+    let buckets = state.app.metadata.list_buckets().await?;
+
+    Ok(xml::list_buckets(buckets.iter().map(|bucket| {
+        ListedBucket {
+            name: &bucket.name,
+            created_at: bucket.created_at,
+        }
+    })))
+}
+
+async fn head_bucket(
+    State(state): State<S3State>,
+    Path(bucket): Path<String>,
+) -> S3Result<StatusCode> {
+    tracing::info!("S3 HeadBucket request for {}", bucket);
+
+    scoped(&state, &bucket).await?;
+    Ok(StatusCode::OK)
+}
+
+async fn create_bucket(
+    State(state): State<S3State>,
+    Path(bucket): Path<String>,
+    RawQuery(query): RawQuery,
+) -> S3Result<Response> {
+    tracing::info!("S3 CreateBucket request for {}", bucket);
+
+    reject_subresources(query.as_deref())?;
+
+    match buckets::create_bucket(State(state.app.clone()), Path(bucket.clone()), None).await {
+        Ok(_) => Ok(Response::builder()
+            .status(StatusCode::OK)
+            .header(header::LOCATION, format!("/{}", bucket))
+            .body(Body::empty())
+            .unwrap()),
+        Err(AppError::Conflict(_)) => Err(S3Error::new(
+            StatusCode::CONFLICT,
+            "BucketAlreadyOwnedByYou",
+            format!("bucket {} already exists", bucket),
+        )),
+        Err(AppError::BadRequest(message)) => Err(S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidBucketName",
+            message,
+        )),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn list_objects(
+    State(state): State<S3State>,
+    Path(bucket): Path<String>,
+    RawQuery(query): RawQuery,
+    Query(params): Query<ListObjectsQuery>,
+) -> S3Result<Response> {
+    tracing::info!("S3 ListObjects request for {}", bucket);
+
+    let scoped = scoped(&state, &bucket).await?;
+
+    if has_subresource(query.as_deref(), "location") {
+        return Ok(xml::location());
+    }
+
+    reject_subresources(query.as_deref())?;
+
+    let v2 = params.list_type == Some(2);
+    let prefix = params.prefix.unwrap_or_default();
+    let delimiter = params.delimiter.filter(|d| !d.is_empty());
+    let max_keys = params
+        .max_keys
+        .unwrap_or(DEFAULT_MAX_KEYS)
+        .min(DEFAULT_MAX_KEYS);
+
+    let marker = if v2 {
+        match &params.continuation_token {
+            Some(token) => Some(decode_token(token)?),
+            None => params.start_after.clone(),
+        }
+    } else {
+        params.marker.clone()
+    };
+
+    let mut cursor = marker.map(|marker| resume_after(marker, delimiter.as_deref()));
+    let mut contents = Vec::new();
+    let mut common_prefixes: Vec<String> = Vec::new();
+    let mut last_emitted: Option<String> = None;
+    let mut truncated = false;
+    let now = Utc::now();
+
+    'pages: loop {
+        let after = cursor.take().map(|key| ListCursor { key, value: None });
+
+        let page = scoped
+            .metadata
+            .list(
+                Some(&prefix),
+                after.as_ref(),
+                SortField::Key,
+                SortOrder::Asc,
+                Some(LIST_PAGE_SIZE),
+            )
+            .await?;
+
+        let exhausted = (page.len() as i64) < LIST_PAGE_SIZE;
+
+        for object in page {
+            cursor = Some(object.key.clone());
+
+            if object.expires_at.is_some_and(|at| at <= now) {
+                continue;
+            }
+
+            let common = delimiter.as_deref().and_then(|delimiter| {
+                let rest = object.key.strip_prefix(prefix.as_str())?;
+                let end = rest.find(delimiter)? + delimiter.len();
+                Some(format!("{}{}", prefix, &rest[..end]))
+            });
+
+            if let Some(common) = &common
+                && common_prefixes.last() == Some(common)
+            {
+                continue;
+            }
+
+            if contents.len() + common_prefixes.len() >= max_keys {
+                truncated = true;
+                break 'pages;
+            }
+
+            match common {
+                Some(common) => {
+                    last_emitted = Some(common.clone());
+                    common_prefixes.push(common);
+                }
+                None => {
+                    last_emitted = Some(object.key.clone());
+                    contents.push(object);
+                }
+            }
+        }
+
+        if exhausted {
+            break;
+        }
+    }
+
+    let next_marker = last_emitted.filter(|_| truncated);
+
+    let style = if v2 {
+        ListingStyle::V2 {
+            continuation_token: params.continuation_token,
+            next_continuation_token: next_marker.map(|marker| URL_SAFE_NO_PAD.encode(marker)),
+            start_after: params.start_after,
+        }
+    } else {
+        ListingStyle::V1 {
+            marker: params.marker,
+            next_marker,
+        }
+    };
+
+    Ok(xml::list_objects(Listing {
+        bucket: &bucket,
+        prefix: &prefix,
+        delimiter: delimiter.as_deref(),
+        max_keys,
+        truncated,
+        contents,
+        common_prefixes,
+        style,
+    }))
+}
+
+async fn get_object(
+    State(state): State<S3State>,
+    Path((bucket, key)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+    headers: HeaderMap,
+) -> S3Result<Response> {
+    tracing::info!("S3 GetObject request for {}/{}", bucket, key);
+
+    reject_subresources(query.as_deref())?;
+    let scoped = scoped(&state, &bucket).await?;
+
+    let response = objects::get_object(
+        State(scoped),
+        Path(key),
+        Query(GetQuery::default()),
+        headers,
+    )
+    .await?;
+
+    Ok(to_s3_response(response))
+}
+
+async fn head_object(
+    State(state): State<S3State>,
+    Path((bucket, key)): Path<(String, String)>,
+) -> S3Result<Response> {
+    tracing::info!("S3 HeadObject request for {}/{}", bucket, key);
+
+    let scoped = scoped(&state, &bucket).await?;
+
+    let response =
+        objects::head_object(State(scoped), Path(key), Query(VersionQuery::default())).await?;
+
+    Ok(to_s3_response(response))
+}
+
+async fn put_object(
+    State(state): State<S3State>,
+    Path((bucket, key)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+    signer: Option<Extension<auth::ChunkSigner>>,
+    headers: HeaderMap,
+    body: Body,
+) -> S3Result<Response> {
+    tracing::info!("S3 PutObject request for {}/{}", bucket, key);
+
+    reject_subresources(query.as_deref())?;
+
+    if headers.contains_key("x-amz-copy-source") {
+        return Err(S3Error::not_implemented("CopyObject"));
+    }
+
+    let scoped = scoped(&state, &bucket).await?;
+    let (headers, body) = translate_upload(headers, body, signer.map(|Extension(signer)| signer))?;
+
+    let Json(metadata) = objects::put_object(State(scoped), Path(key), headers, body).await?;
+
+    Ok(Response::builder()
+        .status(StatusCode::OK)
+        .header(header::ETAG, xml::quote_etag(&metadata.etag))
+        .body(Body::empty())
+        .unwrap())
+}
+
+async fn delete_object(
+    State(state): State<S3State>,
+    Path((bucket, key)): Path<(String, String)>,
+    RawQuery(query): RawQuery,
+) -> S3Result<StatusCode> {
+    tracing::info!("S3 DeleteObject request for {}/{}", bucket, key);
+
+    reject_subresources(query.as_deref())?;
+    let scoped = scoped(&state, &bucket).await?;
+
+    match objects::delete_object(
+        State(scoped),
+        Path(key),
+        Query(VersionQuery::default()),
+        HeaderMap::new(),
+    )
+    .await
+    {
+        Ok(_) | Err(AppError::NotFound(_)) => Ok(StatusCode::NO_CONTENT),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn post_object() -> S3Error {
+    S3Error::not_implemented("Multipart upload")
+}
+
+async fn scoped(state: &S3State, bucket: &str) -> S3Result<AppState> {
+    let bucket = state
+        .app
+        .metadata
+        .get_bucket(bucket)
+        .await?
+        .ok_or_else(|| S3Error::no_such_bucket(bucket))?;
+
+    Ok(state.app.scoped(bucket))
+}
+
+fn translate_upload(
+    mut headers: HeaderMap,
+    body: Body,
+    signer: Option<auth::ChunkSigner>,
+) -> S3Result<(HeaderMap, Body)> {
+    let user_metadata: Vec<(HeaderName, HeaderValue)> = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let suffix = name.as_str().strip_prefix(AMZ_META_PREFIX)?;
+            let name = format!("{}{}", USER_METADATA_PREFIX, suffix).parse().ok()?;
+            Some((name, value.clone()))
+        })
+        .collect();
+
+    headers.extend(user_metadata);
+
+    let content_sha256 = headers
+        .get("x-amz-content-sha256")
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+
+    if content_sha256.len() == 64 && content_sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        headers.insert(
+            "x-lila-content-sha256",
+            HeaderValue::from_str(&content_sha256).unwrap(),
+        );
+    }
+
+    let signer = match content_sha256.as_str() {
+        STREAMING_SIGNED => Some(signer.ok_or_else(|| {
+            S3Error::access_denied("streaming uploads must be signed with SigV4")
+        })?),
+        STREAMING_UNSIGNED => None,
+        streaming if streaming.starts_with("STREAMING-") => {
+            return Err(S3Error::not_implemented(streaming));
+        }
+        _ => return Ok((headers, body)),
+    };
+
+    let decoded_length = headers
+        .get("x-amz-decoded-content-length")
+        .cloned()
+        .ok_or_else(|| {
+            S3Error::new(
+                StatusCode::LENGTH_REQUIRED,
+                "MissingContentLength",
+                "streaming uploads require x-amz-decoded-content-length",
+            )
+        })?;
+
+    headers.insert(header::CONTENT_LENGTH, decoded_length);
+    headers.remove(header::CONTENT_ENCODING);
+
+    let body = Body::from_stream(chunked::decode(body.into_data_stream(), signer));
+    Ok((headers, body))
+}
+
+fn to_s3_response(response: Response) -> Response {
+    let (mut parts, body) = response.into_parts();
+
+    if let Some(etag) = parts
+        .headers
+        .get(header::ETAG)
+        .and_then(|v| v.to_str().ok())
+        .filter(|etag| !etag.starts_with('"'))
+        .and_then(|etag| HeaderValue::from_str(&xml::quote_etag(etag)).ok())
+    {
+        parts.headers.insert(header::ETAG, etag);
+    }
+
+    let user_metadata: Vec<(HeaderName, HeaderValue)> = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let suffix = name.as_str().strip_prefix(USER_METADATA_PREFIX)?;
+            let name = format!("{}{}", AMZ_META_PREFIX, suffix).parse().ok()?;
+            Some((name, value.clone()))
+        })
+        .collect();
+
+    for (name, value) in user_metadata {
+        parts.headers.insert(name, value);
+    }
+
+    Response::from_parts(parts, body).into_response()
+}
+
+fn resume_after(marker: String, delimiter: Option<&str>) -> String {
+    match delimiter {
+        Some(delimiter) if marker.ends_with(delimiter) => format!("{}\u{10FFFF}", marker),
+        _ => marker,
+    }
+}
+
+fn decode_token(token: &str) -> S3Result<String> {
+    URL_SAFE_NO_PAD
+        .decode(token)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .ok_or_else(|| S3Error::invalid_argument("The continuation token provided is incorrect"))
+}
+
+fn has_subresource(query: Option<&str>, name: &str) -> bool {
+    query
+        .unwrap_or("")
+        .split('&')
+        .any(|pair| pair.split_once('=').map_or(pair, |(key, _)| key) == name)
+}
+
+fn reject_subresources(query: Option<&str>) -> S3Result<()> {
+    match UNSUPPORTED_SUBRESOURCES
+        .iter()
+        .find(|name| has_subresource(query, name))
+    {
+        Some(name) => Err(S3Error::not_implemented(&format!(
+            "The {} subresource",
+            name
+        ))),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,267 @@
+use axum::{
+    body::Body,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+
+use crate::{error::AppError, models::ObjectMetadata, request_id};
+
+const NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+#[derive(Debug)]
+pub struct S3Error {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+impl S3Error {
+    pub fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn access_denied(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN, "AccessDenied", message)
+    }
+
+    pub fn no_such_bucket(name: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_FOUND,
+            "NoSuchBucket",
+            format!("The specified bucket does not exist: {}", name),
+        )
+    }
+
+    pub fn not_implemented(operation: &str) -> Self {
+        Self::new(
+            StatusCode::NOT_IMPLEMENTED,
+            "NotImplemented",
+            format!("{} is not supported by lila", operation),
+        )
+    }
+
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "InvalidArgument", message)
+    }
+}
+
+impl From<AppError> for S3Error {
+    fn from(error: AppError) -> Self {
+        let message = error.to_string();
+
+        let (status, code) = match error {
+            AppError::NotFound(_) => (StatusCode::NOT_FOUND, "NoSuchKey"),
+            AppError::BucketNotFound(_) => (StatusCode::NOT_FOUND, "NoSuchBucket"),
+            AppError::UploadNotFound(_) => (StatusCode::NOT_FOUND, "NoSuchUpload"),
+            AppError::TokenNotFound(_) => (StatusCode::NOT_FOUND, "NoSuchKey"),
+            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, "InvalidRequest"),
+            AppError::RetentionLocked(..) => (StatusCode::FORBIDDEN, "AccessDenied"),
+            AppError::Unauthorized => (StatusCode::FORBIDDEN, "AccessDenied"),
+            AppError::Forbidden(_) => (StatusCode::FORBIDDEN, "AccessDenied"),
+            AppError::Conflict(_) => (StatusCode::CONFLICT, "OperationAborted"),
+            AppError::UnsupportedMediaType(_) => (StatusCode::BAD_REQUEST, "InvalidRequest"),
+            AppError::PayloadTooLarge(_) => (StatusCode::BAD_REQUEST, "EntityTooLarge"),
+            AppError::RangeNotSatisfiable(_) => (StatusCode::RANGE_NOT_SATISFIABLE, "InvalidRange"),
+            AppError::PreconditionFailed(_) => {
+                (StatusCode::PRECONDITION_FAILED, "PreconditionFailed")
+            }
+            AppError::ChecksumMismatch(_) => (StatusCode::BAD_REQUEST, "BadDigest"),
+            AppError::TooManyRequests(_) => (StatusCode::SERVICE_UNAVAILABLE, "SlowDown"),
+            AppError::ReadOnly(_) => (StatusCode::SERVICE_UNAVAILABLE, "ServiceUnavailable"),
+            AppError::RequestTimeout(_) => (StatusCode::BAD_REQUEST, "RequestTimeout"),
+            AppError::InsufficientStorage(_) => {
+                (StatusCode::INSUFFICIENT_STORAGE, "InsufficientStorage")
+            }
+            AppError::Database(_)
+            | AppError::Io(_)
+            | AppError::Upstream(_)
+            | AppError::Internal => (StatusCode::INTERNAL_SERVER_ERROR, "InternalError"),
+        };
+
+        Self::new(status, code, message)
+    }
+}
+
+impl IntoResponse for S3Error {
+    fn into_response(self) -> Response {
+        let request_id = request_id::current()
+            .map(|id| format!("<RequestId>{}</RequestId>", escape(&id)))
+            .unwrap_or_default();
+        let body = format!(
+            "<Error><Code>{}</Code><Message>{}</Message>{}</Error>",
+            self.code,
+            escape(&self.message),
+            request_id
+        );
+
+        document(self.status, body)
+    }
+}
+
+pub struct ListedBucket<'a> {
+    pub name: &'a str,
+    pub created_at: DateTime<Utc>,
+}
+
+pub fn list_buckets<'a>(buckets: impl Iterator<Item = ListedBucket<'a>>) -> Response {
+    let entries: String = buckets
+        .map(|bucket| {
+            format!(
+                "<Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket>",
+                escape(bucket.name),
+                timestamp(&bucket.created_at)
+            )
+        })
+        .collect();
+
+    document(
+        StatusCode::OK,
+        format!(
+            "<ListAllMyBucketsResult xmlns=\"{}\">\
+             <Owner><ID>lila</ID><DisplayName>lila</DisplayName></Owner>\
+             <Buckets>{}</Buckets></ListAllMyBucketsResult>",
+            NAMESPACE, entries
+        ),
+    )
+}
+
+pub fn location() -> Response {
+    document(
+        StatusCode::OK,
+        format!("<LocationConstraint xmlns=\"{}\"/>", NAMESPACE),
+    )
+}
+
+pub enum ListingStyle {
+    V1 {
+        marker: Option<String>,
+        next_marker: Option<String>,
+    },
+    V2 {
+        continuation_token: Option<String>,
+        next_continuation_token: Option<String>,
+        start_after: Option<String>,
+    },
+}
+
+pub struct Listing<'a> {
+    pub bucket: &'a str,
+    pub prefix: &'a str,
+    pub delimiter: Option<&'a str>,
+    pub max_keys: usize,
+    pub truncated: bool,
+    pub contents: Vec<ObjectMetadata>,
+    pub common_prefixes: Vec<String>,
+    pub style: ListingStyle,
+}
+
+pub fn list_objects(listing: Listing<'_>) -> Response {
+    let mut body = format!(
+        "<ListBucketResult xmlns=\"{}\"><Name>{}</Name><Prefix>{}</Prefix><MaxKeys>{}</MaxKeys>",
+        NAMESPACE,
+        escape(listing.bucket),
+        escape(listing.prefix),
+        listing.max_keys
+    );
+
+    if let Some(delimiter) = listing.delimiter {
+        body.push_str(&element("Delimiter", delimiter));
+    }
+
+    body.push_str(&format!("<IsTruncated>{}</IsTruncated>", listing.truncated));
+
+    match &listing.style {
+        ListingStyle::V1 {
+            marker,
+            next_marker,
+        } => {
+            body.push_str(&element("Marker", marker.as_deref().unwrap_or("")));
+            if let Some(next) = next_marker {
+                body.push_str(&element("NextMarker", next));
+            }
+        }
+        ListingStyle::V2 {
+            continuation_token,
+            next_continuation_token,
+            start_after,
+        } => {
+            body.push_str(&format!(
+                "<KeyCount>{}</KeyCount>",
+                listing.contents.len() + listing.common_prefixes.len()
+            ));
+            if let Some(token) = continuation_token {
+                body.push_str(&element("ContinuationToken", token));
+            }
+            if let Some(token) = next_continuation_token {
+                body.push_str(&element("NextContinuationToken", token));
+            }
+            if let Some(start_after) = start_after {
+                body.push_str(&element("StartAfter", start_after));
+            }
+        }
+    }
+
+    for object in &listing.contents {
+        body.push_str(&format!(
+            "<Contents><Key>{}</Key><LastModified>{}</LastModified><ETag>{}</ETag>\
+             <Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+            escape(&object.key),
+            timestamp(&object.created_at),
+            escape(&quote_etag(&object.etag)),
+            object.size
+        ));
+    }
+
+    for prefix in &listing.common_prefixes {
+        body.push_str(&format!(
+            "<CommonPrefixes>{}</CommonPrefixes>",
+            element("Prefix", prefix)
+        ));
+    }
+
+    body.push_str("</ListBucketResult>");
+    document(StatusCode::OK, body)
+}
+
+pub fn quote_etag(etag: &str) -> String {
+    format!("\"{}\"", etag)
+}
+
+fn document(status: StatusCode, body: String) -> Response {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/xml")
+        .body(Body::from(format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{}",
+            body
+        )))
+        .unwrap()
+}
+
+fn element(name: &str, value: &str) -> String {
+    format!("<{name}>{}</{name}>", escape(value))
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
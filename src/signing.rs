@@ -0,0 +1,205 @@
+use std::{
+    collections::HashMap,
+    io,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{HeaderMap, Method},
+};
+use chrono::{DateTime, Utc};
+use futures_util::{StreamExt, stream};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AppError, Result},
+    models::ApiToken,
+    storage::MetadataStore,
+};
+
+pub const ALGORITHM: &str = "LILA-HMAC-SHA256";
+pub const DATE_HEADER: &str = "x-lila-date";
+pub const CONTENT_SHA256_HEADER: &str = "x-lila-content-sha256";
+const MAX_CLOCK_SKEW_SECONDS: i64 = 300;
+
+type HmacSha256 = Hmac<Sha256>;
+
+#[derive(Clone, Default)]
+pub struct ReplayGuard {
+    seen: Arc<Mutex<HashMap<Vec<u8>, i64>>>,
+}
+
+impl ReplayGuard {
+    fn first_use(&self, signature: &[u8], expires: i64) -> bool {
+        let now = Utc::now().timestamp();
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, expiry| *expiry >= now);
+        seen.insert(signature.to_vec(), expires).is_none()
+    }
+}
+
+#[derive(Clone)]
+pub struct BodyCheck {
+    mismatch: Arc<AtomicBool>,
+}
+
+impl BodyCheck {
+    pub fn failed(&self) -> bool {
+        self.mismatch.load(Ordering::Relaxed)
+    }
+}
+
+pub async fn verify(
+    metadata: &MetadataStore,
+    replay_guard: &ReplayGuard,
+    credentials: &str,
+    request: Request,
+) -> Result<(ApiToken, Request, BodyCheck)> {
+    let mut credential = None;
+    let mut signature = None;
+
+    for field in credentials.split(',') {
+        match field.trim().split_once('=') {
+            Some(("Credential", v)) => credential = Some(v),
+            Some(("Signature", v)) => signature = hex::decode(v).ok(),
+            _ => {}
+        }
+    }
+
+    let (Some(token_id), Some(signature)) = (credential, signature) else {
+        tracing::warn!("Signed request is missing Credential or Signature");
+        return Err(AppError::Unauthorized);
+    };
+
+    let headers = request.headers();
+
+    let date = header_value(headers, DATE_HEADER).ok_or_else(|| {
+        tracing::warn!("Signed request is missing {}", DATE_HEADER);
+        AppError::Unauthorized
+    })?;
+
+    let signed_at = DateTime::parse_from_rfc3339(date)
+        .map_err(|_| {
+            AppError::BadRequest(format!("{} must be an RFC 3339 timestamp", DATE_HEADER))
+        })?
+        .timestamp();
+
+    if (Utc::now().timestamp() - signed_at).abs() > MAX_CLOCK_SKEW_SECONDS {
+        tracing::warn!("Signed request date {} is outside the allowed window", date);
+        return Err(AppError::Unauthorized);
+    }
+
+    let content_sha256 = header_value(headers, CONTENT_SHA256_HEADER).ok_or_else(|| {
+        AppError::BadRequest(format!("signed requests require {}", CONTENT_SHA256_HEADER))
+    })?;
+
+    let expected_digest = hex::decode(content_sha256)
+        .ok()
+        .filter(|digest| digest.len() == 32)
+        .ok_or_else(|| {
+            AppError::BadRequest(format!("{} must be a hex sha256", CONTENT_SHA256_HEADER))
+        })?;
+
+    let Some((api_token, signing_key)) = metadata.find_token_by_id(token_id).await? else {
+        tracing::warn!("Signed request for unknown token {}", token_id);
+        return Err(AppError::Unauthorized);
+    };
+
+    let Some(signing_key) = signing_key else {
+        tracing::warn!(
+            "Token {} has no signing key and must be reissued to sign requests",
+            api_token.name
+        );
+        return Err(AppError::Unauthorized);
+    };
+
+    let path = request
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or("/");
+
+    let mut mac = HmacSha256::new_from_slice(signing_key.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(string_to_sign(request.method(), path, date, content_sha256).as_bytes());
+
+    if mac.verify_slice(&signature).is_err() {
+        tracing::warn!("Signature mismatch for token {}", api_token.name);
+        return Err(AppError::Unauthorized);
+    }
+
+    if !replay_guard.first_use(&signature, signed_at + MAX_CLOCK_SKEW_SECONDS) {
+        tracing::warn!("Rejected replayed request for token {}", api_token.name);
+        return Err(AppError::Unauthorized);
+    }
+
+    let (parts, body) = request.into_parts();
+    let check = BodyCheck {
+        mismatch: Arc::new(AtomicBool::new(false)),
+    };
+    let body = verify_body(body, expected_digest, check.clone());
+
+    Ok((api_token, Request::from_parts(parts, body), check))
+}
+
+pub fn derive_key(secret: &str) -> String {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(ALGORITHM.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+fn string_to_sign(method: &Method, path: &str, date: &str, content_sha256: &str) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}",
+        ALGORITHM, method, path, date, content_sha256
+    )
+}
+
+fn verify_body(body: Body, expected: Vec<u8>, check: BodyCheck) -> Body {
+    let stream = stream::unfold(
+        (body.into_data_stream(), Some(Sha256::new())),
+        move |(mut stream, mut hasher)| {
+            let expected = expected.clone();
+            let check = check.clone();
+            async move {
+                let digest = hasher.as_mut()?;
+
+                match stream.next().await {
+                    Some(Ok(chunk)) => {
+                        digest.update(&chunk);
+                        Some((Ok(chunk), (stream, hasher)))
+                    }
+                    Some(Err(e)) => Some((Err(io::Error::other(e)), (stream, None))),
+                    None => {
+                        let actual = hasher.take()?.finalize();
+                        if actual.as_slice() == expected.as_slice() {
+                            return None;
+                        }
+
+                        check.mismatch.store(true, Ordering::Relaxed);
+                        Some((
+                            Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                "request body does not match the signed content hash",
+                            )),
+                            (stream, None),
+                        ))
+                    }
+                }
+            }
+        },
+    );
+
+    Body::from_stream(stream)
+}
+
+fn header_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name).and_then(|v| v.to_str().ok())
+}
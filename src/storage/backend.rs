@@ -0,0 +1,163 @@
+use std::{path::Path, sync::Arc};
+
+use async_trait::async_trait;
+use globset::Glob;
+use tokio::sync::mpsc;
+
+use crate::{
+    error::Result,
+    models::{
+        ApiToken, Bucket, BucketPolicy, DatabaseStats, EventOp, ListCursor, ObjectEvent,
+        ObjectMetadata, RateLimit, ResumableUpload, SessionInfo, SortField, SortOrder, TrashEntry,
+        UploadPart, UploadSession,
+    },
+};
+
+pub type MetadataStore = Arc<dyn MetadataBackend>;
+
+#[derive(Default)]
+pub struct SearchFilter<'a> {
+    pub key_pattern: Option<&'a str>,
+    pub key_glob: Option<&'a Glob>,
+    pub etag: Option<&'a str>,
+    pub content_type: Option<&'a str>,
+    pub min_size: Option<i64>,
+    pub max_size: Option<i64>,
+    pub accessed_before: Option<chrono::DateTime<chrono::Utc>>,
+    pub accessed_after: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+pub struct Access {
+    pub key: String,
+    pub id: String,
+    pub at: chrono::DateTime<chrono::Utc>,
+    pub count: i64,
+}
+
+#[async_trait]
+pub trait MetadataBackend: Send + Sync {
+    async fn ping(&self) -> Result<()>;
+    async fn backup_to(&self, path: &Path) -> Result<()>;
+    async fn database_stats(&self) -> Result<DatabaseStats>;
+    async fn optimize(&self, vacuum_pages: u32, analyze: bool) -> Result<()>;
+    async fn close(&self);
+    fn scoped(&self, bucket: &str) -> MetadataStore;
+
+    async fn create_token(
+        &self,
+        token: &ApiToken,
+        token_hash: &str,
+        signing_key: &str,
+    ) -> Result<()>;
+    async fn find_token(&self, token_hash: &str) -> Result<Option<ApiToken>>;
+    async fn find_token_by_id(&self, id: &str) -> Result<Option<(ApiToken, Option<String>)>>;
+    async fn list_tokens(&self) -> Result<Vec<ApiToken>>;
+    async fn set_token_rate_limit(&self, id: &str, rate_limit: Option<RateLimit>) -> Result<bool>;
+    async fn count_tokens(&self) -> Result<i64>;
+    async fn delete_token(&self, id: &str) -> Result<bool>;
+
+    async fn create_session(
+        &self,
+        session_hash: &str,
+        subject: &str,
+        principal: &ApiToken,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()>;
+    async fn find_session(&self, session_hash: &str) -> Result<Option<SessionInfo>>;
+    async fn delete_session(&self, session_hash: &str) -> Result<bool>;
+
+    async fn create_bucket(&self, bucket: &Bucket) -> Result<bool>;
+    async fn get_bucket(&self, name: &str) -> Result<Option<Bucket>>;
+    async fn list_buckets(&self) -> Result<Vec<Bucket>>;
+    async fn set_bucket_policy(&self, name: &str, policy: &BucketPolicy) -> Result<bool>;
+    async fn delete_bucket(&self, name: &str) -> Result<bool>;
+
+    async fn insert(&self, metadata: &ObjectMetadata) -> Result<()>;
+    async fn insert_if(&self, metadata: &ObjectMetadata, generation: i64) -> Result<bool>;
+    async fn next_generation(&self, key: &str) -> Result<i64>;
+    async fn get(&self, key: &str) -> Result<Option<ObjectMetadata>>;
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<ObjectMetadata>>;
+    async fn list(
+        &self,
+        prefix: Option<&str>,
+        after: Option<&ListCursor>,
+        sort: SortField,
+        order: SortOrder,
+        limit: Option<i64>,
+    ) -> Result<Vec<ObjectMetadata>>;
+    fn stream(
+        &self,
+        prefix: Option<String>,
+        sort: SortField,
+        order: SortOrder,
+    ) -> mpsc::Receiver<Result<ObjectMetadata>>;
+    async fn search(
+        &self,
+        filter: &SearchFilter<'_>,
+        sort: SortField,
+        order: SortOrder,
+        limit: Option<i64>,
+    ) -> Result<Vec<ObjectMetadata>>;
+    async fn list_expired(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<ObjectMetadata>>;
+    async fn first_retained(
+        &self,
+        prefix: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<ObjectMetadata>>;
+    async fn set_public(&self, key: &str, public: bool) -> Result<bool>;
+
+    async fn insert_version(&self, metadata: &ObjectMetadata) -> Result<()>;
+    async fn get_version(&self, key: &str, version_id: &str) -> Result<Option<ObjectMetadata>>;
+    async fn list_versions(&self, key: &str) -> Result<Vec<ObjectMetadata>>;
+    async fn delete_version(&self, key: &str, version_id: &str) -> Result<bool>;
+
+    async fn insert_trash(&self, metadata: &ObjectMetadata) -> Result<()>;
+    async fn get_trash(&self, key: &str) -> Result<Option<TrashEntry>>;
+    async fn list_trash(&self, limit: Option<i64>) -> Result<Vec<TrashEntry>>;
+    async fn list_trash_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<TrashEntry>>;
+    async fn delete_trash(&self, id: &str) -> Result<bool>;
+
+    async fn record_access(&self, accesses: &[Access]) -> Result<()>;
+    async fn delete(&self, key: &str) -> Result<bool>;
+    async fn list_keys(&self) -> Result<Vec<String>>;
+    async fn sample(&self, limit: i64) -> Result<Vec<ObjectMetadata>>;
+    async fn inventory(&self) -> Result<Vec<(String, String, String)>>;
+    async fn delete_by_prefix(&self, prefix: &str) -> Result<i64>;
+
+    async fn record_change(&self, op: EventOp, metadata: &ObjectMetadata) -> Result<ObjectEvent>;
+    async fn list_changes(&self, since: u64, limit: i64) -> Result<Vec<ObjectEvent>>;
+    async fn latest_change(&self) -> Result<u64>;
+    async fn prune_changes(&self, cutoff: chrono::DateTime<chrono::Utc>) -> Result<u64>;
+    async fn get_checkpoint(&self, name: &str) -> Result<Option<u64>>;
+    async fn set_checkpoint(&self, name: &str, seq: u64) -> Result<()>;
+
+    async fn get_stats(&self) -> Result<(i64, i64)>;
+    async fn get_total_size(&self) -> Result<i64>;
+    async fn get_total_stats(&self) -> Result<(i64, i64)>;
+    async fn get_prefix_stats(&self, prefix: &str) -> Result<(i64, i64, Option<ObjectMetadata>)>;
+
+    async fn create_upload(&self, upload: &UploadSession) -> Result<()>;
+    async fn get_upload(&self, id: &str) -> Result<Option<UploadSession>>;
+    async fn put_part(&self, upload_id: &str, part: &UploadPart) -> Result<()>;
+    async fn list_parts(&self, upload_id: &str) -> Result<Vec<UploadPart>>;
+    async fn delete_upload(&self, id: &str) -> Result<bool>;
+
+    async fn create_resumable(&self, upload: &ResumableUpload) -> Result<()>;
+    async fn get_resumable(&self, id: &str) -> Result<Option<ResumableUpload>>;
+    async fn update_resumable_offset(&self, id: &str, offset: i64) -> Result<()>;
+    async fn delete_resumable(&self, id: &str) -> Result<bool>;
+
+    async fn acquire_blob(&self, volume: usize, digest: &str, size: i64, inode: u64)
+    -> Result<i64>;
+    async fn find_blob(&self, volume: usize, inode: u64) -> Result<Option<String>>;
+    async fn record_blob_inode(&self, volume: usize, digest: &str, inode: u64) -> Result<()>;
+    async fn release_blob(&self, volume: usize, digest: &str) -> Result<i64>;
+    async fn forget_blob(&self, volume: usize, digest: &str) -> Result<()>;
+}
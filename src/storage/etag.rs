@@ -0,0 +1,90 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use utoipa::ToSchema;
+use xxhash_rust::xxh3::Xxh3;
+
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    ToSchema,
+    async_graphql::Enum,
+)]
+#[serde(rename_all = "lowercase")]
+#[graphql(rename_items = "UPPERCASE")]
+pub enum EtagAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+    Xxh3,
+}
+
+impl EtagAlgorithm {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EtagAlgorithm::Sha256 => "sha256",
+            EtagAlgorithm::Blake3 => "blake3",
+            EtagAlgorithm::Xxh3 => "xxh3",
+        }
+    }
+
+    pub fn hasher(self) -> EtagHasher {
+        match self {
+            EtagAlgorithm::Sha256 => EtagHasher::Sha256(Sha256::new()),
+            EtagAlgorithm::Blake3 => EtagHasher::Blake3(Box::new(blake3::Hasher::new())),
+            EtagAlgorithm::Xxh3 => EtagHasher::Xxh3(Box::new(Xxh3::new())),
+        }
+    }
+}
+
+impl fmt::Display for EtagAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EtagAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(EtagAlgorithm::Sha256),
+            "blake3" => Ok(EtagAlgorithm::Blake3),
+            "xxh3" => Ok(EtagAlgorithm::Xxh3),
+            other => Err(format!("unknown etag algorithm: {}", other)),
+        }
+    }
+}
+
+pub enum EtagHasher {
+    Sha256(Sha256),
+    Blake3(Box<blake3::Hasher>),
+    Xxh3(Box<Xxh3>),
+}
+
+impl EtagHasher {
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            EtagHasher::Sha256(hasher) => hasher.update(data),
+            EtagHasher::Blake3(hasher) => {
+                hasher.update(data);
+            }
+            EtagHasher::Xxh3(hasher) => hasher.update(data),
+        }
+    }
+
+    pub fn finalize(self) -> String {
+        match self {
+            EtagHasher::Sha256(hasher) => hex::encode(hasher.finalize()),
+            EtagHasher::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+            EtagHasher::Xxh3(hasher) => format!("{:032x}", hasher.digest128()),
+        }
+    }
+}
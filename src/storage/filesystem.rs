@@ -1,60 +1,607 @@
-use std::path::PathBuf;
+use std::{
+    os::unix::fs::{FileExt, MetadataExt},
+    path::{Path, PathBuf},
+    pin::Pin,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::SystemTime,
+};
 
+use async_compression::{
+    Level,
+    tokio::{bufread::ZstdDecoder, write::ZstdEncoder},
+};
 use axum::body::Bytes;
-use futures_util::Stream;
+use futures_util::{Stream, StreamExt, TryStreamExt};
+use md5::Md5;
 use sha2::{Digest, Sha256};
-use tokio::{fs, io::AsyncWriteExt};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader, BufWriter},
+};
+use tokio_util::io::{ReaderStream, StreamReader};
+use uuid::Uuid;
 
-use crate::error::{AppError, Result};
+use crate::{
+    compression::AtRestPolicy,
+    error::{AppError, Result},
+    models::{Chunk, ChunkingConfig, DiskUsage, Durability, ObjectMetadata, VolumeUsage},
+    storage::{EtagAlgorithm, Journal, MetadataStore, Upstream},
+};
+
+pub type BlobReader = Box<dyn AsyncRead + Unpin + Send>;
+pub type BlobStream = Pin<Box<dyn Stream<Item = std::io::Result<Bytes>> + Send>>;
+
+const DEFAULT_BUFFER_SIZE: usize = 256 * 1024;
+const ASSEMBLY_CONCURRENCY: usize = 4;
+const SNIFF_BYTES: u64 = 8192;
+
+#[derive(Default)]
+pub struct Layout {
+    pub stored_size: Option<i64>,
+    pub chunks: Option<Vec<Chunk>>,
+}
+
+pub struct Staged {
+    key: String,
+    path: PathBuf,
+    destination: PathBuf,
+    intent: PathBuf,
+    pub etag: String,
+    pub size: i64,
+}
+
+#[derive(Default)]
+pub struct ExpectedChecksums {
+    pub sha256: Option<Vec<u8>>,
+    pub md5: Option<Vec<u8>>,
+}
+
+struct Volume {
+    root: PathBuf,
+    full: AtomicBool,
+    cold: bool,
+}
 
 #[derive(Clone)]
 pub struct FileStorage {
     pub base_path: PathBuf,
+    volumes: Arc<[Volume]>,
+    scope_path: PathBuf,
+    etag_algorithm: EtagAlgorithm,
+    reserve_bytes: u64,
+    upstream: Option<Arc<Upstream>>,
+    compression: Option<AtRestPolicy>,
+    chunking: Option<ChunkingConfig>,
+    refs: Option<MetadataStore>,
+    durability: Durability,
+    journal: Journal,
+    read_buffer: usize,
+    write_buffer: usize,
+    scope: String,
 }
 
 impl FileStorage {
-    pub async fn new(base_path: &str) -> Result<Self> {
-        let path = PathBuf::from(base_path);
-        fs::create_dir_all(&path).await?;
-        Ok(Self { base_path: path })
+    pub async fn new(
+        paths: &[String],
+        etag_algorithm: EtagAlgorithm,
+        reserve_bytes: u64,
+    ) -> Result<Self> {
+        if paths.is_empty() {
+            return Err(AppError::BadRequest(
+                "storage_path must name at least one volume".to_string(),
+            ));
+        }
+
+        let mut volumes = Vec::with_capacity(paths.len());
+        for path in paths {
+            let root = PathBuf::from(path);
+            fs::create_dir_all(&root).await?;
+            volumes.push(Volume {
+                root,
+                full: AtomicBool::new(false),
+                cold: false,
+            });
+        }
+
+        Ok(Self {
+            base_path: volumes[0].root.clone(),
+            journal: Journal::new(&volumes[0].root),
+            volumes: volumes.into(),
+            scope_path: PathBuf::new(),
+            etag_algorithm,
+            reserve_bytes,
+            upstream: None,
+            compression: None,
+            chunking: None,
+            refs: None,
+            durability: Durability::default(),
+            read_buffer: DEFAULT_BUFFER_SIZE,
+            write_buffer: DEFAULT_BUFFER_SIZE,
+            scope: String::new(),
+        })
+    }
+
+    pub fn with_upstream(mut self, upstream: Upstream) -> Self {
+        self.upstream = Some(Arc::new(upstream));
+        self
+    }
+
+    pub fn with_compression(mut self, compression: AtRestPolicy) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn with_chunking(mut self, chunking: ChunkingConfig) -> Self {
+        self.chunking = Some(chunking);
+        self
+    }
+
+    pub fn with_dedup(mut self, refs: MetadataStore) -> Self {
+        self.refs = Some(refs);
+        self
+    }
+
+    pub fn with_durability(mut self, durability: Durability) -> Self {
+        self.durability = durability;
+        self.journal = self.journal.with_sync(durability == Durability::Fsync);
+        self
+    }
+
+    pub fn with_buffer_sizes(mut self, read_buffer: usize, write_buffer: usize) -> Self {
+        self.read_buffer = read_buffer.max(4096);
+        self.write_buffer = write_buffer.max(4096);
+        self
+    }
+
+    pub async fn with_cold_tier(mut self, path: &str) -> Result<Self> {
+        let root = PathBuf::from(path);
+        fs::create_dir_all(&root).await?;
+
+        let mut volumes: Vec<Volume> = self
+            .volumes
+            .iter()
+            .map(|volume| Volume {
+                root: volume.root.clone(),
+                full: AtomicBool::new(volume.full.load(Ordering::Relaxed)),
+                cold: volume.cold,
+            })
+            .collect();
+        volumes.push(Volume {
+            root,
+            full: AtomicBool::new(false),
+            cold: true,
+        });
+
+        self.volumes = volumes.into();
+        Ok(self)
+    }
+
+    pub async fn recover(&self) -> Result<usize> {
+        self.journal.recover().await
+    }
+
+    pub fn etag_algorithm(&self) -> EtagAlgorithm {
+        self.etag_algorithm
+    }
+
+    pub fn scoped(&self, bucket: &str) -> Self {
+        Self {
+            base_path: self.bucket_path(bucket),
+            volumes: self.volumes.clone(),
+            scope_path: self.scope_path.join(".buckets").join(bucket),
+            etag_algorithm: self.etag_algorithm,
+            reserve_bytes: self.reserve_bytes,
+            upstream: self.upstream.clone(),
+            compression: self.compression.clone(),
+            chunking: self.chunking.clone(),
+            refs: self.refs.clone(),
+            durability: self.durability,
+            journal: self.journal.clone(),
+            read_buffer: self.read_buffer,
+            write_buffer: self.write_buffer,
+            scope: format!("{}{}/", self.scope, bucket),
+        }
+    }
+
+    pub async fn create_bucket(&self, bucket: &str) -> Result<()> {
+        for path in self.volume_paths() {
+            fs::create_dir_all(path.join(".buckets").join(bucket)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn remove_bucket(&self, bucket: &str) -> Result<()> {
+        for path in self.volume_paths() {
+            match fs::remove_dir_all(path.join(".buckets").join(bucket)).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    fn bucket_path(&self, bucket: &str) -> PathBuf {
+        self.base_path.join(".buckets").join(bucket)
+    }
+
+    fn volume_path(&self, volume: usize) -> PathBuf {
+        self.volumes[volume].root.join(&self.scope_path)
+    }
+
+    pub fn volume_paths(&self) -> Vec<PathBuf> {
+        (0..self.volumes.len())
+            .map(|volume| self.volume_path(volume))
+            .collect()
+    }
+
+    fn volume_of(&self, path: &Path) -> usize {
+        self.volumes
+            .iter()
+            .position(|volume| path.starts_with(&volume.root))
+            .unwrap_or(0)
+    }
+
+    pub async fn ensure_space(&self, incoming: u64) -> Result<()> {
+        let required = incoming.saturating_add(self.reserve_bytes);
+        let mut available = 0;
+
+        for volume in self.volumes.iter().filter(|volume| !volume.cold) {
+            let root = volume.root.clone();
+            let free = tokio::task::spawn_blocking(move || fs4::available_space(root))
+                .await
+                .map_err(|_| AppError::Internal)??;
+
+            volume.full.store(free < required, Ordering::Relaxed);
+            available = available.max(free);
+        }
+
+        if available < required {
+            tracing::warn!(
+                "Rejecting write of {} bytes: {} bytes free, {} bytes reserved",
+                incoming,
+                available,
+                self.reserve_bytes
+            );
+            return Err(AppError::InsufficientStorage(format!(
+                "not enough free disk space ({} bytes available, {} bytes required)",
+                available, required
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub async fn probe(&self) -> Result<()> {
+        for volume in self.volumes.iter() {
+            let path = volume.root.join(format!(".probe-{}", uuid::Uuid::new_v4()));
+
+            fs::write(&path, b"lila").await?;
+            let contents = fs::read(&path).await;
+            fs::remove_file(&path).await?;
+
+            if contents? != b"lila" {
+                return Err(AppError::Io(std::io::Error::other(format!(
+                    "probe file contents did not round-trip on {}",
+                    volume.root.display()
+                ))));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn disk_usage(&self) -> Result<DiskUsage> {
+        let mut volumes = Vec::with_capacity(self.volumes.len());
+
+        for volume in self.volumes.iter() {
+            let root = volume.root.clone();
+            let (available, total) = tokio::task::spawn_blocking(move || {
+                Ok::<_, std::io::Error>((fs4::available_space(&root)?, fs4::total_space(&root)?))
+            })
+            .await
+            .map_err(|_| AppError::Internal)??;
+
+            volume
+                .full
+                .store(available < self.reserve_bytes, Ordering::Relaxed);
+            volumes.push(VolumeUsage {
+                path: volume.root.display().to_string(),
+                available_bytes: available,
+                total_bytes: total,
+                cold: volume.cold,
+            });
+        }
+
+        Ok(DiskUsage {
+            available_bytes: volumes.iter().map(|volume| volume.available_bytes).sum(),
+            total_bytes: volumes.iter().map(|volume| volume.total_bytes).sum(),
+            reserve_bytes: self.reserve_bytes,
+            volumes,
+        })
+    }
+
+    pub async fn get_object_path(&self, key: &str) -> PathBuf {
+        let hash = key_hash(key);
+        self.locate(&hash, Path::new(&hash[..2]).join(&hash)).await
+    }
+
+    async fn get_version_path(&self, key: &str, version_id: &str) -> PathBuf {
+        let hash = key_hash(key);
+        self.locate(&hash, Path::new(".versions").join(&hash).join(version_id))
+            .await
+    }
+
+    async fn get_trash_path(&self, key: &str, id: &str) -> PathBuf {
+        let hash = key_hash(key);
+        self.locate(&hash, Path::new(".trash").join(&hash).join(id))
+            .await
+    }
+
+    async fn locate(&self, hash: &str, relative: PathBuf) -> PathBuf {
+        if self.volumes.len() == 1 {
+            return self.volume_path(0).join(relative);
+        }
+
+        let order = self.placement(hash);
+        let blob = Path::new(&hash[..2]).join(hash);
+        for candidate in [&relative, &blob] {
+            for volume in &order {
+                if fs::try_exists(self.volume_path(*volume).join(candidate))
+                    .await
+                    .unwrap_or(false)
+                {
+                    return self.volume_path(*volume).join(&relative);
+                }
+            }
+        }
+
+        self.place(&order, relative)
+    }
+
+    fn placement(&self, hash: &str) -> Vec<usize> {
+        let mut order: Vec<(u64, usize)> = self
+            .volumes
+            .iter()
+            .enumerate()
+            .filter(|(_, volume)| !volume.cold)
+            .map(|(index, volume)| {
+                let mut hasher = Sha256::new();
+                hasher.update(hash.as_bytes());
+                hasher.update(volume.root.as_os_str().as_encoded_bytes());
+                let digest = hasher.finalize();
+                (u64::from_be_bytes(digest[..8].try_into().unwrap()), index)
+            })
+            .collect();
+        order.sort_unstable_by(|a, b| b.cmp(a));
+
+        order
+            .into_iter()
+            .map(|(_, volume)| volume)
+            .chain(self.cold_volume())
+            .collect()
+    }
+
+    fn place(&self, order: &[usize], relative: PathBuf) -> PathBuf {
+        let volume = order
+            .iter()
+            .copied()
+            .find(|volume| {
+                let volume = &self.volumes[*volume];
+                !volume.cold && !volume.full.load(Ordering::Relaxed)
+            })
+            .unwrap_or(order[0]);
+        self.volume_path(volume).join(relative)
+    }
+
+    fn cold_volume(&self) -> Option<usize> {
+        self.volumes.iter().position(|volume| volume.cold)
+    }
+
+    fn is_cold(&self, path: &Path) -> bool {
+        self.volumes[self.volume_of(path)].cold
+    }
+
+    async fn hot_path(&self, key: &str) -> PathBuf {
+        let path = self.get_object_path(key).await;
+        if !self.is_cold(&path) {
+            return path;
+        }
+
+        let hash = key_hash(key);
+        self.place(&self.placement(&hash), Path::new(&hash[..2]).join(&hash))
+    }
+
+    async fn writable_path(&self, key: &str) -> Result<PathBuf> {
+        let path = self.get_object_path(key).await;
+        if self.is_cold(&path) {
+            self.remove_blob(&path).await?;
+        }
+
+        Ok(self.hot_path(key).await)
+    }
+
+    pub async fn list_blobs(&self) -> Result<Vec<PathBuf>> {
+        let mut blobs = Vec::new();
+
+        for path in self.volume_paths() {
+            list_volume_blobs(&path, &mut blobs).await?;
+        }
+
+        blobs.sort();
+        Ok(blobs)
+    }
+
+    pub async fn blob_size(&self, key: &str) -> Result<Option<u64>> {
+        self.ensure_local(key).await?;
+
+        let path = self.get_object_path(key).await;
+
+        match fs::metadata(&path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                let mut size = 0;
+                let mut chunks = fs::read_dir(&path).await?;
+                while let Some(chunk) = chunks.next_entry().await? {
+                    size += chunk.metadata().await?.len();
+                }
+                Ok(Some(size))
+            }
+            Ok(metadata) => Ok(Some(metadata.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub async fn compute_etag(&self, object: &ObjectMetadata) -> Result<String> {
+        let mut file = self.reader(object, 0).await?;
+        let mut hasher = object.etag_algorithm.hasher();
+        let mut buffer = vec![0u8; 64 * 1024];
+
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+
+        Ok(hasher.finalize())
     }
 
-    fn get_object_path(&self, key: &str) -> PathBuf {
-        let mut hasher = Sha256::new();
-        hasher.update(key.as_bytes());
-        let hash = hex::encode(hasher.finalize());
+    pub async fn remove_blob(&self, path: &Path) -> Result<()> {
+        if path.starts_with(self.shared_path(self.volume_of(path))) {
+            return self.remove_shared(path).await;
+        }
+
+        if self.detach(path).await? {
+            return Ok(());
+        }
+
+        match fs::remove_file(path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
 
-        let subdir = &hash[..2];
-        self.base_path.join(subdir).join(&hash)
+    pub async fn quarantine(&self, path: &Path) -> Result<PathBuf> {
+        let lost_found = self.volume_path(self.volume_of(path)).join(".lost+found");
+        let destination = lost_found.join(
+            path.file_name()
+                .ok_or_else(|| AppError::BadRequest(format!("not a blob: {}", path.display())))?,
+        );
+        fs::create_dir_all(&lost_found).await?;
+        fs::rename(path, &destination).await?;
+        Ok(destination)
     }
 
-    pub fn get_object_path_string(&self, key: &str) -> String {
-        self.get_object_path(key).display().to_string()
+    pub async fn get_object_path_string(&self, key: &str) -> String {
+        self.get_object_path(key).await.display().to_string()
     }
 
     #[allow(dead_code)]
     pub async fn write(&self, key: &str, data: Vec<u8>) -> Result<String> {
-        let path = self.get_object_path(key);
+        let path = self.writable_path(key).await?;
+        let partial = temp_path(&path);
 
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
 
-        let mut file = fs::File::create(&path).await?;
+        let intent = self.journal.begin(key, &partial).await?;
+        let mut file = fs::File::create(&partial).await?;
         file.write_all(&data).await?;
+        self.sync_file(&mut file).await?;
+        self.replace(&partial, &path).await?;
+        self.journal.finish(&intent).await?;
+        self.publish(key).await?;
 
-        let mut hasher = Sha256::new();
+        let mut hasher = self.etag_algorithm.hasher();
         hasher.update(&data);
-        let etag = hex::encode(hasher.finalize());
 
-        Ok(etag)
+        Ok(hasher.finalize())
     }
 
-    pub async fn write_stream<S, E>(
+    #[tracing::instrument(name = "storage.stage", skip_all, fields(key = %key, size = tracing::field::Empty), err(level = "debug"))]
+    pub async fn stage_stream<S, E>(
         &self,
         key: &str,
+        stream: S,
+        max_size: usize,
+        expected: &ExpectedChecksums,
+    ) -> Result<Staged>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let destination = self.hot_path(key).await;
+        let path = temp_path(&destination);
+        let intent = self.journal.begin(key, &path).await?;
+
+        let (etag, size) = match self.stream_to_path(&path, stream, max_size, expected).await {
+            Ok(written) => written,
+            Err(e) => {
+                fs::remove_file(&path).await.ok();
+                self.journal.finish(&intent).await.ok();
+                return Err(e);
+            }
+        };
+
+        tracing::Span::current().record("size", size);
+        Ok(Staged {
+            key: key.to_string(),
+            path,
+            destination,
+            intent,
+            etag,
+            size,
+        })
+    }
+
+    pub async fn sniff(&self, staged: &Staged) -> Option<&'static str> {
+        let file = fs::File::open(&staged.path).await.ok()?;
+        let mut head = Vec::with_capacity(SNIFF_BYTES as usize);
+        file.take(SNIFF_BYTES).read_to_end(&mut head).await.ok()?;
+
+        infer::get(&head).map(|kind| kind.mime_type())
+    }
+
+    pub async fn commit(&self, staged: Staged) -> Result<()> {
+        let previous = self.get_object_path(&staged.key).await;
+
+        if let Err(e) = self.replace(&staged.path, &staged.destination).await {
+            self.discard(staged).await;
+            return Err(e);
+        }
+
+        if previous != staged.destination {
+            self.remove_blob(&previous).await?;
+        }
+
+        self.journal.finish(&staged.intent).await
+    }
+
+    pub async fn discard(&self, staged: Staged) {
+        let result = async {
+            self.remove_blob(&staged.path).await?;
+            self.journal.finish(&staged.intent).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to discard staged blob of {}: {}", staged.key, e);
+        }
+    }
+
+    async fn stream_to_path<S, E>(
+        &self,
+        path: &Path,
         mut stream: S,
         max_size: usize,
+        expected: &ExpectedChecksums,
     ) -> Result<(String, i64)>
     where
         S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
@@ -62,75 +609,1532 @@ impl FileStorage {
     {
         use futures_util::StreamExt;
 
-        let path = self.get_object_path(key);
-
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
 
-        let mut file = fs::File::create(&path).await?;
-        let mut hasher = Sha256::new();
+        let mut file = BufWriter::with_capacity(self.write_buffer, fs::File::create(path).await?);
+        let mut hasher = self.etag_algorithm.hasher();
+        let mut sha256 = expected.sha256.is_some().then(Sha256::new);
+        let mut md5 = expected.md5.is_some().then(Md5::new);
         let mut total_size: usize = 0;
 
         while let Some(chunk) = stream.next().await {
-            let chunk = chunk.map_err(|e| {
-                AppError::Io(std::io::Error::new(
-                    std::io::ErrorKind::Other,
-                    e.to_string(),
-                ))
-            })?;
+            let chunk = chunk.map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
 
             if total_size + chunk.len() > max_size {
                 drop(file);
-                let _ = fs::remove_file(&path).await;
+                let _ = fs::remove_file(path).await;
                 return Err(AppError::PayloadTooLarge(max_size));
             }
 
             file.write_all(&chunk).await?;
             hasher.update(&chunk);
+            if let Some(sha256) = sha256.as_mut() {
+                sha256.update(&chunk);
+            }
+            if let Some(md5) = md5.as_mut() {
+                md5.update(&chunk);
+            }
             total_size += chunk.len();
         }
 
         file.flush().await?;
-        let etag = hex::encode(hasher.finalize());
+        self.sync_file(file.get_mut()).await?;
+        let sha256 = sha256.map(|sha256| sha256.finalize());
+        let md5 = md5.map(|md5| md5.finalize());
+
+        let mismatch = if expected
+            .sha256
+            .as_ref()
+            .zip(sha256.as_ref())
+            .is_some_and(|(expected, actual)| expected.as_slice() != actual.as_slice())
+        {
+            Some("sha256")
+        } else if expected
+            .md5
+            .as_ref()
+            .zip(md5.as_ref())
+            .is_some_and(|(expected, actual)| expected.as_slice() != actual.as_slice())
+        {
+            Some("md5")
+        } else {
+            None
+        };
+
+        if let Some(algorithm) = mismatch {
+            drop(file);
+            let _ = fs::remove_file(path).await;
+            return Err(AppError::ChecksumMismatch(algorithm.to_string()));
+        }
 
-        Ok((etag, total_size as i64))
+        Ok((hasher.finalize(), total_size as i64))
     }
 
-    pub async fn open(&self, key: &str) -> Result<fs::File> {
-        let path = self.get_object_path(key);
+    #[tracing::instrument(name = "storage.append", skip_all, fields(key = %key, size = tracing::field::Empty), err(level = "debug"))]
+    pub async fn stage_append<S, E>(
+        &self,
+        key: &str,
+        existing: Option<&ObjectMetadata>,
+        stream: S,
+        max_size: usize,
+    ) -> Result<Staged>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let destination = self.hot_path(key).await;
+        let path = temp_path(&destination);
+        let intent = self.journal.begin(key, &path).await?;
 
-        match fs::File::open(&path).await {
-            Ok(file) => Ok(file),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Err(AppError::NotFound(key.to_string()))
+        let (etag, size) = match self.append_to_path(&path, existing, stream, max_size).await {
+            Ok(written) => written,
+            Err(e) => {
+                fs::remove_file(&path).await.ok();
+                self.journal.finish(&intent).await.ok();
+                return Err(e);
             }
-            Err(e) => Err(AppError::Io(e)),
-        }
+        };
+
+        tracing::Span::current().record("size", size);
+        Ok(Staged {
+            key: key.to_string(),
+            path,
+            destination,
+            intent,
+            etag,
+            size,
+        })
     }
 
-    #[allow(dead_code)]
-    pub async fn read(&self, key: &str) -> Result<Vec<u8>> {
-        let path = self.get_object_path(key);
+    async fn append_to_path<S, E>(
+        &self,
+        path: &Path,
+        existing: Option<&ObjectMetadata>,
+        mut stream: S,
+        max_size: usize,
+    ) -> Result<(String, i64)>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        use futures_util::StreamExt;
 
-        match fs::read(&path).await {
-            Ok(data) => Ok(data),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Err(AppError::NotFound(key.to_string()))
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let mut file = BufWriter::with_capacity(self.write_buffer, fs::File::create(path).await?);
+        let mut hasher = self.etag_algorithm.hasher();
+        let mut existing_size: u64 = 0;
+
+        if let Some(object) = existing {
+            let mut source = self.reader(object, 0).await?;
+            let mut buffer = vec![0u8; self.read_buffer];
+
+            loop {
+                let read = source.read(&mut buffer).await?;
+                if read == 0 {
+                    break;
+                }
+                file.write_all(&buffer[..read]).await?;
+                hasher.update(&buffer[..read]);
+                existing_size += read as u64;
             }
-            Err(e) => Err(AppError::Io(e)),
         }
+
+        let mut appended: usize = 0;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| AppError::Io(std::io::Error::other(e.to_string())))?;
+
+            if appended + chunk.len() > max_size {
+                return Err(AppError::PayloadTooLarge(max_size));
+            }
+
+            file.write_all(&chunk).await?;
+            hasher.update(&chunk);
+            appended += chunk.len();
+        }
+
+        file.flush().await?;
+        self.sync_file(file.get_mut()).await?;
+
+        Ok((hasher.finalize(), existing_size as i64 + appended as i64))
     }
 
-    pub async fn delete(&self, key: &str) -> Result<()> {
-        let path = self.get_object_path(key);
+    fn get_upload_dir(&self, upload_id: &str) -> PathBuf {
+        self.base_path.join(".uploads").join(upload_id)
+    }
 
-        match fs::remove_file(&path).await {
-            Ok(_) => Ok(()),
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Err(AppError::NotFound(key.to_string()))
+    pub async fn write_part<S, E>(
+        &self,
+        upload_id: &str,
+        part_number: i64,
+        stream: S,
+        max_size: usize,
+    ) -> Result<(String, i64)>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let path = self.get_upload_dir(upload_id).join(part_number.to_string());
+        self.stream_to_path(&path, stream, max_size, &ExpectedChecksums::default())
+            .await
+    }
+
+    #[tracing::instrument(name = "storage.assemble", skip_all, fields(key = %key, upload_id = %upload_id, size = tracing::field::Empty), err(level = "debug"))]
+    pub async fn stage_parts(
+        &self,
+        upload_id: &str,
+        key: &str,
+        part_numbers: &[i64],
+    ) -> Result<Staged> {
+        let upload_dir = self.get_upload_dir(upload_id);
+        let destination = self.hot_path(key).await;
+        let partial = temp_path(&destination);
+
+        if let Some(parent) = partial.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let intent = self.journal.begin(key, &partial).await?;
+        let result = async {
+            let parts: Vec<PathBuf> = part_numbers
+                .iter()
+                .map(|part_number| upload_dir.join(part_number.to_string()))
+                .collect();
+
+            let mut offsets = Vec::with_capacity(parts.len());
+            let mut total_size: u64 = 0;
+            for part in &parts {
+                offsets.push(total_size);
+                total_size += fs::metadata(part).await?.len();
             }
-            Err(e) => Err(AppError::Io(e)),
+
+            let mut file = fs::File::create(&partial).await?;
+            file.set_len(total_size).await?;
+
+            let hashing = hash_files(parts.clone(), self.etag_algorithm, self.read_buffer);
+            let copying = futures_util::stream::iter(parts.into_iter().zip(offsets))
+                .map(|(part, offset)| {
+                    let partial = partial.clone();
+                    async move {
+                        tokio::task::spawn_blocking(move || copy_part(&part, &partial, offset))
+                            .await
+                            .map_err(std::io::Error::other)?
+                    }
+                })
+                .buffer_unordered(ASSEMBLY_CONCURRENCY)
+                .try_collect::<()>();
+
+            let (etag, ()) = tokio::try_join!(hashing, copying)?;
+
+            self.sync_file(&mut file).await?;
+            Ok::<_, AppError>((etag, total_size as i64))
+        }
+        .await;
+
+        let (etag, size) = match result {
+            Ok(assembled) => assembled,
+            Err(e) => {
+                fs::remove_file(&partial).await.ok();
+                self.journal.finish(&intent).await.ok();
+                return Err(e);
+            }
+        };
+
+        tracing::Span::current().record("size", size);
+        Ok(Staged {
+            key: key.to_string(),
+            path: partial,
+            destination,
+            intent,
+            etag,
+            size,
+        })
+    }
+
+    fn get_resumable_path(&self, upload_id: &str) -> PathBuf {
+        self.get_upload_dir(upload_id).join("data")
+    }
+
+    pub async fn create_resumable(&self, upload_id: &str) -> Result<()> {
+        let path = self.get_resumable_path(upload_id);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
         }
+
+        fs::File::create(&path).await?;
+        Ok(())
+    }
+
+    pub async fn append_resumable<S, E>(
+        &self,
+        upload_id: &str,
+        offset: i64,
+        mut stream: S,
+        max_size: usize,
+    ) -> Result<i64>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        use futures_util::StreamExt;
+
+        let path = self.get_resumable_path(upload_id);
+        let mut file = fs::OpenOptions::new().write(true).open(&path).await?;
+
+        file.set_len(offset as u64).await?;
+        file.seek(std::io::SeekFrom::Start(offset as u64)).await?;
+
+        let mut file = BufWriter::with_capacity(self.write_buffer, file);
+        let mut written: usize = 0;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    tracing::warn!("Resumable upload {} interrupted: {}", upload_id, e);
+                    break;
+                }
+            };
+
+            if written + chunk.len() > max_size {
+                file.flush().await?;
+                return Err(AppError::PayloadTooLarge(max_size));
+            }
+
+            file.write_all(&chunk).await?;
+            written += chunk.len();
+        }
+
+        file.flush().await?;
+        self.sync_file(file.get_mut()).await?;
+
+        Ok(offset + written as i64)
+    }
+
+    #[tracing::instrument(name = "storage.stage_resumable", skip_all, fields(key = %key, upload_id = %upload_id, size = tracing::field::Empty), err(level = "debug"))]
+    pub async fn stage_resumable(&self, upload_id: &str, key: &str) -> Result<Staged> {
+        let source = self.get_resumable_path(upload_id);
+
+        let mut file = fs::File::open(&source).await?;
+        let mut hasher = self.etag_algorithm.hasher();
+        let mut total_size: i64 = 0;
+        let mut buffer = vec![0u8; 64 * 1024];
+
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            total_size += read as i64;
+        }
+        drop(file);
+
+        let destination = self.hot_path(key).await;
+        let path = temp_path(&destination);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let intent = self.journal.begin(key, &path).await?;
+        if let Err(e) = move_path(&source, &path).await {
+            self.journal.finish(&intent).await.ok();
+            return Err(AppError::Io(e));
+        }
+
+        tracing::Span::current().record("size", total_size);
+        Ok(Staged {
+            key: key.to_string(),
+            path,
+            destination,
+            intent,
+            etag: hasher.finalize(),
+            size: total_size,
+        })
+    }
+
+    pub async fn stage_upload<S, E>(
+        &self,
+        upload_id: &str,
+        stream: S,
+        max_size: usize,
+    ) -> Result<PathBuf>
+    where
+        S: Stream<Item = std::result::Result<Bytes, E>> + Unpin,
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let path = self.get_resumable_path(upload_id);
+        self.stream_to_path(&path, stream, max_size, &ExpectedChecksums::default())
+            .await?;
+        Ok(path)
+    }
+
+    pub async fn remove_upload(&self, upload_id: &str) -> Result<()> {
+        match fs::remove_dir_all(self.get_upload_dir(upload_id)).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.copy", skip_all, fields(source = %source.key, destination = %destination_key), err(level = "debug"))]
+    pub async fn stage_copy(
+        &self,
+        source: &ObjectMetadata,
+        destination_key: &str,
+    ) -> Result<Staged> {
+        self.ensure_local(&source.key).await?;
+        let from = self.get_object_path(&source.key).await;
+        let destination = self.hot_path(destination_key).await;
+        let path = temp_path(&destination);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let intent = self.journal.begin(destination_key, &path).await?;
+        let result = async {
+            if let Some(digest) = self.deduplicate(&source.key).await?
+                && self.share(&digest, &path).await?
+            {
+                return Ok(());
+            }
+
+            match copy_blob(&from, &path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    return Err(AppError::NotFound(source.key.clone()));
+                }
+                Err(e) => return Err(AppError::Io(e)),
+            }
+
+            self.deduplicate_path(&path).await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(e) = result {
+            self.remove_blob(&path).await.ok();
+            self.journal.finish(&intent).await.ok();
+            return Err(e);
+        }
+
+        Ok(Staged {
+            key: destination_key.to_string(),
+            path,
+            destination,
+            intent,
+            etag: source.etag.clone(),
+            size: source.size,
+        })
+    }
+
+    pub async fn archive_version(&self, key: &str, version_id: &str) -> Result<()> {
+        self.ensure_local(key).await?;
+        let source = self.get_object_path(key).await;
+        let destination = self.get_version_path(key, version_id).await;
+        Self::move_blob(&source, &destination, key).await
+    }
+
+    pub async fn restore_version(&self, key: &str, version_id: &str) -> Result<()> {
+        let source = self.get_version_path(key, version_id).await;
+        let destination = self.writable_path(key).await?;
+        let partial = temp_path(&destination);
+        Self::move_blob(&source, &partial, key).await?;
+        self.replace(&partial, &destination).await?;
+        self.publish(key).await
+    }
+
+    pub async fn move_to_trash(&self, key: &str, id: &str) -> Result<()> {
+        self.ensure_local(key).await?;
+        let source = self.get_object_path(key).await;
+        let destination = self.get_trash_path(key, id).await;
+        Self::move_blob(&source, &destination, key).await?;
+        self.remove_variants(key).await?;
+        self.unpublish(key).await
+    }
+
+    pub async fn restore_from_trash(&self, key: &str, id: &str) -> Result<()> {
+        let source = self.get_trash_path(key, id).await;
+        let destination = self.writable_path(key).await?;
+        let partial = temp_path(&destination);
+        Self::move_blob(&source, &partial, key).await?;
+        self.replace(&partial, &destination).await?;
+        self.publish(key).await
+    }
+
+    pub async fn delete_trashed(&self, key: &str, id: &str) -> Result<()> {
+        let path = self.get_trash_path(key, id).await;
+        if self.detach(&path).await? {
+            return Ok(());
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    async fn move_blob(source: &Path, destination: &Path, key: &str) -> Result<()> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        match move_path(source, destination).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub async fn open_version_at(
+        &self,
+        key: &str,
+        version_id: &str,
+        offset: u64,
+    ) -> Result<fs::File> {
+        let path = self.get_version_path(key, version_id).await;
+
+        let mut file = match fs::File::open(&path).await {
+            Ok(file) => file,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(AppError::NotFound(key.to_string()));
+            }
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        Ok(file)
+    }
+
+    pub async fn delete_version(&self, key: &str, version_id: &str) -> Result<()> {
+        let path = self.get_version_path(key, version_id).await;
+        if self.detach(&path).await? {
+            return Ok(());
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(_) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.open", skip(self), fields(size = tracing::field::Empty), err(level = "debug"))]
+    pub async fn open(&self, key: &str) -> Result<fs::File> {
+        self.ensure_local(key).await?;
+        let path = self.get_object_path(key).await;
+
+        match fs::File::open(&path).await {
+            Ok(file) => {
+                if let Ok(metadata) = file.metadata().await {
+                    tracing::Span::current().record("size", metadata.len());
+                }
+                Ok(file)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub async fn open_at(&self, key: &str, offset: u64) -> Result<fs::File> {
+        let mut file = self.open(key).await?;
+        file.seek(std::io::SeekFrom::Start(offset)).await?;
+        Ok(file)
+    }
+
+    pub async fn reader(&self, object: &ObjectMetadata, offset: u64) -> Result<BlobReader> {
+        if let Some(chunks) = &object.chunks {
+            self.ensure_local(&object.key).await?;
+            return Ok(chunk_reader(
+                self.get_object_path(&object.key).await,
+                chunks,
+                offset,
+                self.read_buffer,
+            ));
+        }
+
+        if object.stored_size.is_none() {
+            return Ok(Box::new(self.open_at(&object.key, offset).await?));
+        }
+
+        Self::decoder(self.open(&object.key).await?, offset).await
+    }
+
+    pub async fn version_reader(&self, object: &ObjectMetadata, offset: u64) -> Result<BlobReader> {
+        if let Some(chunks) = &object.chunks {
+            return Ok(chunk_reader(
+                self.get_version_path(&object.key, &object.id).await,
+                chunks,
+                offset,
+                self.read_buffer,
+            ));
+        }
+
+        if object.stored_size.is_none() {
+            return Ok(Box::new(
+                self.open_version_at(&object.key, &object.id, offset)
+                    .await?,
+            ));
+        }
+
+        let file = self.open_version_at(&object.key, &object.id, 0).await?;
+        Self::decoder(file, offset).await
+    }
+
+    pub async fn stream(
+        &self,
+        object: &ObjectMetadata,
+        offset: u64,
+        length: u64,
+    ) -> Result<BlobStream> {
+        if object.chunks.is_none() && object.stored_size.is_none() {
+            let file = self.open(&object.key).await?;
+            return Ok(read_blocks(
+                file.into_std().await,
+                offset,
+                length,
+                self.read_buffer,
+            ));
+        }
+
+        let reader = self.reader(object, offset).await?;
+        Ok(Box::pin(ReaderStream::with_capacity(
+            reader.take(length),
+            self.read_buffer,
+        )))
+    }
+
+    pub async fn version_stream(
+        &self,
+        object: &ObjectMetadata,
+        offset: u64,
+        length: u64,
+    ) -> Result<BlobStream> {
+        if object.chunks.is_none() && object.stored_size.is_none() {
+            let file = self.open_version_at(&object.key, &object.id, 0).await?;
+            return Ok(read_blocks(
+                file.into_std().await,
+                offset,
+                length,
+                self.read_buffer,
+            ));
+        }
+
+        let reader = self.version_reader(object, offset).await?;
+        Ok(Box::pin(ReaderStream::with_capacity(
+            reader.take(length),
+            self.read_buffer,
+        )))
+    }
+
+    async fn decoder(file: fs::File, offset: u64) -> Result<BlobReader> {
+        let mut decoder = ZstdDecoder::new(BufReader::new(file));
+        tokio::io::copy(&mut (&mut decoder).take(offset), &mut tokio::io::sink()).await?;
+        Ok(Box::new(decoder))
+    }
+
+    pub async fn finalize_staged(&self, staged: &Staged, content_type: &str) -> Result<Layout> {
+        if let Some(chunks) = self.chunk(staged).await? {
+            return Ok(Layout {
+                stored_size: None,
+                chunks: Some(chunks),
+            });
+        }
+
+        let stored_size = self.compress(staged, content_type).await?;
+        self.deduplicate_path(&staged.path).await?;
+
+        Ok(Layout {
+            stored_size,
+            chunks: None,
+        })
+    }
+
+    #[tracing::instrument(name = "storage.chunk", skip_all, fields(key = %staged.key, chunks = tracing::field::Empty), err(level = "debug"))]
+    async fn chunk(&self, staged: &Staged) -> Result<Option<Vec<Chunk>>> {
+        let Some(chunking) = &self.chunking else {
+            return Ok(None);
+        };
+
+        if (staged.size as u64) < chunking.threshold_mb * 1024 * 1024 {
+            return Ok(None);
+        }
+
+        let chunk_size = chunking.chunk_size_mb.max(1) * 1024 * 1024;
+        let partial = temp_path(&staged.path);
+        let intent = self.journal.begin(&staged.key, &partial).await?;
+        fs::create_dir_all(&partial).await?;
+
+        let result = async {
+            let mut source = fs::File::open(&staged.path).await?;
+            let mut chunks = Vec::new();
+            let mut buffer = vec![0u8; 64 * 1024];
+
+            loop {
+                let mut file = fs::File::create(partial.join(chunk_name(chunks.len()))).await?;
+                let mut hasher = Sha256::new();
+                let mut written = 0u64;
+
+                while written < chunk_size {
+                    let limit = buffer.len().min((chunk_size - written) as usize);
+                    let read = source.read(&mut buffer[..limit]).await?;
+                    if read == 0 {
+                        break;
+                    }
+                    file.write_all(&buffer[..read]).await?;
+                    hasher.update(&buffer[..read]);
+                    written += read as u64;
+                }
+
+                self.sync_file(&mut file).await?;
+
+                if written == 0 {
+                    drop(file);
+                    fs::remove_file(partial.join(chunk_name(chunks.len()))).await?;
+                    break;
+                }
+
+                chunks.push(Chunk {
+                    size: written as i64,
+                    digest: hex::encode(hasher.finalize()),
+                });
+            }
+
+            self.sync_dir(&partial.join(chunk_name(0))).await?;
+            Ok::<_, AppError>(chunks)
+        }
+        .await;
+
+        let chunks = match result {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                fs::remove_dir_all(&partial).await.ok();
+                self.journal.finish(&intent).await.ok();
+                return Err(e);
+            }
+        };
+
+        self.replace(&partial, &staged.path).await?;
+        self.journal.finish(&intent).await?;
+
+        tracing::Span::current().record("chunks", chunks.len());
+        tracing::debug!("Split {} into {} chunks", staged.key, chunks.len());
+        Ok(Some(chunks))
+    }
+
+    #[tracing::instrument(name = "storage.compress", skip(self, staged), fields(key = %staged.key, stored = tracing::field::Empty), err(level = "debug"))]
+    async fn compress(&self, staged: &Staged, content_type: &str) -> Result<Option<i64>> {
+        let Some(policy) = &self.compression else {
+            return Ok(None);
+        };
+
+        if !policy.applies(content_type, staged.size as u64) {
+            return Ok(None);
+        }
+
+        let partial = temp_path(&staged.path);
+        let intent = self.journal.begin(&staged.key, &partial).await?;
+
+        let mut source = fs::File::open(&staged.path).await?;
+        let mut encoder = ZstdEncoder::with_quality(
+            fs::File::create(&partial).await?,
+            Level::Precise(policy.level),
+        );
+        tokio::io::copy(&mut source, &mut encoder).await?;
+        encoder.shutdown().await?;
+        self.sync_file(encoder.get_mut()).await?;
+
+        let stored = fs::metadata(&partial).await?.len() as i64;
+        tracing::Span::current().record("stored", stored);
+
+        if stored >= staged.size {
+            tracing::debug!("Keeping {} uncompressed, zstd saved nothing", staged.key);
+            fs::remove_file(&partial).await?;
+            self.journal.finish(&intent).await?;
+            return Ok(None);
+        }
+
+        self.replace(&partial, &staged.path).await?;
+        self.journal.finish(&intent).await?;
+
+        tracing::debug!(
+            "Compressed {} from {} to {} bytes",
+            staged.key,
+            staged.size,
+            stored
+        );
+        Ok(Some(stored))
+    }
+
+    #[allow(dead_code)]
+    pub async fn read(&self, key: &str) -> Result<Vec<u8>> {
+        self.ensure_local(key).await?;
+        let path = self.get_object_path(key).await;
+
+        match fs::read(&path).await {
+            Ok(data) => Ok(data),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.delete", skip(self), err(level = "debug"))]
+    pub async fn delete(&self, key: &str) -> Result<()> {
+        self.remove_variants(key).await?;
+
+        let path = self.get_object_path(key).await;
+        if self.detach(&path).await? {
+            return self.unpublish(key).await;
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(_) => self.unpublish(key).await,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && self.upstream.is_some() => {
+                self.unpublish(key).await
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                Err(AppError::NotFound(key.to_string()))
+            }
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    #[tracing::instrument(name = "storage.deduplicate", skip(self), err(level = "debug"))]
+    pub async fn deduplicate(&self, key: &str) -> Result<Option<String>> {
+        if self.refs.is_none() {
+            return Ok(None);
+        }
+
+        self.deduplicate_path(&self.get_object_path(key).await)
+            .await
+    }
+
+    async fn deduplicate_path(&self, path: &Path) -> Result<Option<String>> {
+        let Some(refs) = &self.refs else {
+            return Ok(None);
+        };
+
+        let metadata = match fs::metadata(path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        let digest = file_digest(path).await?;
+        let volume = self.volume_of(path);
+        let shared = self.shared_file(volume, &digest);
+
+        if let Some(parent) = shared.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        match fs::metadata(&shared).await {
+            Ok(existing) if same_file(&existing, &metadata) => return Ok(Some(digest)),
+            Ok(_) => link_shared(&shared, path, &digest).await?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                match fs::hard_link(path, &shared).await {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                        link_shared(&shared, path, &digest).await?
+                    }
+                    Err(e) => return Err(AppError::Io(e)),
+                }
+            }
+            Err(e) => return Err(AppError::Io(e)),
+        }
+
+        let inode = fs::metadata(&shared).await?.ino();
+        refs.acquire_blob(volume, &digest, metadata.len() as i64, inode)
+            .await?;
+        Ok(Some(digest))
+    }
+
+    async fn share(&self, digest: &str, destination: &Path) -> Result<bool> {
+        let Some(refs) = &self.refs else {
+            return Ok(false);
+        };
+
+        let volume = self.volume_of(destination);
+        let shared = self.shared_file(volume, digest);
+        let metadata = match fs::metadata(&shared).await {
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        fs::hard_link(&shared, destination).await?;
+        refs.acquire_blob(volume, digest, metadata.len() as i64, metadata.ino())
+            .await?;
+        Ok(true)
+    }
+
+    async fn detach(&self, path: &Path) -> Result<bool> {
+        let metadata = match fs::metadata(path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                fs::remove_dir_all(path).await?;
+                return Ok(true);
+            }
+            Ok(metadata) => metadata,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        if self.refs.is_none() || metadata.nlink() < 2 {
+            return Ok(false);
+        }
+
+        let linked = self.linked_blob(path, &metadata).await?;
+        fs::remove_file(path).await?;
+        self.release(linked).await?;
+        Ok(true)
+    }
+
+    async fn sync_file(&self, file: &mut fs::File) -> Result<()> {
+        match self.durability {
+            Durability::None => {}
+            Durability::Flush => file.flush().await?,
+            Durability::Fsync => {
+                file.flush().await?;
+                file.sync_all().await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn sync_dir(&self, path: &Path) -> Result<()> {
+        if self.durability == Durability::Fsync
+            && let Some(parent) = path.parent()
+        {
+            fs::File::open(parent).await?.sync_all().await?;
+        }
+        Ok(())
+    }
+
+    async fn replace(&self, partial: &Path, path: &Path) -> Result<()> {
+        if fs::metadata(partial).await?.is_dir() {
+            if !self.detach(path).await? {
+                match fs::remove_file(path).await {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                        return Err(AppError::Io(e));
+                    }
+                    _ => {}
+                }
+            }
+            fs::rename(partial, path).await?;
+            return self.sync_dir(path).await;
+        }
+
+        let linked = match fs::metadata(path).await {
+            Ok(metadata) if metadata.is_dir() => {
+                fs::remove_dir_all(path).await?;
+                None
+            }
+            Ok(metadata) if self.refs.is_some() && metadata.nlink() > 1 => {
+                self.linked_blob(path, &metadata).await?
+            }
+            Ok(_) => None,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        fs::rename(partial, path).await?;
+        self.sync_dir(path).await?;
+        self.release(linked).await
+    }
+
+    async fn linked_blob(
+        &self,
+        path: &Path,
+        metadata: &std::fs::Metadata,
+    ) -> Result<Option<(usize, String)>> {
+        let Some(refs) = &self.refs else {
+            return Ok(None);
+        };
+
+        let volume = self.volume_of(path);
+        if let Some(digest) = refs.find_blob(volume, metadata.ino()).await?
+            && self.is_shared(volume, &digest, metadata).await
+        {
+            return Ok(Some((volume, digest)));
+        }
+
+        let digest = file_digest(path).await?;
+        if !self.is_shared(volume, &digest, metadata).await {
+            return Ok(None);
+        }
+
+        refs.record_blob_inode(volume, &digest, metadata.ino())
+            .await?;
+        Ok(Some((volume, digest)))
+    }
+
+    async fn is_shared(&self, volume: usize, digest: &str, metadata: &std::fs::Metadata) -> bool {
+        fs::metadata(self.shared_file(volume, digest))
+            .await
+            .is_ok_and(|existing| same_file(&existing, metadata))
+    }
+
+    async fn release(&self, linked: Option<(usize, String)>) -> Result<()> {
+        let (Some(refs), Some((volume, digest))) = (&self.refs, linked) else {
+            return Ok(());
+        };
+
+        if refs.release_blob(volume, &digest).await? == 0 {
+            self.remove_shared(&self.shared_file(volume, &digest))
+                .await?;
+            tracing::debug!("Removed unreferenced blob {}", digest);
+        }
+
+        Ok(())
+    }
+
+    pub async fn list_unreferenced(&self) -> Result<Vec<PathBuf>> {
+        let mut blobs = Vec::new();
+
+        for volume in 0..self.volumes.len() {
+            let mut dirs = match fs::read_dir(self.shared_path(volume)).await {
+                Ok(dirs) => dirs,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(AppError::Io(e)),
+            };
+
+            while let Some(dir) = dirs.next_entry().await? {
+                if !dir.file_type().await?.is_dir() {
+                    continue;
+                }
+
+                let mut files = fs::read_dir(dir.path()).await?;
+                while let Some(file) = files.next_entry().await? {
+                    let metadata = file.metadata().await?;
+                    if metadata.is_file() && metadata.nlink() == 1 {
+                        blobs.push(file.path());
+                    }
+                }
+            }
+        }
+
+        blobs.sort();
+        Ok(blobs)
+    }
+
+    async fn remove_shared(&self, path: &Path) -> Result<()> {
+        match fs::remove_file(path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(AppError::Io(e)),
+            _ => {}
+        }
+
+        if let (Some(refs), Some(digest)) = (&self.refs, path.file_name()) {
+            refs.forget_blob(self.volume_of(path), &digest.to_string_lossy())
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    fn shared_path(&self, volume: usize) -> PathBuf {
+        self.volumes[volume].root.join(".blobs")
+    }
+
+    fn shared_file(&self, volume: usize, digest: &str) -> PathBuf {
+        self.shared_path(volume).join(&digest[..2]).join(digest)
+    }
+
+    #[tracing::instrument(name = "storage.demote", skip(self), err(level = "debug"))]
+    pub async fn demote(&self, key: &str) -> Result<bool> {
+        let Some(cold) = self.cold_volume() else {
+            return Ok(false);
+        };
+
+        let path = self.get_object_path(key).await;
+        if self.is_cold(&path) || !fs::try_exists(&path).await? {
+            return Ok(false);
+        }
+
+        let relative = path
+            .strip_prefix(&self.volumes[self.volume_of(&path)].root)
+            .map_err(|_| AppError::Internal)?;
+        let destination = self.volumes[cold].root.join(relative);
+        self.relocate(&path, &destination).await?;
+
+        tracing::debug!("Moved {} to the cold tier", key);
+        Ok(true)
+    }
+
+    async fn relocate(&self, path: &Path, destination: &Path) -> Result<()> {
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let shared = self.refs.is_some()
+            && fs::metadata(path)
+                .await
+                .is_ok_and(|metadata| metadata.is_file() && metadata.nlink() > 1);
+
+        if !shared {
+            match fs::rename(path, destination).await {
+                Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+                result => return Ok(result?),
+            }
+        }
+
+        let partial = temp_path(destination);
+        copy_blob(path, &partial).await?;
+        fs::rename(&partial, destination).await?;
+
+        if !self.detach(path).await? {
+            fs::remove_file(path).await?;
+        }
+        self.deduplicate_path(destination).await?;
+        Ok(())
+    }
+
+    pub async fn publish(&self, key: &str) -> Result<()> {
+        match &self.upstream {
+            Some(upstream) => {
+                upstream
+                    .put(&self.upstream_name(key), &self.get_object_path(key).await)
+                    .await
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn variants_path(&self) -> PathBuf {
+        self.volumes[0].root.join(".variants")
+    }
+
+    fn variant_dir(&self, key: &str) -> PathBuf {
+        self.variants_path()
+            .join(key_hash(&format!("{}{}", self.scope, key)))
+    }
+
+    pub async fn read_variant(&self, key: &str, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.variant_dir(key).join(name);
+
+        let data = match fs::read(&path).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        let touched = tokio::task::spawn_blocking(move || {
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())
+        })
+        .await;
+        if let Ok(Err(e)) = touched {
+            tracing::debug!("Failed to touch cached variant {}: {}", name, e);
+        }
+
+        Ok(Some(data))
+    }
+
+    pub async fn store_variant(&self, key: &str, name: &str, data: &[u8]) -> Result<()> {
+        let dir = self.variant_dir(key);
+        fs::create_dir_all(&dir).await?;
+
+        let path = dir.join(name);
+        let partial = temp_path(&path);
+        fs::write(&partial, data).await?;
+        fs::rename(&partial, &path).await?;
+        Ok(())
+    }
+
+    async fn remove_variants(&self, key: &str) -> Result<()> {
+        match fs::remove_dir_all(self.variant_dir(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub async fn trim_variants(&self, max_bytes: u64) -> Result<usize> {
+        let mut dirs = match fs::read_dir(self.variants_path()).await {
+            Ok(dirs) => dirs,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        let mut variants = Vec::new();
+        let mut total = 0;
+        while let Some(dir) = dirs.next_entry().await? {
+            if !dir.file_type().await?.is_dir() {
+                continue;
+            }
+
+            let mut files = fs::read_dir(dir.path()).await?;
+            while let Some(file) = files.next_entry().await? {
+                let metadata = file.metadata().await?;
+                total += metadata.len();
+                variants.push((metadata.modified()?, metadata.len(), file.path()));
+            }
+        }
+
+        if total <= max_bytes {
+            return Ok(0);
+        }
+
+        variants.sort();
+        let mut removed = 0;
+        for (_, size, path) in variants {
+            if total <= max_bytes {
+                break;
+            }
+
+            match fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(AppError::Io(e)),
+            }
+            if let Some(dir) = path.parent() {
+                fs::remove_dir(dir).await.ok();
+            }
+
+            total -= size;
+            removed += 1;
+        }
+
+        Ok(removed)
+    }
+
+    async fn unpublish(&self, key: &str) -> Result<()> {
+        match &self.upstream {
+            Some(upstream) => upstream.delete(&self.upstream_name(key)).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn ensure_local(&self, key: &str) -> Result<()> {
+        let Some(upstream) = &self.upstream else {
+            return Ok(());
+        };
+
+        let path = self.get_object_path(key).await;
+        if fs::try_exists(&path).await? {
+            return Ok(());
+        }
+
+        if upstream.fetch(&self.upstream_name(key), &path).await? {
+            tracing::info!("Restored {} from upstream", key);
+        }
+
+        Ok(())
+    }
+
+    fn upstream_name(&self, key: &str) -> String {
+        format!("{}{}", self.scope, key)
+    }
+}
+
+fn same_file(a: &std::fs::Metadata, b: &std::fs::Metadata) -> bool {
+    a.dev() == b.dev() && a.ino() == b.ino()
+}
+
+async fn link_shared(shared: &Path, path: &Path, digest: &str) -> Result<()> {
+    let partial = temp_path(path);
+    fs::hard_link(shared, &partial).await?;
+    fs::rename(&partial, path).await?;
+    tracing::debug!("Deduplicated {} against blob {}", path.display(), digest);
+    Ok(())
+}
+
+async fn file_digest(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer).await?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn key_hash(key: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+fn temp_path(path: &Path) -> PathBuf {
+    path.with_extension(format!("tmp-{}", Uuid::new_v4().simple()))
+}
+
+async fn move_path(source: &Path, destination: &Path) -> std::io::Result<()> {
+    match fs::rename(source, destination).await {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let partial = temp_path(destination);
+            copy_blob(source, &partial).await?;
+            fs::rename(&partial, destination).await?;
+            if fs::metadata(source).await?.is_dir() {
+                fs::remove_dir_all(source).await
+            } else {
+                fs::remove_file(source).await
+            }
+        }
+        result => result,
+    }
+}
+
+fn chunk_name(index: usize) -> String {
+    format!("{:08}", index)
+}
+
+fn read_blocks(file: std::fs::File, offset: u64, length: u64, block_size: usize) -> BlobStream {
+    let file = Arc::new(file);
+
+    Box::pin(futures_util::stream::try_unfold(
+        (offset, length),
+        move |(position, remaining)| {
+            let file = file.clone();
+            async move {
+                if remaining == 0 {
+                    return Ok(None);
+                }
+
+                let size = remaining.min(block_size as u64) as usize;
+                let block = tokio::task::spawn_blocking(move || {
+                    let mut buffer = vec![0; size];
+                    let read = file.read_at(&mut buffer, position)?;
+                    buffer.truncate(read);
+                    Ok::<_, std::io::Error>(buffer)
+                })
+                .await
+                .map_err(std::io::Error::other)??;
+
+                if block.is_empty() {
+                    return Ok(None);
+                }
+
+                let read = block.len() as u64;
+                Ok(Some((
+                    Bytes::from(block),
+                    (position + read, remaining - read),
+                )))
+            }
+        },
+    ))
+}
+
+fn copy_part(part: &Path, destination: &Path, offset: u64) -> std::io::Result<()> {
+    use std::io::Seek;
+
+    let mut source = std::fs::File::open(part)?;
+    let mut target = std::fs::OpenOptions::new().write(true).open(destination)?;
+    target.seek(std::io::SeekFrom::Start(offset))?;
+    std::io::copy(&mut source, &mut target)?;
+    Ok(())
+}
+
+async fn hash_files(
+    paths: Vec<PathBuf>,
+    algorithm: EtagAlgorithm,
+    buffer_size: usize,
+) -> std::io::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        use std::io::Read;
+
+        let mut hasher = algorithm.hasher();
+        let mut buffer = vec![0u8; buffer_size];
+
+        for path in paths {
+            let mut file = std::fs::File::open(path)?;
+            loop {
+                let read = file.read(&mut buffer)?;
+                if read == 0 {
+                    break;
+                }
+                hasher.update(&buffer[..read]);
+            }
+        }
+
+        Ok(hasher.finalize())
+    })
+    .await
+    .map_err(std::io::Error::other)?
+}
+
+fn chunk_reader(dir: PathBuf, chunks: &[Chunk], offset: u64, buffer_size: usize) -> BlobReader {
+    let mut skip = offset;
+    let mut first = 0;
+
+    while first < chunks.len() && skip >= chunks[first].size as u64 {
+        skip -= chunks[first].size as u64;
+        first += 1;
+    }
+
+    let stream = futures_util::stream::iter(first..chunks.len())
+        .then(move |index| {
+            let path = dir.join(chunk_name(index));
+            async move {
+                let mut file = fs::File::open(&path).await?;
+                if index == first && skip > 0 {
+                    file.seek(std::io::SeekFrom::Start(skip)).await?;
+                }
+                Ok::<_, std::io::Error>(ReaderStream::with_capacity(file, buffer_size))
+            }
+        })
+        .try_flatten();
+
+    Box::new(StreamReader::new(Box::pin(stream)))
+}
+
+async fn copy_blob(source: &Path, destination: &Path) -> std::io::Result<()> {
+    if !fs::metadata(source).await?.is_dir() {
+        return fs::copy(source, destination).await.map(|_| ());
+    }
+
+    fs::create_dir_all(destination).await?;
+    let mut chunks = fs::read_dir(source).await?;
+    while let Some(chunk) = chunks.next_entry().await? {
+        fs::copy(chunk.path(), destination.join(chunk.file_name())).await?;
+    }
+
+    Ok(())
+}
+
+async fn list_volume_blobs(path: &Path, blobs: &mut Vec<PathBuf>) -> Result<()> {
+    let mut dirs = match fs::read_dir(path).await {
+        Ok(dirs) => dirs,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(AppError::Io(e)),
+    };
+
+    while let Some(dir) = dirs.next_entry().await? {
+        let name = dir.file_name().to_string_lossy().into_owned();
+        if name.len() != 2
+            || !name.bytes().all(|b| b.is_ascii_hexdigit())
+            || !dir.file_type().await?.is_dir()
+        {
+            continue;
+        }
+
+        let mut files = fs::read_dir(dir.path()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let file_type = file.file_type().await?;
+            if file_type.is_file() || file_type.is_dir() {
+                blobs.push(file.path());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{models::DatabaseConfig, storage::SqliteMetadataStore};
+
+    const UPLOADS: usize = 64;
+
+    async fn dedup_storage(dir: &Path) -> (FileStorage, MetadataStore) {
+        let database_url = format!("sqlite:{}", dir.join("metadata.db").display());
+        let refs: MetadataStore = Arc::new(
+            SqliteMetadataStore::new(&database_url, &DatabaseConfig::default())
+                .await
+                .unwrap(),
+        );
+        let data = dir.join("data").display().to_string();
+        let storage = FileStorage::new(&[data], EtagAlgorithm::default(), 0)
+            .await
+            .unwrap()
+            .with_dedup(refs.clone());
+
+        (storage, refs)
+    }
+
+    async fn put(
+        storage: &FileStorage,
+        key: &str,
+        body: &'static [u8],
+        barrier: &tokio::sync::Barrier,
+    ) -> Result<()> {
+        let stream =
+            futures_util::stream::iter([Ok::<_, std::io::Error>(Bytes::from_static(body))]);
+        let staged = storage
+            .stage_stream(key, stream, usize::MAX, &ExpectedChecksums::default())
+            .await?;
+        barrier.wait().await;
+
+        if let Err(e) = storage
+            .finalize_staged(&staged, "application/octet-stream")
+            .await
+        {
+            storage.discard(staged).await;
+            return Err(e);
+        }
+
+        storage.commit(staged).await
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn concurrent_identical_uploads_share_one_blob() {
+        let dir = tempfile::tempdir().unwrap();
+        let (storage, refs) = dedup_storage(dir.path()).await;
+        let body: &'static [u8] = b"the same bytes uploaded under many keys";
+
+        let barrier = Arc::new(tokio::sync::Barrier::new(UPLOADS));
+
+        let uploads = (0..UPLOADS).map(|i| {
+            let storage = storage.clone();
+            let barrier = barrier.clone();
+            tokio::spawn(
+                async move { put(&storage, &format!("copies/{}", i), body, &barrier).await },
+            )
+        });
+        for upload in futures_util::future::join_all(uploads).await {
+            upload.unwrap().unwrap();
+        }
+
+        let first = fs::metadata(storage.get_object_path("copies/0").await)
+            .await
+            .unwrap();
+        for i in 0..UPLOADS {
+            let path = storage.get_object_path(&format!("copies/{}", i)).await;
+            assert!(same_file(&fs::metadata(&path).await.unwrap(), &first));
+            assert_eq!(fs::read(&path).await.unwrap(), body);
+        }
+        assert_eq!(first.nlink(), UPLOADS as u64 + 1);
+
+        let digest = refs.find_blob(0, first.ino()).await.unwrap().unwrap();
+        let shared = storage.shared_file(0, &digest);
+        assert!(fs::metadata(&shared).await.is_ok());
+
+        let deletes = (0..UPLOADS).map(|i| {
+            let storage = storage.clone();
+            tokio::spawn(async move { storage.delete(&format!("copies/{}", i)).await })
+        });
+        for delete in futures_util::future::join_all(deletes).await {
+            delete.unwrap().unwrap();
+        }
+
+        assert!(refs.find_blob(0, first.ino()).await.unwrap().is_none());
+        assert!(fs::metadata(&shared).await.is_err());
     }
 }
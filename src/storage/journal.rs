@@ -0,0 +1,119 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, io::AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::error::{AppError, Result};
+
+pub const JOURNAL_DIR: &str = ".journal";
+
+#[derive(Serialize, Deserialize)]
+struct Intent {
+    key: String,
+    path: PathBuf,
+    started_at: DateTime<Utc>,
+}
+
+#[derive(Clone)]
+pub struct Journal {
+    dir: PathBuf,
+    sync: bool,
+}
+
+impl Journal {
+    pub fn new(root: &Path) -> Self {
+        Self {
+            dir: root.join(JOURNAL_DIR),
+            sync: false,
+        }
+    }
+
+    pub fn with_sync(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    pub async fn begin(&self, key: &str, path: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir).await?;
+
+        let entry = self.dir.join(format!("{}.json", Uuid::new_v4().simple()));
+        let intent = Intent {
+            key: key.to_string(),
+            path: path.to_path_buf(),
+            started_at: Utc::now(),
+        };
+
+        let mut file = fs::File::create(&entry).await?;
+        file.write_all(&serde_json::to_vec(&intent).map_err(std::io::Error::other)?)
+            .await?;
+        file.flush().await?;
+        if self.sync {
+            file.sync_all().await?;
+        }
+
+        Ok(entry)
+    }
+
+    pub async fn finish(&self, entry: &Path) -> Result<()> {
+        match fs::remove_file(entry).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(AppError::Io(e)),
+        }
+    }
+
+    pub async fn recover(&self) -> Result<usize> {
+        let mut entries = match fs::read_dir(&self.dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(AppError::Io(e)),
+        };
+
+        let mut discarded = 0;
+
+        while let Some(entry) = entries.next_entry().await? {
+            let intent = fs::read(entry.path())
+                .await
+                .ok()
+                .and_then(|contents| serde_json::from_slice::<Intent>(&contents).ok());
+
+            match intent {
+                Some(intent) => {
+                    let removed = match fs::metadata(&intent.path).await {
+                        Ok(metadata) if metadata.is_dir() => {
+                            fs::remove_dir_all(&intent.path).await?;
+                            true
+                        }
+                        Ok(_) => {
+                            fs::remove_file(&intent.path).await?;
+                            true
+                        }
+                        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+                        Err(e) => return Err(AppError::Io(e)),
+                    };
+
+                    if removed {
+                        tracing::warn!(
+                            "Discarded interrupted write of {} started at {}",
+                            intent.key,
+                            intent.started_at
+                        );
+                        discarded += 1;
+                    }
+                }
+                None => {
+                    tracing::warn!(
+                        "Dropping unreadable journal entry {}",
+                        entry.path().display()
+                    );
+                }
+            }
+
+            fs::remove_file(entry.path()).await?;
+        }
+
+        Ok(discarded)
+    }
+}
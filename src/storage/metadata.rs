@@ -1,248 +1,1093 @@
-use std::{path::Path, str::FromStr};
+use std::{path::Path, str::FromStr, sync::Arc, time::Duration};
 
-use sqlx::{Row, SqlitePool, sqlite::SqliteConnectOptions};
+use async_trait::async_trait;
+use futures_util::{StreamExt, TryStreamExt};
+use sqlx::{
+    Row, Sqlite, SqlitePool,
+    migrate::Migrator,
+    sqlite::{
+        SqliteArguments, SqliteAutoVacuum, SqliteConnectOptions, SqliteJournalMode,
+        SqlitePoolOptions, SqliteRow, SqliteSynchronous,
+    },
+};
+use tokio::sync::mpsc;
 
-use crate::{error::Result, models::ObjectMetadata};
+use super::{Access, MetadataBackend, MetadataStore, SearchFilter};
+use crate::{
+    error::Result,
+    models::{
+        ApiToken, Bucket, BucketPolicy, DatabaseConfig, DatabaseStats, EventOp, ListCursor,
+        ObjectEvent, ObjectMetadata, RateLimit, ResumableUpload, SessionInfo, SortField, SortOrder,
+        TrashEntry, UploadPart, UploadSession,
+    },
+};
+
+const STREAM_BUFFER: usize = 256;
+const OBJECT_COLUMNS: &str = "id, key, size, content_type, etag, etag_algorithm, created_at, \
+     public, expires_at, retain_until, user_metadata, cache_control, cache_expires, stored_size, \
+     chunks, accessed_at, access_count, updated_at, generation";
+const TOKEN_COLUMNS: &str = "id, name, scope, rate_limit_per_second, rate_limit_burst, created_at";
+const CHANGE_COLUMNS: &str = "seq, bucket, op, key, etag, size, created_at";
+const BUCKET_COLUMNS: &str =
+    "name, created_at, quota_bytes, default_content_type, public_read, versioning";
 
 #[derive(Clone)]
-pub struct MetadataStore {
+pub struct SqliteMetadataStore {
     pool: SqlitePool,
+    bucket: String,
 }
 
-impl MetadataStore {
-    pub async fn new(database_url: &str) -> Result<Self> {
-        if let Some(db_path) = database_url.strip_prefix("sqlite:") {
-            if let Some(parent) = Path::new(db_path).parent() {
-                tokio::fs::create_dir_all(parent).await?;
-            }
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+const LEGACY_COLUMNS: [(&str, &str, &str); 19] = [
+    ("objects", "public", "INTEGER NOT NULL DEFAULT 0"),
+    ("objects", "expires_at", "TEXT"),
+    ("objects", "retain_until", "TEXT"),
+    ("objects", "user_metadata", "TEXT NOT NULL DEFAULT '{}'"),
+    (
+        "objects",
+        "etag_algorithm",
+        "TEXT NOT NULL DEFAULT 'sha256'",
+    ),
+    ("objects", "cache_control", "TEXT"),
+    ("objects", "cache_expires", "TEXT"),
+    ("objects", "bucket", "TEXT NOT NULL DEFAULT ''"),
+    ("uploads", "bucket", "TEXT NOT NULL DEFAULT ''"),
+    ("resumable_uploads", "bucket", "TEXT NOT NULL DEFAULT ''"),
+    ("object_versions", "bucket", "TEXT NOT NULL DEFAULT ''"),
+    ("trash", "bucket", "TEXT NOT NULL DEFAULT ''"),
+    ("buckets", "quota_bytes", "INTEGER"),
+    ("buckets", "default_content_type", "TEXT"),
+    ("buckets", "public_read", "INTEGER NOT NULL DEFAULT 0"),
+    ("buckets", "versioning", "INTEGER"),
+    ("tokens", "scope", "TEXT NOT NULL DEFAULT 'admin'"),
+    ("tokens", "rate_limit_per_second", "INTEGER"),
+    ("tokens", "rate_limit_burst", "INTEGER"),
+];
+
+impl SqliteMetadataStore {
+    pub async fn new(database_url: &str, config: &DatabaseConfig) -> Result<Self> {
+        if let Some(db_path) = database_url.strip_prefix("sqlite:")
+            && let Some(parent) = Path::new(db_path).parent()
+        {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let mut options = SqliteConnectOptions::from_str(database_url)?
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::from_str(&config.journal_mode)?)
+            .synchronous(SqliteSynchronous::from_str(&config.synchronous)?)
+            .auto_vacuum(SqliteAutoVacuum::from_str(&config.auto_vacuum)?)
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms));
+
+        if let Some(page_size) = config.page_size {
+            options = options.page_size(page_size);
+        }
+        if let Some(cache_size_kb) = config.cache_size_kb {
+            options = options.pragma("cache_size", format!("-{}", cache_size_kb));
+        }
+
+        let max_connections = config.max_connections.max(1);
+        let min_connections = config.min_connections.min(max_connections);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(max_connections)
+            .min_connections(min_connections)
+            .acquire_timeout(Duration::from_secs(config.acquire_timeout_seconds))
+            .idle_timeout(config.idle_timeout_seconds.map(Duration::from_secs))
+            .connect_with(options)
+            .await?;
+
+        tracing::debug!(
+            "Metadata pool opened with {}..{} connections",
+            min_connections,
+            max_connections
+        );
+
+        upgrade_legacy_schema(&pool).await?;
+        MIGRATOR.run(&pool).await.map_err(sqlx::Error::from)?;
+
+        Ok(Self {
+            pool,
+            bucket: String::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl MetadataBackend for SqliteMetadataStore {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    async fn backup_to(&self, path: &Path) -> Result<()> {
+        sqlx::query("VACUUM INTO ?")
+            .bind(path.to_string_lossy())
+            .execute(&self.pool)
+            .await?;
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(SqliteConnectOptions::new().filename(path))
+            .await?;
+        let scrubbed = async {
+            sqlx::query("PRAGMA secure_delete = ON")
+                .execute(&pool)
+                .await?;
+            sqlx::query("UPDATE tokens SET signing_key = NULL")
+                .execute(&pool)
+                .await?;
+            sqlx::query("VACUUM").execute(&pool).await
+        }
+        .await;
+        pool.close().await;
+
+        scrubbed?;
+        Ok(())
+    }
+
+    async fn database_stats(&self) -> Result<DatabaseStats> {
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+            .fetch_one(&self.pool)
+            .await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let freelist_count: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+            .fetch_one(&self.pool)
+            .await?;
+        let auto_vacuum: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(DatabaseStats {
+            size_bytes: (page_size * page_count) as u64,
+            free_bytes: (page_size * freelist_count) as u64,
+            incremental_vacuum: auto_vacuum == 2,
+        })
+    }
+
+    async fn optimize(&self, vacuum_pages: u32, analyze: bool) -> Result<()> {
+        if analyze {
+            tracing::debug!("Analyzing metadata database");
+            sqlx::query("ANALYZE").execute(&self.pool).await?;
+        }
+
+        sqlx::query("PRAGMA optimize").execute(&self.pool).await?;
+
+        if vacuum_pages > 0 {
+            sqlx::query(&format!("PRAGMA incremental_vacuum({})", vacuum_pages))
+                .execute(&self.pool)
+                .await?;
         }
 
-        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
 
-        let pool = SqlitePool::connect_with(options).await?;
+    fn scoped(&self, bucket: &str) -> MetadataStore {
+        Arc::new(Self {
+            pool: self.pool.clone(),
+            bucket: bucket.to_string(),
+        })
+    }
 
+    async fn create_token(
+        &self,
+        token: &ApiToken,
+        token_hash: &str,
+        signing_key: &str,
+    ) -> Result<()> {
         sqlx::query(
             r#"
-            CREATE TABLE IF NOT EXISTS objects (
-                id TEXT PRIMARY KEY,
-                key TEXT NOT NULL UNIQUE,
-                size INTEGER NOT NULL,
-                content_type TEXT NOT NULL,
-                etag TEXT NOT NULL,
-                created_at TEXT NOT NULL
-            )
+            INSERT INTO tokens
+                (id, name, scope, rate_limit_per_second, rate_limit_burst, token_hash,
+                 signing_key, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                scope = excluded.scope,
+                token_hash = excluded.token_hash,
+                signing_key = excluded.signing_key
             "#,
         )
-        .execute(&pool)
+        .bind(&token.id)
+        .bind(&token.name)
+        .bind(token.scope.as_str())
+        .bind(token.rate_limit.map(|limit| limit.per_second))
+        .bind(token.rate_limit.and_then(|limit| limit.burst))
+        .bind(token_hash)
+        .bind(signing_key)
+        .bind(token.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_token(&self, token_hash: &str) -> Result<Option<ApiToken>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM tokens WHERE token_hash = ?",
+            TOKEN_COLUMNS
+        ))
+        .bind(token_hash)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(row_to_token))
+    }
+
+    async fn find_token_by_id(&self, id: &str) -> Result<Option<(ApiToken, Option<String>)>> {
+        let row = sqlx::query(&format!(
+            "SELECT {}, signing_key FROM tokens WHERE id = ?",
+            TOKEN_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (row_to_token(&row), row.get("signing_key"))))
+    }
+
+    async fn list_tokens(&self) -> Result<Vec<ApiToken>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM tokens ORDER BY created_at",
+            TOKEN_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_token).collect())
+    }
+
+    async fn set_token_rate_limit(&self, id: &str, rate_limit: Option<RateLimit>) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE tokens SET rate_limit_per_second = ?, rate_limit_burst = ? WHERE id = ?",
+        )
+        .bind(rate_limit.map(|limit| limit.per_second))
+        .bind(rate_limit.and_then(|limit| limit.burst))
+        .bind(id)
+        .execute(&self.pool)
         .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_objects_key ON objects(key)")
-            .execute(&pool)
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn count_tokens(&self) -> Result<i64> {
+        let row = sqlx::query("SELECT COUNT(*) as count FROM tokens")
+            .fetch_one(&self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_objects_content_type ON objects(content_type)")
-            .execute(&pool)
+        Ok(row.get("count"))
+    }
+
+    async fn delete_token(&self, id: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM tokens WHERE id = ?")
+            .bind(id)
+            .execute(&mut *tx)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_objects_size ON objects(size)")
-            .execute(&pool)
+        sqlx::query("DELETE FROM sessions WHERE subject = ?")
+            .bind(format!("token:{}", id))
+            .execute(&mut *tx)
             .await?;
 
-        Ok(Self { pool })
+        tx.commit().await?;
+        Ok(result.rows_affected() > 0)
     }
 
-    pub async fn insert(&self, metadata: &ObjectMetadata) -> Result<()> {
+    async fn create_session(
+        &self,
+        session_hash: &str,
+        subject: &str,
+        principal: &ApiToken,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> Result<()> {
+        sqlx::query("DELETE FROM sessions WHERE expires_at < ?")
+            .bind(chrono::Utc::now().to_rfc3339())
+            .execute(&self.pool)
+            .await?;
+
         sqlx::query(
             r#"
-            INSERT INTO objects (id, key, size, content_type, etag, created_at)
+            INSERT INTO sessions (session_hash, subject, name, scope, created_at, expires_at)
             VALUES (?, ?, ?, ?, ?, ?)
-            ON CONFLICT(key) DO UPDATE SET
-                size = excluded.size,
-                content_type = excluded.content_type,
-                etag = excluded.etag,
-                created_at = excluded.created_at
             "#,
         )
-        .bind(&metadata.id)
-        .bind(&metadata.key)
-        .bind(metadata.size)
-        .bind(&metadata.content_type)
-        .bind(&metadata.etag)
-        .bind(metadata.created_at.to_rfc3339())
+        .bind(session_hash)
+        .bind(subject)
+        .bind(&principal.name)
+        .bind(principal.scope.as_str())
+        .bind(principal.created_at.to_rfc3339())
+        .bind(expires_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn find_session(&self, session_hash: &str) -> Result<Option<SessionInfo>> {
+        let row = sqlx::query(
+            "SELECT subject, name, scope, created_at, expires_at FROM sessions
+             WHERE session_hash = ? AND expires_at > ?",
+        )
+        .bind(session_hash)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(row_to_session))
+    }
+
+    async fn delete_session(&self, session_hash: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM sessions WHERE session_hash = ?")
+            .bind(session_hash)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_bucket(&self, bucket: &Bucket) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO buckets
+                (name, created_at, quota_bytes, default_content_type, public_read, versioning)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO NOTHING
+            "#,
+        )
+        .bind(&bucket.name)
+        .bind(bucket.created_at.to_rfc3339())
+        .bind(bucket.policy.quota_bytes)
+        .bind(&bucket.policy.default_content_type)
+        .bind(bucket.policy.public_read)
+        .bind(bucket.policy.versioning)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn get_bucket(&self, name: &str) -> Result<Option<Bucket>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM buckets WHERE name = ?",
+            BUCKET_COLUMNS
+        ))
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(row_to_bucket))
+    }
+
+    async fn list_buckets(&self) -> Result<Vec<Bucket>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM buckets ORDER BY name",
+            BUCKET_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_bucket).collect())
+    }
+
+    async fn set_bucket_policy(&self, name: &str, policy: &BucketPolicy) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE buckets
+            SET quota_bytes = ?, default_content_type = ?, public_read = ?, versioning = ?
+            WHERE name = ?
+            "#,
+        )
+        .bind(policy.quota_bytes)
+        .bind(&policy.default_content_type)
+        .bind(policy.public_read)
+        .bind(policy.versioning)
+        .bind(name)
         .execute(&self.pool)
         .await?;
 
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_bucket(&self, name: &str) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query(
+            "DELETE FROM buckets
+             WHERE name = ? AND NOT EXISTS (SELECT 1 FROM objects WHERE bucket = ?)",
+        )
+        .bind(name)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        sqlx::query(
+            "DELETE FROM upload_parts WHERE upload_id IN (SELECT id FROM uploads WHERE bucket = ?)",
+        )
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+        for table in [
+            "uploads",
+            "resumable_uploads",
+            "object_versions",
+            "trash",
+            "generations",
+        ] {
+            sqlx::query(&format!("DELETE FROM {} WHERE bucket = ?", table))
+                .bind(name)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn insert(&self, metadata: &ObjectMetadata) -> Result<()> {
+        upsert_object(&self.bucket, metadata)
+            .execute(&self.pool)
+            .await?;
+
         Ok(())
     }
 
-    pub async fn get(&self, key: &str) -> Result<Option<ObjectMetadata>> {
+    async fn insert_if(&self, metadata: &ObjectMetadata, generation: i64) -> Result<bool> {
+        let mut tx = self.pool.begin_with("BEGIN IMMEDIATE").await?;
+
+        let current: i64 =
+            sqlx::query("SELECT generation FROM objects WHERE bucket = ? AND key = ?")
+                .bind(&self.bucket)
+                .bind(&metadata.key)
+                .fetch_optional(&mut *tx)
+                .await?
+                .map_or(0, |row| row.get("generation"));
+
+        if current != generation {
+            return Ok(false);
+        }
+
+        upsert_object(&self.bucket, metadata)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    async fn next_generation(&self, key: &str) -> Result<i64> {
         let row = sqlx::query(
-            "SELECT id, key, size, content_type, etag, created_at FROM objects WHERE key = ?",
+            "INSERT INTO generations (bucket, key, generation) VALUES (?, ?, 1)
+             ON CONFLICT(bucket, key) DO UPDATE SET generation = generation + 1
+             RETURNING generation",
         )
+        .bind(&self.bucket)
+        .bind(key)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get("generation"))
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<ObjectMetadata>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM objects WHERE bucket = ? AND key = ?",
+            OBJECT_COLUMNS
+        ))
+        .bind(&self.bucket)
         .bind(key)
         .fetch_optional(&self.pool)
         .await?;
 
-        match row {
-            Some(row) => {
-                let created_at_str: String = row.get("created_at");
-                Ok(Some(ObjectMetadata {
-                    id: row.get("id"),
-                    key: row.get("key"),
-                    size: row.get("size"),
-                    content_type: row.get("content_type"),
-                    etag: row.get("etag"),
-                    created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
-                        .unwrap()
-                        .with_timezone(&chrono::Utc),
-                }))
-            }
-            None => Ok(None),
+        Ok(row.as_ref().map(row_to_metadata))
+    }
+
+    async fn get_many(&self, keys: &[String]) -> Result<Vec<ObjectMetadata>> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let placeholders = vec!["?"; keys.len()].join(", ");
+        let query_str = format!(
+            "SELECT {} FROM objects WHERE bucket = ? AND key IN ({}) ORDER BY key",
+            OBJECT_COLUMNS, placeholders
+        );
+
+        let mut query = sqlx::query(&query_str).bind(&self.bucket);
+        for key in keys {
+            query = query.bind(key);
         }
+
+        let rows = query.fetch_all(&self.pool).await?;
+
+        Ok(rows.iter().map(row_to_metadata).collect())
     }
 
-    pub async fn list(
+    async fn list(
         &self,
         prefix: Option<&str>,
+        after: Option<&ListCursor>,
+        sort: SortField,
+        order: SortOrder,
         limit: Option<i64>,
     ) -> Result<Vec<ObjectMetadata>> {
-        let query = match prefix {
-            Some(p) => {
-                let pattern = format!("{}%", p);
-                sqlx::query(
-                    "SELECT id, key, size, content_type, etag, created_at 
-                     FROM objects 
-                     WHERE key LIKE ? 
-                     ORDER BY key 
-                     LIMIT ?",
-                )
-                .bind(pattern)
-                .bind(limit.unwrap_or(1000))
-            }
-            None => sqlx::query(
-                "SELECT id, key, size, content_type, etag, created_at 
-                     FROM objects 
-                     ORDER BY key 
-                     LIMIT ?",
-            )
-            .bind(limit.unwrap_or(1000)),
+        let mut query_str = format!("SELECT {} FROM objects WHERE bucket = ?", OBJECT_COLUMNS);
+
+        if prefix.is_some() {
+            query_str.push_str(" AND key LIKE ?");
+        }
+
+        let comparison = match order {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
         };
 
-        let rows = query.fetch_all(&self.pool).await?;
+        match (after, sort) {
+            (None, _) => {}
+            (Some(_), SortField::Key) => {
+                query_str.push_str(&format!(" AND key {} ?", comparison));
+            }
+            (Some(_), sort) => {
+                let column = sort_column(sort);
+                query_str.push_str(&format!(
+                    " AND ({column} {comparison} ? OR ({column} = ? AND key {comparison} ?))"
+                ));
+            }
+        }
 
-        let mut objects = Vec::new();
-        for row in rows {
-            let created_at_str: String = row.get("created_at");
-            objects.push(ObjectMetadata {
-                id: row.get("id"),
-                key: row.get("key"),
-                size: row.get("size"),
-                content_type: row.get("content_type"),
-                etag: row.get("etag"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            });
+        query_str.push_str(&order_by(sort, order));
+        query_str.push_str(" LIMIT ?");
+
+        let mut query = sqlx::query(&query_str).bind(&self.bucket);
+
+        if let Some(p) = prefix {
+            query = query.bind(format!("{}%", p));
         }
 
-        Ok(objects)
+        if let Some(cursor) = after {
+            if !matches!(sort, SortField::Key) {
+                query = query.bind(cursor.value.clone()).bind(cursor.value.clone());
+            }
+            query = query.bind(cursor.key.clone());
+        }
+
+        let rows = query
+            .bind(limit.unwrap_or(1000))
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(row_to_metadata).collect())
+    }
+
+    fn stream(
+        &self,
+        prefix: Option<String>,
+        sort: SortField,
+        order: SortOrder,
+    ) -> mpsc::Receiver<Result<ObjectMetadata>> {
+        let pool = self.pool.clone();
+        let bucket = self.bucket.clone();
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+
+        tokio::spawn(async move {
+            let mut query_str = format!("SELECT {} FROM objects WHERE bucket = ?", OBJECT_COLUMNS);
+
+            if prefix.is_some() {
+                query_str.push_str(" AND key LIKE ?");
+            }
+
+            query_str.push_str(&order_by(sort, order));
+
+            let mut query = sqlx::query(&query_str).bind(bucket);
+
+            if let Some(p) = prefix {
+                query = query.bind(format!("{}%", p));
+            }
+
+            let mut rows = query.fetch(&pool);
+
+            while let Some(row) = rows.next().await {
+                let item = row.map(|row| row_to_metadata(&row)).map_err(Into::into);
+
+                if tx.send(item).await.is_err() {
+                    tracing::debug!("Metadata stream receiver dropped");
+                    break;
+                }
+            }
+        });
+
+        rx
     }
 
-    pub async fn search(
+    async fn search(
         &self,
-        key_pattern: Option<&str>,
-        content_type: Option<&str>,
-        min_size: Option<i64>,
-        max_size: Option<i64>,
+        filter: &SearchFilter<'_>,
+        sort: SortField,
+        order: SortOrder,
         limit: Option<i64>,
     ) -> Result<Vec<ObjectMetadata>> {
         let mut conditions = Vec::new();
-        let mut query_str = String::from(
-            "SELECT id, key, size, content_type, etag, created_at FROM objects WHERE 1=1",
-        );
+        let mut query_str = format!("SELECT {} FROM objects WHERE bucket = ?", OBJECT_COLUMNS);
 
-        if key_pattern.is_some() {
+        let glob_prefix = filter.key_glob.map(|glob| literal_prefix(glob.glob()));
+
+        if filter.key_pattern.is_some() {
             conditions.push("key LIKE ?");
         }
-        if content_type.is_some() {
+        if glob_prefix.is_some() {
+            conditions.push("substr(key, 1, ?) = ?");
+        }
+        if filter.etag.is_some() {
+            conditions.push("etag = ?");
+        }
+        if filter.content_type.is_some() {
             conditions.push("content_type = ?");
         }
-        if min_size.is_some() {
+        if filter.min_size.is_some() {
             conditions.push("size >= ?");
         }
-        if max_size.is_some() {
+        if filter.max_size.is_some() {
             conditions.push("size <= ?");
         }
+        if filter.accessed_before.is_some() {
+            conditions.push("COALESCE(accessed_at, created_at) < ?");
+        }
+        if filter.accessed_after.is_some() {
+            conditions.push("accessed_at >= ?");
+        }
 
         for condition in conditions {
             query_str.push_str(" AND ");
             query_str.push_str(condition);
         }
 
-        query_str.push_str(" ORDER BY created_at DESC LIMIT ?");
+        query_str.push_str(&order_by(sort, order));
+
+        if filter.key_glob.is_none() {
+            query_str.push_str(" LIMIT ?");
+        }
 
-        let mut query = sqlx::query(&query_str);
+        let mut query = sqlx::query(&query_str).bind(&self.bucket);
 
-        if let Some(pattern) = key_pattern {
+        if let Some(pattern) = filter.key_pattern {
             query = query.bind(format!("%{}%", pattern));
         }
-        if let Some(ct) = content_type {
+        if let Some(prefix) = glob_prefix {
+            query = query.bind(prefix.chars().count() as i64).bind(prefix);
+        }
+        if let Some(etag) = filter.etag {
+            query = query.bind(etag);
+        }
+        if let Some(ct) = filter.content_type {
             query = query.bind(ct);
         }
-        if let Some(min) = min_size {
+        if let Some(min) = filter.min_size {
             query = query.bind(min);
         }
-        if let Some(max) = max_size {
+        if let Some(max) = filter.max_size {
             query = query.bind(max);
         }
+        if let Some(before) = filter.accessed_before {
+            query = query.bind(before.to_rfc3339());
+        }
+        if let Some(after) = filter.accessed_after {
+            query = query.bind(after.to_rfc3339());
+        }
 
-        query = query.bind(limit.unwrap_or(100));
+        let limit = limit.unwrap_or(100);
 
-        let rows = query.fetch_all(&self.pool).await?;
+        let Some(glob) = filter.key_glob else {
+            let rows = query.bind(limit).fetch_all(&self.pool).await?;
+            return Ok(rows.iter().map(row_to_metadata).collect());
+        };
 
+        let matcher = glob.compile_matcher();
+        let mut rows = query.fetch(&self.pool);
         let mut objects = Vec::new();
-        for row in rows {
-            let created_at_str: String = row.get("created_at");
-            objects.push(ObjectMetadata {
-                id: row.get("id"),
-                key: row.get("key"),
-                size: row.get("size"),
-                content_type: row.get("content_type"),
-                etag: row.get("etag"),
-                created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
-                    .unwrap()
-                    .with_timezone(&chrono::Utc),
-            });
+
+        while let Some(row) = rows.try_next().await? {
+            let metadata = row_to_metadata(&row);
+
+            if matcher.is_match(&metadata.key) {
+                objects.push(metadata);
+
+                if objects.len() as i64 >= limit {
+                    break;
+                }
+            }
         }
 
         Ok(objects)
     }
 
-    pub async fn delete(&self, key: &str) -> Result<bool> {
-        let result = sqlx::query("DELETE FROM objects WHERE key = ?")
-            .bind(key)
-            .execute(&self.pool)
-            .await?;
+    async fn list_expired(
+        &self,
+        now: chrono::DateTime<chrono::Utc>,
+        limit: i64,
+    ) -> Result<Vec<ObjectMetadata>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM objects
+             WHERE bucket = ? AND expires_at IS NOT NULL AND expires_at <= ?
+               AND (retain_until IS NULL OR retain_until <= ?)
+             ORDER BY expires_at
+             LIMIT ?",
+            OBJECT_COLUMNS
+        ))
+        .bind(&self.bucket)
+        .bind(now.to_rfc3339())
+        .bind(now.to_rfc3339())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(result.rows_affected() > 0)
+        Ok(rows.iter().map(row_to_metadata).collect())
     }
 
-    pub async fn delete_by_prefix(&self, prefix: &str) -> Result<i64> {
-        let pattern = format!("{}%", prefix);
-        let result = sqlx::query("DELETE FROM objects WHERE key LIKE ?")
-            .bind(pattern)
-            .execute(&self.pool)
+    async fn first_retained(
+        &self,
+        prefix: &str,
+        now: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Option<ObjectMetadata>> {
+        let row = sqlx::query(&format!(
+            "SELECT {} FROM objects WHERE bucket = ? AND key LIKE ? AND retain_until > ? LIMIT 1",
+            OBJECT_COLUMNS
+        ))
+        .bind(&self.bucket)
+        .bind(format!("{}%", prefix))
+        .bind(now.to_rfc3339())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(row_to_metadata))
+    }
+
+    async fn set_public(&self, key: &str, public: bool) -> Result<bool> {
+        let result = sqlx::query(
+            "UPDATE objects SET public = ?, updated_at = ? WHERE bucket = ? AND key = ?",
+        )
+        .bind(public)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .bind(&self.bucket)
+        .bind(key)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn insert_version(&self, metadata: &ObjectMetadata) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO object_versions (version_id, bucket, key, metadata, archived_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&metadata.id)
+        .bind(&self.bucket)
+        .bind(&metadata.key)
+        .bind(serde_json::to_string(metadata).unwrap())
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_version(&self, key: &str, version_id: &str) -> Result<Option<ObjectMetadata>> {
+        let row = sqlx::query(
+            "SELECT metadata FROM object_versions WHERE bucket = ? AND key = ? AND version_id = ?",
+        )
+        .bind(&self.bucket)
+        .bind(key)
+        .bind(version_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| serde_json::from_str(row.get("metadata")).unwrap()))
+    }
+
+    async fn list_versions(&self, key: &str) -> Result<Vec<ObjectMetadata>> {
+        let rows = sqlx::query(
+            "SELECT metadata FROM object_versions WHERE bucket = ? AND key = ?
+             ORDER BY archived_at DESC",
+        )
+        .bind(&self.bucket)
+        .bind(key)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| serde_json::from_str(row.get("metadata")).unwrap())
+            .collect())
+    }
+
+    async fn delete_version(&self, key: &str, version_id: &str) -> Result<bool> {
+        let result = sqlx::query(
+            "DELETE FROM object_versions WHERE bucket = ? AND key = ? AND version_id = ?",
+        )
+        .bind(&self.bucket)
+        .bind(key)
+        .bind(version_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn insert_trash(&self, metadata: &ObjectMetadata) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO trash (id, bucket, key, metadata, deleted_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&metadata.id)
+        .bind(&self.bucket)
+        .bind(&metadata.key)
+        .bind(serde_json::to_string(metadata).unwrap())
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_trash(&self, key: &str) -> Result<Option<TrashEntry>> {
+        let row = sqlx::query(
+            "SELECT metadata, deleted_at FROM trash WHERE bucket = ? AND key = ?
+             ORDER BY deleted_at DESC LIMIT 1",
+        )
+        .bind(&self.bucket)
+        .bind(key)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.as_ref().map(row_to_trash_entry))
+    }
+
+    async fn list_trash(&self, limit: Option<i64>) -> Result<Vec<TrashEntry>> {
+        let rows = sqlx::query(
+            "SELECT metadata, deleted_at FROM trash WHERE bucket = ?
+             ORDER BY deleted_at DESC LIMIT ?",
+        )
+        .bind(&self.bucket)
+        .bind(limit.unwrap_or(1000))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_trash_entry).collect())
+    }
+
+    async fn list_trash_before(
+        &self,
+        cutoff: chrono::DateTime<chrono::Utc>,
+    ) -> Result<Vec<TrashEntry>> {
+        let rows = sqlx::query(
+            "SELECT metadata, deleted_at FROM trash WHERE bucket = ? AND deleted_at < ?
+             ORDER BY deleted_at",
+        )
+        .bind(&self.bucket)
+        .bind(cutoff.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_trash_entry).collect())
+    }
+
+    async fn delete_trash(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM trash WHERE bucket = ? AND id = ?")
+            .bind(&self.bucket)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn record_access(&self, accesses: &[Access]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for access in accesses {
+            sqlx::query(
+                "UPDATE objects
+                 SET accessed_at = MAX(COALESCE(accessed_at, ''), ?),
+                     access_count = access_count + ?
+                 WHERE bucket = ? AND key = ? AND id = ?",
+            )
+            .bind(access.at.to_rfc3339())
+            .bind(access.count)
+            .bind(&self.bucket)
+            .bind(&access.key)
+            .bind(&access.id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM objects WHERE bucket = ? AND key = ?")
+            .bind(&self.bucket)
+            .bind(key)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_keys(&self) -> Result<Vec<String>> {
+        let rows = sqlx::query("SELECT key FROM objects WHERE bucket = ?")
+            .bind(&self.bucket)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("key")).collect())
+    }
+
+    async fn sample(&self, limit: i64) -> Result<Vec<ObjectMetadata>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM objects WHERE bucket = ? ORDER BY RANDOM() LIMIT ?",
+            OBJECT_COLUMNS
+        ))
+        .bind(&self.bucket)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_metadata).collect())
+    }
+
+    async fn inventory(&self) -> Result<Vec<(String, String, String)>> {
+        let rows = sqlx::query("SELECT bucket, key, etag FROM objects")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("bucket"), row.get("key"), row.get("etag")))
+            .collect())
+    }
+
+    async fn delete_by_prefix(&self, prefix: &str) -> Result<i64> {
+        let pattern = format!("{}%", prefix);
+        let result = sqlx::query("DELETE FROM objects WHERE bucket = ? AND key LIKE ?")
+            .bind(&self.bucket)
+            .bind(pattern)
+            .execute(&self.pool)
             .await?;
 
         Ok(result.rows_affected() as i64)
     }
 
-    pub async fn get_stats(&self) -> Result<(i64, i64)> {
+    async fn record_change(&self, op: EventOp, metadata: &ObjectMetadata) -> Result<ObjectEvent> {
+        let timestamp = chrono::Utc::now();
+        let row = sqlx::query(
+            "INSERT INTO changes (bucket, op, key, etag, size, created_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             RETURNING seq",
+        )
+        .bind(&self.bucket)
+        .bind(op.as_str())
+        .bind(&metadata.key)
+        .bind(&metadata.etag)
+        .bind(metadata.size)
+        .bind(timestamp.to_rfc3339())
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ObjectEvent {
+            seq: row.get::<i64, _>("seq") as u64,
+            op,
+            bucket: (!self.bucket.is_empty()).then(|| self.bucket.clone()),
+            key: metadata.key.clone(),
+            etag: metadata.etag.clone(),
+            size: metadata.size,
+            timestamp,
+        })
+    }
+
+    async fn list_changes(&self, since: u64, limit: i64) -> Result<Vec<ObjectEvent>> {
+        let rows = sqlx::query(&format!(
+            "SELECT {} FROM changes WHERE seq > ? AND (? = '' OR bucket = ?)
+             ORDER BY seq LIMIT ?",
+            CHANGE_COLUMNS
+        ))
+        .bind(since as i64)
+        .bind(&self.bucket)
+        .bind(&self.bucket)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(row_to_change).collect())
+    }
+
+    async fn latest_change(&self) -> Result<u64> {
+        let row = sqlx::query(
+            "SELECT COALESCE(MAX(seq), 0) AS seq FROM changes WHERE ? = '' OR bucket = ?",
+        )
+        .bind(&self.bucket)
+        .bind(&self.bucket)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.get::<i64, _>("seq") as u64)
+    }
+
+    async fn prune_changes(&self, cutoff: chrono::DateTime<chrono::Utc>) -> Result<u64> {
+        let result = sqlx::query(
+            "DELETE FROM changes
+             WHERE created_at < ? AND seq <= COALESCE((SELECT MIN(seq) FROM checkpoints), seq)",
+        )
+        .bind(cutoff.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_checkpoint(&self, name: &str) -> Result<Option<u64>> {
+        let row = sqlx::query("SELECT seq FROM checkpoints WHERE name = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get::<i64, _>("seq") as u64))
+    }
+
+    async fn set_checkpoint(&self, name: &str, seq: u64) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO checkpoints (name, seq, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(name) DO UPDATE SET seq = excluded.seq, updated_at = excluded.updated_at",
+        )
+        .bind(name)
+        .bind(seq as i64)
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_stats(&self) -> Result<(i64, i64)> {
         tracing::debug!("Executing stats query");
 
         let row = sqlx::query(
-            "SELECT COUNT(*) as count, COALESCE(SUM(size), 0) as total_size FROM objects",
+            "SELECT COUNT(*) as count, COALESCE(SUM(size), 0) as total_size
+             FROM objects WHERE bucket = ?",
         )
+        .bind(&self.bucket)
         .fetch_one(&self.pool)
         .await?;
 
@@ -255,4 +1100,722 @@ impl MetadataStore {
 
         Ok((count, total_size))
     }
+
+    async fn get_total_size(&self) -> Result<i64> {
+        let total_size = sqlx::query_scalar("SELECT COALESCE(SUM(size), 0) FROM objects")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(total_size)
+    }
+
+    async fn get_total_stats(&self) -> Result<(i64, i64)> {
+        let row = sqlx::query(
+            "SELECT COUNT(*) as count, COALESCE(SUM(size), 0) as total_size FROM objects",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((row.get("count"), row.get("total_size")))
+    }
+
+    async fn get_prefix_stats(&self, prefix: &str) -> Result<(i64, i64, Option<ObjectMetadata>)> {
+        let prefix_len = prefix.chars().count() as i64;
+
+        let row = sqlx::query(
+            "SELECT COUNT(*) as count, COALESCE(SUM(size), 0) as total_size
+             FROM objects
+             WHERE bucket = ? AND substr(key, 1, ?) = ?",
+        )
+        .bind(&self.bucket)
+        .bind(prefix_len)
+        .bind(prefix)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let largest = sqlx::query(&format!(
+            "SELECT {} FROM objects WHERE bucket = ? AND substr(key, 1, ?) = ?
+             ORDER BY size DESC, key LIMIT 1",
+            OBJECT_COLUMNS
+        ))
+        .bind(&self.bucket)
+        .bind(prefix_len)
+        .bind(prefix)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok((
+            row.get("count"),
+            row.get("total_size"),
+            largest.as_ref().map(row_to_metadata),
+        ))
+    }
+
+    async fn create_upload(&self, upload: &UploadSession) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO uploads (id, bucket, key, content_type, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&upload.id)
+        .bind(&self.bucket)
+        .bind(&upload.key)
+        .bind(&upload.content_type)
+        .bind(upload.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_upload(&self, id: &str) -> Result<Option<UploadSession>> {
+        let row = sqlx::query(
+            "SELECT id, key, content_type, created_at FROM uploads WHERE bucket = ? AND id = ?",
+        )
+        .bind(&self.bucket)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let created_at_str: String = row.get("created_at");
+            UploadSession {
+                id: row.get("id"),
+                key: row.get("key"),
+                content_type: row.get("content_type"),
+                created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            }
+        }))
+    }
+
+    async fn put_part(&self, upload_id: &str, part: &UploadPart) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO upload_parts (upload_id, part_number, size, etag, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT(upload_id, part_number) DO UPDATE SET
+                size = excluded.size,
+                etag = excluded.etag,
+                created_at = excluded.created_at
+            "#,
+        )
+        .bind(upload_id)
+        .bind(part.part_number)
+        .bind(part.size)
+        .bind(&part.etag)
+        .bind(part.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn list_parts(&self, upload_id: &str) -> Result<Vec<UploadPart>> {
+        let rows = sqlx::query(
+            "SELECT part_number, size, etag, created_at FROM upload_parts
+             WHERE upload_id = ?
+             ORDER BY part_number",
+        )
+        .bind(upload_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut parts = Vec::new();
+        for row in rows {
+            let created_at_str: String = row.get("created_at");
+            parts.push(UploadPart {
+                part_number: row.get("part_number"),
+                size: row.get("size"),
+                etag: row.get("etag"),
+                created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            });
+        }
+
+        Ok(parts)
+    }
+
+    async fn delete_upload(&self, id: &str) -> Result<bool> {
+        sqlx::query(
+            "DELETE FROM upload_parts
+             WHERE upload_id IN (SELECT id FROM uploads WHERE bucket = ? AND id = ?)",
+        )
+        .bind(&self.bucket)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        let result = sqlx::query("DELETE FROM uploads WHERE bucket = ? AND id = ?")
+            .bind(&self.bucket)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn create_resumable(&self, upload: &ResumableUpload) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO resumable_uploads
+                (id, bucket, key, content_type, upload_length, upload_offset, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&upload.id)
+        .bind(&self.bucket)
+        .bind(&upload.key)
+        .bind(&upload.content_type)
+        .bind(upload.length)
+        .bind(upload.offset)
+        .bind(upload.created_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_resumable(&self, id: &str) -> Result<Option<ResumableUpload>> {
+        let row = sqlx::query(
+            "SELECT id, key, content_type, upload_length, upload_offset, created_at
+             FROM resumable_uploads WHERE bucket = ? AND id = ?",
+        )
+        .bind(&self.bucket)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| {
+            let created_at_str: String = row.get("created_at");
+            ResumableUpload {
+                id: row.get("id"),
+                key: row.get("key"),
+                content_type: row.get("content_type"),
+                length: row.get("upload_length"),
+                offset: row.get("upload_offset"),
+                created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                    .unwrap()
+                    .with_timezone(&chrono::Utc),
+            }
+        }))
+    }
+
+    async fn update_resumable_offset(&self, id: &str, offset: i64) -> Result<()> {
+        sqlx::query("UPDATE resumable_uploads SET upload_offset = ? WHERE bucket = ? AND id = ?")
+            .bind(offset)
+            .bind(&self.bucket)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_resumable(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM resumable_uploads WHERE bucket = ? AND id = ?")
+            .bind(&self.bucket)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn acquire_blob(
+        &self,
+        volume: usize,
+        digest: &str,
+        size: i64,
+        inode: u64,
+    ) -> Result<i64> {
+        let refs = sqlx::query_scalar(
+            r#"
+            INSERT INTO blob_refs (volume, digest, size, refs, inode) VALUES (?, ?, ?, 1, ?)
+            ON CONFLICT(volume, digest) DO UPDATE SET refs = refs + 1, inode = excluded.inode
+            RETURNING refs
+            "#,
+        )
+        .bind(volume as i64)
+        .bind(digest)
+        .bind(size)
+        .bind(inode as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(refs)
+    }
+
+    async fn find_blob(&self, volume: usize, inode: u64) -> Result<Option<String>> {
+        let digest =
+            sqlx::query_scalar("SELECT digest FROM blob_refs WHERE volume = ? AND inode = ?")
+                .bind(volume as i64)
+                .bind(inode as i64)
+                .fetch_optional(&self.pool)
+                .await?;
+
+        Ok(digest)
+    }
+
+    async fn record_blob_inode(&self, volume: usize, digest: &str, inode: u64) -> Result<()> {
+        sqlx::query("UPDATE blob_refs SET inode = ? WHERE volume = ? AND digest = ?")
+            .bind(inode as i64)
+            .bind(volume as i64)
+            .bind(digest)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn release_blob(&self, volume: usize, digest: &str) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        let refs: Option<i64> = sqlx::query_scalar(
+            "UPDATE blob_refs SET refs = refs - 1 WHERE volume = ? AND digest = ? RETURNING refs",
+        )
+        .bind(volume as i64)
+        .bind(digest)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let refs = refs.unwrap_or(0);
+        if refs <= 0 {
+            sqlx::query("DELETE FROM blob_refs WHERE volume = ? AND digest = ?")
+                .bind(volume as i64)
+                .bind(digest)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(refs.max(0))
+    }
+
+    async fn forget_blob(&self, volume: usize, digest: &str) -> Result<()> {
+        sqlx::query("DELETE FROM blob_refs WHERE volume = ? AND digest = ?")
+            .bind(volume as i64)
+            .bind(digest)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+fn upsert_object<'a>(
+    bucket: &'a str,
+    metadata: &'a ObjectMetadata,
+) -> sqlx::query::Query<'a, Sqlite, SqliteArguments<'a>> {
+    sqlx::query(
+        r#"
+        INSERT INTO objects
+            (id, bucket, key, size, content_type, etag, etag_algorithm, created_at, public,
+             expires_at, retain_until, user_metadata, cache_control, cache_expires, stored_size,
+             chunks, accessed_at, access_count, updated_at, generation)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(bucket, key) DO UPDATE SET
+            id = excluded.id,
+            size = excluded.size,
+            content_type = excluded.content_type,
+            etag = excluded.etag,
+            etag_algorithm = excluded.etag_algorithm,
+            created_at = excluded.created_at,
+            public = excluded.public,
+            expires_at = excluded.expires_at,
+            retain_until = excluded.retain_until,
+            user_metadata = excluded.user_metadata,
+            cache_control = excluded.cache_control,
+            cache_expires = excluded.cache_expires,
+            stored_size = excluded.stored_size,
+            chunks = excluded.chunks,
+            accessed_at = excluded.accessed_at,
+            access_count = excluded.access_count,
+            updated_at = excluded.updated_at,
+            generation = excluded.generation
+        "#,
+    )
+    .bind(&metadata.id)
+    .bind(bucket)
+    .bind(&metadata.key)
+    .bind(metadata.size)
+    .bind(&metadata.content_type)
+    .bind(&metadata.etag)
+    .bind(metadata.etag_algorithm.as_str())
+    .bind(metadata.created_at.to_rfc3339())
+    .bind(metadata.public)
+    .bind(metadata.expires_at.map(|t| t.to_rfc3339()))
+    .bind(metadata.retain_until.map(|t| t.to_rfc3339()))
+    .bind(serde_json::to_string(&metadata.user_metadata).unwrap())
+    .bind(&metadata.cache_control)
+    .bind(&metadata.cache_expires)
+    .bind(metadata.stored_size)
+    .bind(
+        metadata
+            .chunks
+            .as_ref()
+            .map(|chunks| serde_json::to_string(chunks).unwrap()),
+    )
+    .bind(metadata.accessed_at.map(|t| t.to_rfc3339()))
+    .bind(metadata.access_count)
+    .bind(metadata.updated_at.map(|t| t.to_rfc3339()))
+    .bind(metadata.generation)
+}
+
+fn literal_prefix(pattern: &str) -> &str {
+    let end = pattern
+        .find(['*', '?', '[', '{', '\\'])
+        .unwrap_or(pattern.len());
+    &pattern[..end]
+}
+
+fn sort_column(sort: SortField) -> &'static str {
+    match sort {
+        SortField::Key => "key",
+        SortField::Size => "size",
+        SortField::CreatedAt => "created_at",
+    }
+}
+
+fn order_by(sort: SortField, order: SortOrder) -> String {
+    let direction = match order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+
+    match sort {
+        SortField::Key => format!(" ORDER BY key {}", direction),
+        sort => format!(
+            " ORDER BY {} {}, key {}",
+            sort_column(sort),
+            direction,
+            direction
+        ),
+    }
+}
+
+fn row_to_metadata(row: &SqliteRow) -> ObjectMetadata {
+    let created_at_str: String = row.get("created_at");
+    ObjectMetadata {
+        id: row.get("id"),
+        key: row.get("key"),
+        size: row.get("size"),
+        content_type: row.get("content_type"),
+        etag: row.get("etag"),
+        etag_algorithm: row
+            .get::<String, _>("etag_algorithm")
+            .parse()
+            .unwrap_or_default(),
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        public: row.get("public"),
+        expires_at: parse_optional_timestamp(row, "expires_at"),
+        retain_until: parse_optional_timestamp(row, "retain_until"),
+        user_metadata: serde_json::from_str(row.get("user_metadata")).unwrap_or_default(),
+        cache_control: row.get("cache_control"),
+        cache_expires: row.get("cache_expires"),
+        stored_size: row.get("stored_size"),
+        chunks: row
+            .get::<Option<String>, _>("chunks")
+            .and_then(|chunks| serde_json::from_str(&chunks).ok()),
+        accessed_at: parse_optional_timestamp(row, "accessed_at"),
+        access_count: row.get("access_count"),
+        updated_at: parse_optional_timestamp(row, "updated_at"),
+        generation: row.get("generation"),
+    }
+}
+
+fn parse_optional_timestamp(
+    row: &SqliteRow,
+    column: &str,
+) -> Option<chrono::DateTime<chrono::Utc>> {
+    row.get::<Option<String>, _>(column).map(|t| {
+        chrono::DateTime::parse_from_rfc3339(&t)
+            .unwrap()
+            .with_timezone(&chrono::Utc)
+    })
+}
+
+fn row_to_bucket(row: &SqliteRow) -> Bucket {
+    let created_at_str: String = row.get("created_at");
+    Bucket {
+        name: row.get("name"),
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+        policy: BucketPolicy {
+            quota_bytes: row.get("quota_bytes"),
+            default_content_type: row.get("default_content_type"),
+            public_read: row.get("public_read"),
+            versioning: row.get("versioning"),
+        },
+    }
+}
+
+fn row_to_token(row: &SqliteRow) -> ApiToken {
+    let created_at_str: String = row.get("created_at");
+    ApiToken {
+        id: row.get("id"),
+        name: row.get("name"),
+        scope: row.get::<String, _>("scope").parse().unwrap_or_default(),
+        rate_limit: row
+            .get::<Option<u32>, _>("rate_limit_per_second")
+            .map(|per_second| RateLimit {
+                per_second,
+                burst: row.get("rate_limit_burst"),
+            }),
+        created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+    }
+}
+
+fn row_to_session(row: &SqliteRow) -> SessionInfo {
+    let created_at_str: String = row.get("created_at");
+    let expires_at_str: String = row.get("expires_at");
+    SessionInfo {
+        principal: ApiToken {
+            id: format!("session:{}", row.get::<String, _>("subject")),
+            name: row.get("name"),
+            scope: row.get::<String, _>("scope").parse().unwrap_or_default(),
+            rate_limit: None,
+            created_at: chrono::DateTime::parse_from_rfc3339(&created_at_str)
+                .unwrap()
+                .with_timezone(&chrono::Utc),
+        },
+        expires_at: chrono::DateTime::parse_from_rfc3339(&expires_at_str)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+    }
+}
+
+fn row_to_change(row: &SqliteRow) -> ObjectEvent {
+    let bucket: String = row.get("bucket");
+    let created_at: String = row.get("created_at");
+    ObjectEvent {
+        seq: row.get::<i64, _>("seq") as u64,
+        op: row.get::<String, _>("op").parse().unwrap_or(EventOp::Put),
+        bucket: (!bucket.is_empty()).then_some(bucket),
+        key: row.get("key"),
+        etag: row.get("etag"),
+        size: row.get("size"),
+        timestamp: chrono::DateTime::parse_from_rfc3339(&created_at)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+    }
+}
+
+fn row_to_trash_entry(row: &SqliteRow) -> TrashEntry {
+    let deleted_at_str: String = row.get("deleted_at");
+    TrashEntry {
+        metadata: serde_json::from_str(row.get("metadata")).unwrap(),
+        deleted_at: chrono::DateTime::parse_from_rfc3339(&deleted_at_str)
+            .unwrap()
+            .with_timezone(&chrono::Utc),
+    }
+}
+
+async fn upgrade_legacy_schema(pool: &SqlitePool) -> Result<()> {
+    if !table_exists(pool, "objects").await? || table_exists(pool, "_sqlx_migrations").await? {
+        return Ok(());
+    }
+
+    tracing::info!("Upgrading database created before schema migrations");
+
+    for (table, column, definition) in LEGACY_COLUMNS {
+        if table_exists(pool, table).await? {
+            add_column_if_missing(pool, table, column, definition).await?;
+        }
+    }
+
+    scope_objects_by_bucket(pool).await
+}
+
+async fn table_exists(pool: &SqlitePool, table: &str) -> Result<bool> {
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_one(pool)
+            .await?;
+
+    Ok(count > 0)
+}
+
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+
+    let exists = columns
+        .iter()
+        .any(|row| row.get::<String, _>("name") == column);
+
+    if !exists {
+        tracing::info!("Adding column {}.{}", table, column);
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn scope_objects_by_bucket(pool: &SqlitePool) -> Result<()> {
+    let sql: String = sqlx::query_scalar(
+        "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'objects'",
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if sql.contains("UNIQUE (bucket, key)") {
+        return Ok(());
+    }
+
+    tracing::info!("Rebuilding objects table with bucket-scoped keys");
+
+    let mut tx = pool.begin().await?;
+
+    let columns = sqlx::query("PRAGMA table_info(objects)")
+        .fetch_all(&mut *tx)
+        .await?;
+
+    let mut names = Vec::new();
+    let mut definitions = Vec::new();
+
+    for row in &columns {
+        let name: String = row.get("name");
+        let mut definition = format!("{} {}", name, row.get::<String, _>("type"));
+
+        if row.get::<i64, _>("pk") != 0 {
+            definition.push_str(" PRIMARY KEY");
+        }
+        if row.get::<i64, _>("notnull") != 0 {
+            definition.push_str(" NOT NULL");
+        }
+        if let Some(default) = row.get::<Option<String>, _>("dflt_value") {
+            definition.push_str(&format!(" DEFAULT {}", default));
+        }
+
+        names.push(name);
+        definitions.push(definition);
+    }
+
+    let names = names.join(", ");
+
+    sqlx::query(&format!(
+        "CREATE TABLE objects_rebuild ({}, UNIQUE (bucket, key))",
+        definitions.join(", ")
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(&format!(
+        "INSERT INTO objects_rebuild ({names}) SELECT {names} FROM objects"
+    ))
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DROP TABLE objects").execute(&mut *tx).await?;
+    sqlx::query("ALTER TABLE objects_rebuild RENAME TO objects")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::storage::EtagAlgorithm;
+
+    const WRITERS: usize = 16;
+
+    async fn store(dir: &Path) -> SqliteMetadataStore {
+        let database_url = format!("sqlite:{}", dir.join("metadata.db").display());
+        SqliteMetadataStore::new(&database_url, &DatabaseConfig::default())
+            .await
+            .unwrap()
+    }
+
+    fn object(key: &str, etag: &str, generation: i64) -> ObjectMetadata {
+        ObjectMetadata {
+            id: etag.to_string(),
+            key: key.to_string(),
+            size: 1,
+            content_type: "text/plain".to_string(),
+            etag: etag.to_string(),
+            etag_algorithm: EtagAlgorithm::default(),
+            created_at: Utc::now(),
+            public: false,
+            expires_at: None,
+            retain_until: None,
+            user_metadata: Default::default(),
+            cache_control: None,
+            cache_expires: None,
+            stored_size: None,
+            chunks: None,
+            accessed_at: None,
+            access_count: 0,
+            updated_at: None,
+            generation,
+        }
+    }
+
+    #[tokio::test]
+    async fn insert_if_rejects_a_stale_generation() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = store(dir.path()).await;
+
+        assert!(store.insert_if(&object("a", "first", 1), 0).await.unwrap());
+        assert!(!store.insert_if(&object("a", "blind", 2), 0).await.unwrap());
+        assert!(!store.insert_if(&object("a", "ahead", 3), 2).await.unwrap());
+
+        let current = store.get("a").await.unwrap().unwrap();
+        assert_eq!(current.etag, "first");
+        assert_eq!(current.generation, 1);
+
+        assert!(store.insert_if(&object("a", "second", 4), 1).await.unwrap());
+        let current = store.get("a").await.unwrap().unwrap();
+        assert_eq!(current.etag, "second");
+        assert_eq!(current.generation, 4);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 8)]
+    async fn concurrent_insert_if_admits_one_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = Arc::new(store(dir.path()).await);
+
+        let writers = (0..WRITERS).map(|i| {
+            let store = store.clone();
+            tokio::spawn(async move {
+                let metadata = object("contended", &format!("writer-{}", i), i as i64 + 1);
+                store.insert_if(&metadata, 0).await
+            })
+        });
+
+        let mut winners = Vec::new();
+        for (i, writer) in futures_util::future::join_all(writers)
+            .await
+            .into_iter()
+            .enumerate()
+        {
+            if writer.unwrap().unwrap() {
+                winners.push(format!("writer-{}", i));
+            }
+        }
+
+        assert_eq!(winners.len(), 1);
+        let current = store.get("contended").await.unwrap().unwrap();
+        assert_eq!(current.etag, winners[0]);
+    }
 }
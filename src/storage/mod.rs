@@ -1,5 +1,13 @@
+pub mod backend;
+pub mod etag;
 pub mod filesystem;
+pub mod journal;
 pub mod metadata;
+pub mod upstream;
 
-pub use filesystem::FileStorage;
-pub use metadata::MetadataStore;
+pub use backend::{Access, MetadataBackend, MetadataStore, SearchFilter};
+pub use etag::EtagAlgorithm;
+pub use filesystem::{BlobStream, ExpectedChecksums, FileStorage, Staged};
+pub use journal::Journal;
+pub use metadata::SqliteMetadataStore;
+pub use upstream::Upstream;
@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use chrono::Utc;
+use futures_util::StreamExt;
+use percent_encoding::utf8_percent_encode;
+use reqwest::{Method, RequestBuilder, StatusCode, Url, header};
+use sha2::{Digest, Sha256};
+use tokio::{fs, io::AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use uuid::Uuid;
+
+use crate::{
+    error::{AppError, Result},
+    models::UpstreamConfig,
+    s3::auth::{UNRESERVED, hmac},
+};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+pub struct Upstream {
+    client: reqwest::Client,
+    endpoint: Url,
+    host: String,
+    config: UpstreamConfig,
+}
+
+impl Upstream {
+    pub fn new(config: &UpstreamConfig) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint)
+            .map_err(|e| AppError::Upstream(format!("invalid upstream endpoint: {}", e)))?;
+
+        let host = match (endpoint.host_str(), endpoint.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => {
+                return Err(AppError::Upstream(format!(
+                    "upstream endpoint {} has no host",
+                    config.endpoint
+                )));
+            }
+        };
+
+        Ok(Self {
+            client: reqwest::Client::new(),
+            endpoint,
+            host,
+            config: config.clone(),
+        })
+    }
+
+    #[tracing::instrument(name = "upstream.put", skip(self, path), err(level = "debug"))]
+    pub async fn put(&self, name: &str, path: &Path) -> Result<()> {
+        let file = fs::File::open(path).await?;
+        let length = file.metadata().await?.len();
+
+        let response = self
+            .request(Method::PUT, name)
+            .header(header::CONTENT_LENGTH, length)
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(file)))
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("upload of {} failed: {}", name, e)))?;
+
+        check(response, name).await?;
+        tracing::debug!("Uploaded {} ({} bytes) to upstream", name, length);
+        Ok(())
+    }
+
+    #[tracing::instrument(name = "upstream.fetch", skip(self, path), err(level = "debug"))]
+    pub async fn fetch(&self, name: &str, path: &Path) -> Result<bool> {
+        let response = self
+            .request(Method::GET, name)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("download of {} failed: {}", name, e)))?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+
+        let response = check(response, name).await?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let partial = path.with_extension(format!("{}.part", Uuid::new_v4().simple()));
+        let mut file = fs::File::create(&partial).await?;
+        let mut body = response.bytes_stream();
+
+        while let Some(chunk) = body.next().await {
+            let chunk = match chunk {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    drop(file);
+                    let _ = fs::remove_file(&partial).await;
+                    return Err(AppError::Upstream(format!(
+                        "download of {} failed: {}",
+                        name, e
+                    )));
+                }
+            };
+            file.write_all(&chunk).await?;
+        }
+
+        file.flush().await?;
+        fs::rename(&partial, path).await?;
+
+        tracing::debug!("Fetched {} from upstream", name);
+        Ok(true)
+    }
+
+    #[tracing::instrument(name = "upstream.delete", skip(self), err(level = "debug"))]
+    pub async fn delete(&self, name: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, name)
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("delete of {} failed: {}", name, e)))?;
+
+        if response.status() != StatusCode::NOT_FOUND {
+            check(response, name).await?;
+        }
+
+        Ok(())
+    }
+
+    fn request(&self, method: Method, name: &str) -> RequestBuilder {
+        let path = format!(
+            "{}/{}/{}",
+            self.endpoint.path().trim_end_matches('/'),
+            self.config.bucket,
+            format!("{}{}", self.config.prefix, name)
+                .split('/')
+                .map(|segment| utf8_percent_encode(segment, UNRESERVED).to_string())
+                .collect::<Vec<_>>()
+                .join("/")
+        );
+
+        let now = Utc::now();
+        let date = now.format("%Y%m%d").to_string();
+        let timestamp = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let scope = format!("{}/{}/s3/aws4_request", date, self.config.region);
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, self.host, UNSIGNED_PAYLOAD, timestamp, SIGNED_HEADERS, UNSIGNED_PAYLOAD
+        );
+
+        let string_to_sign = format!(
+            "{}\n{}\n{}\n{}",
+            ALGORITHM,
+            timestamp,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = hmac(format!("AWS4{}", self.config.secret_key).as_bytes(), &date);
+        let key = hmac(&key, &self.config.region);
+        let key = hmac(&key, "s3");
+        let key = hmac(&key, "aws4_request");
+        let signature = hex::encode(hmac(&key, &string_to_sign));
+
+        let mut url = self.endpoint.clone();
+        url.set_path(&path);
+
+        self.client
+            .request(method, url)
+            .header(header::HOST, &self.host)
+            .header("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .header("x-amz-date", timestamp)
+            .header(
+                header::AUTHORIZATION,
+                format!(
+                    "{} Credential={}/{}, SignedHeaders={}, Signature={}",
+                    ALGORITHM, self.config.access_key, scope, SIGNED_HEADERS, signature
+                ),
+            )
+    }
+}
+
+async fn check(response: reqwest::Response, name: &str) -> Result<reqwest::Response> {
+    let status = response.status();
+
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.text().await.unwrap_or_default();
+    Err(AppError::Upstream(format!(
+        "upstream returned {} for {}: {}",
+        status,
+        name,
+        body.trim()
+    )))
+}
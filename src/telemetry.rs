@@ -0,0 +1,171 @@
+use std::time::{Duration, Instant};
+
+use axum::{
+    Router,
+    body::Body,
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+};
+use http_body_util::BodyExt;
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig, WithHttpConfig};
+use opentelemetry_sdk::{
+    Resource,
+    trace::{Sampler, SdkTracerProvider},
+};
+use tracing::{Span, Subscriber, field::Empty};
+use tracing_subscriber::{EnvFilter, Layer, registry::LookupSpan};
+
+use crate::{
+    error::Result, models::OtlpConfig, request_id::REQUEST_ID_HEADER, storage::MetadataStore,
+};
+
+const REQUEST_DURATION: &str = "lila_http_request_duration_seconds";
+const DURATION_BUCKETS: [f64; 12] = [
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Clone)]
+struct MetricsState {
+    handle: PrometheusHandle,
+    metadata: MetadataStore,
+}
+
+pub fn install() -> std::result::Result<PrometheusHandle, BuildError> {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(REQUEST_DURATION.to_string()),
+            &DURATION_BUCKETS,
+        )?
+        .install_recorder()
+}
+
+pub fn otlp_layer<S>(
+    config: &OtlpConfig,
+) -> std::result::Result<(impl Layer<S>, SdkTracerProvider), Box<dyn std::error::Error>>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    let mut exporter = SpanExporter::builder()
+        .with_http()
+        .with_headers(config.headers.clone().into_iter().collect());
+
+    if let Some(endpoint) = &config.endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter.build()?)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+            config.sample_ratio,
+        ))))
+        .with_resource(
+            Resource::builder()
+                .with_service_name(config.service_name.clone())
+                .build(),
+        )
+        .build();
+
+    let layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer("lila"))
+        .with_filter(EnvFilter::try_new(&config.filter)?);
+
+    Ok((layer, provider))
+}
+
+pub fn request_span(request: &Request) -> Span {
+    let route = matched_route(request);
+
+    tracing::info_span!(
+        "request",
+        otel.name = format!("{} {}", request.method(), route),
+        otel.kind = "server",
+        otel.status_code = Empty,
+        method = %request.method(),
+        uri = %request.uri(),
+        route,
+        request_id = request
+            .headers()
+            .get(&REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok()),
+        status = Empty,
+    )
+}
+
+pub fn record_response(response: &Response, latency: Duration, span: &Span) {
+    let status = response.status();
+
+    span.record("status", status.as_u16());
+    if status.is_server_error() {
+        span.record("otel.status_code", "ERROR");
+    }
+
+    tracing::info!(
+        latency = %format!("{} ms", latency.as_millis()),
+        status = status.as_u16(),
+        "finished processing request"
+    );
+}
+
+pub fn router<S>(handle: PrometheusHandle, metadata: MetadataStore, path: &str) -> Router<S> {
+    Router::new()
+        .route(path, get(render))
+        .with_state(MetricsState { handle, metadata })
+}
+
+pub async fn track(request: Request, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = matched_route(&request).to_string();
+
+    let request = request.map(|body| count_bytes(body, "lila_bytes_received_total"));
+
+    let response = next.run(request).await;
+
+    metrics::counter!(
+        "lila_http_requests_total",
+        "method" => method.clone(),
+        "route" => route.clone(),
+        "status" => response.status().as_u16().to_string()
+    )
+    .increment(1);
+    metrics::histogram!(REQUEST_DURATION, "method" => method, "route" => route)
+        .record(started.elapsed().as_secs_f64());
+
+    response.map(|body| count_bytes(body, "lila_bytes_sent_total"))
+}
+
+fn count_bytes(body: Body, name: &'static str) -> Body {
+    Body::new(body.map_frame(move |frame| {
+        if let Some(data) = frame.data_ref() {
+            metrics::counter!(name).increment(data.len() as u64);
+        }
+        frame
+    }))
+}
+
+async fn render(State(state): State<MetricsState>) -> Result<Response> {
+    let (objects, bytes) = state.metadata.get_total_stats().await?;
+    metrics::gauge!("lila_objects").set(objects as f64);
+    metrics::gauge!("lila_stored_bytes").set(bytes as f64);
+
+    state.handle.run_upkeep();
+
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.handle.render(),
+    )
+        .into_response())
+}
+
+fn matched_route(request: &Request) -> &str {
+    request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or("unmatched")
+}
@@ -0,0 +1,103 @@
+use std::{
+    io,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use axum::{
+    BoxError,
+    body::{Body, Bytes},
+    extract::{Request, State},
+    http::Method,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use futures_util::StreamExt;
+use tokio::time::Instant;
+
+use crate::{error::AppError, models::TimeoutConfig};
+
+pub async fn timeout_middleware(
+    State(policy): State<Arc<TimeoutConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let upload = matches!(
+        *request.method(),
+        Method::PUT | Method::POST | Method::PATCH
+    );
+    let limit = match upload {
+        true => policy.upload_seconds,
+        false => policy.download_seconds,
+    };
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let stalled = Arc::new(AtomicBool::new(false));
+
+    let request = match policy.idle_body_seconds {
+        Some(seconds) => {
+            let idle = Duration::from_secs(seconds);
+            let stalled = stalled.clone();
+            request.map(|body| guarded(body, move || Instant::now() + idle, stalled))
+        }
+        None => request,
+    };
+
+    let response = match limit {
+        Some(limit) => {
+            let deadline = Instant::now() + Duration::from_secs(limit);
+
+            match tokio::time::timeout_at(deadline, next.run(request)).await {
+                Ok(response) => response
+                    .map(|body| guarded(body, move || deadline, Arc::new(AtomicBool::new(false)))),
+                Err(_) => {
+                    tracing::warn!(
+                        "{} {} exceeded the {}s request timeout",
+                        method,
+                        path,
+                        limit
+                    );
+                    return AppError::RequestTimeout(format!("request exceeded {} seconds", limit))
+                        .into_response();
+                }
+            }
+        }
+        None => next.run(request).await,
+    };
+
+    if stalled.load(Ordering::Relaxed) {
+        tracing::warn!("{} {} stalled while sending its body", method, path);
+        return AppError::RequestTimeout("request body stalled".to_string()).into_response();
+    }
+
+    response
+}
+
+fn guarded(
+    body: Body,
+    deadline: impl Fn() -> Instant + Send + 'static,
+    elapsed: Arc<AtomicBool>,
+) -> Body {
+    let stream = futures_util::stream::unfold(Some(body.into_data_stream()), move |stream| {
+        let deadline = deadline();
+        let elapsed = elapsed.clone();
+        async move {
+            let mut stream = stream?;
+            match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(Some(chunk)) => Some((chunk.map_err(BoxError::from), Some(stream))),
+                Ok(None) => None,
+                Err(_) => {
+                    elapsed.store(true, Ordering::Relaxed);
+                    let error = io::Error::new(io::ErrorKind::TimedOut, "body timeout elapsed");
+                    Some((Err::<Bytes, BoxError>(error.into()), None))
+                }
+            }
+        }
+    });
+
+    Body::from_stream(stream)
+}
@@ -0,0 +1,41 @@
+use axum::{
+    Router,
+    extract::State,
+    http::{StatusCode, Uri, header, uri::Authority},
+    response::{IntoResponse, Redirect, Response},
+};
+use axum_server::tls_rustls::RustlsConfig;
+
+use crate::models::Config;
+
+pub async fn load(config: &Config) -> std::io::Result<Option<RustlsConfig>> {
+    let (Some(cert), Some(key)) = (&config.tls_cert_path, &config.tls_key_path) else {
+        return Ok(None);
+    };
+
+    let _ = rustls::crypto::ring::default_provider().install_default();
+
+    RustlsConfig::from_pem_file(cert, key).await.map(Some)
+}
+
+pub fn redirect_router(https_port: u16) -> Router {
+    Router::new().fallback(redirect).with_state(https_port)
+}
+
+async fn redirect(State(https_port): State<u16>, headers: header::HeaderMap, uri: Uri) -> Response {
+    let Some(authority) = headers
+        .get(header::HOST)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<Authority>().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, "Missing Host header").into_response();
+    };
+
+    let path = uri.path_and_query().map(|p| p.as_str()).unwrap_or("/");
+    let location = match https_port {
+        443 => format!("https://{}{}", authority.host(), path),
+        port => format!("https://{}:{}{}", authority.host(), port, path),
+    };
+
+    Redirect::permanent(&location).into_response()
+}